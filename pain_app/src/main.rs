@@ -1,35 +1,122 @@
 use bevy::prelude::*;
-use pain_bevy_visualizer::{ParticlePlugin, SimulationResource};
-use pain_core::SimulationState;
+use pain_bevy_visualizer::{ParticlePlugin, ASSET_DIR};
+use pain_core::particle_export::export_trajectory_particles;
+use pain_core::starter::{diagnose_starter, parse_feeding_history};
+use pain_core::web_export::export_trajectory_web;
 
-const SIM_WIDTH: f32 = 1000.0;
-const SIM_HEIGHT: f32 = 720.0;
-const SIM_DEPTH: f32 = 1000.0;
+// `--starter "1:2:2@24/12,1:2:2@24/12"` prints a starter diagnostic and exits
+fn starter_diagnostic(args: &[String]) -> Option<std::io::Result<String>> {
+    let history = args.windows(2).find(|pair| pair[0] == "--starter")?;
+    Some(parse_feeding_history(&history[1]).map(|feedings| {
+        let report = diagnose_starter(&feedings);
+        format!("{} rafraîchis simulés\n{}", report.feedings, report.summary())
+    }))
+}
+
+// `--export-hdf5 <trajectory> <file.h5>` converts a recorded trajectory and exits
+fn hdf5_export(args: &[String]) -> Option<std::io::Result<String>> {
+    let export = args.windows(3).find(|args| args[0] == "--export-hdf5")?;
+    Some(export_trajectory(&export[1], &export[2]))
+}
+
+// `--export <trajectory> <file.xyz|file.vtk>` writes the frames for ParaView or OVITO and exits
+fn particle_export(args: &[String]) -> Option<std::io::Result<String>> {
+    let export = args.windows(3).find(|args| args[0] == "--export")?;
+    Some(export_particles(&export[1], &export[2]))
+}
+
+// `--export-web <trajectory> <replay.html|replay.pweb>` writes a replay for the browser viewer and exits
+fn web_export(args: &[String]) -> Option<std::io::Result<String>> {
+    let export = args.windows(3).find(|args| args[0] == "--export-web")?;
+    Some(export_web(&export[1], &export[2]))
+}
+
+// `--notebook <trajectory>` prints the notes and the end of a recorded run and exits
+fn notebook_report(args: &[String]) -> Option<std::io::Result<String>> {
+    let notebook = args.windows(2).find(|pair| pair[0] == "--notebook")?;
+    Some(
+        pain_core::Trajectory::load_from_file(std::path::Path::new(&notebook[1]))
+            .and_then(|trajectory| trajectory.final_state())
+            .and_then(|state| {
+                state.map(|state| state.notebook_report()).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "trajectoire sans point de reprise",
+                    )
+                })
+            }),
+    )
+}
+
+fn export_particles(trajectory: &str, output: &str) -> std::io::Result<String> {
+    let trajectory = pain_core::Trajectory::load_from_file(std::path::Path::new(trajectory))?;
+    let frames = export_trajectory_particles(&trajectory, std::path::Path::new(output))?;
+    Ok(format!("{} images exportées dans {}", frames, output))
+}
 
+fn export_web(trajectory: &str, output: &str) -> std::io::Result<String> {
+    let trajectory = pain_core::Trajectory::load_from_file(std::path::Path::new(trajectory))?;
+    let export = export_trajectory_web(&trajectory, std::path::Path::new(output))?;
+    Ok(format!(
+        "{} images ({} images clés, {} Ko) exportées dans {}",
+        export.frames,
+        export.keyframes,
+        export.bytes.div_ceil(1024),
+        output
+    ))
+}
+
+#[cfg(feature = "hdf5")]
+fn export_trajectory(trajectory: &str, output: &str) -> std::io::Result<String> {
+    let trajectory = pain_core::Trajectory::load_from_file(std::path::Path::new(trajectory))?;
+    pain_core::hdf5_export::export_trajectory_hdf5(&trajectory, std::path::Path::new(output))?;
+    Ok(format!(
+        "{} images exportées dans {}",
+        trajectory
+            .records()
+            .iter()
+            .filter(|record| record.is_frame())
+            .count(),
+        output
+    ))
+}
+
+#[cfg(not(feature = "hdf5"))]
+fn export_trajectory(_trajectory: &str, _output: &str) -> std::io::Result<String> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "export HDF5 indisponible : recompiler avec --features hdf5",
+    ))
+}
+
+// The plugin creates the simulation itself (classic recipe, honouring --seed)
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match starter_diagnostic(&args)
+        .or_else(|| hdf5_export(&args))
+        .or_else(|| particle_export(&args))
+        .or_else(|| web_export(&args))
+        .or_else(|| notebook_report(&args))
+    {
+        Some(Ok(report)) => {
+            println!("{}", report);
+            return;
+        }
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+        None => {}
+    }
+
     App::new()
+        // Fonts and visual themes live with the visualizer crate
         .add_plugins((
-            DefaultPlugins,
+            DefaultPlugins.set(AssetPlugin {
+                file_path: ASSET_DIR.to_string(),
+                ..default()
+            }),
         ))
         .add_plugins(ParticlePlugin)
-        .insert_resource(SimulationResource {
-            state: create_initial_simulation(),
-        })
-        .add_systems(Update, update_simulation_state)
         .run();
 }
-
-fn create_initial_simulation() -> SimulationState {
-    let mut sim_state = SimulationState::new(SIM_WIDTH, SIM_HEIGHT, SIM_DEPTH);
-    sim_state.initialize_classic_recipe();
-    sim_state
-}
-
-// Système pour mettre à jour l'état de la simulation à chaque frame
-fn update_simulation_state(
-    mut sim_resource: ResMut<SimulationResource>,
-    time: Res<Time>,
-) {
-    // Mettre à jour la simulation avec le delta time
-    sim_resource.state.tick(time.delta_seconds());
-}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use pain_core::{SimAction, SimulationState};
+
+use crate::inputs::{InputPlayback, InputRecording};
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+// Vitesses proposées par F6 (m/s) : air calme, léger courant d'air, fenêtre ouverte
+const DRAFT_SPEEDS: [f32; 3] = [0.0, 0.5, 1.5];
+
+// Flèches par côté au-dessus de la pâte
+const ARROWS_PER_SIDE: usize = 4;
+
+// Courant d'air depuis les préférences (vitesse et direction horizontale x, z)
+pub fn apply_draft(state: &mut SimulationState, speed: f32, direction: [f32; 2]) {
+    let airflow = &mut state.environment.airflow;
+    airflow.speed = speed.max(0.0);
+    airflow.direction = nalgebra::Vector3::new(direction[0], 0.0, direction[1]);
+}
+
+// F6 : change la force du courant d'air, Maj+F6 : le fait tourner d'un quart de tour
+pub fn cycle_draft(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut inputs: ResMut<InputRecording>,
+    mut worker: ResMut<SimulationWorker>,
+    playback: Res<InputPlayback>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F6) || playback.is_active() {
+        return;
+    }
+    if inputs.is_recording() && !worker.settle(&mut sim_resource.state) {
+        return;
+    }
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let airflow = &sim_resource.state.environment.airflow;
+    let (mut speed, mut direction) = (airflow.speed, airflow.direction);
+    if shift {
+        direction = nalgebra::Vector3::new(-direction.z, 0.0, direction.x);
+    } else {
+        let current = DRAFT_SPEEDS
+            .iter()
+            .position(|&draft| draft >= speed)
+            .unwrap_or(0);
+        speed = DRAFT_SPEEDS[(current + 1) % DRAFT_SPEEDS.len()];
+    }
+    inputs.apply(&mut sim_resource.state, SimAction::SetDraft { speed, direction });
+}
+
+// Petites flèches au-dessus de la surface, dans le sens du courant d'air
+pub fn draw_airflow_arrows(mut gizmos: Gizmos, sim_resource: Res<SimulationResource>) {
+    let state = &sim_resource.state;
+    let airflow = &state.environment.airflow;
+    let Some(direction) = airflow.horizontal_direction() else {
+        return;
+    };
+    if airflow.speed <= 0.0 {
+        return;
+    }
+    let direction = Vec3::new(direction.x, 0.0, direction.z);
+    let length = 25.0 + 25.0 * airflow.speed;
+    let y = state.height + 20.0;
+    for i in 0..ARROWS_PER_SIDE {
+        for j in 0..ARROWS_PER_SIDE {
+            let x = (i as f32 + 0.5) / ARROWS_PER_SIDE as f32 * state.width;
+            let z = (j as f32 + 0.5) / ARROWS_PER_SIDE as f32 * state.depth;
+            let center = Vec3::new(x, y, z);
+            gizmos.arrow(
+                center - direction * length / 2.0,
+                center + direction * length / 2.0,
+                Color::srgba(0.7, 0.9, 1.0, 0.8),
+            );
+        }
+    }
+}
+
+// Ligne du panneau
+pub fn draft_line(state: &SimulationState) -> String {
+    let speed = state.environment.airflow.speed;
+    if speed > 0.0 {
+        format!("Courant d'air: {:.1} m/s\n", speed)
+    } else {
+        String::new()
+    }
+}
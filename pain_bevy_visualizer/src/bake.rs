@@ -0,0 +1,24 @@
+use pain_config::TemperatureUnit;
+use pain_core::SimulationState;
+
+// Ligne du panneau pendant et après la cuisson : four, levures, mie et poussée
+pub fn bake_line(state: &SimulationState, unit: TemperatureUnit) -> String {
+    let Some(oven) = &state.oven else {
+        return String::new();
+    };
+    let yeast = if oven.yeast_killed {
+        ", levures mortes"
+    } else {
+        ""
+    };
+    format!(
+        "Four: {:.0} {}{}\n  mie prise {:.0} %, poussée au four +{:.0} %\n",
+        unit.convert(oven.temperature),
+        unit.symbol(),
+        yeast,
+        oven.denaturation * 100.0,
+        oven.gas_expansion(state.temperature.min(state.bake.crumb_max_temp))
+            .max(0.0)
+            * 100.0
+    )
+}
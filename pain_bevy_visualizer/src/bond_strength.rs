@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use pain_core::SimulationState;
+use std::collections::VecDeque;
+
+use crate::SimulationResource;
+
+// Un point de la courbe toutes les 5 s simulées, les 40 derniers affichés
+const SAMPLE_INTERVAL: f32 = 5.0;
+const HISTORY_LEN: usize = 40;
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Force moyenne des liaisons au fil du temps et coloration des liaisons (F2) :
+// une pâte trop fermentée voit son réseau s'affaiblir avant de s'effondrer
+#[derive(Resource, Default)]
+pub struct BondStrengthView {
+    pub tinted: bool,
+    history: VecDeque<f32>,
+    last_sample: Option<f32>,
+}
+
+pub fn toggle_bond_tint(keyboard_input: Res<ButtonInput<KeyCode>>, mut view: ResMut<BondStrengthView>) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        view.tinted = !view.tinted;
+    }
+}
+
+pub fn sample_bond_strength(sim_resource: Res<SimulationResource>, mut view: ResMut<BondStrengthView>) {
+    let time = sim_resource.state.time_elapsed;
+    // Nouvelle pâte ou sauvegarde rechargée : la courbe repart de zéro
+    if view.last_sample.is_some_and(|last| time < last) {
+        view.history.clear();
+        view.last_sample = None;
+    }
+    if view.last_sample.is_some_and(|last| time - last < SAMPLE_INTERVAL) {
+        return;
+    }
+    let Some(strength) = sim_resource.state.average_bond_strength() else {
+        return;
+    };
+    if view.history.len() == HISTORY_LEN {
+        view.history.pop_front();
+    }
+    view.history.push_back(strength);
+    view.last_sample = Some(time);
+}
+
+pub fn bond_strength_line(view: &BondStrengthView, state: &SimulationState) -> String {
+    let sparkline: String = view
+        .history
+        .iter()
+        .map(|strength| {
+            let level = (strength.clamp(0.0, 1.0) * (SPARK_CHARS.len() - 1) as f32).round() as usize;
+            SPARK_CHARS[level]
+        })
+        .collect();
+    let strength = state
+        .average_bond_strength()
+        .map(|strength| format!("{:.0} %", strength * 100.0))
+        .unwrap_or_else(|| "-".to_string());
+    format!("Force des liaisons: {} (pH {:.1}) {}", strength, state.dough_ph(), sparkline)
+}
@@ -0,0 +1,104 @@
+use bevy::audio::{Pitch, PitchBundle, PlaybackSettings, Volume};
+use bevy::prelude::*;
+use pain_core::SimulationState;
+use std::time::Duration;
+
+use crate::{world_up, PreferencesResource, SimulationResource};
+
+// Durée de vie d'une bouffée (s, temps réel)
+const PUFF_SECONDS: f32 = 0.8;
+// Bouffées affichées à la fois au plus, les plus anciennes s'effacent
+const MAX_PUFFS: usize = 64;
+// Son d'un rot : une note grave et brève
+const BURP_FREQUENCY: f32 = 95.0;
+const BURP_MILLIS: u64 = 120;
+
+struct Puff {
+    pos: Vec3,
+    molecules: u32,
+    started: f32, // Temps réel d'apparition
+}
+
+// Bouffées de gaz en cours et dernier instant de simulation déjà montré
+#[derive(Resource, Default)]
+pub struct BurpPuffs {
+    puffs: Vec<Puff>,
+    seen_until: f32,
+}
+
+// Chaque rot de la simulation devient une bouffée à la surface, avec un son
+// si les préférences le demandent
+pub fn spawn_burp_puffs(
+    mut commands: Commands,
+    time: Res<Time>,
+    sim_resource: Res<SimulationResource>,
+    prefs: Res<PreferencesResource>,
+    mut puffs: ResMut<BurpPuffs>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    let state = &sim_resource.state;
+    // Recette relancée ou sauvegarde rechargée : on repart de l'instant courant
+    if state.time_elapsed < puffs.seen_until {
+        puffs.seen_until = state.time_elapsed;
+        puffs.puffs.clear();
+        return;
+    }
+    let now = time.elapsed_seconds();
+    let mut heard = false;
+    for (_, burp) in state.burps_since(puffs.seen_until) {
+        puffs.puffs.push(Puff {
+            pos: Vec3::new(burp.pos.x, burp.pos.y, burp.pos.z),
+            molecules: burp.molecules,
+            started: now,
+        });
+        heard = true;
+    }
+    puffs.seen_until = state.time_elapsed;
+    let excess = puffs.puffs.len().saturating_sub(MAX_PUFFS);
+    puffs.puffs.drain(..excess);
+
+    // Un seul son par image, même si plusieurs rots tombent ensemble
+    if heard && prefs.0.burp_sound {
+        commands.spawn(PitchBundle {
+            source: pitches.add(Pitch::new(
+                BURP_FREQUENCY,
+                Duration::from_millis(BURP_MILLIS),
+            )),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(0.4)),
+        });
+    }
+}
+
+// Sphères qui gonflent et s'estompent au-dessus de la pâte
+pub fn draw_burp_puffs(mut gizmos: Gizmos, time: Res<Time>, mut puffs: ResMut<BurpPuffs>) {
+    let now = time.elapsed_seconds();
+    puffs.puffs.retain(|puff| now - puff.started < PUFF_SECONDS);
+    for puff in &puffs.puffs {
+        let age = (now - puff.started) / PUFF_SECONDS;
+        let size = 4.0 + 16.0 * age * (puff.molecules as f32).sqrt();
+        let color = Color::srgba(0.95, 0.95, 0.9, 0.8 * (1.0 - age));
+        // Elle monte un peu en se dissipant
+        let pos = puff.pos + world_up() * 10.0 * age;
+        gizmos.sphere(pos, Quat::IDENTITY, size, color);
+    }
+}
+
+// B : active ou coupe le son des rots
+pub fn toggle_burp_sound(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut prefs: ResMut<PreferencesResource>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyB) {
+        prefs.0.burp_sound = !prefs.0.burp_sound;
+    }
+}
+
+// Ligne du panneau : rots depuis le début et rythme sur les 10 dernières minutes
+pub fn burp_line(state: &SimulationState, sound: bool) -> String {
+    format!(
+        "Rots: {} ({:.1}/min){}",
+        state.ledger.burps,
+        state.burp_rate(600.0),
+        if sound { ", son" } else { "" }
+    )
+}
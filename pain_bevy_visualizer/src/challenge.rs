@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use pain_core::SimulationState;
+
+use crate::SimulationResource;
+
+// Pas d'hydratation au clavier
+const HYDRATION_STEP: f32 = 0.02;
+
+// Défi farine mystère (M) : les propriétés de la farine sont tirées au hasard
+// et cachées, au boulanger de les deviner à l'observation de la pâte
+#[derive(Resource, Default)]
+pub struct ChallengeDialog {
+    pub visible: bool,
+    announced: bool, // Note déjà affichée dans la console
+}
+
+fn challenge_text(state: &SimulationState) -> String {
+    let mut text = String::from("[M] Défi farine mystère\n\n");
+    let Some(challenge) = &state.challenge else {
+        text.push_str("[Maj+M: tirer une farine mystère]");
+        return text;
+    };
+    text.push_str(&format!(
+        "Hydratation: {:.0} %{}\n",
+        challenge.hydration * 100.0,
+        if state.salt_added || state.yeast_added {
+            ""
+        } else {
+            " [L/Maj+L: ±2 %, avant sel et levure]"
+        }
+    ));
+    let score = state.challenge_score();
+    match score {
+        Some(score) => text.push_str(&format!(
+            "\nNote: {}/100 - {}\n  hydratation {:.0} %, développement {:.0} %, volume {:.0} %, mie {:.0} %\n",
+            score.points,
+            score.verdict(),
+            score.hydration * 100.0,
+            score.development * 100.0,
+            score.volume * 100.0,
+            score.crumb * 100.0
+        )),
+        None => text.push_str(
+            "Observez la pâte (liaisons, réseau, sucre, levée) puis\nrabattez (F/G/H), pointez, façonnez et cuisez (N) :\nle pain est noté une fois refroidi.\n",
+        ),
+    }
+    if challenge.revealed || score.is_some() {
+        text.push_str(&format!("\nFarine: {}", challenge.flour.describe()));
+    } else {
+        text.push_str("\n[I: révéler la farine / Maj+M: nouvelle farine]");
+    }
+    text
+}
+
+pub fn start_challenge_from_args(
+    mut sim_resource: ResMut<SimulationResource>,
+    mut dialog: ResMut<ChallengeDialog>,
+) {
+    if std::env::args().any(|arg| arg == "--challenge") {
+        sim_resource.state.start_challenge();
+        dialog.visible = true;
+    }
+}
+
+pub fn challenge_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut dialog: ResMut<ChallengeDialog>,
+) {
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        if shift {
+            sim_resource.state.start_challenge();
+            dialog.visible = true;
+            dialog.announced = false;
+            println!("Nouvelle farine mystère");
+        } else {
+            dialog.visible = !dialog.visible;
+        }
+    }
+    let state = &mut sim_resource.state;
+    let Some(challenge) = state.challenge else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        let step = if shift {
+            -HYDRATION_STEP
+        } else {
+            HYDRATION_STEP
+        };
+        if state.set_challenge_hydration(challenge.hydration + step) {
+            println!(
+                "Hydratation: {:.0} %",
+                state.challenge.map_or(0.0, |c| c.hydration) * 100.0
+            );
+        } else {
+            println!("Le sel ou la levure sont déjà dans la pâte");
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyI) {
+        if let Some(flour) = state.reveal_challenge() {
+            println!("Farine mystère: {}", flour.describe());
+        }
+    }
+    if !dialog.announced {
+        if let Some(score) = state.challenge_score() {
+            dialog.announced = true;
+            dialog.visible = true;
+            println!("Pain noté {}/100 : {}", score.points, score.verdict());
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ChallengeDialogText;
+
+pub fn setup_challenge_dialog(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::srgb(0.95, 1.0, 0.85),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.05, 0.08, 0.02, 0.85).into(),
+            ..default()
+        },
+        ChallengeDialogText,
+    ));
+}
+
+pub fn update_challenge_dialog(
+    dialog: Res<ChallengeDialog>,
+    sim_resource: Res<SimulationResource>,
+    mut query: Query<(&mut Text, &mut Style), With<ChallengeDialogText>>,
+) {
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if dialog.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if dialog.visible {
+            text.sections[0].value = challenge_text(&sim_resource.state);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use nalgebra::Vector3;
+use pain_core::session::{decode_frame, ServerMessage, DEFAULT_PORT, FRAME_VERSION, HOST};
+use pain_core::{CameraCursor, KneadAction, SessionClient, SessionEntry, SessionServer, SimAction};
+
+use crate::preferences::{binding_just_pressed, PreferencesResource};
+use crate::{OrbitCamera, SimulationResource};
+
+// La pâte et les caméras partent vers les invités au plus toutes les
+// FRAME_INTERVAL secondes réelles
+const FRAME_INTERVAL: f32 = 0.5;
+// Lignes du journal partagé affichées
+const LOG_LINES: usize = 6;
+
+pub enum SessionRole {
+    Host(SessionServer),
+    Guest(SessionClient),
+}
+
+// Séance de co-boulange (--host / --join <adresse>) : l'hôte fait tourner la
+// simulation, les invités envoient leurs gestes et voient la pâte de l'hôte,
+// les caméras des autres et le journal commun
+#[derive(Resource, Default)]
+pub struct CoBaking {
+    pub role: Option<SessionRole>,
+    pub log: Vec<SessionEntry>,
+    pub cursors: Vec<CameraCursor>,
+    status: String,
+    since_frame: f32,
+}
+
+impl CoBaking {
+    pub fn is_guest(&self) -> bool {
+        matches!(self.role, Some(SessionRole::Guest(_)))
+    }
+
+    // Notre numéro de participant, pour ne pas dessiner notre propre caméra
+    fn me(&self) -> Option<u32> {
+        match &self.role {
+            Some(SessionRole::Host(_)) => Some(HOST),
+            Some(SessionRole::Guest(client)) => client.participant(),
+            None => None,
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("Co-boulange : {}\n", self.status);
+        let start = self.log.len().saturating_sub(LOG_LINES);
+        for entry in &self.log[start..] {
+            text.push_str(&format!(
+                "{:>6.0} s  {} {}\n",
+                entry.time, entry.name, entry.text
+            ));
+        }
+        text
+    }
+}
+
+fn argument(args: &[String], name: &str) -> Option<String> {
+    args.windows(2)
+        .find(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+}
+
+pub fn start_cobaking(mut cobaking: ResMut<CoBaking>) {
+    let args: Vec<String> = std::env::args().collect();
+    let name = argument(&args, "--name")
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "Boulanger".to_string());
+    if let Some(addr) = argument(&args, "--join") {
+        match SessionClient::connect(addr.as_str(), &name) {
+            Ok(client) => {
+                cobaking.status = format!("invité de {}", addr);
+                cobaking.role = Some(SessionRole::Guest(client));
+            }
+            Err(err) => eprintln!("Connexion à {} impossible: {}", addr, err),
+        }
+    } else if args.iter().any(|arg| arg == "--host") {
+        let port = argument(&args, "--port")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT);
+        match SessionServer::bind(("0.0.0.0", port), &name) {
+            Ok(server) => {
+                println!("Séance de co-boulange ouverte sur le port {}", port);
+                cobaking.status = format!("hôte sur le port {}", port);
+                cobaking.role = Some(SessionRole::Host(server));
+            }
+            Err(err) => eprintln!("Port {} indisponible: {}", port, err),
+        }
+    }
+}
+
+// Chez l'invité, les gestes partent chez l'hôte qui les applique (ou les
+// refuse) ; la pâte locale est remplacée à la trame suivante
+pub fn send_guest_actions(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    prefs: Res<PreferencesResource>,
+    sim_resource: Res<SimulationResource>,
+    mut cobaking: ResMut<CoBaking>,
+) {
+    let Some(SessionRole::Guest(client)) = &mut cobaking.role else {
+        return;
+    };
+    let keys = &prefs.0.keybindings;
+    let state = &sim_resource.state;
+    let mut actions = Vec::new();
+    if binding_just_pressed(&keyboard_input, &keys.add_salt, KeyCode::KeyS) {
+        actions.push(SimAction::AddSalt);
+    }
+    if binding_just_pressed(&keyboard_input, &keys.add_yeast, KeyCode::KeyY) {
+        actions.push(SimAction::AddYeast);
+    }
+    if binding_just_pressed(&keyboard_input, &keys.fold, KeyCode::KeyC) {
+        actions.push(SimAction::centered_fold(state));
+    }
+    for (binding, fallback, knead) in [
+        (
+            &keys.stretch_and_fold,
+            KeyCode::KeyF,
+            KneadAction::StretchAndFold,
+        ),
+        (&keys.coil_fold, KeyCode::KeyG, KneadAction::CoilFold),
+        (&keys.slap_and_fold, KeyCode::KeyH, KneadAction::SlapAndFold),
+    ] {
+        if binding_just_pressed(&keyboard_input, binding, fallback) {
+            actions.push(SimAction::Knead(knead));
+        }
+    }
+    if binding_just_pressed(&keyboard_input, &keys.next_phase, KeyCode::KeyN) {
+        if let Some(next) = state.phase.next() {
+            actions.push(SimAction::AdvanceTo(next));
+        }
+    }
+    for action in actions {
+        if let Err(err) = client.send_action(action) {
+            eprintln!("Geste non envoyé: {}", err);
+        }
+    }
+}
+
+pub fn run_cobaking(
+    time: Res<Time<Real>>,
+    mut cobaking: ResMut<CoBaking>,
+    mut sim_resource: ResMut<SimulationResource>,
+    cameras: Query<(&Transform, &OrbitCamera)>,
+) {
+    let cobaking = &mut *cobaking;
+    let Some(role) = &mut cobaking.role else {
+        return;
+    };
+    cobaking.since_frame += time.delta_seconds();
+    let send_frame = cobaking.since_frame >= FRAME_INTERVAL;
+    if send_frame {
+        cobaking.since_frame = 0.0;
+    }
+    let camera = cameras.iter().next().map(|(transform, orbit)| {
+        let position = transform.translation;
+        let target = position + transform.forward() * orbit.radius;
+        (
+            Vector3::new(position.x, position.y, position.z),
+            Vector3::new(target.x, target.y, target.z),
+        )
+    });
+
+    match role {
+        SessionRole::Host(server) => {
+            for entry in server.poll(&mut sim_resource.state) {
+                println!("[co-boulange] {} {}", entry.name, entry.text);
+            }
+            if send_frame {
+                if let Some((position, target)) = camera {
+                    server.set_host_cursor(position, target);
+                }
+                if let Err(err) = server.broadcast_state(&sim_resource.state) {
+                    eprintln!("Trame non envoyée: {}", err);
+                }
+            }
+            cobaking.log = server.log().to_vec();
+            cobaking.cursors = server.cursors().to_vec();
+            cobaking.status = format!(
+                "hôte, invités : {}",
+                server.guests().collect::<Vec<_>>().join(", ")
+            );
+        }
+        SessionRole::Guest(client) => {
+            let messages = match client.poll() {
+                Ok(messages) => messages,
+                Err(err) => {
+                    eprintln!("Séance de co-boulange terminée: {}", err);
+                    cobaking.status = "déconnecté".to_string();
+                    cobaking.role = None;
+                    return;
+                }
+            };
+            for message in messages {
+                match message {
+                    ServerMessage::Welcome { log, .. } => cobaking.log = log,
+                    ServerMessage::State { version, .. } if version != FRAME_VERSION => {
+                        eprintln!(
+                            "Séance de co-boulange terminée: l'hôte envoie des trames en version {}, cette version lit la {}",
+                            version, FRAME_VERSION
+                        );
+                        cobaking.status = "version incompatible".to_string();
+                        cobaking.role = None;
+                        return;
+                    }
+                    ServerMessage::State { frame, .. } => match decode_frame(&frame) {
+                        Ok(state) => sim_resource.state = state,
+                        Err(err) => eprintln!("Trame illisible: {}", err),
+                    },
+                    ServerMessage::Cursors(cursors) => cobaking.cursors = cursors,
+                    ServerMessage::Entry(entry) => {
+                        println!("[co-boulange] {} {}", entry.name, entry.text);
+                        cobaking.log.push(entry);
+                    }
+                }
+            }
+            if let (true, Some((position, target))) = (send_frame, camera) {
+                if let Err(err) = client.send_cursor(position, target) {
+                    eprintln!("Caméra non envoyée: {}", err);
+                }
+            }
+        }
+    }
+}
+
+// Une couleur par participant
+fn participant_color(participant: u32) -> Color {
+    Color::hsl((participant as f32 * 137.5) % 360.0, 0.8, 0.6)
+}
+
+// Caméras des autres : une sphère à leur place et leur ligne de visée
+pub fn draw_cursors(mut gizmos: Gizmos, cobaking: Res<CoBaking>) {
+    let me = cobaking.me();
+    for cursor in cobaking
+        .cursors
+        .iter()
+        .filter(|cursor| Some(cursor.participant) != me)
+    {
+        let color = participant_color(cursor.participant);
+        let position = Vec3::new(cursor.position.x, cursor.position.y, cursor.position.z);
+        let target = Vec3::new(cursor.target.x, cursor.target.y, cursor.target.z);
+        gizmos.sphere(position, Quat::IDENTITY, 15.0, color);
+        gizmos.line(position, target, color);
+        gizmos.sphere(target, Quat::IDENTITY, 6.0, color);
+    }
+}
+
+#[derive(Component)]
+pub struct CoBakingText;
+
+pub fn setup_cobaking_log(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 14.0,
+                    color: Color::srgb(0.85, 0.95, 1.0),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Percent(35.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.02, 0.05, 0.1, 0.8).into(),
+            ..default()
+        },
+        CoBakingText,
+    ));
+}
+
+pub fn update_cobaking_log(
+    cobaking: Res<CoBaking>,
+    mut query: Query<(&mut Text, &mut Style), With<CoBakingText>>,
+) {
+    let visible = cobaking.role.is_some() || !cobaking.log.is_empty();
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if visible {
+            text.sections[0].value = cobaking.text();
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use pain_core::SimulationState;
+
+use crate::SimulationResource;
+
+// Bandeau rouge en haut de l'écran quand la pâte s'effondre : une expérience
+// de surfermentation montre ainsi clairement l'instant de l'échec. Il prévient
+// déjà quand la pâte est trop poussée, avant qu'elle ne s'effondre.
+#[derive(Component)]
+pub struct CollapseBanner;
+
+pub fn setup_collapse_banner(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::srgb(1.0, 0.25, 0.2),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Percent(35.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.2, 0.0, 0.0, 0.85).into(),
+            ..default()
+        },
+        CollapseBanner,
+    ));
+}
+
+fn collapse_text(state: &SimulationState) -> Option<String> {
+    let collapsed = state.collapse.record.map(|record| {
+        format!(
+            "!! Pâte effondrée à t={:.0} s ({:.0} min)\n-{:.1} de levée sur {:.1}, {} liaisons rompues",
+            record.time,
+            record.time / 60.0,
+            record.height_drop,
+            record.peak_rise,
+            record.bonds_torn
+        )
+    });
+    collapsed.or_else(|| {
+        state.proofing.overproof.map(|signs| {
+            format!(
+                "! Pâte trop poussée\nsucre restant {:.0} %, CO₂ qui s'échappe {:.0} %, réseau {:.0} %",
+                signs.sugar_left * 100.0,
+                signs.escape_rate * 100.0,
+                signs.network_left * 100.0
+            )
+        })
+    })
+}
+
+pub fn update_collapse_banner(
+    sim_resource: Res<SimulationResource>,
+    mut query: Query<(&mut Text, &mut Style), With<CollapseBanner>>,
+) {
+    let text_value = collapse_text(&sim_resource.state);
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if text_value.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        let value = text_value.as_deref().unwrap_or_default();
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_string();
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use pain_core::{
+    ProcessSchedule, ScheduleStep, SimAction, SimulationState, StepCondition, StepNotification,
+};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::time_scale::{SimulationPause, TimeScale};
+use crate::SimulationResource;
+
+const BANNER_DURATION: Duration = Duration::from_secs(30);
+const COMPANION_LOG_FILE: &str = "companion_log.csv";
+
+// Mode compagnon : la simulation avance au rythme de l'horloge murale (x1) pendant
+// que le boulanger fait sa vraie pâte, et le planning lui envoie des notifications.
+#[derive(Resource)]
+pub struct CompanionMode {
+    pub enabled: bool,
+    pub schedule: ProcessSchedule,
+    pub banner: Option<(String, Instant)>,
+}
+
+impl Default for CompanionMode {
+    fn default() -> Self {
+        CompanionMode {
+            enabled: std::env::args().any(|arg| arg == "--companion"),
+            schedule: ProcessSchedule::default(),
+            banner: None,
+        }
+    }
+}
+
+impl CompanionMode {
+    pub fn banner_text(&self) -> Option<&str> {
+        match &self.banner {
+            Some((text, shown_at)) if shown_at.elapsed() < BANNER_DURATION => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// Planning par défaut calqué sur la recette classique
+pub fn default_companion_schedule(state: &SimulationState) -> ProcessSchedule {
+    let autolyse = state.autolyse_time;
+    let fold = SimAction::centered_fold(state);
+    ProcessSchedule::new(vec![
+        ScheduleStep {
+            label: "Fin de l'autolyse : ajouter le sel".to_string(),
+            at: autolyse,
+            action: Some(SimAction::AddSalt),
+            when: None,
+            timeout: None,
+        },
+        ScheduleStep {
+            label: "Ajouter le levain".to_string(),
+            at: autolyse + 300.0,
+            action: Some(SimAction::AddYeast),
+            when: None,
+            timeout: None,
+        },
+        ScheduleStep {
+            label: "Rabat n°1".to_string(),
+            at: autolyse + 1800.0,
+            action: Some(fold.clone()),
+            when: None,
+            timeout: None,
+        },
+        ScheduleStep {
+            label: "Rabat n°2".to_string(),
+            at: autolyse + 3600.0,
+            action: Some(fold.clone()),
+            when: None,
+            timeout: None,
+        },
+        ScheduleStep {
+            label: "Rabat n°3".to_string(),
+            at: autolyse + 5400.0,
+            action: Some(fold),
+            when: None,
+            timeout: None,
+        },
+        ScheduleStep {
+            // Pointage jusqu'à +50 % de volume, au plus tard 5 h après l'autolyse
+            label: "Façonnage".to_string(),
+            at: autolyse + 3.0 * 3600.0,
+            action: None,
+            when: Some(StepCondition::RiseAtLeast(0.5)),
+            timeout: Some(2.0 * 3600.0),
+        },
+        ScheduleStep {
+            label: "Enfournement".to_string(),
+            at: autolyse + 6.0 * 3600.0,
+            action: None,
+            when: None,
+            timeout: None,
+        },
+    ])
+}
+
+pub fn setup_companion(mut companion: ResMut<CompanionMode>, sim_resource: Res<SimulationResource>) {
+    if companion.enabled {
+        companion.schedule = default_companion_schedule(&sim_resource.state);
+        println!("Mode compagnon actif : le temps simulé suit l'horloge réelle");
+    }
+}
+
+// Journal CSV pour comparer l'état prédit avec la vraie pâte à chaque étape
+fn log_notification(notification: &StepNotification) {
+    let Some(dir) = pain_config::config_dir() else {
+        return;
+    };
+    let path = dir.join(COMPANION_LOG_FILE);
+    let is_new = !path.exists();
+    let file = std::fs::create_dir_all(&dir)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+    let result = file.and_then(|mut file| {
+        if is_new {
+            writeln!(file, "step,scheduled_s,sim_time_s,temperature_c,bonds,co2,sugar")?;
+        }
+        let predicted = &notification.predicted;
+        writeln!(
+            file,
+            "\"{}\",{:.0},{:.0},{:.1},{},{},{}",
+            notification.label,
+            notification.scheduled_at,
+            predicted.time,
+            predicted.temperature,
+            predicted.bond_count,
+            predicted.co2_count,
+            predicted.sugar_count
+        )
+    });
+    if let Err(err) = result {
+        eprintln!("Impossible d'écrire le journal compagnon: {}", err);
+    }
+}
+
+// Système : verrouille le facteur temps (sans pause) et déclenche les étapes dues
+pub fn run_companion(
+    mut companion: ResMut<CompanionMode>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut time_scale: ResMut<TimeScale>,
+    mut pause: ResMut<SimulationPause>,
+) {
+    if !companion.enabled {
+        return;
+    }
+    if time_scale.0 != 1.0 {
+        time_scale.0 = 1.0;
+    }
+    // La pâte suit l'horloge réelle, elle ne s'arrête pas
+    if pause.paused {
+        pause.toggle();
+    }
+
+    let notifications = companion.schedule.poll(&mut sim_resource.state);
+    for notification in notifications {
+        // La cloche du terminal sert de notification sonore minimale
+        if notification.timed_out {
+            println!("\x07[Compagnon] {} (délai dépassé)", notification.label);
+        } else {
+            println!("\x07[Compagnon] {}", notification.label);
+        }
+        log_notification(&notification);
+        companion.banner = Some((notification.label, Instant::now()));
+    }
+}
@@ -0,0 +1,357 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::{CompressedImageFormats, ImageSampler, ImageType};
+use pain_core::timeline::{format_duration, parse_duration};
+use pain_core::{CrossSection, CrumbAnalysis};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::SimulationResource;
+
+const PHOTOS_FILE: &str = "photos.toml";
+// Finesse et épaisseur de la coupe simulée
+const SECTION_COLUMNS: usize = 96;
+const SECTION_THICKNESS: f32 = 80.0;
+// La coupe est recalculée au plus toutes les SECTION_REFRESH secondes réelles
+const SECTION_REFRESH: f32 = 0.5;
+const IMAGE_WIDTH: f32 = 300.0;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PhotoTime {
+    Seconds(f32),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+struct PhotoEntry {
+    at: PhotoTime,
+    #[serde(default)]
+    label: String,
+    path: PathBuf,
+}
+
+// [[photo]] at = "3h", label = "Rabat 3", path = "rabat3.png"
+#[derive(Deserialize)]
+struct PhotoFile {
+    #[serde(default)]
+    photo: Vec<PhotoEntry>,
+}
+
+// Une photo réelle rattachée à un instant du planning
+pub struct PhotoSlot {
+    pub at: f32,
+    pub label: String,
+    image: Option<Handle<Image>>,
+}
+
+// Comparaison photo / coupe simulée (V) pour caler la simulation sur la
+// réalité : la photo la plus récente du planning face à la coupe du moment
+#[derive(Resource, Default)]
+pub struct PhotoComparison {
+    pub visible: bool,
+    pub slots: Vec<PhotoSlot>,
+    section: Handle<Image>,
+    since_refresh: f32,
+}
+
+impl PhotoComparison {
+    // Dernière photo déjà atteinte par la simulation, sinon la première
+    fn current_slot(&self, now: f32) -> Option<&PhotoSlot> {
+        self.slots
+            .iter()
+            .rev()
+            .find(|slot| slot.at <= now)
+            .or(self.slots.first())
+    }
+
+    fn text(&self, now: f32, crumb: &CrumbAnalysis) -> String {
+        let mut text = String::from("[V] Photo réelle / coupe simulée\n");
+        text.push_str(&format!(
+            "{} : {} alvéoles, ouverture {:.0} %\ntaille : alvéoles {}\n",
+            crumb.label(),
+            crumb.bubble_count(),
+            crumb.open_crumb_score * 100.0,
+            crumb.histogram_text()
+        ));
+        let Some(current) = self.current_slot(now) else {
+            text.push_str(&format!(
+                "Aucune photo : décrivez-les dans {} ou --photos <fichier>",
+                PHOTOS_FILE
+            ));
+            return text;
+        };
+        text.push_str(&format!(
+            "{} à {} (simulation à {}){}\n",
+            current.label,
+            format_duration(current.at.round()),
+            format_duration(now.round()),
+            if current.image.is_some() {
+                ""
+            } else {
+                ", photo illisible"
+            }
+        ));
+        for slot in &self.slots {
+            let marker = if std::ptr::eq(slot, current) {
+                '▶'
+            } else {
+                ' '
+            };
+            text.push_str(&format!(
+                "{} {} {}\n",
+                marker,
+                format_duration(slot.at.round()),
+                slot.label
+            ));
+        }
+        text
+    }
+}
+
+fn photos_path() -> Option<PathBuf> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.windows(2).find(|pair| pair[0] == "--photos") {
+        Some(pair) => Some(PathBuf::from(&pair[1])),
+        None => pain_config::config_dir().map(|dir| dir.join(PHOTOS_FILE)),
+    }
+}
+
+fn read_photo_file(path: &Path) -> Result<Vec<PhotoEntry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file: PhotoFile = toml::from_str(&text).map_err(|err| err.to_string())?;
+    Ok(file.photo)
+}
+
+// Le format est déduit de l'extension ; Bevy lit le PNG par défaut
+fn load_photo(path: &Path) -> Result<Image, String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+fn load_slots(images: &mut Assets<Image>) -> Vec<PhotoSlot> {
+    let Some(path) = photos_path().filter(|path| path.exists()) else {
+        return Vec::new();
+    };
+    let entries = match read_photo_file(&path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Photos {} ignorées: {}", path.display(), err);
+            return Vec::new();
+        }
+    };
+    // Les chemins relatifs partent du dossier du fichier
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut slots: Vec<PhotoSlot> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let at = match &entry.at {
+                PhotoTime::Seconds(seconds) => Some(*seconds),
+                PhotoTime::Text(text) => parse_duration(text),
+            };
+            let Some(at) = at else {
+                eprintln!("Photo {} ignorée: instant illisible", entry.path.display());
+                return None;
+            };
+            let path = base.join(&entry.path);
+            let image = match load_photo(&path) {
+                Ok(image) => Some(images.add(image)),
+                Err(err) => {
+                    eprintln!("Photo {} illisible: {}", path.display(), err);
+                    None
+                }
+            };
+            let label = if entry.label.is_empty() {
+                entry.path.display().to_string()
+            } else {
+                entry.label
+            };
+            Some(PhotoSlot { at, label, image })
+        })
+        .collect();
+    slots.sort_by(|a, b| a.at.total_cmp(&b.at));
+    slots
+}
+
+// Mie claire là où la pâte est dense, alvéoles sombres là où est le CO₂
+fn section_image(section: &CrossSection) -> Image {
+    let max_dough = section.max_dough().max(1) as f32;
+    let mut data = Vec::with_capacity(section.columns * section.rows * 4);
+    for (dough, gas) in section.dough.iter().zip(&section.gas) {
+        let color = if *gas > 0 {
+            [60, 38, 22]
+        } else if *dough > 0 {
+            let density = *dough as f32 / max_dough;
+            let shade = |light: f32, dense: f32| (light + (dense - light) * density) as u8;
+            [
+                shade(170.0, 245.0),
+                shade(140.0, 225.0),
+                shade(100.0, 180.0),
+            ]
+        } else {
+            [18, 18, 24]
+        };
+        data.extend_from_slice(&[color[0], color[1], color[2], 255]);
+    }
+    Image::new(
+        Extent3d {
+            width: section.columns as u32,
+            height: section.rows as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+#[derive(Component)]
+pub struct PhotoComparisonPanel;
+
+#[derive(Component)]
+pub struct PhotoComparisonText;
+
+#[derive(Component)]
+pub struct PhotoComparisonImage;
+
+pub fn setup_photo_comparison(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut comparison: ResMut<PhotoComparison>,
+    sim_resource: Res<SimulationResource>,
+) {
+    comparison.slots = load_slots(&mut images);
+    let section = sim_resource
+        .state
+        .cross_section(SECTION_COLUMNS, SECTION_THICKNESS);
+    comparison.section = images.add(section_image(&section));
+
+    let image_style = Style {
+        width: Val::Px(IMAGE_WIDTH),
+        margin: UiRect::all(Val::Px(4.0)),
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(60.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    display: Display::None,
+                    ..default()
+                },
+                background_color: Color::srgba(0.05, 0.04, 0.02, 0.9).into(),
+                ..default()
+            },
+            PhotoComparisonPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::srgb(1.0, 0.95, 0.85),
+                        ..default()
+                    },
+                ),
+                PhotoComparisonText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        ImageBundle {
+                            style: image_style.clone(),
+                            ..default()
+                        },
+                        PhotoComparisonImage,
+                    ));
+                    row.spawn(ImageBundle {
+                        style: image_style,
+                        image: UiImage::new(comparison.section.clone()),
+                        ..default()
+                    });
+                });
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_photo_comparison(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut comparison: ResMut<PhotoComparison>,
+    sim_resource: Res<SimulationResource>,
+    mut images: ResMut<Assets<Image>>,
+    mut panels: Query<&mut Style, (With<PhotoComparisonPanel>, Without<PhotoComparisonImage>)>,
+    mut texts: Query<&mut Text, With<PhotoComparisonText>>,
+    mut photos: Query<(&mut UiImage, &mut Style), With<PhotoComparisonImage>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyV) {
+        comparison.visible = !comparison.visible;
+        comparison.since_refresh = SECTION_REFRESH;
+    }
+    for mut style in panels.iter_mut() {
+        style.display = if comparison.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    if !comparison.visible {
+        return;
+    }
+
+    let state = &sim_resource.state;
+    let now = state.time_elapsed;
+    comparison.since_refresh += time.delta_seconds();
+    if comparison.since_refresh >= SECTION_REFRESH {
+        comparison.since_refresh = 0.0;
+        let section = state.cross_section(SECTION_COLUMNS, SECTION_THICKNESS);
+        images.insert(&comparison.section, section_image(&section));
+    }
+
+    let photo = comparison
+        .current_slot(now)
+        .and_then(|slot| slot.image.clone());
+    for (mut image, mut style) in photos.iter_mut() {
+        style.display = if photo.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if let Some(photo) = &photo {
+            if image.texture != *photo {
+                image.texture = photo.clone();
+            }
+        }
+    }
+    let crumb = state.crumb_analysis();
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = comparison.text(now, &crumb);
+    }
+}
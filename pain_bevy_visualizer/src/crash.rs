@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use pain_core::snapshot::{decode_state, encode_state};
+use pain_core::{EmergencySnapshot, SimulationEvent};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+// Dernier état sain (encodé) et son journal, lisibles depuis le hook de panique
+static LAST_GOOD_STATE: Mutex<Option<(Vec<u8>, String)>> = Mutex::new(None);
+
+const CAPTURE_INTERVAL: Duration = Duration::from_secs(10);
+
+// Instantané de secours trouvé au démarrage, en attente d'une décision de l'utilisateur
+#[derive(Resource, Default)]
+pub struct RecoveryOffer(pub Option<EmergencySnapshot>);
+
+#[derive(Resource, Default)]
+pub struct CrashGuard {
+    last_capture: Option<Instant>,
+}
+
+pub fn recovery_dir() -> Option<PathBuf> {
+    pain_config::config_dir().map(|dir| dir.join("recovery"))
+}
+
+pub fn load_recovery_offer() -> RecoveryOffer {
+    let Some(dir) = recovery_dir() else {
+        return RecoveryOffer::default();
+    };
+    match EmergencySnapshot::read_from(&dir) {
+        Ok(snapshot) => RecoveryOffer(snapshot),
+        Err(err) => {
+            eprintln!("Instantané de secours illisible: {}", err);
+            // Laissé par une autre version ou tronqué : il ne sera jamais repris
+            if matches!(err.kind(), ErrorKind::InvalidData | ErrorKind::UnexpectedEof) {
+                if let Err(err) = EmergencySnapshot::discard(&dir) {
+                    eprintln!("Suppression de l'instantané impossible: {}", err);
+                }
+            }
+            RecoveryOffer::default()
+        }
+    }
+}
+
+// Écrit le dernier état sain sur disque ; utilisable depuis un hook de panique
+fn write_emergency_snapshot(reason: &str) {
+    let Some(dir) = recovery_dir() else {
+        return;
+    };
+    let Ok(last_good) = LAST_GOOD_STATE.try_lock() else {
+        return;
+    };
+    if let Some((state_bytes, event_log)) = last_good.as_ref() {
+        let snapshot = EmergencySnapshot {
+            reason: reason.to_string(),
+            state_bytes: state_bytes.clone(),
+            event_log: event_log.clone(),
+        };
+        match snapshot.write_to(&dir) {
+            Ok(()) => eprintln!("Instantané de secours écrit dans {}", dir.display()),
+            Err(err) => eprintln!("Échec de l'instantané de secours: {}", err),
+        }
+    }
+}
+
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        write_emergency_snapshot(&format!("panic: {}", info));
+        default_hook(info);
+    }));
+}
+
+// Système : capture périodique de l'état sain et détection des explosions numériques
+pub fn guard_simulation(
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    mut guard: ResMut<CrashGuard>,
+) {
+    if let Some(molecule_id) = sim_resource.state.first_non_finite() {
+        // Le calcul en cours écraserait la pâte restaurée : on réessaie à
+        // l'image suivante
+        if !worker.settle(&mut sim_resource.state) {
+            return;
+        }
+        write_emergency_snapshot(&format!("NaN detected on molecule {}", molecule_id));
+
+        // On repart du dernier état sain pour ne pas perdre la session en cours
+        let restored = LAST_GOOD_STATE
+            .lock()
+            .ok()
+            .and_then(|last_good| last_good.as_ref().map(|(bytes, _)| decode_state(bytes)));
+        if let Some(Ok(state)) = restored {
+            sim_resource.state = state;
+        }
+        let time = sim_resource.state.time_elapsed;
+        sim_resource
+            .state
+            .events
+            .push(time, SimulationEvent::InstabilityDetected { molecule_id });
+        return;
+    }
+
+    if guard
+        .last_capture
+        .is_none_or(|instant| instant.elapsed() >= CAPTURE_INTERVAL)
+    {
+        match encode_state(&sim_resource.state) {
+            Ok(bytes) => {
+                if let Ok(mut last_good) = LAST_GOOD_STATE.lock() {
+                    *last_good = Some((bytes, sim_resource.state.events.to_text()));
+                }
+            }
+            Err(err) => eprintln!("Capture de l'état impossible: {}", err),
+        }
+        guard.last_capture = Some(Instant::now());
+    }
+}
+
+// F10 reprend depuis l'instantané de secours, F11 l'ignore
+pub fn handle_recovery_offer(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut offer: ResMut<RecoveryOffer>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+) {
+    let Some(snapshot) = offer.0.as_ref() else {
+        return;
+    };
+    let resume = keyboard_input.just_pressed(KeyCode::F10);
+    if !resume && !keyboard_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+    if resume {
+        // Le calcul en cours écraserait la pâte reprise
+        if !worker.settle(&mut sim_resource.state) {
+            eprintln!("Calcul en cours, reprise impossible pour l'instant");
+            return;
+        }
+        match snapshot.restore() {
+            Ok(state) => {
+                sim_resource.state = state;
+                println!("Simulation reprise depuis l'instantané de secours");
+            }
+            Err(err) => eprintln!("Reprise impossible: {}", err),
+        }
+    }
+    if let Some(dir) = recovery_dir() {
+        if let Err(err) = EmergencySnapshot::discard(&dir) {
+            eprintln!("Suppression de l'instantané impossible: {}", err);
+        }
+    }
+    offer.0 = None;
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use pain_core::{SimStats, SimulationState};
+
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+// Fenêtre sur laquelle la cadence des pas est moyennée
+const TICK_RATE_WINDOW: f32 = 1.0;
+
+// Panneau de débogage (F3) : santé de la simulation en tête, pour qu'une
+// seule capture d'écran suffise à un rapport de bug, puis registres de masse
+// et de réactions
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    tick_rate: f32,
+    window: Option<(f32, u64)>, // Début de la fenêtre de mesure : instant, pas comptés
+}
+
+impl DebugOverlay {
+    // Pas par seconde réelle, mis à jour une fois par fenêtre
+    fn measure(&mut self, now: f32, ticks: u64) {
+        match self.window {
+            Some((start, counted)) if now - start >= TICK_RATE_WINDOW => {
+                self.tick_rate = ticks.saturating_sub(counted) as f32 / (now - start);
+                self.window = Some((now, ticks));
+            }
+            Some(_) => {}
+            None => self.window = Some((now, ticks)),
+        }
+    }
+}
+
+fn health_text(state: &SimulationState, stats: &SimStats, tick_rate: f32, entities: usize) -> String {
+    let tick = &state.last_tick;
+    let slowest = tick
+        .slowest_stage()
+        .map(|(stage, time)| format!("{} {:.2} ms", stage.label(), time.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        "[F3] Santé de la simulation\n\n\
+         Cadence: {:.0} pas/s  physique: {:.2} ms/pas (plus lente : {})\n\
+         Molécules: {} ({} emplacements)  liaisons: {}  cellules: {}  entités: {}\n\
+         Mémoire de l'état: {:.1} Mio  événements: {}\n\
+         Résidu du solveur: {:.4}{}\n\
+         Graine: {}  t={:.2} s\n\
+         Empreinte de l'état: {:016x}\n\n",
+        tick_rate,
+        tick.wall_time.as_secs_f64() * 1000.0,
+        slowest,
+        stats.molecules,
+        stats.molecule_slots,
+        stats.bonds,
+        stats.grid_cells,
+        entities,
+        stats.memory_mib(),
+        stats.events,
+        tick.solver_residual,
+        if tick.solver_converged { "" } else { " (non convergé)" },
+        stats.seed,
+        stats.time,
+        state.state_hash()
+    )
+}
+
+#[derive(Component)]
+pub struct DebugOverlayText;
+
+pub fn setup_debug_overlay(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::srgb(0.6, 1.0, 0.6),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        },
+        DebugOverlayText,
+    ));
+}
+
+fn overlay_text(state: &SimulationState) -> String {
+    let mass = &state.ledger;
+    let reactions = &state.reactions;
+    let tick = &state.last_tick;
+    let check = match state.reaction_violation() {
+        Some(violation) => format!("!! {}", violation),
+        None => "OK".to_string(),
+    };
+    format!(
+        "Registres de conservation\n\n\
+         Masse initiale: {:.1}  ajoutée: {:.1}\n\
+         Réactions (net): {:+.1}\n\
+         Évaporée: {:.1}  CO₂ perdu: {:.1}  éthanol perdu: {:.1}\n\
+         Attendue: {:.1}  mesurée: {:.1}  écart: {:.2e}\n\n\
+         Sucres fermentés: {} ({:.1} → {:.1} de CO₂ + éthanol)\n\
+         CO₂: {} ({} échappé)  éthanol: {} ({} évaporé)\n\
+         Amidon hydrolysé: {} ({:.1} → {:.1} de sucre)\n\
+         Liaisons formées: {}  rompues: {}\n\
+         Thiols consommés: {}  libérés: {}\n\
+         Vérification: {}\n\
+         Quarantaine: {} NaN retirées, {} remises dans la boîte, {} échappées retirées ({:.1} de masse)\n\n\
+         Dernier pas: {} réactions, molécules +{} / -{}\n\
+         Vitesse max: {:.2}  résidu: {:.3}  durée: {:.2} ms",
+        mass.initial_mass,
+        mass.added_mass,
+        mass.reaction_delta,
+        mass.water_evaporated,
+        mass.co2_lost,
+        mass.ethanol_lost,
+        mass.expected_mass(),
+        state.total_mass(),
+        state.conservation_error(),
+        reactions.sugars_fermented,
+        reactions.sugar_mass_consumed,
+        reactions.product_mass,
+        reactions.co2_produced,
+        mass.co2_escaped,
+        reactions.ethanol_produced,
+        mass.ethanol_escaped,
+        reactions.starch_hydrolyzed,
+        reactions.starch_mass_consumed,
+        reactions.sugar_mass_released,
+        reactions.bonds_formed,
+        reactions.bonds_broken,
+        reactions.thiols_consumed,
+        reactions.thiols_freed,
+        check,
+        state.quarantine.non_finite_removed,
+        state.quarantine.escaped_reprojected,
+        state.quarantine.escaped_removed,
+        mass.quarantined_mass,
+        tick.reactions_fired(),
+        tick.molecules_added,
+        tick.molecules_removed,
+        tick.max_velocity,
+        tick.solver_residual,
+        tick.wall_time.as_secs_f64() * 1000.0
+    )
+}
+
+pub fn update_debug_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    worker: Res<SimulationWorker>,
+    mut overlay: ResMut<DebugOverlay>,
+    sim_resource: Res<SimulationResource>,
+    entities: Query<Entity>,
+    mut query: Query<(&mut Text, &mut Style), With<DebugOverlayText>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.visible = !overlay.visible;
+    }
+    overlay.measure(time.elapsed_seconds(), worker.ticks());
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if overlay.visible { Display::Flex } else { Display::None };
+        if overlay.visible {
+            let state = &sim_resource.state;
+            // L'empreinte parcourt toute la pâte : calculée seulement panneau ouvert
+            let stats = state.stats();
+            text.sections[0].value = health_text(state, &stats, overlay.tick_rate, entities.iter().len())
+                + &overlay_text(state);
+        }
+    }
+}
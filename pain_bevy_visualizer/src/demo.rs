@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+use pain_core::{SimAction, SimulationState};
+
+use crate::preferences::PreferencesResource;
+use crate::time_scale::TimeScale;
+use crate::worker::SimulationWorker;
+use crate::{new_simulation, OrbitCamera, SimulationResource};
+
+// Pause après la dernière étape avant de relancer la démonstration
+const LOOP_PAUSE: f32 = 20.0;
+// Rotation lente et continue de la caméra (rad/s)
+const IDLE_ORBIT_SPEED: f32 = 0.05;
+
+// Position de caméra visée par une étape
+#[derive(Clone, Copy)]
+pub struct CameraKey {
+    pub radius: f32,
+    pub azimuth: f32,
+    pub elevation: f32,
+}
+
+// Étape du scénario, déclenchée en secondes réelles depuis le début de la boucle
+pub struct DemoStep {
+    pub at: f32,
+    pub caption: &'static str,
+    pub action: Option<SimAction>,
+    pub time_scale: Option<f32>,
+    pub camera: Option<CameraKey>,
+}
+
+// Mode démonstration pour les écrans d'exposition : scénario en boucle sans
+// intervention, activé par --demo.
+#[derive(Resource)]
+pub struct DemoMode {
+    pub enabled: bool,
+    pub steps: Vec<DemoStep>,
+    pub next_step: usize,
+    pub elapsed: f32,
+    pub caption: String,
+    // Transition caméra en cours : départ, arrivée, début, durée
+    camera_move: Option<(CameraKey, CameraKey, f32, f32)>,
+}
+
+#[derive(Component)]
+pub struct DemoCaption;
+
+impl Default for DemoMode {
+    fn default() -> Self {
+        DemoMode {
+            enabled: std::env::args().any(|arg| arg == "--demo"),
+            steps: Vec::new(),
+            next_step: 0,
+            elapsed: 0.0,
+            caption: String::new(),
+            camera_move: None,
+        }
+    }
+}
+
+fn camera_key(radius: f32, azimuth_deg: f32, elevation_deg: f32) -> CameraKey {
+    CameraKey {
+        radius,
+        azimuth: azimuth_deg.to_radians(),
+        elevation: elevation_deg.to_radians(),
+    }
+}
+
+// Scénario par défaut : autolyse, sel, levain, rabats puis fermentation accélérée
+pub fn default_demo_script(state: &SimulationState) -> Vec<DemoStep> {
+    let fold = SimAction::centered_fold(state);
+    vec![
+        DemoStep {
+            at: 0.0,
+            caption: "Farine + eau : les protéines s'hydratent (autolyse)",
+            action: None,
+            time_scale: Some(1.0),
+            camera: Some(camera_key(1400.0, 45.0, 30.0)),
+        },
+        DemoStep {
+            at: 12.0,
+            caption: "Les gluténines forment des ponts disulfure",
+            action: None,
+            time_scale: Some(8.0),
+            camera: Some(camera_key(700.0, 90.0, 15.0)),
+        },
+        DemoStep {
+            at: 25.0,
+            caption: "Ajout du sel : le réseau se resserre",
+            action: Some(SimAction::AddSalt),
+            time_scale: Some(4.0),
+            camera: Some(camera_key(900.0, 160.0, 35.0)),
+        },
+        DemoStep {
+            at: 38.0,
+            caption: "Ajout du levain : les levures consomment les sucres",
+            action: Some(SimAction::AddYeast),
+            time_scale: Some(16.0),
+            camera: Some(camera_key(600.0, 220.0, 10.0)),
+        },
+        DemoStep {
+            at: 52.0,
+            caption: "Rabat : on étire et on replie la pâte",
+            action: Some(fold.clone()),
+            time_scale: Some(2.0),
+            camera: Some(camera_key(1000.0, 270.0, 45.0)),
+        },
+        DemoStep {
+            at: 62.0,
+            caption: "Second rabat",
+            action: Some(fold),
+            time_scale: Some(2.0),
+            camera: None,
+        },
+        DemoStep {
+            at: 72.0,
+            caption: "Fermentation : le CO₂ gonfle la pâte",
+            action: None,
+            time_scale: Some(64.0),
+            camera: Some(camera_key(1500.0, 360.0, 25.0)),
+        },
+    ]
+}
+
+fn lerp_camera(from: CameraKey, to: CameraKey, t: f32) -> CameraKey {
+    // Lissage en début et fin de mouvement
+    let t = t * t * (3.0 - 2.0 * t);
+    CameraKey {
+        radius: from.radius + (to.radius - from.radius) * t,
+        azimuth: from.azimuth + (to.azimuth - from.azimuth) * t,
+        elevation: from.elevation + (to.elevation - from.elevation) * t,
+    }
+}
+
+pub fn setup_demo(
+    mut commands: Commands,
+    mut demo: ResMut<DemoMode>,
+    sim_resource: Res<SimulationResource>,
+) {
+    if !demo.enabled {
+        return;
+    }
+    demo.steps = default_demo_script(&sim_resource.state);
+    println!("Mode démonstration actif");
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 34.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                left: Val::Px(40.0),
+                ..default()
+            },
+            ..default()
+        },
+        DemoCaption,
+    ));
+}
+
+// Système : joue le scénario, anime la caméra et relance la boucle
+#[allow(clippy::too_many_arguments)]
+pub fn run_demo(
+    mut demo: ResMut<DemoMode>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    mut time_scale: ResMut<TimeScale>,
+    prefs: Res<PreferencesResource>,
+    real_time: Res<Time<Real>>,
+    mut camera_query: Query<&mut OrbitCamera>,
+    mut caption_query: Query<&mut Text, With<DemoCaption>>,
+) {
+    if !demo.enabled {
+        return;
+    }
+    let dt = real_time.delta_seconds();
+    demo.elapsed += dt;
+    let elapsed = demo.elapsed;
+
+    // Fin du scénario : on repart d'une pâte neuve
+    let script_end = demo.steps.last().map(|step| step.at).unwrap_or(0.0);
+    if demo.next_step >= demo.steps.len() && elapsed > script_end + LOOP_PAUSE {
+        // Le calcul en cours écraserait la pâte neuve : ce sera l'image suivante
+        if !worker.settle(&mut sim_resource.state) {
+            return;
+        }
+        sim_resource.state = new_simulation(&prefs.0);
+        demo.next_step = 0;
+        demo.elapsed = 0.0;
+        return;
+    }
+
+    let Ok(mut orbit) = camera_query.get_single_mut() else {
+        return;
+    };
+    let current = CameraKey {
+        radius: orbit.radius,
+        azimuth: orbit.azimuth,
+        elevation: orbit.elevation,
+    };
+
+    while demo.next_step < demo.steps.len() && demo.steps[demo.next_step].at <= elapsed {
+        let step = &demo.steps[demo.next_step];
+        if let Some(action) = &step.action {
+            sim_resource.state.apply_action(action);
+        }
+        if let Some(scale) = step.time_scale {
+            time_scale.0 = scale;
+        }
+        let camera = step.camera;
+        demo.caption = step.caption.to_string();
+        if let Some(target) = camera {
+            // On part de l'angle équivalent le plus proche pour ne pas refaire des tours
+            let mut from = current;
+            let turn = std::f32::consts::TAU;
+            from.azimuth = target.azimuth + (from.azimuth - target.azimuth + turn / 2.0).rem_euclid(turn) - turn / 2.0;
+            demo.camera_move = Some((from, target, elapsed, 6.0));
+        }
+        demo.next_step += 1;
+    }
+
+    match demo.camera_move {
+        Some((from, to, start, duration)) => {
+            let t = ((elapsed - start) / duration).clamp(0.0, 1.0);
+            let key = lerp_camera(from, to, t);
+            orbit.radius = key.radius;
+            orbit.azimuth = key.azimuth;
+            orbit.elevation = key.elevation;
+            if t >= 1.0 {
+                demo.camera_move = None;
+            }
+        }
+        None => orbit.azimuth += IDLE_ORBIT_SPEED * dt,
+    }
+
+    for mut text in caption_query.iter_mut() {
+        if text.sections[0].value != demo.caption {
+            text.sections[0].value = demo.caption.clone();
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use nalgebra::Vector3;
+use pain_core::DoughPhase;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::time_scale::TimeScale;
+use crate::{world_up, OrbitCamera, SimulationResource};
+
+// Les mains glissent sur le plan de travail, la paume à cette hauteur
+const HAND_HEIGHT: f32 = 40.0;
+
+// Clic droit maintenu pendant le façonnage : la main droite suit la souris
+// sur le plan de travail (Maj : la main gauche), la paume tournée vers le
+// centre de la pâte. Relâcher le bouton lève les mains.
+pub fn drive_hands(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<OrbitCamera>>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut sim_resource: ResMut<SimulationResource>,
+) {
+    if mouse_button_input.just_released(MouseButton::Right) {
+        sim_resource.state.lift_hands();
+        return;
+    }
+    if !mouse_button_input.pressed(MouseButton::Right) || sim_resource.state.phase != DoughPhase::Shape {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let bench = sim_resource.state.tools.surface.map_or(0.0, |surface| surface.height);
+    let plane_y = bench + HAND_HEIGHT;
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    let Some(distance) = ray.intersect_plane(world_up() * plane_y, InfinitePlane3d::new(world_up())) else {
+        return;
+    };
+    let point = ray.get_point(distance);
+    let state = &mut sim_resource.state;
+    let center = Vector3::new(
+        point.x.clamp(0.0, state.width),
+        plane_y,
+        point.z.clamp(0.0, state.depth),
+    );
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let index = if shift { 1 } else { 0 };
+    let mut facing = state.center() - center;
+    facing.y = 0.0;
+    // Vitesse en temps de simulation, pour que la pâte soit entraînée au bon rythme
+    state.drag_hand(index, center, time.delta_seconds() * time_scale.0);
+    if let Some(facing) = facing.try_normalize(f32::EPSILON) {
+        state.tools.hands[index].facing = facing;
+    }
+}
+
+// Plan de travail en grille claire, mains en sphères avec une flèche vers
+// leur côté bombé
+pub fn draw_tools(mut gizmos: Gizmos, sim_resource: Res<SimulationResource>) {
+    let state = &sim_resource.state;
+    if let Some(surface) = state.tools.surface {
+        let position = Vec3::new(state.width / 2.0, surface.height, state.depth / 2.0);
+        gizmos.rect(
+            position,
+            Quat::from_rotation_x(FRAC_PI_2),
+            Vec2::new(state.width, state.depth),
+            Color::srgba(0.8, 0.7, 0.5, 0.6),
+        );
+    }
+    for hand in state.tools.hands.iter().filter(|hand| hand.active) {
+        let center = Vec3::new(hand.center.x, hand.center.y, hand.center.z);
+        let facing = Vec3::new(hand.facing.x, hand.facing.y, hand.facing.z);
+        let color = Color::srgb(0.95, 0.75, 0.6);
+        gizmos.sphere(center, Quat::IDENTITY, hand.radius, color);
+        gizmos.arrow(center, center + facing * hand.radius * 1.5, color);
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use pain_core::{InputPlayer, InputRecorder, InputReplay, SimAction, SimulationState};
+use std::path::PathBuf;
+
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+// Enregistrement des gestes (Maj+F7 pour démarrer / arrêter) : la pâte de
+// départ, la durée de chaque image et chaque geste (sel, levure, rabats,
+// environnement...) à son temps simulé. Rejoué avec --play-inputs, il refait
+// la même pâte au bit près, de quoi partager une cuisson. Les mains du
+// façonnage ne sont pas enregistrées.
+#[derive(Resource, Default)]
+pub struct InputRecording {
+    recorder: Option<InputRecorder>,
+}
+
+impl InputRecording {
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    // Fait le geste, noté s'il y a un enregistrement. La pâte doit être la
+    // vraie (SimulationWorker::settle) : un geste fait sur la copie affichée
+    // serait perdu et manquerait à la relecture.
+    pub fn apply(&mut self, state: &mut SimulationState, action: SimAction) {
+        match self.recorder.as_mut() {
+            Some(recorder) => recorder.apply(state, action),
+            None => state.apply_action(&action),
+        }
+    }
+
+    // Une image confiée au fil de calcul
+    pub fn frame(&mut self, duration: f32, max_tick: f32) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.frame(duration, max_tick);
+        }
+    }
+
+    // Termine l'enregistrement sur `state`, la vraie pâte, et l'écrit
+    pub fn stop(&mut self, state: &SimulationState) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let replay = recorder.finish(state);
+        let Some(path) = inputs_path(replay.seed) else {
+            eprintln!("Aucun dossier de configuration pour les gestes");
+            return;
+        };
+        match replay.save_to_file(&path) {
+            Ok(()) => println!(
+                "{} gestes sur {:.0} s enregistrés dans {}",
+                replay.action_count(),
+                replay.duration(),
+                path.display()
+            ),
+            Err(err) => eprintln!("Enregistrement des gestes impossible: {}", err),
+        }
+    }
+}
+
+fn inputs_path(seed: u64) -> Option<PathBuf> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    pain_config::config_dir()
+        .map(|dir| dir.join("gestes").join(format!("gestes-{}-{}.hopin", seed, stamp)))
+}
+
+// Relecture des gestes avec --play-inputs <fichier> : la pâte repart de celle
+// de l'enregistrement, image par image, puis la main revient au boulanger
+#[derive(Resource)]
+pub struct InputPlayback {
+    player: Option<InputPlayer>,
+}
+
+impl Default for InputPlayback {
+    fn default() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let player = args
+            .windows(2)
+            .find(|pair| pair[0] == "--play-inputs")
+            .and_then(|pair| match InputReplay::load_from_file(&PathBuf::from(&pair[1])) {
+                Ok(replay) => Some(InputPlayer::new(replay)),
+                Err(err) => {
+                    eprintln!("Relecture impossible de {}: {}", pair[1], err);
+                    None
+                }
+            });
+        InputPlayback { player }
+    }
+}
+
+impl InputPlayback {
+    pub fn is_active(&self) -> bool {
+        self.player.is_some()
+    }
+
+    // Fait les gestes dus et donne la prochaine image (durée, pas maximal) ;
+    // None une fois la relecture finie, avec le verdict
+    pub fn next_frame(&mut self, state: &mut SimulationState) -> Option<(f32, f32)> {
+        let player = self.player.as_mut()?;
+        if let Some(frame) = player.next_frame(state) {
+            return Some(frame);
+        }
+        if player.matches_end(state) {
+            println!("Relecture terminée : la pâte est celle de l'enregistrement");
+        } else if let Some(time) = player.diverged_at() {
+            println!("Relecture terminée : la pâte a divergé à t={:.0} s", time);
+        } else {
+            println!("Relecture terminée : la pâte finale diffère de l'enregistrement");
+        }
+        self.player = None;
+        None
+    }
+}
+
+// La pâte de départ de l'enregistrement remplace la pâte neuve
+pub fn start_input_playback(mut playback: ResMut<InputPlayback>, mut sim_resource: ResMut<SimulationResource>) {
+    let Some(player) = &playback.player else {
+        return;
+    };
+    let replay = player.replay();
+    match replay.start_state() {
+        Ok(state) => {
+            println!(
+                "Relecture des gestes : {} (graine {}), {} gestes",
+                replay.recipe_name,
+                replay.seed,
+                replay.action_count()
+            );
+            sim_resource.state = state;
+        }
+        Err(err) => {
+            eprintln!("Pâte de départ illisible: {}", err);
+            playback.player = None;
+        }
+    }
+}
+
+// Maj+F7 démarre l'enregistrement des gestes, Maj+F7 à nouveau l'arrête
+pub fn toggle_input_recording(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut inputs: ResMut<InputRecording>,
+    mut worker: ResMut<SimulationWorker>,
+    mut sim_resource: ResMut<SimulationResource>,
+    playback: Res<InputPlayback>,
+) {
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if !shift || !keyboard_input.just_pressed(KeyCode::F7) || playback.is_active() {
+        return;
+    }
+    // Le départ comme la fin doivent être la vraie pâte
+    if !worker.settle(&mut sim_resource.state) {
+        eprintln!("Calcul en cours, enregistrement des gestes impossible pour l'instant");
+        return;
+    }
+    if inputs.is_recording() {
+        inputs.stop(&sim_resource.state);
+        return;
+    }
+    match InputRecorder::start(&sim_resource.state) {
+        Ok(recorder) => inputs.recorder = Some(recorder),
+        Err(err) => eprintln!("Enregistrement des gestes impossible: {}", err),
+    }
+}
+
+// Ligne du panneau : gestes enregistrés ou rejoués
+pub fn inputs_line(inputs: &InputRecording, playback: &InputPlayback) -> String {
+    if let Some(player) = &playback.player {
+        let replay = player.replay();
+        return format!(
+            "Relecture des gestes: {} (graine {}), jusqu'à t={:.0} s\n",
+            replay.recipe_name, replay.seed, replay.end_time
+        );
+    }
+    match &inputs.recorder {
+        Some(recorder) => format!(
+            "● Gestes: {} notés sur {} images [Maj+F7: arrêter]\n",
+            recorder.actions_recorded(),
+            recorder.frames_recorded()
+        ),
+        None => String::new(),
+    }
+}
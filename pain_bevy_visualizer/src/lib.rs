@@ -1,5 +1,58 @@
+mod airflow;
+mod bake;
+mod bond_strength;
+mod burp;
+mod challenge;
+mod cobaking;
+mod collapse;
+mod companion;
+mod comparison;
+mod crash;
+mod debug_overlay;
+mod demo;
+mod hands;
+mod inputs;
+mod notebook;
+mod observation;
+mod preferences;
+mod replay;
+mod save;
+mod scene;
+mod schedule_editor;
+mod sets;
+mod starter;
+mod style;
 mod time_scale;
-use time_scale::TimeScale;
+mod worker;
+use airflow::{apply_draft, cycle_draft, draft_line, draw_airflow_arrows};
+use bake::bake_line;
+use bond_strength::{bond_strength_line, sample_bond_strength, toggle_bond_tint, BondStrengthView};
+use burp::{burp_line, draw_burp_puffs, spawn_burp_puffs, toggle_burp_sound, BurpPuffs};
+use challenge::{challenge_input, setup_challenge_dialog, start_challenge_from_args, update_challenge_dialog, ChallengeDialog};
+use cobaking::{draw_cursors, run_cobaking, send_guest_actions, setup_cobaking_log, start_cobaking, update_cobaking_log, CoBaking};
+use collapse::{setup_collapse_banner, update_collapse_banner};
+use companion::{run_companion, setup_companion, CompanionMode};
+use comparison::{setup_photo_comparison, update_photo_comparison, PhotoComparison};
+use debug_overlay::{setup_debug_overlay, update_debug_overlay, DebugOverlay};
+use demo::{run_demo, setup_demo, DemoMode};
+use crash::{guard_simulation, handle_recovery_offer, CrashGuard, RecoveryOffer};
+use hands::{draw_tools, drive_hands};
+use inputs::{inputs_line, start_input_playback, toggle_input_recording, InputPlayback, InputRecording};
+use notebook::{notebook_line, setup_note_prompt, type_note, update_note_prompt, NoteEntry};
+use observation::{apply_observation_boxes, edit_observation_boxes, observation_lines, sync_observation_boxes};
+use preferences::{
+    binding_just_pressed, camera_bookmarks, save_preferences_on_change,
+    save_preferences_on_exit, PreferencesResource,
+};
+use replay::{record_trajectory, replay_line, run_replay, ReplayMode, TrajectoryRecording};
+use save::{fast_forward_hour, quick_save_load, rewind_checkpoint};
+use scene::{pull_scene_changes, update_bonds, update_particles, SceneSync};
+use sets::{configure_frame_sets, FrameSet, LoadingAssets};
+use schedule_editor::{load_schedule, schedule_editor_ui, ScheduleEditor};
+use starter::{setup_starter_dialog, update_starter_dialog, StarterDialog};
+use style::{reload_visual_style, LegendText, StyleAssets, VisualStyle, VisualStyleLoader};
+use time_scale::{time_scale_line, EffectiveScale, SimulationPause, TimeScale};
+use worker::{setup_lag_indicator, update_lag_indicator, SimulationWorker};
 use bevy::prelude::*;
 use bevy::asset::AssetServer;
 use bevy::ui::*;
@@ -14,23 +67,31 @@ struct OrbitCamera {
     pub azimuth: f32,
     pub elevation: f32,
 }
+
+// Le haut de la simulation (pain_core::UP), le même que celui de la
+// visionneuse web
+pub(crate) fn world_up() -> Vec3 {
+    Vec3::new(pain_core::UP.x, pain_core::UP.y, pain_core::UP.z)
+}
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy_egui::EguiPlugin;
 use avian3d::prelude::*;
-use pain_core::{MoleculeType, SimulationState};
+use pain_config::PanelSide;
+use pain_core::{AmbientTemperature, BudgetSettings, DoughPhase, EnvironmentPreset, KneadAction, MoleculeId, MoleculeType, RecipePreset, SimulationConfig, Perturbation, Recipe, SimAction, SimulationState, Species};
 use bevy::ecs::world::FromWorld;
 
 // Component pour représenter une particule de la simulation
 #[derive(Component)]
 pub struct MoleculeParticle {
-    pub id: u64,
+    pub id: MoleculeId,
     pub mol_type: MoleculeType,
 }
 
 // Component pour représenter une liaison entre molécules
 #[derive(Component)]
 pub struct GlutenBond {
-    pub molecule_a_id: u64,
-    pub molecule_b_id: u64,
+    pub molecule_a_id: MoleculeId,
+    pub molecule_b_id: MoleculeId,
 }
 
 // Resource pour contenir l'état de la simulation
@@ -40,52 +101,248 @@ pub struct SimulationResource {
 }
 
 impl FromWorld for SimulationResource {
-    fn from_world(_world: &mut World) -> Self {
-        let mut sim_state = SimulationState::new(1000.0, 720.0, 1000.0);
-        sim_state.initialize_classic_recipe();
-        SimulationResource { state: sim_state }
+    fn from_world(world: &mut World) -> Self {
+        let prefs = world
+            .get_resource::<PreferencesResource>()
+            .map(|prefs| prefs.0.clone())
+            .unwrap_or_default();
+        SimulationResource { state: new_simulation(&prefs) }
+    }
+}
+
+// Graine passée avec --seed <n> pour rejouer exactement une simulation
+fn seed_from_args() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .find(|pair| pair[0] == "--seed")
+        .and_then(|pair| pair[1].parse().ok())
+}
+
+// Constantes physiques : --config <fichier> ou simulation.toml du dossier de configuration
+fn simulation_config() -> SimulationConfig {
+    let args: Vec<String> = std::env::args().collect();
+    let path = match args.windows(2).find(|pair| pair[0] == "--config") {
+        Some(pair) => std::path::PathBuf::from(&pair[1]),
+        None => match pain_config::config_dir() {
+            Some(dir) if dir.join(SIMULATION_CONFIG_FILE).exists() => dir.join(SIMULATION_CONFIG_FILE),
+            _ => return SimulationConfig::default(),
+        },
+    };
+    match SimulationConfig::load_from(&path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Configuration {} ignorée: {}", path.display(), err);
+            SimulationConfig::default()
+        }
+    }
+}
+
+// Recette choisie dans les préférences (classique si inconnue)
+fn recipe_preset(prefs: &pain_config::Preferences) -> RecipePreset {
+    RecipePreset::from_id(&prefs.last_recipe).unwrap_or(RecipePreset::Classic)
+}
+
+// Fichier de la recette choisie dans le dossier de configuration, écrit par
+// l'éditeur de planning : ses valeurs remplacent celles du préréglage et ses
+// étapes [[step]] sont son planning
+fn recipe_path(prefs: &pain_config::Preferences) -> Option<std::path::PathBuf> {
+    let file = format!("{}.toml", recipe_preset(prefs).id());
+    pain_config::config_dir().map(|dir| dir.join(RECIPES_DIR).join(file))
+}
+
+// Recette de la pâte neuve : son fichier s'il existe, sinon le préréglage
+fn recipe(prefs: &pain_config::Preferences) -> Recipe {
+    let preset = recipe_preset(prefs).recipe();
+    let Some(path) = recipe_path(prefs).filter(|path| path.exists()) else {
+        return preset;
+    };
+    match Recipe::load_from(&path) {
+        Ok(recipe) => recipe,
+        Err(err) => {
+            eprintln!("Recette {} ignorée: {}", path.display(), err);
+            preset
+        }
     }
 }
 
+// Nouvelle pâte : recette des préférences et zones d'observation des préférences
+pub fn new_simulation(prefs: &pain_config::Preferences) -> SimulationState {
+    let mut state = match seed_from_args() {
+        Some(seed) => SimulationState::new_with_seed(1000.0, 720.0, 1000.0, seed),
+        None => SimulationState::new(1000.0, 720.0, 1000.0),
+    };
+    state.config = simulation_config();
+    // Pas fixes de MAX_TICK quelle que soit la cadence d'affichage ; les
+    // particules sont interpolées entre deux pas
+    state.timestep.enabled = true;
+    state.timestep.rate = 1.0 / MAX_TICK;
+    // Pâte plus ou moins dense selon la machine
+    state.budget = BudgetSettings {
+        resolution: prefs.resolution,
+        max_molecules: prefs.max_molecules,
+    };
+    state.initialize_recipe(recipe(prefs));
+    load_schedule(&mut state, prefs);
+    apply_observation_boxes(&mut state, &prefs.observation_boxes);
+    if let Some(room) = &prefs.ambient_room {
+        apply_ambient_room(&mut state, room);
+        apply_draft(&mut state, room.draft_speed, room.draft_direction);
+    }
+    println!("Graine de la simulation: {}", state.seed);
+    state
+}
+
+// Température de la pièce au fil de la journée (la cuisine refroidit la nuit)
+fn apply_ambient_room(state: &mut SimulationState, room: &pain_config::AmbientRoom) {
+    state.environment.start_hour = room.start_hour;
+    state.environment.ambient = if room.schedule.is_empty() {
+        AmbientTemperature::Daily {
+            mean: room.mean,
+            amplitude: room.amplitude,
+            coldest_hour: room.coldest_hour,
+        }
+    } else {
+        AmbientTemperature::Schedule(room.schedule.iter().map(|&[hour, temp]| (hour, temp)).collect())
+    };
+}
+
+const SIMULATION_CONFIG_FILE: &str = "simulation.toml";
+const RECIPES_DIR: &str = "recipes";
+
+// Dossier des polices et des thèmes, pour les binaires lancés depuis une autre crate
+pub const ASSET_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+
+// Température de la pièce quand la pâte sort du frigo ou de l'étuve (°C)
+const ROOM_TEMPERATURE: f32 = 22.0;
+
+// Pas de simulation maximal (s) ; les pas plus longs sont découpés
+const MAX_TICK: f32 = 0.25;
+// Image la plus longue suivie au facteur demandé (20 images/s) : au-delà,
+// c'est un à-coup de l'affichage, et la pâte prend du retard plutôt que de
+// confier des minutes d'un coup au fil de calcul. x1000 reste tenu à toute
+// cadence normale.
+const HITCH_FRAME: f32 = 0.05;
+
 // Plugin principal pour le système de particules et physique
 pub struct ParticlePlugin;
 
 impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
+        crash::install_panic_hook();
+        configure_frame_sets(app);
         app
+            .insert_resource(crash::load_recovery_offer())
+            .init_resource::<CrashGuard>()
             .init_resource::<TimeScale>()
+            .init_resource::<SimulationPause>()
+            .init_resource::<EffectiveScale>()
+            .init_resource::<PreferencesResource>()
             .init_resource::<SimulationResource>()
+            .init_resource::<SceneSync>()
+            .init_resource::<CompanionMode>()
+            .init_resource::<DemoMode>()
+            .init_resource::<DebugOverlay>()
+            .init_resource::<BondStrengthView>()
+            .init_resource::<BurpPuffs>()
+            .init_resource::<StarterDialog>()
+            .init_resource::<ChallengeDialog>()
+            .init_resource::<CoBaking>()
+            .init_resource::<ScheduleEditor>()
+            .init_resource::<NoteEntry>()
+            .init_resource::<PhotoComparison>()
+            .init_resource::<TrajectoryRecording>()
+            .init_resource::<ReplayMode>()
+            .init_resource::<InputRecording>()
+            .init_resource::<InputPlayback>()
+            .init_resource::<SimulationWorker>()
+            .init_asset::<VisualStyle>()
+            .init_asset_loader::<VisualStyleLoader>()
+            .init_resource::<StyleAssets>()
             .add_plugins(PhysicsPlugins::default())
             .add_systems(Startup, setup_ui_panel)
-            .add_systems(Startup, setup)
+            .add_systems(Startup, (setup, setup_companion, setup_demo, setup_debug_overlay, setup_starter_dialog, setup_challenge_dialog, start_challenge_from_args, setup_cobaking_log, start_cobaking, setup_note_prompt, setup_photo_comparison, setup_lag_indicator, setup_collapse_banner, start_input_playback))
+            .add_plugins(EguiPlugin)
+            // La saisie d'une note et l'éditeur de planning passent avant les
+            // raccourcis clavier qu'ils neutralisent
+            .add_systems(Update, (type_note, schedule_editor_ui).before(FrameSet::Input))
+            // Entrées : clavier, souris, compagnon et démo agissent sur la simulation
             .add_systems(Update, (
-                update_particles,
-                update_bonds,
                 handle_user_input,
                 orbit_camera_control,
+                camera_bookmarks,
+                handle_recovery_offer,
+                edit_observation_boxes,
+                quick_save_load,
+                rewind_checkpoint,
+                fast_forward_hour,
+                toggle_input_recording,
+                cycle_draft,
+                toggle_bond_tint,
+                toggle_burp_sound,
+                drive_hands,
+                challenge_input,
+                send_guest_actions,
+                run_cobaking,
+                run_companion,
+                run_demo,
+            ).in_set(FrameSet::Input))
+            // Pas de simulation (ou relecture), puis ce qui observe l'état obtenu
+            .add_systems(Update, (
+                step_simulation,
+                run_replay,
+                (guard_simulation, record_trajectory, sample_bond_strength).after(step_simulation),
+            ).in_set(FrameSet::SimStep))
+            // Le thème peut arriver pendant le chargement : hors des ensembles conditionnés
+            .add_systems(Update, reload_visual_style.before(FrameSet::SyncEntities))
+            .add_systems(Update, (
+                pull_scene_changes,
+                (update_particles, update_bonds).after(pull_scene_changes),
+                sync_observation_boxes,
+                draw_airflow_arrows,
+                draw_cursors,
+                spawn_burp_puffs,
+                draw_burp_puffs.after(spawn_burp_puffs),
+                draw_tools,
+            ).in_set(FrameSet::SyncEntities))
+            .add_systems(Update, (
                 update_ui_panel,
-            ))
+                apply_panel_layout,
+                update_debug_overlay,
+                update_starter_dialog,
+                update_challenge_dialog,
+                update_cobaking_log,
+                update_note_prompt,
+                update_photo_comparison,
+                update_lag_indicator,
+                update_collapse_banner,
+                save_preferences_on_change,
+            ).in_set(FrameSet::Ui))
+            .add_systems(Last, save_preferences_on_exit)
             .add_plugins(FrameTimeDiagnosticsPlugin)
             .add_plugins(LogDiagnosticsPlugin::default());
     // Système d'initialisation du panneau d'UI
     fn setup_ui_panel(
         mut commands: Commands,
         asset_server: Res<AssetServer>,
+        prefs: Res<PreferencesResource>,
+        visual_style: Res<StyleAssets>,
+        mut loading: ResMut<LoadingAssets>,
     ) {
         let font: Handle<Font> = asset_server.load("fonts/FiraMono-Medium.ttf");
+        loading.0.push(font.clone().untyped());
+        let mut style = Style {
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexStart,
+            justify_content: JustifyContent::FlexStart,
+            ..default()
+        };
+        layout_panel_style(&mut style, &prefs.0.panel);
         let ui_entity = commands.spawn(NodeBundle {
-            style: Style {
-                width: Val::Px(340.0),
-                height: Val::Percent(100.0),
-                position_type: PositionType::Absolute,
-                right: Val::Px(0.0),
-                top: Val::Px(0.0),
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::FlexStart,
-                justify_content: JustifyContent::FlexStart,
-                ..default()
-            },
-            background_color: Color::rgba(0.08, 0.08, 0.12, 0.92).into(),
+            style,
+            background_color: Color::srgba(0.08, 0.08, 0.12, 0.92).into(),
             ..default()
         })
         .with_children(|parent| {
@@ -104,15 +361,37 @@ impl Plugin for ParticlePlugin {
                 },
                 ..default()
             });
+            parent.spawn((
+                TextBundle {
+                    text: legend_text(&font, &visual_style.style),
+                    style: Style {
+                        margin: UiRect::horizontal(Val::Px(18.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                LegendText,
+            ));
         })
         .id();
         commands.insert_resource(UiTextEntity(Some(ui_entity)));
     }
     // Système pour mettre à jour le panneau d'UI avec les données de la simulation
+    #[allow(clippy::too_many_arguments)]
     fn update_ui_panel(
         sim_resource: Res<SimulationResource>,
         ui_text: Res<UiTextEntity>,
         time_scale: Res<TimeScale>,
+        effective_scale: Res<EffectiveScale>,
+        pause: Res<SimulationPause>,
+        prefs: Res<PreferencesResource>,
+        recovery: Res<RecoveryOffer>,
+        companion: Res<CompanionMode>,
+        recording: Res<TrajectoryRecording>,
+        replay: Res<ReplayMode>,
+        inputs: Res<InputRecording>,
+        playback: Res<InputPlayback>,
+        bond_view: Res<BondStrengthView>,
         mut text_query: Query<&mut Text>,
         children_query: Query<&Children>,
     ) {
@@ -121,30 +400,148 @@ impl Plugin for ParticlePlugin {
                 if let Some(&text_entity) = children.first() {
                     if let Ok(mut text) = text_query.get_mut(text_entity) {
                         let state = &sim_resource.state;
-                        let flour = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Gliadin | MoleculeType::Glutenin { .. })).count();
-                        let water = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Water)).count();
-                        let yeast = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Yeast)).count();
-                        let co2 = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::CO2)).count();
-                        let ethanol = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Ethanol)).count();
-                        let sugar = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Sugar)).count();
-                        let salt = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Salt)).count();
-                        let ash = state.grid.get_all_molecules().iter().filter(|m| matches!(m.mol_type, MoleculeType::Ash)).count();
-                        let bonds = state.bonds.len();
+                        // Comptes tenus à jour par le cœur, sans reparcourir les molécules à chaque image
+                        let stats = state.stats();
+                        // Réseau, levée et collant : ceux du dernier échantillon
+                        let metrics = state.metrics.latest().copied().unwrap_or_else(|| state.sample_metrics());
+                        // En défi, la farine et l'amidon trahiraient la teneur en protéines
+                        let hidden = state.challenge.is_some_and(|challenge| !challenge.revealed);
+                        let shown = |count: usize| if hidden { "?".to_string() } else { count.to_string() };
+                        let flour = shown(stats.proteins());
+                        let water = stats.species.water_units;
+                        let yeast = stats.count(Species::Yeast);
+                        let co2 = stats.count(Species::CO2);
+                        let ethanol = stats.count(Species::Ethanol);
+                        let sugar = stats.count(Species::Sugar);
+                        // Grains restants et part déjà dissoute dans l'eau de la pâte
+                        let salt = if state.salt_added {
+                            format!("{} grains, {:.0} % dissous", stats.count(Species::Salt), state.dissolved_salt_fraction() * 100.0)
+                        } else {
+                            stats.count(Species::Salt).to_string()
+                        };
+                        let ash = stats.count(Species::Ash);
+                        let starch = shown(stats.count(Species::Starch));
+                        let fat = stats.count(Species::Fat);
+                        let bonds = stats.bonds;
+                        // Jauge de développement du gluten, mise en avant en tête du panneau
+                        let filled = (metrics.development.clamp(0.0, 1.0) * 20.0).round() as usize;
+                        let development_line = format!(
+                            "Développement du gluten: {:.0} %\n[{}{}]\n\n",
+                            metrics.development * 100.0,
+                            "█".repeat(filled),
+                            "░".repeat(20 - filled)
+                        );
+                        let network_line = format!(
+                            "Réseau: plus grand amas {} ({:.0} %){}",
+                            metrics.largest_component,
+                            metrics.largest_fraction * 100.0,
+                            if metrics.percolates { ", percolé" } else { "" }
+                        );
+                        let solver = state.last_solver_report;
+                        let solver_line = format!(
+                            "Solveur: {} it., résidu {:.3}{}",
+                            solver.iterations,
+                            solver.residual,
+                            if solver.converged { "" } else { " (non convergé!)" }
+                        );
+                        let mass = state.mass_report();
+                        let mass_line = format!(
+                            "Perte de masse: {:.1} % (cible 10-15 %)\n  eau {:.0}, CO₂ {:.0}, éthanol {:.0}",
+                            mass.loss_percent, mass.water_evaporated, mass.co2_lost, mass.ethanol_lost
+                        );
+                        let acid_line = format!(
+                            "Acidité: {:.2}, pH {:.2} (acétique {:.0} %)",
+                            state.acidity.total(),
+                            stats.ph,
+                            state.acidity.acetic_fraction() * 100.0
+                        );
+                        let strength_line = bond_strength_line(&bond_view, state);
+                        let rise_line = format!(
+                            "Levée: {:.1} (max {:.1}), volume x{:.2}",
+                            metrics.rise,
+                            state.collapse.peak_rise(),
+                            metrics.volume_ratio
+                        );
+                        let burps = burp_line(state, prefs.0.burp_sound);
+                        let sticky_line = format!(
+                            "Collant: {:.0} %{}",
+                            metrics.stickiness * 100.0,
+                            if state.phase == DoughPhase::Shape { " (Q: fleurer)" } else { "" }
+                        );
+                        // Tests du boulanger avant le façonnage
+                        let poke = match metrics.poke {
+                            Some(poke) => format!("{:.0} %", poke * 100.0),
+                            None => "pas encore".to_string(),
+                        };
+                        let readiness_line = format!(
+                            "Voile: {:.0} %, test du doigt: {}{}\nPousse: {}",
+                            metrics.windowpane * 100.0,
+                            poke,
+                            if state.readiness_settings.passes(metrics.windowpane, metrics.poke) { " -> prête à façonner" } else { "" },
+                            state.proof_state().label()
+                        );
+                        let zones = observation_lines(state);
+                        let notes = notebook_line(state);
+                        let draft = draft_line(state);
+                        let bake = bake_line(state, prefs.0.temperature_unit);
+                        let replay = replay_line(&recording, &replay) + &inputs_line(&inputs, &playback);
+                        let recipe = &state.recipe_name;
                         let time = state.time_elapsed;
-                        let temp = state.temperature;
-                        // Détermination de la phase
-                        let phase = if !state.salt_added && !state.yeast_added {
-                            "Autolyse"
-                        } else if state.salt_added && !state.yeast_added {
-                            "Après sel, avant levure"
-                        } else if state.salt_added && state.yeast_added {
-                            "Fermentation"
+                        let unit = prefs.0.temperature_unit;
+                        let temp = unit.convert(state.temperature);
+                        let unit_symbol = unit.symbol();
+                        let room = state
+                            .ambient_temperature()
+                            .map(|ambient| match state.environment.preset {
+                                Some(EnvironmentPreset::RoomTemp { .. }) | None => {
+                                    let hour = state.hour_of_day();
+                                    format!(
+                                        " (pièce {:.1} {unit_symbol} à {:02}h{:02})",
+                                        unit.convert(ambient),
+                                        hour as u32,
+                                        (hour.fract() * 60.0) as u32
+                                    )
+                                }
+                                Some(preset) => format!(" ({} {:.1} {unit_symbol})", preset.label(), unit.convert(ambient)),
+                            })
+                            .unwrap_or_default();
+                        // Écart entre le cœur et la surface, dès qu'il se sent
+                        let (coldest, warmest) = state.temperature_range();
+                        let gradient = if warmest - coldest >= 0.5 {
+                            format!(
+                                "\n  cœur {:.1} {unit_symbol}, de {:.1} à {:.1} {unit_symbol}",
+                                unit.convert(state.core_temperature()),
+                                unit.convert(coldest),
+                                unit.convert(warmest)
+                            )
                         } else {
-                            "Préparation"
+                            String::new()
+                        };
+                        let phase = format!("{} ({:.0} min)", stats.phase.label(), state.phase_elapsed() / 60.0);
+                        let recovery = match &recovery.0 {
+                            Some(snapshot) => format!(
+                                "!! Instantané de secours trouvé ({})\n[F10: reprendre / F11: ignorer]\n\n",
+                                snapshot.reason
+                            ),
+                            None => String::new(),
+                        };
+                        let companion_line = if companion.enabled {
+                            let next = companion
+                                .schedule
+                                .next_step()
+                                .map(|step| format!("Prochaine étape: {} ({:.0} min)\n", step.label, (step.at - time).max(0.0) / 60.0))
+                                .unwrap_or_default();
+                            let banner = companion
+                                .banner_text()
+                                .map(|text| format!(">> {} <<\n", text))
+                                .unwrap_or_default();
+                            format!("Mode compagnon (x1, horloge réelle)\n{banner}{next}\n")
+                        } else {
+                            String::new()
                         };
                         text.sections[0].value = format!(
-                            "House of pain 3D - Simulation\n\n[Appuyez sur S pour ajouter du sel]\n[Appuyez sur Y pour ajouter de la levure]\n[Appuyez sur C pour malaxer la pâte]\n[+/- pour accélérer/ralentir le temps]\n\nFacteur temps: x{:.2}\nPhase: {phase}\nTempérature: {temp:.1} °C\nTemps: {time:.1} s\nFarine: {flour}\nEau: {water}\nLevure: {yeast}\nCO₂: {co2}\nEthanol: {ethanol}\nSucre: {sugar}\nSel: {salt}\nCendres: {ash}\nLiaisons gluten: {bonds}",
-                            time_scale.0, phase=phase, temp=temp, time=time, flour=flour, water=water, yeast=yeast, co2=co2, ethanol=ethanol, sugar=sugar, salt=salt, ash=ash, bonds=bonds
+                            "{recovery}House of pain 3D - Simulation\n\n{development_line}{replay}{companion_line}[Appuyez sur S pour ajouter du sel]\n[Appuyez sur Y pour ajouter de la levure]\n[Appuyez sur C pour malaxer la pâte]\n[P: recette suivante / N: phase suivante / E: frigo, étuve, pièce]\n[J: secousse / K: couper 20% des liaisons]\n[O: zone d'observation / Maj+O: effacer]\n[F5: sauvegarder / F9: recharger / Retour arrière: revenir 20 min en arrière / Maj+Retour arrière: avancer d'1 h]\n[F3: débogage / F4: levain / F12: note / V: photos / M: défi]\n[F6: courant d'air / Maj+F6: direction / F7: enregistrer / Maj+F7: gestes / F2: force des liaisons]\n[B: son des rots / Q: fleurer au façonnage]\n[Clic droit: main droite sur le plan de travail, Maj: main gauche (façonnage)]\n[+/- pour accélérer/ralentir le temps / Espace: pause / Entrée: un pas]\n\nFacteur temps: {}\nRecette: {recipe}\nPhase: {phase}\nTempérature: {temp:.1} {unit_symbol}{room}{gradient}\n{draft}{bake}Temps: {time:.1} s\nGraine: {seed}\nFarine: {flour}\nEau: {water}\nLevure: {yeast}\nCO₂: {co2}\nEthanol: {ethanol}\nSucre: {sugar}\nSel: {salt}\nCendres: {ash}\nAmidon: {starch}\nMatière grasse: {fat}\nLiaisons gluten: {bonds}\n{network_line}\n{acid_line}\n{strength_line}\n{rise_line}\n{sticky_line}\n{readiness_line}\n{mass_line}\n{burps}\n{solver_line}\n{zones}{notes}",
+                            time_scale_line(&time_scale, &effective_scale, &pause), recovery=recovery, development_line=development_line, companion_line=companion_line, phase=phase, temp=temp, unit_symbol=unit_symbol, time=time, seed=state.seed, flour=flour, water=water, yeast=yeast, co2=co2, ethanol=ethanol, sugar=sugar, salt=salt, ash=ash, starch=starch, bonds=bonds, network_line=network_line, acid_line=acid_line, strength_line=strength_line, rise_line=rise_line, readiness_line=readiness_line, mass_line=mass_line, burps=burps, solver_line=solver_line, zones=zones, notes=notes
                         );
                     }
                 }
@@ -154,11 +551,127 @@ impl Plugin for ParticlePlugin {
     }
 }
 
+// Légende générée depuis le registre des espèces : une pastille colorée par espèce
+fn legend_text(font: &Handle<Font>, style: &VisualStyle) -> Text {
+    let mut sections = vec![TextSection::new(
+        "Légende\n",
+        TextStyle {
+            font: font.clone(),
+            font_size: 16.0,
+            color: Color::WHITE,
+        },
+    )];
+    for info in Species::all() {
+        sections.push(TextSection::new(
+            "● ",
+            TextStyle {
+                font: font.clone(),
+                font_size: 16.0,
+                color: style.species_color(info),
+            },
+        ));
+        sections.push(TextSection::new(
+            format!("{} (r={:.1}) : {}\n", info.name, info.radius, info.description),
+            TextStyle {
+                font: font.clone(),
+                font_size: 14.0,
+                color: Color::srgb(0.8, 0.8, 0.8),
+            },
+        ));
+    }
+    Text::from_sections(sections)
+}
+
+// Applique la disposition du panneau (côté, largeur, visibilité) à son style
+fn layout_panel_style(style: &mut Style, layout: &pain_config::PanelLayout) {
+    style.width = Val::Px(layout.width);
+    style.display = if layout.visible { Display::Flex } else { Display::None };
+    match layout.side {
+        PanelSide::Left => {
+            style.left = Val::Px(0.0);
+            style.right = Val::Auto;
+        }
+        PanelSide::Right => {
+            style.left = Val::Auto;
+            style.right = Val::Px(0.0);
+        }
+    }
+}
+
+// Système qui répercute les changements de préférences sur le panneau
+fn apply_panel_layout(
+    prefs: Res<PreferencesResource>,
+    ui_text: Res<UiTextEntity>,
+    mut style_query: Query<&mut Style>,
+) {
+    if !prefs.is_changed() {
+        return;
+    }
+    if let Some(panel_entity) = ui_text.0 {
+        if let Ok(mut style) = style_query.get_mut(panel_entity) {
+            layout_panel_style(&mut style, &prefs.0.panel);
+        }
+    }
+}
+
+// Système pour faire avancer la simulation à chaque frame, sur le fil de
+// calcul pour que la fenêtre reste réactive si un pas bloque
+#[allow(clippy::too_many_arguments)]
+fn step_simulation(
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+    time_scale: Res<TimeScale>,
+    mut effective_scale: ResMut<EffectiveScale>,
+    mut pause: ResMut<SimulationPause>,
+    companion: Res<CompanionMode>,
+    replay: Res<ReplayMode>,
+    cobaking: Res<CoBaking>,
+    mut inputs: ResMut<InputRecording>,
+    mut playback: ResMut<InputPlayback>,
+) {
+    // En relecture, c'est la trajectoire qui fait avancer la pâte, et chez un
+    // invité de co-boulange la pâte de l'hôte
+    if replay.is_active() || cobaking.is_guest() {
+        return;
+    }
+    // Calcul précédent toujours en cours : on l'attend un peu, sinon on
+    // affiche la copie et on saute cette image
+    if !worker.settle(&mut sim_resource.state) {
+        effective_scale.record(0.0, real_time.delta_seconds());
+        return;
+    }
+    // En pause, seul le pas demandé fait avancer la pâte
+    let single_step = pause.paused;
+    if pause.paused && !pause.take_step() {
+        return;
+    }
+    // En relecture des gestes, les images de l'enregistrement ; en mode
+    // compagnon on suit l'horloge réelle, sans le plafond du temps virtuel
+    let (duration, max_tick) = match playback.next_frame(&mut sim_resource.state) {
+        Some(frame) => frame,
+        None if single_step => (MAX_TICK, MAX_TICK),
+        None if companion.enabled => (real_time.delta_seconds(), MAX_TICK),
+        None => (time.delta_seconds().min(HITCH_FRAME) * time_scale.0, MAX_TICK),
+    };
+    if duration <= 0.0 {
+        return;
+    }
+
+    // Découpé en pas de `max_tick` au plus par le fil de calcul
+    inputs.frame(duration, max_tick);
+    effective_scale.record(duration, real_time.delta_seconds());
+    worker.submit(&mut sim_resource.state, duration, max_tick);
+    worker.settle(&mut sim_resource.state);
+}
+
 // Fonction d'initialisation de la scène
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    prefs: Res<PreferencesResource>,
 ) {
     // Lumière ambiante plus forte
     commands.insert_resource(AmbientLight {
@@ -183,11 +696,16 @@ fn setup(
     });
 
     // Caméra
-    // Caméra orbitale initiale
+    // Caméra orbitale initiale (dernière vue enregistrée si disponible)
     let center = Vec3::new(500.0, 360.0, 500.0);
-    let radius = 1200.0;
-    let azimuth = std::f32::consts::FRAC_PI_4; // 45°
-    let elevation = std::f32::consts::FRAC_PI_6; // 30°
+    let (radius, azimuth, elevation) = match &prefs.0.last_camera {
+        Some(view) => (view.radius, view.azimuth, view.elevation),
+        None => (
+            1200.0,
+            std::f32::consts::FRAC_PI_4, // 45°
+            std::f32::consts::FRAC_PI_6, // 30°
+        ),
+    };
     let (x, y, z) = (
         center.x + radius * azimuth.cos() * elevation.cos(),
         center.y + radius * elevation.sin(),
@@ -195,7 +713,7 @@ fn setup(
     );
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_xyz(x, y, z).looking_at(center, Vec3::Y),
+            transform: Transform::from_xyz(x, y, z).looking_at(center, world_up()),
             projection: Projection::Perspective(PerspectiveProjection {
                 far: 5000.0,
                 ..default()
@@ -253,306 +771,162 @@ fn orbit_camera_control(
             center.z + orbit.radius * orbit.azimuth.sin() * orbit.elevation.cos(),
         );
         transform.translation = Vec3::new(x, y, z);
-        transform.look_at(center, Vec3::Y);
+        transform.look_at(center, world_up());
     }
 }
 
-// Système pour mettre à jour les particules à partir de l'état de la simulation
-fn update_particles(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut particle_query: Query<(Entity, &mut Transform, &mut MoleculeParticle)>,
-    sim_resource: Res<SimulationResource>,
+// Système pour gérer les entrées utilisateur
+#[allow(clippy::too_many_arguments)]
+fn handle_user_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut time_scale: ResMut<TimeScale>,
+    mut pause: ResMut<SimulationPause>,
+    mut prefs: ResMut<PreferencesResource>,
+    mut inputs: ResMut<InputRecording>,
+    mut worker: ResMut<SimulationWorker>,
+    playback: Res<InputPlayback>,
+    companion: Res<CompanionMode>,
+    _time: Res<Time>,
 ) {
-    let sim_state = &sim_resource.state;
-
-    let materials_map = create_materials_if_needed(&mut materials);
+    let keys = prefs.0.keybindings.clone();
 
-    // Map id -> entity pour update rapide
-    let mut entity_map = std::collections::HashMap::new();
-    for (entity, _, particle) in particle_query.iter_mut() {
-        entity_map.insert(particle.id, entity);
+    // Accélérer le temps avec +, ralentir avec -, de x0,25 à x1000 ; pause avec
+    // Espace, un pas avec Entrée (verrouillé à x1 en mode compagnon)
+    let time_controls = !companion.enabled;
+    if time_controls && (binding_just_pressed(&keyboard_input, &keys.faster, KeyCode::Equal) || keyboard_input.just_pressed(KeyCode::NumpadAdd)) {
+        time_scale.faster();
+        println!("Facteur temps: x{}", time_scale.0);
     }
-
-    // Synchronise ou crée les entités
-    // Calculer le nombre de liaisons par molécule (pour les glutenines)
-    let mut bond_count: std::collections::HashMap<u64, usize> = std::collections::HashMap::new();
-    for bond in &sim_state.bonds {
-        *bond_count.entry(bond.molecule_a_id).or_insert(0) += 1;
-        *bond_count.entry(bond.molecule_b_id).or_insert(0) += 1;
+    if time_controls && (binding_just_pressed(&keyboard_input, &keys.slower, KeyCode::Minus) || keyboard_input.just_pressed(KeyCode::NumpadSubtract)) {
+        time_scale.slower();
+        println!("Facteur temps: x{}", time_scale.0);
     }
-
-    for molecule in sim_state.grid.get_all_molecules() {
-        let pos = Vec3::new(
-            molecule.pos.x as f32,
-            molecule.pos.y as f32,
-            molecule.pos.z as f32,
-        );
-        // Taille de base
-        let mut scale = Vec3::ONE;
-        // Si c'est une glutenine, on grossit selon le nombre de liaisons
-        if let MoleculeType::Glutenin { .. } = molecule.mol_type {
-            let n_bonds = bond_count.get(&molecule.id).copied().unwrap_or(0);
-            // 1.0 (seule) à 2.0 (très liée)
-            scale = Vec3::splat(1.0 + (n_bonds as f32 * 0.3).min(1.0));
-        }
-        if let Some(entity) = entity_map.get(&molecule.id) {
-            if let Ok((_, mut transform, _)) = particle_query.get_mut(*entity) {
-                transform.translation = pos;
-                transform.scale = scale;
-            }
-        } else {
-            let material_handle = match &molecule.mol_type {
-                MoleculeType::Gliadin => materials_map.gliadin.clone(),
-                MoleculeType::Glutenin { has_free_thiol: true } => materials_map.reactive_glutenin.clone(),
-                MoleculeType::Glutenin { has_free_thiol: false } => materials_map.bonded_glutenin.clone(),
-                MoleculeType::Water => materials_map.water.clone(),
-                MoleculeType::Yeast => materials_map.yeast.clone(),
-                MoleculeType::CO2 => materials_map.co2.clone(),
-                MoleculeType::Ethanol => materials_map.ethanol.clone(),
-                MoleculeType::Sugar => materials_map.sugar.clone(),
-                MoleculeType::Salt => materials_map.salt.clone(),
-                MoleculeType::Ash => materials_map.ash.clone(),
-            };
-            let radius = 3.0;
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(Sphere::new(radius)),
-                    material: material_handle,
-                    transform: Transform::from_translation(pos).with_scale(scale),
-                    ..default()
-                },
-                MoleculeParticle {
-                    id: molecule.id,
-                    mol_type: molecule.mol_type.clone(),
-                },
-            ));
-        }
+    if time_controls && binding_just_pressed(&keyboard_input, &keys.pause, KeyCode::Space) {
+        pause.toggle();
+        println!("{}", if pause.paused { "Simulation en pause" } else { "Simulation reprise" });
     }
-    // Supprime les entités orphelines
-    let valid_ids: std::collections::HashSet<u64> = sim_state.grid.get_all_molecules().iter().map(|m| m.id).collect();
-    for (entity, _, particle) in particle_query.iter() {
-        if !valid_ids.contains(&particle.id) {
-            commands.entity(entity).despawn();
-        }
+    if time_controls && binding_just_pressed(&keyboard_input, &keys.single_step, KeyCode::Enter) {
+        pause.request_step();
     }
-}
-
-// Structure pour stocker les handles des matériaux
-struct MaterialHandles {
-    gliadin: Handle<StandardMaterial>,
-    reactive_glutenin: Handle<StandardMaterial>,
-    bonded_glutenin: Handle<StandardMaterial>,
-    water: Handle<StandardMaterial>,
-    yeast: Handle<StandardMaterial>,
-    co2: Handle<StandardMaterial>,
-    ethanol: Handle<StandardMaterial>,
-    sugar: Handle<StandardMaterial>,
-    salt: Handle<StandardMaterial>,
-    ash: Handle<StandardMaterial>,
-}
 
-// Fonction utilitaire pour créer les matériaux si nécessaire
-fn create_materials_if_needed(materials: &mut ResMut<Assets<StandardMaterial>>) -> MaterialHandles {
-    let gliadin = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 0.27, 0.0), // orange-rouge
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let reactive_glutenin = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 1.0, 0.0), // jaune
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let bonded_glutenin = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.0, 1.0, 0.0), // vert
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let water = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.0, 0.4, 1.0), // bleu vif
-        perceptual_roughness: 0.2,
-        reflectance: 0.2,
-        ..default()
-    });
-    let yeast = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 1.0, 1.0), // blanc
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let co2 = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.0, 1.0, 1.0), // cyan
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let ethanol = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.6, 0.0, 0.8), // violet
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let sugar = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 0.0, 0.6), // rose
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let salt = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.5, 0.5, 0.5), // gris
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    let ash = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.0, 0.0, 0.0), // noir
-        perceptual_roughness: 0.5,
-        reflectance: 0.2,
-        ..default()
-    });
-    MaterialHandles {
-        gliadin,
-        reactive_glutenin,
-        bonded_glutenin,
-        water,
-        yeast,
-        co2,
-        ethanol,
-        sugar,
-        salt,
-        ash,
-    }
-}
-
-// Système pour mettre à jour les liaisons (bonds) entre molécules
-fn update_bonds(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    sim_resource: Res<SimulationResource>,
-    bond_query: Query<Entity, With<GlutenBond>>,
-    particle_query: Query<(Entity, &MoleculeParticle), With<MoleculeParticle>>,
-) {
-    // Supprimer les anciennes liaisons
-    for entity in bond_query.iter() {
-        commands.entity(entity).despawn();
+    // Basculer entre °C et °F avec la touche 'U'
+    if binding_just_pressed(&keyboard_input, &keys.toggle_units, KeyCode::KeyU) {
+        prefs.0.temperature_unit = prefs.0.temperature_unit.toggled();
     }
 
-    // Créer les nouvelles liaisons en tant que contraintes physiques
-    let bond_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(1.0, 0.3, 0.5), // Rouge clair
-        ..default()
-    });
-
-    // Créer une map des entités par ID de molécule pour les liaisons
-    let mut particle_map = std::collections::HashMap::new();
-    for (entity, particle) in particle_query.iter() {
-        particle_map.insert(particle.id, entity);
-    }
-
-    for bond in &sim_resource.state.bonds {
-        if let (Some(mol_a), Some(mol_b)) = (
-            sim_resource.state.grid.get_molecule(bond.molecule_a_id),
-            sim_resource.state.grid.get_molecule(bond.molecule_b_id)
-        ) {
-            // Créer une contrainte physique entre les deux particules
-            if let (Some(entity_a), Some(entity_b)) = (
-                particle_map.get(&bond.molecule_a_id),
-                particle_map.get(&bond.molecule_b_id)
-            ) {
-                // Créer une contrainte de distance entre les deux particules
-                /* commands.spawn(
-                    DistanceJoint::new(*entity_a, *entity_b)
-                        .with_local_anchor_1(Vec3::ZERO)
-                        .with_local_anchor_2(Vec3::ZERO)
-                        .with_rest_length(bond.target_distance as f32)
-                ); */
-            }
-
-            // Créer la visualisation de la liaison
-            let pos_a = Vec3::new(
-                mol_a.pos.x as f32,
-                mol_a.pos.y as f32,
-                mol_a.pos.z as f32,
-            );
-
-            let pos_b = Vec3::new(
-                mol_b.pos.x as f32,
-                mol_b.pos.y as f32,
-                mol_b.pos.z as f32,
-            );
-
-            // Créer un cylindre pour représenter la liaison
-            let bond_length = pos_a.distance(pos_b);
-            let bond_center = (pos_a + pos_b) / 2.0;
-
-            // Calculer la rotation pour aligner le cylindre entre les deux molécules
-            let direction = (pos_b - pos_a).normalize();
-            let up = Vec3::Y;
-            let rotation = Quat::from_rotation_arc(up, direction);
-
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(Cylinder::new(5.0, bond_length)),
-                    material: bond_material.clone(),
-                    transform: Transform::from_translation(bond_center)
-                        .with_rotation(rotation),
-                    ..default()
-                },
-                GlutenBond {
-                    molecule_a_id: bond.molecule_a_id,
-                    molecule_b_id: bond.molecule_b_id,
-                }
-            ));
-        }
+    // Afficher/masquer le panneau avec Tab
+    if binding_just_pressed(&keyboard_input, &keys.toggle_panel, KeyCode::Tab) {
+        prefs.0.panel.visible = !prefs.0.panel.visible;
     }
-}
 
-// Système pour gérer les entrées utilisateur
-fn handle_user_input(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut sim_resource: ResMut<SimulationResource>,
-    mut time_scale: ResMut<TimeScale>,
-    _time: Res<Time>,
-) {
-    // Accélérer le temps avec +, ralentir avec -
-    if keyboard_input.just_pressed(KeyCode::Plus) || keyboard_input.just_pressed(KeyCode::NumpadAdd) {
-        time_scale.0 = (time_scale.0 * 2.0).min(128.0);
-        println!("Facteur temps: x{}", time_scale.0);
+    // Pendant la relecture des gestes, seuls ceux de l'enregistrement comptent
+    if playback.is_active() {
+        return;
     }
-    if keyboard_input.just_pressed(KeyCode::Minus) || keyboard_input.just_pressed(KeyCode::NumpadSubtract) {
-        time_scale.0 = (time_scale.0 / 2.0).max(0.125);
-        println!("Facteur temps: x{}", time_scale.0);
+    // Pendant l'enregistrement, les gestes portent sur la vraie pâte et pas
+    // sur la copie affichée, quitte à attendre le calcul en cours
+    if inputs.is_recording() && !worker.settle(&mut sim_resource.state) {
+        return;
     }
+
     // Ajouter du sel avec la touche 'S'
-    if keyboard_input.just_pressed(KeyCode::KeyS) && !sim_resource.state.salt_added {
-        sim_resource.state.add_salt();
+    if binding_just_pressed(&keyboard_input, &keys.add_salt, KeyCode::KeyS) && !sim_resource.state.salt_added {
+        inputs.apply(&mut sim_resource.state, SimAction::AddSalt);
         println!("Salt added!");
     }
     
     // Ajouter de la levure avec la touche 'Y'
-    if keyboard_input.just_pressed(KeyCode::KeyY) && !sim_resource.state.yeast_added {
-        sim_resource.state.add_yeast();
+    if binding_just_pressed(&keyboard_input, &keys.add_yeast, KeyCode::KeyY) && !sim_resource.state.yeast_added {
+        inputs.apply(&mut sim_resource.state, SimAction::AddYeast);
         println!("Yeast added!");
     }
     
     // Simuler un pli (fold) avec la touche 'C'
-    if keyboard_input.just_pressed(KeyCode::KeyC) {
-        let center = nalgebra::Vector3::new(500.0, 360.0, 500.0);
-        let force = nalgebra::Vector3::new(0.0, 30.0, 0.0);
-        sim_resource.state.apply_force_to_region(center, 200.0, force);
+    if binding_just_pressed(&keyboard_input, &keys.fold, KeyCode::KeyC) {
+        let fold = SimAction::centered_fold(&sim_resource.state);
+        inputs.apply(&mut sim_resource.state, fold);
         println!("Fold applied!");
     }
     
+    // Gestes du boulanger : étirer et rabattre (F), rabat en bobine (G), frapper et rabattre (H)
+    for (binding, fallback, knead) in [
+        (&keys.stretch_and_fold, KeyCode::KeyF, KneadAction::StretchAndFold),
+        (&keys.coil_fold, KeyCode::KeyG, KneadAction::CoilFold),
+        (&keys.slap_and_fold, KeyCode::KeyH, KneadAction::SlapAndFold),
+    ] {
+        if binding_just_pressed(&keyboard_input, binding, fallback) {
+            inputs.apply(&mut sim_resource.state, SimAction::Knead(knead));
+            println!("{}", knead.label());
+        }
+    }
+
+    // Fleurer la pâte avec la touche 'Q', seulement pendant le façonnage
+    if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        let amount = sim_resource.state.stickiness_settings.dust_amount;
+        if sim_resource.state.phase == DoughPhase::Shape {
+            inputs.apply(&mut sim_resource.state, SimAction::DustFlour(amount));
+            println!("Pâte fleurée ({:.0} % de farine en plus)", amount * 100.0);
+        } else {
+            println!("On ne fleure la pâte qu'au façonnage");
+        }
+    }
+
+    // Perturbations pédagogiques : secousse (J) et coupure de 20 % des liaisons (K)
+    if keyboard_input.just_pressed(KeyCode::KeyJ) {
+        inputs.apply(&mut sim_resource.state, SimAction::Perturb(Perturbation::VelocityNoise { magnitude: 2.0 }));
+        println!("Secousse appliquée à {} molécules", sim_resource.state.grid.len());
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyK) {
+        let before = sim_resource.state.bonds().len();
+        inputs.apply(&mut sim_resource.state, SimAction::Perturb(Perturbation::RemoveBonds { fraction: 0.2 }));
+        println!("{} liaisons coupées", before - sim_resource.state.bonds().len());
+    }
+
+    // Recette suivante avec la touche 'P' (repart d'une pâte neuve)
+    if binding_just_pressed(&keyboard_input, &keys.next_recipe, KeyCode::KeyP) {
+        // Le calcul en cours écraserait la pâte neuve
+        if !worker.settle(&mut sim_resource.state) {
+            eprintln!("Calcul en cours, changement de recette impossible pour l'instant");
+            return;
+        }
+        inputs.stop(&sim_resource.state);
+        prefs.0.last_recipe = recipe_preset(&prefs.0).next().id().to_string();
+        sim_resource.state = new_simulation(&prefs.0);
+        println!("Recette: {}", sim_resource.state.recipe_name);
+    }
+
+    // Phase suivante avec la touche 'N' (façonnage, apprêt, cuisson...)
+    if binding_just_pressed(&keyboard_input, &keys.next_phase, KeyCode::KeyN) {
+        let next = sim_resource.state.phase.next();
+        if let Some(phase) = next {
+            inputs.apply(&mut sim_resource.state, SimAction::AdvanceTo(phase));
+        }
+        match next.filter(|phase| sim_resource.state.phase == *phase) {
+            Some(phase) => println!("Phase: {}", phase),
+            None => println!("La pâte est déjà refroidie"),
+        }
+    }
+
+    // Frigo (pointage au froid), étuve ou retour à la pièce avec la touche 'E'
+    if binding_just_pressed(&keyboard_input, &keys.next_environment, KeyCode::KeyE) {
+        let next = match sim_resource.state.environment.preset {
+            None | Some(EnvironmentPreset::RoomTemp { .. }) => EnvironmentPreset::Fridge,
+            Some(EnvironmentPreset::Fridge) => EnvironmentPreset::Proofer,
+            Some(EnvironmentPreset::Proofer) => EnvironmentPreset::RoomTemp { temp: ROOM_TEMPERATURE },
+        };
+        inputs.apply(&mut sim_resource.state, SimAction::SetEnvironment(next));
+        println!("Environnement: {}", next.label());
+    }
+
     // Réinitialiser avec la touche 'R'
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
-        sim_resource.state = SimulationState::new(1000.0, 720.0, 1000.0);
-        sim_resource.state.initialize_classic_recipe();
+    if binding_just_pressed(&keyboard_input, &keys.reset, KeyCode::KeyR) {
+        if !worker.settle(&mut sim_resource.state) {
+            eprintln!("Calcul en cours, réinitialisation impossible pour l'instant");
+            return;
+        }
+        inputs.stop(&sim_resource.state);
+        sim_resource.state = new_simulation(&prefs.0);
         println!("Simulation reset!");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use pain_core::timeline::format_duration;
+use pain_core::{Note, SimulationState};
+use std::path::PathBuf;
+
+use crate::SimulationResource;
+
+// Nombre de notes rappelées dans le panneau
+const PANEL_NOTES: usize = 3;
+// Longueur de la frise des notes en relecture
+const BAR_WIDTH: usize = 40;
+
+// Carnet de laboratoire : F12 ouvre la saisie d'une note datée du temps
+// simulé, Entrée l'ajoute, Échap l'abandonne ; Maj+F12 exporte le carnet
+#[derive(Resource, Default)]
+pub struct NoteEntry {
+    pub typing: bool,
+    pub text: String,
+}
+
+fn notebook_path(seed: u64) -> Option<PathBuf> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    pain_config::config_dir().map(|dir| {
+        dir.join("carnets")
+            .join(format!("carnet-{}-{}.md", seed, stamp))
+    })
+}
+
+fn export_notebook(state: &SimulationState) {
+    let Some(path) = notebook_path(state.seed) else {
+        eprintln!("Aucun dossier de configuration pour le carnet");
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, state.notebook_report()));
+    match written {
+        Ok(()) => println!("Carnet exporté dans {}", path.display()),
+        Err(err) => eprintln!("Export du carnet impossible: {}", err),
+    }
+}
+
+// Tourne avant les autres entrées : pendant la saisie, les lettres tapées ne
+// doivent pas déclencher les raccourcis (S, Y, C...)
+pub fn type_note(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut key_events: EventReader<KeyboardInput>,
+    mut entry: ResMut<NoteEntry>,
+    mut sim_resource: ResMut<SimulationResource>,
+) {
+    if !entry.typing {
+        key_events.clear();
+        if keyboard_input.just_pressed(KeyCode::F12) {
+            let shift = keyboard_input.pressed(KeyCode::ShiftLeft)
+                || keyboard_input.pressed(KeyCode::ShiftRight);
+            if shift {
+                export_notebook(&sim_resource.state);
+            } else {
+                entry.typing = true;
+                entry.text.clear();
+            }
+        }
+        return;
+    }
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => entry
+                .text
+                .extend(characters.chars().filter(|c| !c.is_control())),
+            Key::Space => entry.text.push(' '),
+            Key::Backspace => {
+                entry.text.pop();
+            }
+            Key::Enter => {
+                let text = std::mem::take(&mut entry.text);
+                if sim_resource.state.annotate(&text) {
+                    println!("Note ajoutée à t={:.0} s", sim_resource.state.time_elapsed);
+                }
+                entry.typing = false;
+            }
+            Key::Escape => {
+                entry.text.clear();
+                entry.typing = false;
+            }
+            _ => {}
+        }
+    }
+    keyboard_input.reset_all();
+}
+
+#[derive(Component)]
+pub struct NotePromptText;
+
+pub fn setup_note_prompt(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::srgb(1.0, 1.0, 0.85),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.1, 0.1, 0.02, 0.9).into(),
+            ..default()
+        },
+        NotePromptText,
+    ));
+}
+
+pub fn update_note_prompt(
+    entry: Res<NoteEntry>,
+    sim_resource: Res<SimulationResource>,
+    mut query: Query<(&mut Text, &mut Style), With<NotePromptText>>,
+) {
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if entry.typing {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if entry.typing {
+            text.sections[0].value = format!(
+                "Note à {} : {}_\n[Entrée: ajouter / Échap: annuler]",
+                format_duration(sim_resource.state.time_elapsed.round()),
+                entry.text
+            );
+        }
+    }
+}
+
+// Ligne du panneau : les dernières notes de la pâte
+pub fn notebook_line(state: &SimulationState) -> String {
+    let notes = state.notebook.notes();
+    if notes.is_empty() {
+        return String::new();
+    }
+    let mut line = format!(
+        "Carnet ({} notes) [F12: noter / Maj+F12: exporter]\n",
+        notes.len()
+    );
+    for note in &notes[notes.len().saturating_sub(PANEL_NOTES)..] {
+        line.push_str(&format!(
+            "  {} {}\n",
+            format_duration(note.time.round()),
+            note.text
+        ));
+    }
+    line
+}
+
+// Frise de la relecture : '|' pour chaque note, '^' pour le curseur
+pub fn notes_bar(notes: &[Note], start: f32, duration: f32, now: f32) -> String {
+    if notes.is_empty() || duration <= 0.0 {
+        return String::new();
+    }
+    let column = |time: f32| {
+        (((time - start) / duration).clamp(0.0, 1.0) * (BAR_WIDTH - 1) as f32).round() as usize
+    };
+    let mut bar = vec!['─'; BAR_WIDTH];
+    for note in notes {
+        bar[column(note.time)] = '|';
+    }
+    bar[column(now)] = '^';
+    format!("[{}]\n", bar.into_iter().collect::<String>())
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use nalgebra::Vector3;
+use pain_config::ObservationBox;
+use pain_core::SimulationState;
+
+use crate::preferences::PreferencesResource;
+use crate::SimulationResource;
+
+// Demi-côté de la zone ajoutée avec la touche O
+const DEFAULT_BOX_HALF_SIZE: f32 = 120.0;
+
+// Boîte translucide représentant une zone d'observation
+#[derive(Component)]
+pub struct ObservationBoxMarker;
+
+// Ajoute à la simulation les zones d'observation définies dans les préférences
+pub fn apply_observation_boxes(state: &mut SimulationState, boxes: &[ObservationBox]) {
+    state.observations.clear();
+    for zone in boxes {
+        state.add_observation_volume(
+            &zone.name,
+            Vector3::from(zone.min),
+            Vector3::from(zone.max),
+        );
+    }
+}
+
+// Lignes du panneau : compteurs de chaque zone
+pub fn observation_lines(state: &SimulationState) -> String {
+    let mut lines = String::new();
+    for volume in &state.observations {
+        let counters = &volume.counters;
+        lines.push_str(&format!(
+            "[{}] {} mol., CO₂ +{}, liaisons +{}, entrées {} / sorties {}\n",
+            volume.name,
+            volume.population(),
+            counters.co2_produced,
+            counters.bonds_formed,
+            counters.molecules_entered,
+            counters.molecules_exited
+        ));
+    }
+    lines
+}
+
+// O ajoute une zone centrée sur la pâte, Maj+O supprime toutes les zones
+pub fn edit_observation_boxes(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut prefs: ResMut<PreferencesResource>,
+    mut sim_resource: ResMut<SimulationResource>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyO) {
+        return;
+    }
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if shift {
+        prefs.0.observation_boxes.clear();
+        sim_resource.state.observations.clear();
+        println!("Zones d'observation supprimées");
+        return;
+    }
+
+    let center = sim_resource.state.center();
+    let half = Vector3::repeat(DEFAULT_BOX_HALF_SIZE);
+    let zone = ObservationBox {
+        name: format!("Zone {}", prefs.0.observation_boxes.len() + 1),
+        min: (center - half).into(),
+        max: (center + half).into(),
+    };
+    sim_resource.state.add_observation_volume(&zone.name, center - half, center + half);
+    println!("Zone d'observation ajoutée: {}", zone.name);
+    prefs.0.observation_boxes.push(zone);
+}
+
+// Recrée les boîtes translucides quand la liste des zones change
+pub fn sync_observation_boxes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    sim_resource: Res<SimulationResource>,
+    query: Query<Entity, With<ObservationBoxMarker>>,
+    mut shown: Local<Vec<(Vec3, Vec3)>>,
+) {
+    let wanted: Vec<(Vec3, Vec3)> = sim_resource
+        .state
+        .observations
+        .iter()
+        .map(|volume| {
+            let (min, max) = (volume.min, volume.max);
+            (Vec3::new(min.x, min.y, min.z), Vec3::new(max.x, max.y, max.z))
+        })
+        .collect();
+    if *shown == wanted {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgba(1.0, 0.85, 0.3, 0.12),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    for (min, max) in &wanted {
+        let size = *max - *min;
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::new(size.x, size.y, size.z)),
+                material: material.clone(),
+                transform: Transform::from_translation((*min + *max) / 2.0),
+                ..default()
+            },
+            ObservationBoxMarker,
+        ));
+    }
+    *shown = wanted;
+}
@@ -0,0 +1,149 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use pain_config::{CameraBookmark, Preferences};
+use pain_core::RecipePreset;
+
+use crate::OrbitCamera;
+
+// Resource contenant les préférences chargées au démarrage
+#[derive(Resource)]
+pub struct PreferencesResource(pub Preferences);
+
+impl Default for PreferencesResource {
+    fn default() -> Self {
+        let mut prefs = Preferences::load();
+        // --recipe <nom> choisit la recette de départ (et devient la dernière utilisée)
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(pair) = args.windows(2).find(|pair| pair[0] == "--recipe") {
+            match RecipePreset::from_id(&pair[1]) {
+                Some(preset) => prefs.last_recipe = preset.id().to_string(),
+                None => eprintln!("Recette inconnue: {}", pair[1]),
+            }
+        }
+        PreferencesResource(prefs)
+    }
+}
+
+// Convertit un nom de touche du fichier de préférences en KeyCode Bevy
+pub fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    let key = match name {
+        "A" => KeyCode::KeyA,
+        "B" => KeyCode::KeyB,
+        "C" => KeyCode::KeyC,
+        "D" => KeyCode::KeyD,
+        "E" => KeyCode::KeyE,
+        "F" => KeyCode::KeyF,
+        "G" => KeyCode::KeyG,
+        "H" => KeyCode::KeyH,
+        "I" => KeyCode::KeyI,
+        "J" => KeyCode::KeyJ,
+        "K" => KeyCode::KeyK,
+        "L" => KeyCode::KeyL,
+        "M" => KeyCode::KeyM,
+        "N" => KeyCode::KeyN,
+        "O" => KeyCode::KeyO,
+        "P" => KeyCode::KeyP,
+        "Q" => KeyCode::KeyQ,
+        "R" => KeyCode::KeyR,
+        "S" => KeyCode::KeyS,
+        "T" => KeyCode::KeyT,
+        "U" => KeyCode::KeyU,
+        "V" => KeyCode::KeyV,
+        "W" => KeyCode::KeyW,
+        "X" => KeyCode::KeyX,
+        "Y" => KeyCode::KeyY,
+        "Z" => KeyCode::KeyZ,
+        "Space" => KeyCode::Space,
+        "Tab" => KeyCode::Tab,
+        "Enter" => KeyCode::Enter,
+        "Equal" => KeyCode::Equal,
+        "Minus" => KeyCode::Minus,
+        "NumpadAdd" => KeyCode::NumpadAdd,
+        "NumpadSubtract" => KeyCode::NumpadSubtract,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// Vérifie si la touche configurée vient d'être pressée (repli sur la touche par défaut)
+pub fn binding_just_pressed(
+    keyboard_input: &ButtonInput<KeyCode>,
+    binding: &str,
+    fallback: KeyCode,
+) -> bool {
+    keyboard_input.just_pressed(key_code_from_name(binding).unwrap_or(fallback))
+}
+
+pub fn bookmark_from_camera(name: &str, orbit: &OrbitCamera) -> CameraBookmark {
+    CameraBookmark {
+        name: name.to_string(),
+        radius: orbit.radius,
+        azimuth: orbit.azimuth,
+        elevation: orbit.elevation,
+    }
+}
+
+// Ctrl+1..9 enregistre la vue courante, 1..9 la restaure
+pub fn camera_bookmarks(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut prefs: ResMut<PreferencesResource>,
+    mut query: Query<&mut OrbitCamera>,
+) {
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let ctrl = keyboard_input.pressed(KeyCode::ControlLeft)
+        || keyboard_input.pressed(KeyCode::ControlRight);
+
+    for (slot, key) in DIGITS.iter().enumerate() {
+        if !keyboard_input.just_pressed(*key) {
+            continue;
+        }
+        let slot = (slot + 1).to_string();
+        for mut orbit in query.iter_mut() {
+            if ctrl {
+                let bookmark = bookmark_from_camera(&format!("Vue {}", slot), &orbit);
+                prefs.0.camera_bookmarks.insert(slot.clone(), bookmark);
+                println!("Vue {} enregistrée", slot);
+            } else if let Some(bookmark) = prefs.0.camera_bookmarks.get(&slot) {
+                orbit.radius = bookmark.radius;
+                orbit.azimuth = bookmark.azimuth;
+                orbit.elevation = bookmark.elevation;
+            }
+        }
+    }
+}
+
+// Sauvegarde dès qu'une préférence change (unités, panneau, favoris...)
+pub fn save_preferences_on_change(prefs: Res<PreferencesResource>) {
+    if prefs.is_changed() && !prefs.is_added() {
+        if let Err(err) = prefs.0.save() {
+            eprintln!("Impossible d'enregistrer les préférences: {}", err);
+        }
+    }
+}
+
+// À la fermeture, on mémorise aussi la dernière position de caméra
+pub fn save_preferences_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    mut prefs: ResMut<PreferencesResource>,
+    query: Query<&OrbitCamera>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    if let Some(orbit) = query.iter().next() {
+        prefs.0.last_camera = Some(bookmark_from_camera("Dernière vue", orbit));
+    }
+    if let Err(err) = prefs.0.save() {
+        eprintln!("Impossible d'enregistrer les préférences: {}", err);
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use pain_core::{Note, Trajectory, TrajectoryPlayer, TrajectoryRecorder, TrajectoryWriter};
+use std::path::PathBuf;
+
+use crate::notebook::notes_bar;
+use crate::time_scale::TimeScale;
+use crate::SimulationResource;
+
+// Saut du curseur de relecture avec [ et ] (fraction de la durée totale)
+const SCRUB_STEP: f32 = 0.05;
+
+// Enregistrement de la trajectoire en cours (F7 pour démarrer / arrêter),
+// écrit au fil de l'eau dans `path`
+#[derive(Resource, Default)]
+pub struct TrajectoryRecording {
+    pub recorder: Option<TrajectoryRecorder>,
+    pub path: Option<PathBuf>,
+}
+
+// Relecture d'une trajectoire passée avec --replay <fichier>
+#[derive(Resource)]
+pub struct ReplayMode {
+    pub player: Option<TrajectoryPlayer>,
+    pub playing: bool,
+    pub notes: Vec<Note>, // Toutes les notes de la trajectoire, pour la frise
+}
+
+impl Default for ReplayMode {
+    fn default() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let player = args
+            .windows(2)
+            .find(|pair| pair[0] == "--replay")
+            .and_then(|pair| match Trajectory::load_from_file(&PathBuf::from(&pair[1])) {
+                Ok(trajectory) => Some(TrajectoryPlayer::new(trajectory)),
+                Err(err) => {
+                    eprintln!("Relecture impossible de {}: {}", pair[1], err);
+                    None
+                }
+            });
+        let notes = player
+            .as_ref()
+            .and_then(|player| player.trajectory().notes().ok())
+            .unwrap_or_default();
+        ReplayMode {
+            player,
+            playing: true,
+            notes,
+        }
+    }
+}
+
+impl ReplayMode {
+    pub fn is_active(&self) -> bool {
+        self.player.is_some()
+    }
+}
+
+fn trajectory_path(seed: u64) -> Option<PathBuf> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    pain_config::config_dir()
+        .map(|dir| dir.join("trajectories").join(format!("trajectoire-{}-{}.traj", seed, stamp)))
+}
+
+// F7 démarre l'enregistrement, F7 à nouveau l'arrête et termine le fichier
+pub fn record_trajectory(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut recording: ResMut<TrajectoryRecording>,
+    sim_resource: Res<SimulationResource>,
+    replay: Res<ReplayMode>,
+) {
+    if replay.is_active() {
+        return;
+    }
+    // Maj+F7 enregistre les gestes, pas la trajectoire
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if keyboard_input.just_pressed(KeyCode::F7) && !shift {
+        match recording.recorder.take() {
+            Some(recorder) => {
+                let path = recording.path.take().unwrap_or_default();
+                match recorder.finish() {
+                    Ok(_) => println!("Trajectoire enregistrée dans {}", path.display()),
+                    Err(err) => eprintln!("Enregistrement impossible: {}", err),
+                }
+            }
+            None => match trajectory_path(sim_resource.state.seed) {
+                Some(path) => match TrajectoryWriter::create(&path) {
+                    Ok(writer) => {
+                        recording.recorder =
+                            Some(TrajectoryRecorder::default().streaming_to(writer));
+                        recording.path = Some(path);
+                    }
+                    Err(err) => eprintln!("Enregistrement impossible: {}", err),
+                },
+                None => eprintln!("Aucun dossier de configuration pour la trajectoire"),
+            },
+        }
+    }
+    if let Some(recorder) = recording.recorder.as_mut() {
+        if let Err(err) = recorder.record(&sim_resource.state) {
+            eprintln!("Enregistrement interrompu: {}", err);
+            recording.recorder = None;
+            recording.path = None;
+        }
+    }
+}
+
+// Avance la relecture ; F8 met en pause, [ et ] déplacent le curseur
+pub fn run_replay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut replay: ResMut<ReplayMode>,
+    mut sim_resource: ResMut<SimulationResource>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        replay.playing = !replay.playing;
+    }
+    let playing = replay.playing;
+    let Some(player) = replay.player.as_mut() else {
+        return;
+    };
+    let duration = player.trajectory().duration();
+    let start = player.trajectory().start_time();
+    let mut target = player.time();
+    if playing {
+        target += time.delta_seconds() * time_scale.0;
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        target -= duration * SCRUB_STEP;
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        target += duration * SCRUB_STEP;
+    }
+    let target = target.clamp(start, start + duration);
+    if let Err(err) = player.seek(&mut sim_resource.state, target) {
+        eprintln!("Relecture interrompue: {}", err);
+        replay.player = None;
+    }
+}
+
+// Ligne du panneau : enregistrement ou relecture en cours
+pub fn replay_line(recording: &TrajectoryRecording, replay: &ReplayMode) -> String {
+    if let Some(player) = &replay.player {
+        let trajectory = player.trajectory();
+        return format!(
+            "Relecture: {:.0} / {:.0} s{} [F8: pause, [ / ]: reculer / avancer]\n{}",
+            player.time() - trajectory.start_time(),
+            trajectory.duration(),
+            if replay.playing { "" } else { " (pause)" },
+            notes_bar(&replay.notes, trajectory.start_time(), trajectory.duration(), player.time())
+        );
+    }
+    match &recording.recorder {
+        Some(recorder) => format!(
+            "● Enregistrement: {} images, {} points de reprise [F7: arrêter]\n",
+            recorder.frames_recorded(),
+            recorder.keyframes_recorded()
+        ),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use pain_core::SimulationState;
+use std::path::PathBuf;
+
+use crate::inputs::{InputPlayback, InputRecording};
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+const QUICKSAVE_FILE: &str = "quicksave.pain";
+// Retour arrière : vers le point de reprise le plus proche d'il y a 20 min
+const REWIND_SECONDS: f32 = 20.0 * 60.0;
+// Maj+Retour arrière : une heure simulée en accéléré
+const FAST_FORWARD_SECONDS: f32 = 60.0 * 60.0;
+
+// Emplacement de la sauvegarde rapide (dossier de configuration/saves)
+pub fn quicksave_path() -> Option<PathBuf> {
+    pain_config::config_dir().map(|dir| dir.join("saves").join(QUICKSAVE_FILE))
+}
+
+// F5 sauvegarde la pâte en cours, F9 la recharge
+pub fn quick_save_load(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    mut inputs: ResMut<InputRecording>,
+) {
+    let save = keyboard_input.just_pressed(KeyCode::F5);
+    let load = keyboard_input.just_pressed(KeyCode::F9);
+    if !save && !load {
+        return;
+    }
+    let Some(path) = quicksave_path() else {
+        eprintln!("Aucun dossier de configuration pour la sauvegarde");
+        return;
+    };
+    // On sauve la vraie pâte, et le calcul en cours écraserait celle rechargée
+    if !worker.settle(&mut sim_resource.state) {
+        eprintln!("Calcul en cours, sauvegarde ou chargement impossible pour l'instant");
+        return;
+    }
+
+    if save {
+        match sim_resource.state.save_to_file(&path) {
+            Ok(()) => println!("Simulation sauvegardée dans {}", path.display()),
+            Err(err) => eprintln!("Sauvegarde impossible: {}", err),
+        }
+    } else {
+        match SimulationState::load_from_file(&path) {
+            Ok(state) => {
+                // Les gestes notés menaient à la pâte quittée
+                if inputs.is_recording() {
+                    inputs.stop(&sim_resource.state);
+                }
+                sim_resource.state = state;
+                println!("Simulation rechargée depuis {}", path.display());
+            }
+            Err(err) => eprintln!("Chargement impossible: {}", err),
+        }
+    }
+}
+
+// Retour arrière : la pâte revient au point de reprise automatique le plus
+// proche d'il y a 20 minutes simulées, quand un rabat ou la pousse a mal tourné
+pub fn rewind_checkpoint(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    mut inputs: ResMut<InputRecording>,
+) {
+    let shift =
+        keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if shift || !keyboard_input.just_pressed(KeyCode::Backspace) {
+        return;
+    }
+    // Le calcul en cours écraserait la pâte ramenée en arrière
+    if !worker.settle(&mut sim_resource.state) {
+        eprintln!("Calcul en cours, retour en arrière impossible pour l'instant");
+        return;
+    }
+    // Les gestes notés menaient à la pâte quittée
+    if !sim_resource.state.checkpoints.is_empty() {
+        inputs.stop(&sim_resource.state);
+    }
+    let from = sim_resource.state.time_elapsed;
+    match sim_resource.state.rewind(REWIND_SECONDS) {
+        Ok(Some(time)) => println!(
+            "Retour en arrière de {:.0} min (t={:.0} s)",
+            (from - time) / 60.0,
+            time
+        ),
+        Ok(None) => println!("Aucun point de reprise pour revenir en arrière"),
+        Err(err) => eprintln!("Retour en arrière impossible: {}", err),
+    }
+}
+
+// Avance rapide : Maj+Retour arrière fait passer une heure de pointe en
+// quelques instants (la chimie par grands pas, la pâte ne bouge qu'entre
+// deux), puis la simulation reprend en détail. Elle s'arrête au four.
+pub fn fast_forward_hour(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    mut inputs: ResMut<InputRecording>,
+    playback: Res<InputPlayback>,
+) {
+    let shift =
+        keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if !shift || !keyboard_input.just_pressed(KeyCode::Backspace) || playback.is_active() {
+        return;
+    }
+    // Le calcul en cours écraserait la pâte avancée
+    if !worker.settle(&mut sim_resource.state) {
+        eprintln!("Calcul en cours, avance rapide impossible pour l'instant");
+        return;
+    }
+    // Une relecture des gestes ne referait pas l'avance rapide
+    inputs.stop(&sim_resource.state);
+    let report = sim_resource.state.fast_forward(FAST_FORWARD_SECONDS);
+    if report.to <= report.from {
+        println!("La pâte est au four, pas d'avance rapide");
+        return;
+    }
+    println!(
+        "Avance rapide de {:.0} min (t={:.0} s) : {} sucres fermentés{}",
+        (report.to - report.from) / 60.0,
+        report.to,
+        report.sugars_fermented,
+        if report.reached_bake {
+            ", arrêt au four"
+        } else {
+            ""
+        }
+    );
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use pain_core::{Bond, Molecule, MoleculeId, MoleculeType, SimChanges, SimulationState, SyncToken};
+use std::collections::{HashMap, HashSet};
+
+use crate::bond_strength::BondStrengthView;
+use crate::style::StyleAssets;
+use crate::{GlutenBond, MoleculeParticle, SimulationResource};
+
+type BondKey = (MoleculeId, MoleculeId);
+
+// Ce que la scène a déjà reçu de la pâte. Chaque image ne demande que ce qui
+// a changé depuis la précédente (journal de pain_core) : les entités des
+// molécules et des liaisons restent en place tant qu'elles existent.
+#[derive(Resource, Default)]
+pub struct SceneSync {
+    token: SyncToken,
+    changes: SimChanges, // Reçus à cette image
+    particles: HashMap<MoleculeId, Entity>,
+    bonds: HashMap<BondKey, Entity>,
+    bond_count: HashMap<MoleculeId, usize>, // Pour grossir les gluténines liées
+    moving: HashSet<MoleculeId>, // Bougées au dernier pas, interpolées à chaque image
+    alpha: f32, // Interpolation déjà dessinée
+    redraw: bool, // Les molécules en mouvement sont à replacer
+}
+
+// Même clé pour une liaison dans un sens ou dans l'autre, comme le journal
+fn bond_key(bond: &Bond) -> BondKey {
+    let (a, b) = (bond.molecule_a_id, bond.molecule_b_id);
+    (a.min(b), a.max(b))
+}
+
+pub fn pull_scene_changes(mut sim_resource: ResMut<SimulationResource>, mut sync: ResMut<SceneSync>) {
+    // Pâte neuve, rechargée, relue ou reçue de l'hôte : son journal commence ici
+    if !sim_resource.state.tracks_changes() {
+        sim_resource.state.track_changes();
+    }
+    let state = &sim_resource.state;
+    let changes = state.changes_since(sync.token);
+    let sync = &mut *sync;
+    sync.token = changes.token;
+    if changes.full {
+        sync.bond_count.clear();
+    }
+    for (a, b) in &changes.bonds_removed {
+        for id in [a, b] {
+            if let Some(count) = sync.bond_count.get_mut(id) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+    for (a, b) in &changes.bonds_added {
+        *sync.bond_count.entry(*a).or_insert(0) += 1;
+        *sync.bond_count.entry(*b).or_insert(0) += 1;
+    }
+    for id in &changes.removed {
+        sync.bond_count.remove(id);
+    }
+    if !changes.is_empty() {
+        sync.moving = changes.moved.iter().chain(&changes.added).copied().collect();
+    }
+    // Sans nouveau pas, l'interpolation peut tout de même avancer
+    let alpha = state.interpolation_alpha();
+    sync.redraw = !changes.is_empty() || alpha != sync.alpha;
+    sync.alpha = alpha;
+    sync.changes = changes;
+}
+
+fn particle_transform(
+    state: &SimulationState,
+    molecule: &Molecule,
+    bond_count: &HashMap<MoleculeId, usize>,
+    style: &StyleAssets,
+) -> Transform {
+    // Entre les deux derniers pas fixes, pour un mouvement fluide
+    let drawn = state.interpolated_pos(molecule);
+    // Taille de base, donnée par le thème
+    let mut scale = style.style.particle_radius * style.style.species_scale(molecule.mol_type.species());
+    // Si c'est une glutenine, on grossit selon le nombre de liaisons
+    if let MoleculeType::Glutenin { .. } = molecule.mol_type {
+        let n_bonds = bond_count.get(&molecule.id).copied().unwrap_or(0);
+        // 1.0 (seule) à 2.0 (très liée) avec le thème par défaut
+        scale *= 1.0 + (n_bonds as f32 * style.style.glutenin_bond_growth).min(style.style.glutenin_max_growth);
+    }
+    // Un amas d'eau garde le volume cumulé de ses molécules
+    if let MoleculeType::WaterCluster { size } = molecule.mol_type {
+        scale *= (size as f32).cbrt();
+    }
+    Transform::from_xyz(drawn.x, drawn.y, drawn.z).with_scale(Vec3::splat(scale))
+}
+
+// Système pour mettre à jour les particules : seules celles qui ont changé
+pub fn update_particles(
+    mut commands: Commands,
+    mut sync: ResMut<SceneSync>,
+    mut particle_query: Query<(&mut Transform, &mut MoleculeParticle, &mut Handle<StandardMaterial>)>,
+    sim_resource: Res<SimulationResource>,
+    style: Res<StyleAssets>,
+) {
+    let state = &sim_resource.state;
+    let sync = &mut *sync;
+    let changes = &sync.changes;
+
+    if changes.full {
+        for (_, entity) in sync.particles.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+    for id in &changes.removed {
+        if let Some(entity) = sync.particles.remove(id) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for molecule in changes.added.iter().filter_map(|id| state.grid.get_molecule(*id)) {
+        let entity = commands
+            .spawn((
+                PbrBundle {
+                    mesh: style.sphere_mesh.clone(),
+                    material: style.materials[&molecule.mol_type.species()].clone(),
+                    transform: particle_transform(state, molecule, &sync.bond_count, &style),
+                    ..default()
+                },
+                MoleculeParticle {
+                    id: molecule.id,
+                    mol_type: molecule.mol_type.clone(),
+                },
+            ))
+            .id();
+        sync.particles.insert(molecule.id, entity);
+    }
+
+    // Le thème change la taille de toutes les particules
+    let mut stale: HashSet<MoleculeId> = if style.is_changed() {
+        sync.particles.keys().copied().collect()
+    } else {
+        HashSet::new()
+    };
+    if sync.redraw {
+        stale.extend(&sync.moving);
+    }
+    stale.extend(&changes.retyped);
+    // Une gluténine grossit avec ses liaisons
+    stale.extend(changes.bonds_added.iter().chain(&changes.bonds_removed).flat_map(|(a, b)| [*a, *b]));
+    for id in stale {
+        let (Some(entity), Some(molecule)) = (sync.particles.get(&id), state.grid.get_molecule(id)) else {
+            continue;
+        };
+        // Créée à cette image : déjà à sa place
+        let Ok((mut transform, mut particle, mut material)) = particle_query.get_mut(*entity) else {
+            continue;
+        };
+        *transform = particle_transform(state, molecule, &sync.bond_count, &style);
+        if particle.mol_type != molecule.mol_type {
+            // L'espèce change (gluténine liée) : on change de matériau
+            let species = molecule.mol_type.species();
+            if particle.mol_type.species() != species {
+                *material = style.materials[&species].clone();
+            }
+            particle.mol_type = molecule.mol_type.clone();
+        }
+    }
+}
+
+// Cylindre unité étiré entre les deux particules, à leurs positions interpolées
+fn bond_transform(state: &SimulationState, mol_a: &Molecule, mol_b: &Molecule, style: &StyleAssets) -> Transform {
+    let drawn_a = state.interpolated_pos(mol_a);
+    let drawn_b = state.interpolated_pos(mol_b);
+    let pos_a = Vec3::new(drawn_a.x, drawn_a.y, drawn_a.z);
+    let pos_b = Vec3::new(drawn_b.x, drawn_b.y, drawn_b.z);
+    let direction = (pos_b - pos_a).try_normalize().unwrap_or(Vec3::Y);
+    let radius = style.style.bond_radius;
+    Transform::from_translation((pos_a + pos_b) / 2.0)
+        .with_rotation(Quat::from_rotation_arc(Vec3::Y, direction))
+        .with_scale(Vec3::new(radius, pos_a.distance(pos_b), radius))
+}
+
+fn bond_material(
+    state: &SimulationState,
+    bond: &Bond,
+    style: &StyleAssets,
+    bond_view: &BondStrengthView,
+) -> Handle<StandardMaterial> {
+    if bond_view.tinted {
+        // Du jaune pâle (sur le point de rompre) à la couleur des liaisons intactes
+        let strength = state.bond_strength(bond).unwrap_or(1.0);
+        style.bond_strength_material(strength).clone()
+    } else {
+        style.bond_material.clone()
+    }
+}
+
+// Système pour mettre à jour les liaisons (bonds) : créées et supprimées au
+// fil du journal, replacées quand une de leurs molécules bouge
+pub fn update_bonds(
+    mut commands: Commands,
+    mut sync: ResMut<SceneSync>,
+    mut bond_query: Query<(&mut Transform, &mut Handle<StandardMaterial>), With<GlutenBond>>,
+    sim_resource: Res<SimulationResource>,
+    style: Res<StyleAssets>,
+    bond_view: Res<BondStrengthView>,
+) {
+    let state = &sim_resource.state;
+    let sync = &mut *sync;
+    let changes = &sync.changes;
+
+    if changes.full {
+        for (_, entity) in sync.bonds.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+    for key in &changes.bonds_removed {
+        if let Some(entity) = sync.bonds.remove(key) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let added: HashSet<BondKey> = changes.bonds_added.iter().copied().collect();
+    // Coloration par force : elle bouge à chaque pas, pour toutes les liaisons
+    let restyle = style.is_changed() || bond_view.is_changed() || (bond_view.tinted && !changes.is_empty());
+    for bond in state.bonds() {
+        let key = bond_key(bond);
+        let new = added.contains(&key) && !sync.bonds.contains_key(&key);
+        let moved = sync.redraw && (sync.moving.contains(&key.0) || sync.moving.contains(&key.1));
+        if !(new || moved || restyle) {
+            continue;
+        }
+        let (Some(mol_a), Some(mol_b)) = (
+            state.grid.get_molecule(bond.molecule_a_id),
+            state.grid.get_molecule(bond.molecule_b_id),
+        ) else {
+            continue;
+        };
+        let transform = bond_transform(state, mol_a, mol_b, &style);
+        if new {
+            let entity = commands
+                .spawn((
+                    PbrBundle {
+                        mesh: style.bond_mesh.clone(),
+                        material: bond_material(state, bond, &style, &bond_view),
+                        transform,
+                        ..default()
+                    },
+                    GlutenBond {
+                        molecule_a_id: bond.molecule_a_id,
+                        molecule_b_id: bond.molecule_b_id,
+                    },
+                ))
+                .id();
+            sync.bonds.insert(key, entity);
+        } else if let Some(Ok((mut bond_transform, mut material))) =
+            sync.bonds.get(&key).map(|entity| bond_query.get_mut(*entity))
+        {
+            *bond_transform = transform;
+            if restyle {
+                *material = bond_material(state, bond, &style, &bond_view);
+            }
+        }
+    }
+}
@@ -0,0 +1,499 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use pain_core::timeline::{
+    format_duration, parse_duration, Timeline, TimelineAction, TimelineStep,
+};
+use pain_core::{ActionScheduler, BakeSettings, Recipe, SimulationState, StepCondition};
+use std::mem::discriminant;
+use std::path::PathBuf;
+
+use crate::preferences::PreferencesResource;
+use crate::worker::SimulationWorker;
+use crate::SimulationResource;
+
+// Écart entre une étape ajoutée et la dernière
+const NEW_STEP_GAP: f32 = 30.0 * 60.0;
+const PREVIEW_HEIGHT: f32 = 36.0;
+
+// Déclencheurs proposés, avec leur seuil de départ
+const CONDITIONS: [StepCondition; 5] = [
+    StepCondition::DevelopmentAtLeast(0.8),
+    StepCondition::RiseAtLeast(0.5),
+    StepCondition::PhAtMost(4.2),
+    StepCondition::TemperatureAtLeast(24.0),
+    StepCondition::TemperatureAtMost(6.0),
+];
+
+// Délais proposés pour une étape qui attend son déclencheur
+const TIMEOUTS: [Option<f32>; 5] = [
+    None,
+    Some(1800.0),
+    Some(3600.0),
+    Some(7200.0),
+    Some(14400.0),
+];
+
+// --schedule <fichier> remplace le planning de la recette
+fn schedule_arg() -> Option<PathBuf> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .find(|pair| pair[0] == "--schedule")
+        .map(|pair| PathBuf::from(&pair[1]))
+}
+
+// Planning : --schedule <fichier> ou les étapes du fichier de la recette
+fn schedule_path(prefs: &pain_config::Preferences) -> Option<PathBuf> {
+    schedule_arg().or_else(|| crate::recipe_path(prefs))
+}
+
+// Charge le planning enregistré dans la nouvelle pâte, s'il existe
+pub fn load_schedule(state: &mut SimulationState, prefs: &pain_config::Preferences) {
+    let Some(path) = schedule_path(prefs).filter(|path| path.exists()) else {
+        return;
+    };
+    match ActionScheduler::load_from(&path, state) {
+        Ok(scheduler) if scheduler.schedule().steps().is_empty() => {}
+        Ok(scheduler) => {
+            println!(
+                "Planning {} : {} étapes",
+                path.display(),
+                scheduler.schedule().steps().len()
+            );
+            state.scheduler = scheduler;
+        }
+        Err(err) => eprintln!("Planning {} ignoré: {}", path.display(), err),
+    }
+}
+
+// Planning de départ quand la recette n'en a pas encore
+fn default_timeline() -> Timeline {
+    let step = |at: f32, action: TimelineAction| TimelineStep {
+        at,
+        action: Some(action),
+        label: None,
+        temperature: None,
+        when: None,
+        timeout: None,
+    };
+    Timeline {
+        steps: vec![
+            step(1800.0, TimelineAction::Salt),
+            step(1800.0, TimelineAction::Yeast),
+            step(3600.0, TimelineAction::Fold),
+            step(5400.0, TimelineAction::Fold),
+            step(7200.0, TimelineAction::Fold),
+            TimelineStep {
+                when: Some(StepCondition::RiseAtLeast(0.5)),
+                timeout: Some(3600.0),
+                ..step(4.0 * 3600.0, TimelineAction::Shape)
+            },
+            step(8.0 * 3600.0, TimelineAction::Bake),
+        ],
+    }
+}
+
+fn condition_name(condition: StepCondition) -> &'static str {
+    match condition {
+        StepCondition::DevelopmentAtLeast(_) => "développement ≥",
+        StepCondition::RiseAtLeast(_) => "levée ≥",
+        StepCondition::PhAtMost(_) => "pH ≤",
+        StepCondition::TemperatureAtLeast(_) => "température ≥",
+        StepCondition::TemperatureAtMost(_) => "température ≤",
+    }
+}
+
+fn timeout_label(timeout: Option<f32>) -> String {
+    match timeout {
+        Some(seconds) => format!("délai {}", format_duration(seconds)),
+        None => "sans délai".to_string(),
+    }
+}
+
+// Action de l'étape ; le four et la consigne de température se règlent à côté
+fn action_picker(ui: &mut egui::Ui, index: usize, step: &mut TimelineStep) {
+    let before = step.action;
+    egui::ComboBox::from_id_source(("action", index))
+        .selected_text(step.label())
+        .width(190.0)
+        .show_ui(ui, |ui| {
+            for action in TimelineAction::all() {
+                ui.selectable_value(&mut step.action, Some(action), action.label(None));
+            }
+        });
+    if step.action != before {
+        step.label = None;
+        step.temperature = match step.action {
+            Some(TimelineAction::Temperature) => Some(24.0),
+            Some(TimelineAction::Bake) => Some(BakeSettings::default().oven_temperature),
+            _ => None,
+        };
+    }
+    let range = match step.action {
+        Some(TimelineAction::Bake) => 100.0..=300.0,
+        _ => -20.0..=60.0,
+    };
+    match step.temperature.as_mut() {
+        Some(temperature) => {
+            ui.add(egui::DragValue::new(temperature).range(range).suffix(" °C"));
+        }
+        None => {
+            ui.label("");
+        }
+    }
+}
+
+fn percent_editor(ui: &mut egui::Ui, share: &mut f32, max: f32) {
+    let mut percent = *share * 100.0;
+    let response = ui.add(
+        egui::DragValue::new(&mut percent)
+            .range(0.0..=max)
+            .suffix(" %"),
+    );
+    if response.changed() {
+        *share = percent / 100.0;
+    }
+}
+
+// Seuil du déclencheur, en pourcentage pour les jauges
+fn threshold_editor(ui: &mut egui::Ui, condition: &mut StepCondition) {
+    match condition {
+        StepCondition::DevelopmentAtLeast(share) => percent_editor(ui, share, 100.0),
+        StepCondition::RiseAtLeast(share) => percent_editor(ui, share, 300.0),
+        StepCondition::PhAtMost(ph) => {
+            ui.add(
+                egui::DragValue::new(ph)
+                    .speed(0.05)
+                    .range(3.0..=7.0)
+                    .fixed_decimals(1),
+            );
+        }
+        StepCondition::TemperatureAtLeast(temperature)
+        | StepCondition::TemperatureAtMost(temperature) => {
+            ui.add(
+                egui::DragValue::new(temperature)
+                    .range(-20.0..=60.0)
+                    .suffix(" °C"),
+            );
+        }
+    }
+}
+
+// Déclencheur de l'étape : à l'heure, ou dès qu'une mesure est atteinte avec
+// un délai au-delà duquel elle part quand même
+fn trigger_picker(ui: &mut egui::Ui, index: usize, step: &mut TimelineStep) {
+    ui.horizontal(|ui| {
+        let selected = step.when.map_or("à l'heure", condition_name);
+        egui::ComboBox::from_id_source(("when", index))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut step.when, None, "à l'heure");
+                for condition in CONDITIONS {
+                    let same = step
+                        .when
+                        .is_some_and(|when| discriminant(&when) == discriminant(&condition));
+                    if ui
+                        .selectable_label(same, condition_name(condition))
+                        .clicked()
+                        && !same
+                    {
+                        step.when = Some(condition);
+                    }
+                }
+            });
+        if let Some(condition) = step.when.as_mut() {
+            threshold_editor(ui, condition);
+            egui::ComboBox::from_id_source(("timeout", index))
+                .selected_text(timeout_label(step.timeout))
+                .show_ui(ui, |ui| {
+                    for timeout in TIMEOUTS {
+                        ui.selectable_value(&mut step.timeout, timeout, timeout_label(timeout));
+                    }
+                });
+        }
+    });
+}
+
+// Éditeur de planning (F1) : étapes, déclencheurs et délais, enregistrés
+// dans le fichier de la recette
+#[derive(Resource, Default)]
+pub struct ScheduleEditor {
+    pub visible: bool,
+    pub timeline: Timeline,
+    recipe: Option<String>, // Recette dont le planning est ouvert
+    path: Option<PathBuf>,
+    message: Option<String>,
+}
+
+impl ScheduleEditor {
+    // Planning de la recette choisie, ou celui de départ
+    fn open(&mut self, prefs: &pain_config::Preferences) {
+        self.path = schedule_path(prefs);
+        self.recipe = Some(prefs.last_recipe.clone());
+        self.message = None;
+        self.timeline = match self.path.as_deref().filter(|path| path.exists()) {
+            Some(path) => match Timeline::load_from(path) {
+                Ok(timeline) if timeline.steps.is_empty() => default_timeline(),
+                Ok(timeline) => timeline,
+                Err(err) => {
+                    self.message = Some(format!("Planning {} illisible: {}", path.display(), err));
+                    default_timeline()
+                }
+            },
+            None => default_timeline(),
+        };
+    }
+
+    fn sort_steps(&mut self) {
+        self.timeline.steps.sort_by(|a, b| a.at.total_cmp(&b.at));
+    }
+
+    // Déplacer une étape dans la liste échange son heure avec sa voisine
+    fn swap_steps(&mut self, a: usize, b: usize) {
+        let at = self.timeline.steps[a].at;
+        self.timeline.steps[a].at = self.timeline.steps[b].at;
+        self.timeline.steps[b].at = at;
+        self.timeline.steps.swap(a, b);
+    }
+
+    fn add_step(&mut self) {
+        let at = self
+            .timeline
+            .steps
+            .last()
+            .map_or(0.0, |step| step.at + NEW_STEP_GAP);
+        self.timeline.steps.push(TimelineStep {
+            at,
+            action: Some(TimelineAction::Fold),
+            label: None,
+            temperature: None,
+            when: None,
+            timeout: None,
+        });
+    }
+
+    // Une ligne par étape : faite ou non, heure, action, déclencheur
+    fn steps_grid(&mut self, ui: &mut egui::Ui, now: f32) {
+        if self.timeline.steps.is_empty() {
+            ui.label("Aucune étape");
+            return;
+        }
+        let count = self.timeline.steps.len();
+        let mut swap = None;
+        let mut remove = None;
+        let mut sort = false;
+        egui::Grid::new("schedule_steps")
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, step) in self.timeline.steps.iter_mut().enumerate() {
+                    let done = step.deadline().is_some_and(|deadline| deadline <= now);
+                    ui.label(if done { "✔" } else { "" });
+                    let time = ui.add(
+                        egui::DragValue::new(&mut step.at)
+                            .speed(60.0)
+                            .range(0.0..=f32::MAX)
+                            .custom_formatter(|seconds, _| format_duration(seconds as f32))
+                            .custom_parser(|text| parse_duration(text).map(f64::from)),
+                    );
+                    // Remise dans l'ordre une fois l'heure choisie, pas pendant
+                    // qu'on la fait glisser
+                    sort |= time.drag_stopped() || time.lost_focus();
+                    action_picker(ui, index, step);
+                    trigger_picker(ui, index, step);
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(index > 0, egui::Button::new("↑")).clicked() {
+                            swap = Some((index, index - 1));
+                        }
+                        if ui
+                            .add_enabled(index + 1 < count, egui::Button::new("↓"))
+                            .clicked()
+                        {
+                            swap = Some((index, index + 1));
+                        }
+                        if ui.button("🗑").on_hover_text("Retirer l'étape").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        if let Some((a, b)) = swap {
+            self.swap_steps(a, b);
+        }
+        if let Some(index) = remove {
+            self.timeline.steps.remove(index);
+        }
+        if sort {
+            self.sort_steps();
+        }
+    }
+
+    // Frise de 0 à la dernière échéance : les étapes, la fenêtre pendant
+    // laquelle leur déclencheur peut partir et l'heure de la pâte
+    fn preview(&self, ui: &mut egui::Ui, now: f32) {
+        let end = self
+            .timeline
+            .steps
+            .iter()
+            .map(|step| step.deadline().unwrap_or(step.at))
+            .fold(now, f32::max)
+            .max(1.0);
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), PREVIEW_HEIGHT),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        let x = |time: f32| egui::lerp(rect.left()..=rect.right(), (time / end).min(1.0));
+        let middle = rect.center().y;
+        painter.hline(
+            rect.x_range(),
+            middle,
+            egui::Stroke::new(1.0, visuals.weak_text_color()),
+        );
+        for step in &self.timeline.steps {
+            if step.when.is_some() {
+                let until = step.deadline().unwrap_or(end);
+                let window =
+                    egui::Rect::from_x_y_ranges(x(step.at)..=x(until), middle - 4.0..=middle + 4.0);
+                painter.rect_filled(window, 2.0, visuals.selection.bg_fill);
+            }
+            let color = if step.deadline().is_some_and(|deadline| deadline <= now) {
+                visuals.weak_text_color()
+            } else {
+                visuals.strong_text_color()
+            };
+            painter.vline(
+                x(step.at),
+                middle - 10.0..=middle + 10.0,
+                egui::Stroke::new(2.0, color),
+            );
+        }
+        painter.vline(
+            x(now),
+            rect.y_range(),
+            egui::Stroke::new(2.0, egui::Color32::from_rgb(230, 90, 60)),
+        );
+        // L'étape la plus proche du pointeur
+        if let Some(pointer) = response.hover_pos() {
+            let distance = |step: &TimelineStep| (x(step.at) - pointer.x).abs();
+            let nearest = self
+                .timeline
+                .steps
+                .iter()
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+            if let Some(step) = nearest {
+                response.on_hover_text(format!("{} : {}", format_duration(step.at), step.label()));
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("0");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(format_duration(end));
+            });
+        });
+        let mut summary = format!("Maintenant : {}", format_duration(now));
+        if let Some(next) = self.timeline.steps.iter().find(|step| step.at > now) {
+            summary.push_str(&format!(
+                ", prochaine étape dans {} : {}",
+                format_duration(next.at - now),
+                next.label()
+            ));
+        }
+        ui.label(summary);
+    }
+
+    // Enregistre les étapes dans le fichier de la recette et remplace le
+    // planning de la pâte en cours ; les étapes déjà passées ne sont pas
+    // rejouées. Un fichier de recette neuf part des valeurs de `recipe`.
+    fn save(&mut self, state: &mut SimulationState, recipe: &Recipe) {
+        let mut scheduler = self.timeline.scheduler(state);
+        scheduler.skip_before(state.time_elapsed);
+        state.scheduler = scheduler;
+        let Some(path) = &self.path else {
+            self.message = Some("Planning appliqué (pas de dossier de configuration)".to_string());
+            return;
+        };
+        let fresh_recipe = !path.exists() && schedule_arg().is_none();
+        let saved = if fresh_recipe {
+            recipe.save_to(path)
+        } else {
+            Ok(())
+        }
+        .and_then(|()| self.timeline.save_into(path));
+        self.message = Some(match saved {
+            Ok(()) => format!("Planning enregistré dans {}", path.display()),
+            Err(err) => format!("Impossible d'enregistrer le planning: {}", err),
+        });
+    }
+}
+
+// Fenêtre de l'éditeur de planning (F1). Tant qu'elle se sert du clavier ou
+// de la souris, les raccourcis et la caméra ne les voient pas.
+pub fn schedule_editor_ui(
+    mut contexts: EguiContexts,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut mouse_input: ResMut<ButtonInput<MouseButton>>,
+    mut editor: ResMut<ScheduleEditor>,
+    mut sim_resource: ResMut<SimulationResource>,
+    mut worker: ResMut<SimulationWorker>,
+    prefs: Res<PreferencesResource>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        editor.visible = !editor.visible;
+    }
+    if !editor.visible {
+        return;
+    }
+    // Une autre recette a son propre planning
+    if editor.recipe.as_deref() != Some(prefs.0.last_recipe.as_str()) {
+        editor.open(&prefs.0);
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let now = sim_resource.state.time_elapsed;
+    let mut open = true;
+    let mut save = false;
+    let mut reload = false;
+    egui::Window::new("Planning")
+        .open(&mut open)
+        .default_width(640.0)
+        .show(ctx, |ui| {
+            editor.steps_grid(ui, now);
+            if ui.button("Ajouter une étape").clicked() {
+                editor.add_step();
+            }
+            ui.separator();
+            editor.preview(ui, now);
+            ui.separator();
+            ui.horizontal(|ui| {
+                save = ui
+                    .button("Enregistrer dans la recette et appliquer")
+                    .clicked();
+                reload = ui.button("Annuler les modifications").clicked();
+            });
+            if let Some(message) = &editor.message {
+                ui.label(message);
+            }
+        });
+    editor.visible &= open;
+    if reload {
+        editor.open(&prefs.0);
+    }
+    if save {
+        // Le calcul en cours écraserait le planning installé
+        if worker.settle(&mut sim_resource.state) {
+            let recipe = crate::recipe(&prefs.0);
+            editor.save(&mut sim_resource.state, &recipe);
+        } else {
+            editor.message =
+                Some("Calcul en cours, planning non enregistré pour l'instant".to_string());
+        }
+    }
+    if ctx.wants_keyboard_input() {
+        keyboard_input.reset_all();
+    }
+    if ctx.wants_pointer_input() || ctx.is_pointer_over_area() {
+        mouse_input.reset_all();
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+// Étapes d'une frame, exécutées dans cet ordre : les entrées modifient la
+// simulation, le pas la fait avancer, les entités suivent l'état, puis l'UI
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FrameSet {
+    Input,
+    SimStep,
+    SyncEntities,
+    Ui,
+}
+
+// L'application attend ses ressources (police, ...) avant de simuler
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AppState {
+    #[default]
+    Loading,
+    Playing,
+}
+
+// Ressources chargées au démarrage dont dépend l'état Playing
+#[derive(Resource, Default)]
+pub struct LoadingAssets(pub Vec<UntypedHandle>);
+
+pub fn configure_frame_sets(app: &mut App) {
+    app.init_state::<AppState>()
+        .init_resource::<LoadingAssets>()
+        .configure_sets(
+            Update,
+            (
+                FrameSet::Input,
+                FrameSet::SimStep,
+                FrameSet::SyncEntities,
+                FrameSet::Ui,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(Update, finish_loading.run_if(in_state(AppState::Loading)));
+}
+
+// Passe en Playing quand tout est chargé ; un fichier manquant ne bloque pas le démarrage
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    loading: Res<LoadingAssets>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let ready = loading.0.iter().all(|handle| {
+        asset_server.is_loaded_with_dependencies(handle.id())
+            || matches!(asset_server.get_load_state(handle.id()), Some(LoadState::Failed(_)) | None)
+    });
+    if ready {
+        next_state.set(AppState::Playing);
+    }
+}
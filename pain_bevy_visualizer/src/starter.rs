@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use pain_core::starter::{
+    default_feeding_history, diagnose_starter, parse_feeding_history, Feeding,
+};
+use pain_core::StarterReport;
+
+// Diagnostic du levain (F4) : historique de rafraîchis, ajustable au clavier
+#[derive(Resource)]
+pub struct StarterDialog {
+    pub visible: bool,
+    pub history: Vec<Feeding>,
+    pub report: StarterReport,
+}
+
+impl Default for StarterDialog {
+    fn default() -> Self {
+        // --starter "1:2:2@24/12,..." remplace la routine par défaut
+        let args: Vec<String> = std::env::args().collect();
+        let history = args
+            .windows(2)
+            .find(|pair| pair[0] == "--starter")
+            .and_then(|pair| parse_feeding_history(&pair[1]).ok())
+            .unwrap_or_else(default_feeding_history);
+        StarterDialog {
+            visible: false,
+            report: diagnose_starter(&history),
+            history,
+        }
+    }
+}
+
+impl StarterDialog {
+    fn text(&self) -> String {
+        let mut text = String::from("[F4] Diagnostic du levain\n\n");
+        for (i, feeding) in self.history.iter().enumerate() {
+            text.push_str(&format!(
+                "Rafraîchi {}: {}:{}:{} à {:.0}°C pendant {:.0} h\n",
+                i + 1,
+                feeding.starter,
+                feeding.flour,
+                feeding.water,
+                feeding.temperature,
+                feeding.hours
+            ));
+        }
+        text.push_str(&format!(
+            "\n{}\n\n[PgPréc/PgSuiv: température] [Début/Fin: part de farine et d'eau]",
+            self.report.summary()
+        ));
+        text
+    }
+}
+
+#[derive(Component)]
+pub struct StarterDialogText;
+
+pub fn setup_starter_dialog(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::srgb(1.0, 0.9, 0.7),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.1, 0.05, 0.0, 0.85).into(),
+            ..default()
+        },
+        StarterDialogText,
+    ));
+}
+
+pub fn update_starter_dialog(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut dialog: ResMut<StarterDialog>,
+    mut query: Query<(&mut Text, &mut Style), With<StarterDialogText>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        dialog.visible = !dialog.visible;
+    }
+    if dialog.visible {
+        let mut temperature_change = 0.0;
+        let mut flour_change = 0.0;
+        if keyboard_input.just_pressed(KeyCode::PageUp) {
+            temperature_change = 1.0;
+        }
+        if keyboard_input.just_pressed(KeyCode::PageDown) {
+            temperature_change = -1.0;
+        }
+        if keyboard_input.just_pressed(KeyCode::End) {
+            flour_change = 1.0;
+        }
+        if keyboard_input.just_pressed(KeyCode::Home) {
+            flour_change = -1.0;
+        }
+        if temperature_change != 0.0 || flour_change != 0.0 {
+            for feeding in &mut dialog.history {
+                feeding.temperature = (feeding.temperature + temperature_change).clamp(4.0, 40.0);
+                feeding.flour = (feeding.flour + flour_change).clamp(1.0, 20.0);
+                feeding.water = (feeding.water + flour_change).clamp(1.0, 20.0);
+            }
+            dialog.report = diagnose_starter(&dialog.history);
+        }
+    }
+
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if dialog.visible { Display::Flex } else { Display::None };
+        if dialog.visible {
+            text.sections[0].value = dialog.text();
+        }
+    }
+}
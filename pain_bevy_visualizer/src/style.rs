@@ -0,0 +1,280 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use pain_core::{Species, SpeciesInfo};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::preferences::PreferencesResource;
+use crate::sets::LoadingAssets;
+
+// Les thèmes sont cherchés dans assets/styles/<palette>.style.ron
+const STYLE_DIR: &str = "styles";
+
+// Nuances entre liaison intacte et liaison sur le point de rompre (F2)
+const BOND_STRENGTH_LEVELS: usize = 8;
+
+// Apparence de la scène, chargée depuis un fichier RON et rechargée à chaud
+// quand il est modifié. Les valeurs absentes gardent leur valeur par défaut,
+// les couleurs absentes viennent du registre des espèces.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VisualStyle {
+    pub base_palette: String, // Couleurs du registre : "default" ou "colorblind"
+    pub particle_radius: f32,
+    pub reflectance: f32,
+    pub glutenin_bond_growth: f32, // Grossissement d'une gluténine par liaison
+    pub glutenin_max_growth: f32,
+    pub bond_radius: f32,
+    pub bond_color: [f32; 3],
+    pub weak_bond_color: [f32; 3], // Liaison sans réserve, en coloration par force
+    pub bond_roughness: f32,
+    pub species: HashMap<Species, SpeciesStyle>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SpeciesStyle {
+    pub color: Option<[f32; 3]>,
+    pub scale: f32,
+    pub roughness: Option<f32>,
+}
+
+impl Default for VisualStyle {
+    fn default() -> Self {
+        VisualStyle {
+            base_palette: "default".to_string(),
+            particle_radius: 3.0,
+            reflectance: 0.2,
+            glutenin_bond_growth: 0.3,
+            glutenin_max_growth: 1.0,
+            bond_radius: 5.0,
+            bond_color: [1.0, 0.3, 0.5],
+            weak_bond_color: [0.9, 0.8, 0.3],
+            bond_roughness: 0.5,
+            species: HashMap::new(),
+        }
+    }
+}
+
+impl Default for SpeciesStyle {
+    fn default() -> Self {
+        SpeciesStyle {
+            color: None,
+            scale: 1.0,
+            roughness: None,
+        }
+    }
+}
+
+impl VisualStyle {
+    // Couleur d'une espèce : celle du thème, sinon celle de la palette du registre
+    // Palette "colorblind" : couleurs Okabe-Ito, distinguables pour les daltoniens
+    pub fn species_color(&self, info: &SpeciesInfo) -> Color {
+        let registry = match self.base_palette.as_str() {
+            "colorblind" => info.colorblind_color,
+            _ => info.color,
+        };
+        let [r, g, b] = self
+            .species
+            .get(&info.species)
+            .and_then(|style| style.color)
+            .unwrap_or(registry);
+        Color::srgb(r, g, b)
+    }
+
+    pub fn species_scale(&self, species: Species) -> f32 {
+        self.species.get(&species).map_or(1.0, |style| style.scale)
+    }
+
+    fn species_roughness(&self, info: &SpeciesInfo) -> f32 {
+        self.species
+            .get(&info.species)
+            .and_then(|style| style.roughness)
+            .unwrap_or(info.roughness)
+    }
+
+    fn bond_material(&self) -> StandardMaterial {
+        self.bond_strength_material(1.0)
+    }
+
+    // Mélange la couleur des liaisons faibles et celle des liaisons intactes
+    fn bond_strength_material(&self, strength: f32) -> StandardMaterial {
+        let weak = Color::srgb(self.weak_bond_color[0], self.weak_bond_color[1], self.weak_bond_color[2]);
+        let [r, g, b] = self.bond_color;
+        StandardMaterial {
+            base_color: weak.mix(&Color::srgb(r, g, b), strength.clamp(0.0, 1.0)),
+            perceptual_roughness: self.bond_roughness,
+            ..default()
+        }
+    }
+
+    fn bond_strength_materials(&self) -> impl Iterator<Item = StandardMaterial> + '_ {
+        (0..BOND_STRENGTH_LEVELS)
+            .map(|level| self.bond_strength_material(level as f32 / (BOND_STRENGTH_LEVELS - 1) as f32))
+    }
+
+    fn species_material(&self, info: &SpeciesInfo) -> StandardMaterial {
+        StandardMaterial {
+            base_color: self.species_color(info),
+            perceptual_roughness: self.species_roughness(info),
+            reflectance: self.reflectance,
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StyleLoadError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for StyleLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StyleLoadError::Io(err) => write!(f, "lecture du thème impossible: {}", err),
+            StyleLoadError::Ron(err) => write!(f, "thème invalide: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StyleLoadError {}
+
+#[derive(Default)]
+pub struct VisualStyleLoader;
+
+impl AssetLoader for VisualStyleLoader {
+    type Asset = VisualStyle;
+    type Settings = ();
+    type Error = StyleLoadError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<VisualStyle, StyleLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(StyleLoadError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(StyleLoadError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["style.ron"]
+    }
+}
+
+// Thème actif et matériaux partagés par toutes les particules ; modifier le
+// thème met à jour les matériaux en place, sans recréer les entités
+#[derive(Resource)]
+pub struct StyleAssets {
+    pub handle: Handle<VisualStyle>,
+    pub style: VisualStyle,
+    pub materials: HashMap<Species, Handle<StandardMaterial>>,
+    pub bond_material: Handle<StandardMaterial>,
+    pub bond_strength_materials: Vec<Handle<StandardMaterial>>, // Du plus faible au plus solide
+    pub sphere_mesh: Handle<Mesh>, // Sphère unité, mise à l'échelle par particule
+    pub bond_mesh: Handle<Mesh>, // Cylindre unité, étiré par liaison
+}
+
+impl FromWorld for StyleAssets {
+    fn from_world(world: &mut World) -> Self {
+        let palette = world
+            .get_resource::<PreferencesResource>()
+            .map(|prefs| prefs.0.palette.clone())
+            .unwrap_or_else(|| "default".to_string());
+        let handle: Handle<VisualStyle> = world
+            .resource::<AssetServer>()
+            .load(format!("{}/{}.style.ron", STYLE_DIR, palette));
+        if let Some(mut loading) = world.get_resource_mut::<LoadingAssets>() {
+            loading.0.push(handle.clone().untyped());
+        }
+
+        // En attendant le fichier (ou s'il manque), la palette des préférences
+        let style = VisualStyle {
+            base_palette: palette,
+            ..default()
+        };
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let species_materials = Species::all()
+            .map(|info| (info.species, materials.add(style.species_material(info))))
+            .collect();
+        let bond_material = materials.add(style.bond_material());
+        let bond_strength_materials = style
+            .bond_strength_materials()
+            .map(|material| materials.add(material))
+            .collect();
+        let sphere_mesh = world.resource_mut::<Assets<Mesh>>().add(Sphere::new(1.0));
+        let bond_mesh = world.resource_mut::<Assets<Mesh>>().add(Cylinder::new(1.0, 1.0));
+        StyleAssets {
+            handle,
+            style,
+            materials: species_materials,
+            bond_material,
+            bond_strength_materials,
+            sphere_mesh,
+            bond_mesh,
+        }
+    }
+}
+
+impl StyleAssets {
+    // Matériau de la nuance la plus proche d'une force de liaison (0 à 1)
+    pub fn bond_strength_material(&self, strength: f32) -> &Handle<StandardMaterial> {
+        let last = self.bond_strength_materials.len().saturating_sub(1);
+        let level = (strength.clamp(0.0, 1.0) * last as f32).round() as usize;
+        &self.bond_strength_materials[level.min(last)]
+    }
+}
+
+// Texte de la légende, recoloré quand le thème change
+#[derive(Component)]
+pub struct LegendText;
+
+// Applique le thème chargé ou modifié sur le disque
+pub fn reload_visual_style(
+    mut events: EventReader<AssetEvent<VisualStyle>>,
+    styles: Res<Assets<VisualStyle>>,
+    mut assets: ResMut<StyleAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut legend: Query<&mut Text, With<LegendText>>,
+) {
+    let changed = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == assets.handle.id()
+        }
+        _ => false,
+    });
+    if !changed {
+        return;
+    }
+    let Some(style) = styles.get(&assets.handle) else {
+        return;
+    };
+    assets.style = style.clone();
+
+    for info in Species::all() {
+        if let Some(material) = assets
+            .materials
+            .get(&info.species)
+            .and_then(|handle| materials.get_mut(handle))
+        {
+            *material = style.species_material(info);
+        }
+    }
+    if let Some(material) = materials.get_mut(&assets.bond_material) {
+        *material = style.bond_material();
+    }
+    for (handle, material) in assets.bond_strength_materials.iter().zip(style.bond_strength_materials()) {
+        if let Some(slot) = materials.get_mut(handle) {
+            *slot = material;
+        }
+    }
+    for mut text in legend.iter_mut() {
+        let font = text.sections[0].style.font.clone();
+        *text = crate::legend_text(&font, style);
+    }
+    println!("Thème visuel appliqué");
+}
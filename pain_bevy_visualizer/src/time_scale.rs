@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 
+// Facteurs proposés par +/- : du ralenti au quart jusqu'à mille fois plus vite
+const SCALES: [f32; 13] = [
+    0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 125.0, 250.0, 500.0, 1000.0,
+];
+
 #[derive(Resource)]
 pub struct TimeScale(pub f32);
 
@@ -8,3 +13,86 @@ impl Default for TimeScale {
         TimeScale(1.0)
     }
 }
+
+impl TimeScale {
+    // Facteur suivant, même depuis un facteur hors de la liste (démo)
+    pub fn faster(&mut self) {
+        self.0 = SCALES
+            .iter()
+            .copied()
+            .find(|&scale| scale > self.0)
+            .unwrap_or(SCALES[SCALES.len() - 1]);
+    }
+
+    pub fn slower(&mut self) {
+        self.0 = SCALES
+            .iter()
+            .copied()
+            .rfind(|&scale| scale < self.0)
+            .unwrap_or(SCALES[0]);
+    }
+}
+
+// Pause (Espace) et pas à pas (Entrée) : le facteur temps reste celui choisi
+#[derive(Resource, Default)]
+pub struct SimulationPause {
+    pub paused: bool,
+    step: bool, // Un pas demandé, fait à la prochaine image
+}
+
+impl SimulationPause {
+    pub fn toggle(&mut self) {
+        self.paused = !self.paused;
+        self.step = false;
+    }
+
+    // Met en pause s'il le faut et demande un pas
+    pub fn request_step(&mut self) {
+        self.paused = true;
+        self.step = true;
+    }
+
+    // Le pas demandé, une seule fois
+    pub fn take_step(&mut self) -> bool {
+        std::mem::take(&mut self.step)
+    }
+}
+
+// Facteur réellement tenu ces dernières secondes : sous le facteur demandé
+// quand l'affichage rame ou que le fil de calcul ne suit pas
+#[derive(Resource, Default)]
+pub struct EffectiveScale {
+    sim: f32,  // Secondes de pâte confiées au calcul, en moyenne glissante
+    real: f32, // Secondes d'horloge réelle sur la même fenêtre
+}
+
+impl EffectiveScale {
+    pub fn record(&mut self, sim_seconds: f32, real_seconds: f32) {
+        // Fenêtre d'environ deux secondes
+        let keep = (1.0 - real_seconds / 2.0).clamp(0.0, 1.0);
+        self.sim = self.sim * keep + sim_seconds;
+        self.real = self.real * keep + real_seconds;
+    }
+
+    pub fn factor(&self) -> Option<f32> {
+        (self.real > 0.0).then(|| self.sim / self.real)
+    }
+}
+
+// Ligne du panneau : facteur temps, celui tenu s'il est nettement en dessous,
+// et la pause
+pub fn time_scale_line(
+    time_scale: &TimeScale,
+    effective: &EffectiveScale,
+    pause: &SimulationPause,
+) -> String {
+    if pause.paused {
+        return format!("x{:.2} (en pause, Entrée: un pas)", time_scale.0);
+    }
+    match effective.factor() {
+        Some(factor) if factor < time_scale.0 * 0.9 => {
+            format!("x{:.2} (tenu: x{:.2})", time_scale.0, factor)
+        }
+        _ => format!("x{:.2}", time_scale.0),
+    }
+}
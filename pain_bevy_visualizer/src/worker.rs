@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use pain_core::{frame_ticks, SimulationState, TickSummary};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SendError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Attente maximale du calcul par image ; au-delà l'interface continue avec
+// la dernière copie de la pâte
+const FRAME_BUDGET: Duration = Duration::from_millis(30);
+// Un pas plus long que ça est signalé comme lent
+const SLOW_TICK: Duration = Duration::from_millis(100);
+// Âge maximal de la copie de réserve : elle n'est affichée que si le calcul
+// dépasse le budget, et reprise d'image en image tant qu'il le respecte
+const SPARE_REFRESH: Duration = Duration::from_millis(250);
+
+struct StepJob {
+    state: SimulationState,
+    duration: f32,
+    max_tick: f32,
+}
+
+struct StepDone {
+    state: SimulationState,
+    slowest: Option<TickSummary>, // Pas le plus long de la tâche
+    ticks: u64,
+}
+
+// La pâte telle que confiée au fil de calcul, copiée par lui pendant qu'il
+// la calcule : c'est elle qui reprend si le calcul panique, jamais la copie
+// affichée qui peut dater de SPARE_REFRESH
+type Backup = Arc<Mutex<Option<SimulationState>>>;
+
+// Fil de calcul de la simulation : à chaque image l'interface lui confie la
+// pâte, affiche une copie de réserve à la place et ne l'attend que
+// FRAME_BUDGET. La copie n'est refaite que de temps en temps, ou à chaque
+// image quand le calcul prend du retard.
+#[derive(Resource)]
+pub struct SimulationWorker {
+    jobs: Sender<StepJob>,
+    results: Mutex<Receiver<StepDone>>,
+    backup: Backup,
+    in_flight: Option<Instant>, // Début du calcul en cours
+    spare: Option<SimulationState>, // Copie de réserve quand elle n'est pas affichée
+    spare_taken: Option<Instant>, // Date de la copie de réserve
+    late: bool, // Le dernier calcul a dépassé le budget
+    slow_tick: Option<TickSummary>, // Dernier pas trop long, tant que ça dure
+    ticks: u64, // Pas calculés depuis le lancement, pour la cadence
+}
+
+impl Default for SimulationWorker {
+    fn default() -> Self {
+        let backup = Backup::default();
+        let (jobs, results) = spawn_worker(backup.clone());
+        SimulationWorker {
+            jobs,
+            results: Mutex::new(results),
+            backup,
+            in_flight: None,
+            spare: None,
+            spare_taken: None,
+            late: false,
+            slow_tick: None,
+            ticks: 0,
+        }
+    }
+}
+
+fn spawn_worker(backup: Backup) -> (Sender<StepJob>, Receiver<StepDone>) {
+    let (job_sender, job_receiver) = mpsc::channel::<StepJob>();
+    let (done_sender, done_receiver) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("simulation".to_string())
+        .spawn(move || {
+            for mut job in job_receiver {
+                if let Ok(mut backup) = backup.lock() {
+                    *backup = Some(job.state.clone());
+                }
+                // Découpé en petits pas pour ne pas déstabiliser la physique
+                let mut slowest: Option<TickSummary> = None;
+                let mut ticks = 0;
+                // Le même découpage que la relecture des gestes
+                for dt in frame_ticks(job.duration, job.max_tick) {
+                    let summary = job.state.tick(dt);
+                    if slowest.is_none_or(|slowest| summary.wall_time > slowest.wall_time) {
+                        slowest = Some(summary);
+                    }
+                    // Avec des pas fixes, un appel peut en compter plusieurs ou aucun
+                    ticks += u64::from(summary.substeps);
+                }
+                // Libérée ici plutôt que sur le fil de l'interface
+                if let Ok(mut backup) = backup.lock() {
+                    *backup = None;
+                }
+                let done = StepDone {
+                    state: job.state,
+                    slowest,
+                    ticks,
+                };
+                if done_sender.send(done).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("impossible de lancer le fil de simulation");
+    (job_sender, done_receiver)
+}
+
+impl SimulationWorker {
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // Confie la pâte de `state` au fil de calcul pour `duration` secondes
+    // simulées ; `state` garde la copie de réserve en attendant
+    pub fn submit(&mut self, state: &mut SimulationState, duration: f32, max_tick: f32) {
+        let fresh = self
+            .spare_taken
+            .is_some_and(|taken| !self.late && taken.elapsed() < SPARE_REFRESH);
+        let display = match self.spare.take() {
+            Some(spare) if fresh => spare,
+            _ => {
+                self.spare_taken = Some(Instant::now());
+                state.clone()
+            }
+        };
+        let job = StepJob {
+            state: std::mem::replace(state, display),
+            duration,
+            max_tick,
+        };
+        if let Err(SendError(job)) = self.jobs.send(job) {
+            eprintln!("Fil de simulation arrêté, redémarrage");
+            *state = job.state;
+            self.restart();
+            return;
+        }
+        self.in_flight = Some(Instant::now());
+    }
+
+    // La pâte calculée si elle arrive dans le budget de l'image
+    fn wait(&mut self) -> Option<SimulationState> {
+        self.in_flight?;
+        let received = match self.results.lock() {
+            Ok(results) => results.recv_timeout(FRAME_BUDGET),
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(done) => {
+                // Rendu après coup, il compte encore comme en retard
+                self.late = self
+                    .in_flight
+                    .is_some_and(|started| started.elapsed() > FRAME_BUDGET);
+                self.in_flight = None;
+                self.ticks += done.ticks;
+                self.slow_tick = done
+                    .slowest
+                    .filter(|summary| summary.wall_time > SLOW_TICK);
+                Some(done.state)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.late = true;
+                None
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                // Le calcul a paniqué : on reprend la pâte telle que confiée,
+                // la copie affichée seulement si le fil n'a pas pu la copier
+                let backup = self.backup.lock().ok().and_then(|mut backup| backup.take());
+                match backup {
+                    Some(_) => eprintln!("Le fil de simulation s'est arrêté, reprise d'avant le dernier calcul"),
+                    None => eprintln!("Le fil de simulation s'est arrêté, reprise depuis la dernière image"),
+                }
+                self.restart();
+                backup
+            }
+        }
+    }
+
+    // Met la vraie pâte dans `state` plutôt que la copie affichée, en
+    // attendant le calcul en cours ; false s'il n'arrive pas à temps. La
+    // copie retourne en réserve pour l'image suivante.
+    pub fn settle(&mut self, state: &mut SimulationState) -> bool {
+        if !self.is_busy() {
+            return true;
+        }
+        match self.wait() {
+            Some(done) => {
+                self.spare = Some(std::mem::replace(state, done));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn restart(&mut self) {
+        // Le fil a pu paniquer en tenant le verrou
+        self.backup = Backup::default();
+        let (jobs, results) = spawn_worker(self.backup.clone());
+        self.jobs = jobs;
+        self.results = Mutex::new(results);
+        self.in_flight = None;
+    }
+
+    // Bandeau d'alerte, vide quand tout va bien
+    pub fn lag_text(&self, display: &SimulationState) -> String {
+        let stage = |summary: &TickSummary| {
+            summary
+                .slowest_stage()
+                .map(|(stage, time)| format!("{} {:.0} ms", stage.label(), time.as_secs_f64() * 1000.0))
+                .unwrap_or_else(|| "?".to_string())
+        };
+        match self.in_flight {
+            Some(started) if started.elapsed() > FRAME_BUDGET => format!(
+                "Simulation en retard : calcul en cours depuis {:.1} s (dernier pas : {}), actions ignorées",
+                started.elapsed().as_secs_f32(),
+                stage(&display.last_tick)
+            ),
+            _ => match &self.slow_tick {
+                Some(summary) => format!(
+                    "Simulation ralentie : pas de {:.0} ms (étape la plus lente : {})",
+                    summary.wall_time.as_secs_f64() * 1000.0,
+                    stage(summary)
+                ),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct LagIndicatorText;
+
+pub fn setup_lag_indicator(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::srgb(1.0, 0.5, 0.3),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        },
+        LagIndicatorText,
+    ));
+}
+
+pub fn update_lag_indicator(
+    worker: Res<SimulationWorker>,
+    sim_resource: Res<crate::SimulationResource>,
+    mut query: Query<(&mut Text, &mut Style), With<LagIndicatorText>>,
+) {
+    let text_value = worker.lag_text(&sim_resource.state);
+    for (mut text, mut style) in query.iter_mut() {
+        style.display = if text_value.is_empty() {
+            Display::None
+        } else {
+            Display::Flex
+        };
+        if text.sections[0].value != text_value {
+            text.sections[0].value.clone_from(&text_value);
+        }
+    }
+}
@@ -0,0 +1,723 @@
+use pain_core::autosave::load_latest_autosave;
+use pain_core::particle_export::ParticleExporter;
+use pain_core::prelude::*;
+use pain_core::schedule::{ProcessSchedule, ScheduleStep, StepCondition};
+use pain_core::sensitivity::run_sensitivity;
+use pain_core::starter::parse_feeding_history;
+use pain_core::timeline::ActionScheduler;
+use pain_core::{
+    AutosaveSettings, Autosaver, Boundaries, BudgetSettings, ContainerShape, Feeding, FlourProfile,
+    GramScale, InputPlayer, InputReplay, Outcome, Preferment, ReactionRule, SensitivitySettings,
+    Starter, TickProfiler,
+};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const USAGE: &str = "pain_cli --duration <s> [--recipe <fichier.toml|classic|baguette|...>] \
+[--flour <g>] [--flour-type bread|t65|whole|rye|spelt] [--preferment poolish|biga] \
+[--starter <levain:farine:eau@°C/h,...>] [--malt <%>] [--ascorbic <ppm>] \
+[--resolution <x>] [--max-molecules <n>] \
+[--interval <s>] [--dt <s>] [--seed <n>] [--size <l,h,p>] [--config <fichier.toml>] \
+[--environment fridge|proofer|room] [--boundaries box|bowl|banneton|periodic] \
+[--container box|bowl|banneton] [--output <fichier.csv>] \
+[--frames <fichier.xyz|fichier.vtk>] [--mix-until <%>] [--schedule <fichier.toml>] \
+[--profile <fichier.folded>] [--reactions <fichier.toml>] \
+[--autosave <dossier>] [--autosave-every <min simulées>] [--autosave-wall <min réelles>] \
+[--autosave-keep <n>] [--no-autosave] [--resume <fichier.pain|dossier>]
+pain_cli --sensitivity <fichier.csv> [--recipe <...>] [--step <%>] [--seeds <n>] \
+[--duration <s>] [--dt <s>] [--seed <n>] [--size <l,h,p>] [--config <fichier.toml>]
+pain_cli --play-inputs <fichier.hopin>";
+
+// Long runs save themselves here unless told otherwise
+const DEFAULT_AUTOSAVE_DIR: &str = "autosaves";
+
+const CSV_HEADER: &str =
+    "time_s,temperature_c,bonds,co2,ph,rise_height,development,volume,volume_ratio,burps,\
+co2_produced,co2_escaped,ethanol_produced,ethanol_escaped";
+
+struct Options {
+    recipe: Recipe,
+    config: SimulationConfig,
+    duration: f32,
+    interval: f32, // Sim seconds between two CSV rows
+    dt: f32,
+    seed: Option<u64>,
+    size: Option<(f32, f32, f32)>, // Container, smaller is denser and bonds sooner
+    budget: Option<BudgetSettings>, // Molecules per recipe and cap, the saved ones when resuming
+    preferment: Option<Preferment>, // Part of the flour fermented ahead, not when resuming
+    starter: Option<Vec<Feeding>>, // Feedings of the starter the levain comes from, not when resuming
+    environment: Option<EnvironmentPreset>,
+    boundaries: Option<Boundaries>, // Faces of the box, the saved ones when resuming
+    container: Option<String>,      // Vessel in the box, sized once the box is known
+    output: Option<String>,
+    frames: Option<String>, // Particles for ParaView / OVITO, one frame per CSV row
+    mix_until: Option<f32>, // Gluten development (%) reported as the end of mixing
+    malt: Option<f32>,      // Diastatic malt, % of the flour, not when resuming
+    ascorbic: Option<f32>,  // Ascorbic acid, ppm of the flour, not when resuming
+    schedule: Option<String>, // TOML timeline replacing the salt and levain at the end of the autolyse
+    profile: Option<String>,  // Time per phase and tick stage, folded stacks for a flamegraph
+    reactions: Vec<ReactionRule>, // Chemistry added to the built-in reactions
+    autosave: Option<(PathBuf, AutosaveSettings)>, // None with --no-autosave
+    resume: Option<String>,   // Save file, or autosave directory to take the newest from
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.windows(2)
+        .find(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+}
+
+fn number<T: std::str::FromStr>(args: &[String], flag: &str) -> io::Result<Option<T>> {
+    value(args, flag)
+        .map(|text| {
+            text.parse()
+                .map_err(|_| invalid(format!("{} : nombre attendu, pas {:?}", flag, text)))
+        })
+        .transpose()
+}
+
+// A preset id or the path of a TOML recipe
+fn recipe(arg: Option<&str>) -> io::Result<Recipe> {
+    match arg {
+        None => Ok(RecipePreset::Classic.into()),
+        Some(arg) => match RecipePreset::from_id(arg) {
+            Some(preset) => Ok(preset.into()),
+            None => Recipe::load_from(Path::new(arg))
+                .map_err(|err| invalid(format!("recette {} : {}", arg, err))),
+        },
+    }
+}
+
+fn budget(args: &[String]) -> io::Result<Option<BudgetSettings>> {
+    let resolution: Option<f32> = number(args, "--resolution")?;
+    let max_molecules = number(args, "--max-molecules")?;
+    if resolution.is_none() && max_molecules.is_none() {
+        return Ok(None);
+    }
+    let defaults = BudgetSettings::default();
+    let budget = BudgetSettings {
+        resolution: resolution.unwrap_or(defaults.resolution),
+        max_molecules: max_molecules.unwrap_or(defaults.max_molecules),
+    };
+    if budget.resolution.is_nan() || budget.resolution <= 0.0 {
+        return Err(invalid("--resolution doit être positif".to_string()));
+    }
+    Ok(Some(budget))
+}
+
+fn environment(arg: Option<&str>) -> io::Result<Option<EnvironmentPreset>> {
+    match arg {
+        None => Ok(None),
+        Some("fridge") => Ok(Some(EnvironmentPreset::Fridge)),
+        Some("proofer") => Ok(Some(EnvironmentPreset::Proofer)),
+        Some("room") => Ok(Some(EnvironmentPreset::RoomTemp { temp: 22.0 })),
+        Some(other) => Err(invalid(format!("environnement inconnu : {}", other))),
+    }
+}
+
+fn preferment(arg: Option<&str>) -> io::Result<Option<Preferment>> {
+    match arg {
+        None => Ok(None),
+        Some("poolish") => Ok(Some(Preferment::poolish())),
+        Some("biga") => Ok(Some(Preferment::biga())),
+        Some(other) => Err(invalid(format!("préferment inconnu : {}", other))),
+    }
+}
+
+fn boundaries(arg: Option<&str>) -> io::Result<Option<Boundaries>> {
+    match arg {
+        None => Ok(None),
+        Some("box") => Ok(Some(Boundaries::closed_box())),
+        Some("bowl") => Ok(Some(Boundaries::open_bowl())),
+        Some("banneton") => Ok(Some(Boundaries::covered_banneton())),
+        Some("periodic") => Ok(Some(Boundaries::periodic())),
+        Some(other) => Err(invalid(format!("bords inconnus : {}", other))),
+    }
+}
+
+fn container(arg: &str, state: &SimulationState) -> io::Result<ContainerShape> {
+    match arg {
+        "box" => Ok(ContainerShape::Box),
+        "bowl" => Ok(ContainerShape::bowl(state.width, state.depth)),
+        "banneton" => Ok(ContainerShape::banneton(state.width, state.depth)),
+        other => Err(invalid(format!("récipient inconnu : {}", other))),
+    }
+}
+
+fn size(arg: Option<&str>) -> io::Result<Option<(f32, f32, f32)>> {
+    let Some(arg) = arg else {
+        return Ok(None);
+    };
+    let dims: Vec<f32> = arg
+        .split(',')
+        .map(|dim| dim.trim().parse::<f32>().ok().filter(|dim| *dim > 0.0))
+        .collect::<Option<_>>()
+        .ok_or_else(|| {
+            invalid(format!(
+                "--size : trois longueurs positives attendues, pas {:?}",
+                arg
+            ))
+        })?;
+    match dims[..] {
+        [width, height, depth] => Ok(Some((width, height, depth))),
+        _ => Err(invalid(format!(
+            "--size : trois longueurs attendues, pas {:?}",
+            arg
+        ))),
+    }
+}
+
+// Intervals in minutes, 0 turns that interval off
+fn autosave(args: &[String]) -> io::Result<Option<(PathBuf, AutosaveSettings)>> {
+    if args.iter().any(|arg| arg == "--no-autosave") {
+        return Ok(None);
+    }
+    let defaults = AutosaveSettings::default();
+    let sim_minutes: Option<f32> = number(args, "--autosave-every")?;
+    let wall_minutes: Option<f32> = number(args, "--autosave-wall")?;
+    if [sim_minutes, wall_minutes]
+        .iter()
+        .flatten()
+        .any(|minutes| !(minutes.is_finite() && *minutes >= 0.0))
+    {
+        return Err(invalid(
+            "--autosave-every et --autosave-wall : minutes positives attendues".to_string(),
+        ));
+    }
+    let settings = AutosaveSettings {
+        sim_interval: match sim_minutes {
+            Some(minutes) => Some(minutes * 60.0).filter(|seconds| *seconds > 0.0),
+            None => defaults.sim_interval,
+        },
+        wall_interval: match wall_minutes {
+            Some(minutes) => {
+                Some(Duration::from_secs_f32(minutes * 60.0)).filter(|interval| !interval.is_zero())
+            }
+            None => defaults.wall_interval,
+        },
+        keep: number(args, "--autosave-keep")?.unwrap_or(defaults.keep),
+    };
+    let dir = value(args, "--autosave").unwrap_or(DEFAULT_AUTOSAVE_DIR);
+    Ok(Some((PathBuf::from(dir), settings)))
+}
+
+fn config(arg: Option<&str>) -> io::Result<SimulationConfig> {
+    match arg {
+        Some(path) => SimulationConfig::load_from(Path::new(path))
+            .map_err(|err| invalid(format!("configuration {} : {}", path, err))),
+        None => Ok(SimulationConfig::default()),
+    }
+}
+
+fn parse_options(args: &[String]) -> io::Result<Options> {
+    let duration = number(args, "--duration")?
+        .ok_or_else(|| invalid(format!("--duration manquant\n{}", USAGE)))?;
+    let mut recipe = recipe(value(args, "--recipe"))?;
+    if let Some(id) = value(args, "--flour-type") {
+        let flour = FlourProfile::from_id(id)
+            .ok_or_else(|| invalid(format!("farine inconnue : {}", id)))?;
+        recipe = recipe.with_flour(flour);
+    }
+    // Molecule counts from the grams of the recipe instead of the demo ones
+    if let Some(flour_g) = number(args, "--flour")? {
+        recipe.scale = Some(GramScale::new(flour_g));
+    }
+    let options = Options {
+        recipe,
+        config: config(value(args, "--config"))?,
+        duration,
+        interval: number(args, "--interval")?.unwrap_or(60.0),
+        dt: number(args, "--dt")?.unwrap_or(0.25),
+        seed: number(args, "--seed")?,
+        size: size(value(args, "--size"))?,
+        budget: budget(args)?,
+        preferment: preferment(value(args, "--preferment"))?,
+        starter: value(args, "--starter")
+            .map(|history| {
+                parse_feeding_history(history)
+                    .map_err(|err| invalid(format!("--starter {} : {}", history, err)))
+            })
+            .transpose()?,
+        environment: environment(value(args, "--environment"))?,
+        boundaries: boundaries(value(args, "--boundaries"))?,
+        container: value(args, "--container").map(str::to_string),
+        output: value(args, "--output").map(str::to_string),
+        frames: value(args, "--frames").map(str::to_string),
+        mix_until: number(args, "--mix-until")?,
+        malt: number(args, "--malt")?,
+        ascorbic: number(args, "--ascorbic")?,
+        schedule: value(args, "--schedule").map(str::to_string),
+        profile: value(args, "--profile").map(str::to_string),
+        reactions: match value(args, "--reactions") {
+            Some(path) => ReactionRule::load_from(Path::new(path))
+                .map_err(|err| invalid(format!("réactions {} : {}", path, err)))?,
+            None => Vec::new(),
+        },
+        autosave: autosave(args)?,
+        resume: value(args, "--resume").map(str::to_string),
+    };
+    if !(options.dt > 0.0 && options.interval > 0.0 && options.duration >= 0.0) {
+        return Err(invalid(
+            "--dt et --interval doivent être positifs".to_string(),
+        ));
+    }
+    if [options.malt, options.ascorbic]
+        .iter()
+        .flatten()
+        .any(|dose| !(dose.is_finite() && *dose > 0.0))
+    {
+        return Err(invalid(
+            "--malt et --ascorbic : doses positives attendues".to_string(),
+        ));
+    }
+    Ok(options)
+}
+
+fn write_row(out: &mut impl Write, state: &SimulationState) -> io::Result<()> {
+    let dough = state.summary();
+    let rise = state.rise_metrics();
+    writeln!(
+        out,
+        "{:.2},{:.2},{},{},{:.3},{:.2},{:.3},{:.0},{:.3},{},{},{},{},{}",
+        dough.time,
+        dough.temperature,
+        dough.bond_count,
+        dough.co2_count,
+        state.dough_ph(),
+        rise.rise,
+        state.gluten_development().total,
+        rise.volume,
+        rise.volume_ratio,
+        state.ledger.burps,
+        state.reactions.co2_produced,
+        state.ledger.co2_escaped,
+        state.reactions.ethanol_produced,
+        state.ledger.ethanol_escaped
+    )
+}
+
+fn write_sample(
+    out: &mut impl Write,
+    frames: Option<&mut ParticleExporter>,
+    state: &SimulationState,
+) -> io::Result<()> {
+    write_row(out, state)?;
+    match frames {
+        Some(frames) => frames.write_frame(state),
+        None => Ok(()),
+    }
+}
+
+// Salt and levain go in once the recipe's autolyse is over, like the baker does,
+// unless a timeline says otherwise, then mixing ends once the gluten is
+// developed enough if asked
+fn baker_schedule(recipe: &Recipe, additions: bool, mix_until: Option<f32>) -> ProcessSchedule {
+    let mut steps = Vec::new();
+    if additions {
+        steps.extend([
+            ScheduleStep {
+                label: "sel".to_string(),
+                at: recipe.autolyse_time,
+                action: Some(SimAction::AddSalt),
+                when: None,
+                timeout: None,
+            },
+            ScheduleStep {
+                label: "levain".to_string(),
+                at: recipe.autolyse_time,
+                action: Some(SimAction::AddYeast),
+                when: None,
+                timeout: None,
+            },
+        ]);
+    }
+    if let Some(percent) = mix_until {
+        steps.push(ScheduleStep {
+            label: format!("fin du pétrissage (développement ≥ {} %)", percent),
+            at: recipe.autolyse_time,
+            action: None,
+            when: Some(StepCondition::DevelopmentAtLeast(percent / 100.0)),
+            timeout: None,
+        });
+    }
+    ProcessSchedule::new(steps)
+}
+
+// A save file, or the newest autosave of a directory that still loads
+fn resumed(path: &Path) -> io::Result<SimulationState> {
+    let (file, state) = if path.is_dir() {
+        load_latest_autosave(path)?.ok_or_else(|| {
+            invalid(format!(
+                "aucune sauvegarde à reprendre dans {}",
+                path.display()
+            ))
+        })?
+    } else {
+        let state = SimulationState::load_from_file(path)
+            .map_err(|err| invalid(format!("reprise {} : {}", path.display(), err)))?;
+        (path.to_path_buf(), state)
+    };
+    eprintln!(
+        "Reprise de {} à t={:.0} s",
+        file.display(),
+        state.time_elapsed
+    );
+    Ok(state)
+}
+
+fn run(options: Options) -> io::Result<SimulationState> {
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut frames = options
+        .frames
+        .as_deref()
+        .map(|path| ParticleExporter::create(Path::new(path)))
+        .transpose()?;
+    let mut schedule = baker_schedule(
+        &options.recipe,
+        options.schedule.is_none(),
+        options.mix_until,
+    );
+    let mut state = match &options.resume {
+        Some(path) => resumed(Path::new(path))?,
+        None => {
+            let mut builder = SimulationState::builder()
+                .recipe(options.recipe)
+                .config(options.config);
+            if let Some(seed) = options.seed {
+                builder = builder.seed(seed);
+            }
+            if let Some((width, height, depth)) = options.size {
+                builder = builder.size(width, height, depth);
+            }
+            if let Some(budget) = &options.budget {
+                builder = builder.budget(budget.clone());
+            }
+            if let Some(preferment) = &options.preferment {
+                builder = builder.preferment(preferment.clone());
+            }
+            // The starter is kept the same way whatever the dough's seed
+            if let Some(history) = &options.starter {
+                let starter = Starter::from_history(history, options.seed.unwrap_or_default());
+                let levain = starter.levain();
+                eprintln!(
+                    "Levain après {} rafraîchis : vigueur {:.0} %, pH {:.1}",
+                    starter.feedings,
+                    levain.vigor * 100.0,
+                    levain.ph
+                );
+                builder = builder.starter(&starter);
+            }
+            if let Some(preset) = options.environment {
+                builder = builder.environment(preset);
+            }
+            let mut state = builder.build();
+            // The improvers go in with the flour
+            if let Some(percent) = options.malt {
+                state.add_malt(percent / 100.0);
+            }
+            if let Some(ppm) = options.ascorbic {
+                state.add_ascorbic_acid(ppm / 1e6);
+            }
+            state
+        }
+    };
+    if let Some(boundaries) = options.boundaries {
+        state.boundaries = boundaries;
+    }
+    // The same dough whatever --dt, cut into the fixed steps the visualizer runs
+    state.use_fixed_steps(options.dt);
+    // A resumed dough keeps its molecules, only the new cap holds from here on
+    if let (Some(_), Some(budget)) = (&options.resume, &options.budget) {
+        state.budget = budget.clone();
+    }
+    if let Some(arg) = &options.container {
+        let shape = container(arg, &state)?;
+        state.set_container(shape);
+    }
+    // A resumed dough keeps the chemistry it was saved with unless given new rules
+    if options.resume.is_none() || !options.reactions.is_empty() {
+        state.extra_reactions = options.reactions;
+    }
+    if let Some(path) = &options.schedule {
+        state.scheduler = ActionScheduler::load_from(Path::new(path), &state)
+            .map_err(|err| invalid(format!("planning {} : {}", path, err)))?;
+        state.scheduler.skip_before(state.time_elapsed);
+    }
+    schedule.skip_before(state.time_elapsed);
+    let mut autosaver = options
+        .autosave
+        .map(|(dir, settings)| Autosaver::new(&dir, settings))
+        .transpose()
+        .map_err(|err| invalid(format!("sauvegarde automatique : {}", err)))?;
+
+    let mut profiler = options.profile.as_ref().map(|_| TickProfiler::new());
+
+    writeln!(out, "{}", CSV_HEADER)?;
+    write_sample(&mut out, frames.as_mut(), &state)?;
+    // Counted in ticks so a long run never stalls on f32 time rounding; a
+    // resumed dough only runs what is left of the duration
+    let ticks = ((options.duration - state.time_elapsed).max(0.0) / options.dt).ceil() as u64;
+    let ticks_per_row = ((options.interval / options.dt).round() as u64).max(1);
+    for tick in 1..=ticks {
+        for step in schedule.poll(&mut state) {
+            let late = if step.timed_out {
+                " (délai dépassé)"
+            } else {
+                ""
+            };
+            eprintln!("t={:.0} s : {}{}", step.predicted.time, step.label, late);
+        }
+        let tick_start = state.time_elapsed;
+        let summary = state.tick(options.dt);
+        if let Some(profiler) = profiler.as_mut() {
+            profiler.record(state.phase, &summary);
+        }
+        for entry in state.events.iter().filter(|entry| entry.time == tick_start) {
+            if let SimulationEvent::ScheduledStep { label, timed_out } = &entry.event {
+                let late = if *timed_out {
+                    " (délai dépassé)"
+                } else {
+                    ""
+                };
+                eprintln!("t={:.0} s : {}{}", entry.time, label, late);
+            }
+        }
+        if let Some(record) = state
+            .collapse
+            .record
+            .filter(|record| record.time == state.time_elapsed)
+        {
+            eprintln!("!! Pâte effondrée à t={:.0} s", record.time);
+        }
+        if state.proofing.ready_at == Some(state.time_elapsed) {
+            eprintln!("t={:.0} s : pâte prête à façonner", state.time_elapsed);
+        }
+        if let Some(signs) = state
+            .proofing
+            .overproof
+            .filter(|signs| signs.time == state.time_elapsed)
+        {
+            eprintln!(
+                "! Pâte trop poussée à t={:.0} s (sucre restant {:.0} %, réseau {:.0} %)",
+                state.time_elapsed,
+                signs.sugar_left * 100.0,
+                signs.network_left * 100.0
+            );
+        }
+        if tick % ticks_per_row == 0 || tick == ticks {
+            write_sample(&mut out, frames.as_mut(), &state)?;
+        }
+        if let Some(autosaver) = autosaver.as_mut() {
+            // A full disk must not end a run that is still going fine
+            match autosaver.poll(&state) {
+                Ok(Some(path)) => eprintln!(
+                    "t={:.0} s : sauvegarde automatique {}",
+                    state.time_elapsed,
+                    path.display()
+                ),
+                Ok(None) => {}
+                Err(err) => eprintln!("Sauvegarde automatique impossible : {}", err),
+            }
+        }
+    }
+    out.flush()?;
+    if let Some(frames) = frames {
+        frames.finish()?;
+    }
+    if let (Some(profiler), Some(path)) = (profiler, &options.profile) {
+        profiler.save_folded(Path::new(path))?;
+        eprintln!(
+            "Profil : {:.2?} sur {} pas, écrit dans {}",
+            profiler.total(),
+            profiler.ticks(),
+            path
+        );
+    }
+    if !state.extra_reactions.is_empty() {
+        eprintln!(
+            "Réactions ajoutées : {} survenues",
+            state.reactions.other_reactions
+        );
+    }
+    Ok(state)
+}
+
+// `--sensitivity <out.csv>`: every recipe parameter nudged by --step percent
+// (5 by default) over --seeds seeded runs, written as tornado chart rows
+fn sensitivity(args: &[String], output: &str) -> io::Result<()> {
+    let recipe = recipe(value(args, "--recipe"))?;
+    let defaults = SensitivitySettings::default();
+    let first_seed: u64 = number(args, "--seed")?.unwrap_or(1);
+    let seed_count: u64 = number(args, "--seeds")?.unwrap_or(defaults.seeds.len() as u64);
+    let settings = SensitivitySettings {
+        step: number(args, "--step")?.map_or(defaults.step, |percent: f32| percent / 100.0),
+        seeds: (first_seed..first_seed + seed_count.max(1)).collect(),
+        duration: number(args, "--duration")?.unwrap_or(defaults.duration),
+        dt: number(args, "--dt")?.unwrap_or(defaults.dt),
+        size: size(value(args, "--size"))?.unwrap_or(defaults.size),
+        config: config(value(args, "--config"))?,
+        ..defaults
+    };
+    if !(settings.dt > 0.0
+        && settings.duration >= 0.0
+        && settings.step > 0.0
+        && settings.step < 1.0)
+    {
+        return Err(invalid(
+            "--dt, --duration et --step (entre 0 et 100 %) doivent être positifs".to_string(),
+        ));
+    }
+    let runs = settings.run_count();
+    eprintln!(
+        "Sensibilité de {} : {} paramètres à ±{:.0} %, {} graines, {} simulations de {:.0} s",
+        recipe.name,
+        settings.parameters.len(),
+        settings.step * 100.0,
+        settings.seeds.len(),
+        runs,
+        settings.duration
+    );
+    let report = run_sensitivity(&recipe, &settings, |done| {
+        eprint!("\r{}/{} simulations", done, runs);
+    });
+    eprintln!();
+
+    let mut out = BufWriter::new(File::create(output)?);
+    writeln!(
+        out,
+        "outcome,parameter,base_value,low_value,high_value,baseline,low_change,high_change,swing,effect_size"
+    )?;
+    for outcome in Outcome::all() {
+        let bars = report.tornado(outcome);
+        for bar in &bars {
+            let Some(effect) = report.effects.iter().find(|e| e.parameter == bar.parameter) else {
+                continue;
+            };
+            writeln!(
+                out,
+                "{:?},{:?},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{}",
+                outcome,
+                bar.parameter,
+                effect.base_value,
+                effect.low_value,
+                effect.high_value,
+                report.baseline.get(outcome),
+                bar.low_change,
+                bar.high_change,
+                bar.swing,
+                bar.effect_size
+                    .map(|size| format!("{:.2}", size))
+                    .unwrap_or_default()
+            )?;
+        }
+        // The knobs that matter for this outcome, noise-level ones left out
+        let leading: Vec<String> = bars
+            .iter()
+            .filter(|bar| bar.swing > 0.0 && bar.effect_size.is_none_or(|size| size >= 1.0))
+            .take(3)
+            .map(|bar| {
+                format!(
+                    "{} ({:+.3} / {:+.3})",
+                    bar.parameter.label(),
+                    bar.low_change,
+                    bar.high_change
+                )
+            })
+            .collect();
+        eprintln!(
+            "{} ({:.3}) : {}",
+            outcome.label(),
+            report.baseline.get(outcome),
+            if leading.is_empty() {
+                "aucun paramètre au-dessus du bruit".to_string()
+            } else {
+                leading.join(", ")
+            }
+        );
+    }
+    out.flush()?;
+    eprintln!("Tornade écrite dans {}", output);
+    Ok(())
+}
+
+// Replays the gestures recorded in the visualizer on the dough they started
+// from, and tells whether it came out the same
+fn play_inputs(path: &Path) -> io::Result<SimulationState> {
+    let replay = InputReplay::load_from_file(path)
+        .map_err(|err| invalid(format!("gestes {} : {}", path.display(), err)))?;
+    eprintln!(
+        "Relecture de {} gestes sur {:.0} s : {} (graine {})",
+        replay.action_count(),
+        replay.duration(),
+        replay.recipe_name,
+        replay.seed
+    );
+    let mut state = replay.start_state()?;
+    let mut player = InputPlayer::new(replay);
+    if player.play(&mut state) {
+        eprintln!("Même pâte qu'à l'enregistrement");
+    } else if let Some(time) = player.diverged_at() {
+        eprintln!("!! La pâte a divergé de l'enregistrement à t={:.0} s", time);
+    } else {
+        eprintln!("!! La pâte finale diffère de l'enregistrement");
+    }
+    Ok(state)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    if let Some(output) = value(&args, "--sensitivity") {
+        if let Err(err) = sensitivity(&args, output) {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+        return;
+    }
+    let result = match value(&args, "--play-inputs") {
+        Some(path) => play_inputs(Path::new(path)),
+        None => parse_options(&args).and_then(run),
+    };
+    match result {
+        Ok(state) => {
+            eprintln!(
+                "Terminé à t={:.0} s (graine {}) : {} liaisons, pH {:.2}, levée {:.1}",
+                state.time_elapsed,
+                state.seed,
+                state.bonds().len(),
+                state.dough_ph(),
+                state.rise()
+            );
+            let crumb = state.crumb_analysis();
+            eprintln!(
+                "Mie : {}, {} alvéoles, ouverture {:.0} % (taille : alvéoles) {}",
+                crumb.label(),
+                crumb.bubble_count(),
+                crumb.open_crumb_score * 100.0,
+                crumb.histogram_text()
+            );
+            if let Some(record) = state.collapse.record {
+                eprintln!(
+                    "Effondrement à t={:.0} s : -{:.1} de levée sur {:.1}, {} liaisons rompues",
+                    record.time, record.height_drop, record.peak_rise, record.bonds_torn
+                );
+            }
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CONFIG_DIR_ENV: &str = "HOUSE_OF_PAIN_CONFIG_DIR";
+const PREFERENCES_FILE: &str = "preferences.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanelSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub add_salt: String,
+    pub add_yeast: String,
+    pub fold: String,
+    pub reset: String,
+    pub faster: String,
+    pub slower: String,
+    pub pause: String,
+    pub single_step: String,
+    pub toggle_units: String,
+    pub toggle_panel: String,
+    pub next_recipe: String,
+    pub next_phase: String,
+    pub next_environment: String,
+    pub stretch_and_fold: String,
+    pub coil_fold: String,
+    pub slap_and_fold: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub radius: f32,
+    pub azimuth: f32,
+    pub elevation: f32,
+}
+
+// Observation box in simulation coordinates, applied to every new simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationBox {
+    pub name: String,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+// Room temperature over the day: a sinusoid, or a schedule of
+// [hour, °C] points when one is given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientRoom {
+    pub mean: f32,
+    pub amplitude: f32,
+    pub coldest_hour: f32,
+    pub start_hour: f32,
+    pub schedule: Vec<[f32; 2]>,
+    pub draft_speed: f32,          // m/s, 0 = still air
+    pub draft_direction: [f32; 2], // Horizontal (x, z) direction the air moves to
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub visible: bool,
+    pub side: PanelSide,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub palette: String,
+    pub temperature_unit: TemperatureUnit,
+    pub keybindings: KeyBindings,
+    pub last_recipe: String,
+    pub last_camera: Option<CameraBookmark>,
+    pub camera_bookmarks: BTreeMap<String, CameraBookmark>, // By slot, "1" to "9"
+    pub panel: PanelLayout,
+    pub observation_boxes: Vec<ObservationBox>,
+    pub ambient_room: Option<AmbientRoom>, // None keeps the dough at a constant temperature
+    pub burp_sound: bool, // Short low tone when gas escapes the dough
+    pub resolution: f32,  // Molecules per recipe relative to the demo
+    pub max_molecules: usize, // 0 = no limit
+}
+
+impl TemperatureUnit {
+    pub fn convert(&self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            TemperatureUnit::Celsius => TemperatureUnit::Fahrenheit,
+            TemperatureUnit::Fahrenheit => TemperatureUnit::Celsius,
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            add_salt: "S".to_string(),
+            add_yeast: "Y".to_string(),
+            fold: "C".to_string(),
+            reset: "R".to_string(),
+            faster: "Equal".to_string(),
+            slower: "Minus".to_string(),
+            pause: "Space".to_string(),
+            single_step: "Enter".to_string(),
+            toggle_units: "U".to_string(),
+            toggle_panel: "Tab".to_string(),
+            next_recipe: "P".to_string(),
+            next_phase: "N".to_string(),
+            next_environment: "E".to_string(),
+            stretch_and_fold: "F".to_string(),
+            coil_fold: "G".to_string(),
+            slap_and_fold: "H".to_string(),
+        }
+    }
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout {
+            visible: true,
+            side: PanelSide::Right,
+            width: 340.0,
+        }
+    }
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            palette: "default".to_string(),
+            temperature_unit: TemperatureUnit::Celsius,
+            keybindings: KeyBindings::default(),
+            last_recipe: "classic".to_string(),
+            last_camera: None,
+            camera_bookmarks: BTreeMap::new(),
+            panel: PanelLayout::default(),
+            observation_boxes: Vec::new(),
+            ambient_room: None,
+            burp_sound: false,
+            resolution: 1.0,
+            max_molecules: 0,
+        }
+    }
+}
+
+impl Default for AmbientRoom {
+    fn default() -> Self {
+        AmbientRoom {
+            mean: 22.0,
+            amplitude: 3.0,
+            coldest_hour: 5.0,
+            start_hour: 8.0,
+            schedule: Vec::new(),
+            draft_speed: 0.0,
+            draft_direction: [1.0, 0.0],
+        }
+    }
+}
+
+impl Preferences {
+    // Loads the preferences from the default location, falling back to defaults
+    // when the file is missing or unreadable so a bad file never blocks startup.
+    pub fn load() -> Self {
+        let Some(path) = preferences_path() else {
+            return Preferences::default();
+        };
+        if !path.exists() {
+            return Preferences::default();
+        }
+        match Preferences::load_from(&path) {
+            Ok(prefs) => prefs,
+            Err(err) => {
+                eprintln!("Ignoring preferences at {}: {}", path.display(), err);
+                Preferences::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = preferences_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        self.save_to(&path)
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text =
+            toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+// Config directory shared by every front-end; can be overridden with
+// HOUSE_OF_PAIN_CONFIG_DIR (handy for portable installs).
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::config_dir().map(|dir| dir.join("house_of_pain"))
+}
+
+pub fn preferences_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PREFERENCES_FILE))
+}
@@ -0,0 +1,94 @@
+// Bond solver on dense gluten networks, by network size and thread count:
+//   cargo bench -p pain_core --features parallel
+use nalgebra::Vector3;
+use pain_core::prelude::*;
+use pain_core::{BondColoring, MoleculeId, TickStage};
+use std::time::{Duration, Instant};
+
+const SPACING: f32 = 4.0; // Between neighbor glutenins, also the bond length
+const SIDES: [usize; 4] = [10, 16, 24, 32];
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+const TICKS: u32 = 10;
+const DT: f32 = 0.25;
+
+// Cubic lattice of glutenins, each bonded to its six neighbors: far denser
+// than a kneaded dough, where a thiol makes a single bridge
+fn dense_network(side: usize) -> SimulationState {
+    let extent = (side + 2) as f32 * SPACING;
+    let mut state = SimulationState::new_with_seed(extent, extent, extent, 1);
+    state.solver.iterations = 4;
+    let index = |x: usize, y: usize, z: usize| (x * side + y) * side + z;
+    let mut ids: Vec<MoleculeId> = Vec::with_capacity(side * side * side);
+    for x in 0..side {
+        for y in 0..side {
+            for z in 0..side {
+                let pos = Vector3::new(x as f32 + 1.0, y as f32 + 1.0, z as f32 + 1.0) * SPACING;
+                let glutenin = MoleculeType::Glutenin {
+                    has_free_thiol: false,
+                };
+                ids.push(
+                    state
+                        .grid
+                        .insert(Molecule::new(glutenin, pos, Vector3::zeros())),
+                );
+            }
+        }
+    }
+    for x in 0..side {
+        for y in 0..side {
+            for z in 0..side {
+                let here = ids[index(x, y, z)];
+                let neighbors = [
+                    (x + 1 < side).then(|| index(x + 1, y, z)),
+                    (y + 1 < side).then(|| index(x, y + 1, z)),
+                    (z + 1 < side).then(|| index(x, y, z + 1)),
+                ];
+                for neighbor in neighbors.into_iter().flatten() {
+                    let bond = state.solver.new_bond(here, ids[neighbor], SPACING);
+                    state.push_bond(bond);
+                }
+            }
+        }
+    }
+    state
+}
+
+fn main() {
+    println!(
+        "{:>9} {:>9} {:>8} {:>11} {:>8} {:>12} {:>9}",
+        "molécules", "liaisons", "couleurs", "coloration", "threads", "solveur/pas", "accél."
+    );
+    for side in SIDES {
+        let state = dense_network(side);
+        let start = Instant::now();
+        let coloring = BondColoring::build(state.bonds());
+        let coloring_time = start.elapsed();
+        assert!(coloring.is_valid(state.bonds()));
+
+        let mut single = None;
+        for threads in THREADS {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("thread pool");
+            let mut run = state.clone();
+            let solver: Duration = pool.install(|| {
+                (0..TICKS)
+                    .map(|_| run.tick(DT).stage_time(TickStage::BondSolver))
+                    .sum()
+            });
+            let per_tick = solver / TICKS;
+            let single = *single.get_or_insert(per_tick);
+            println!(
+                "{:>9} {:>9} {:>8} {:>11.2?} {:>8} {:>12.2?} {:>8.2}x",
+                state.grid.len(),
+                state.bonds().len(),
+                coloring.color_count(),
+                coloring_time,
+                threads,
+                per_tick,
+                single.as_secs_f64() / per_tick.as_secs_f64().max(f64::EPSILON)
+            );
+        }
+    }
+}
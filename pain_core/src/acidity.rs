@@ -0,0 +1,147 @@
+use crate::{MoleculeType, SimulationState};
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+
+// Lactic acid bacteria carried by the levain. They are not simulated one by
+// one: each yeast molecule stands for its share of the culture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcidSettings {
+    pub production_rate: f32, // Acid units per levain molecule per hour at the optimum
+    pub optimum_temp: f32,    // °C where the bacteria are most active
+    pub q10: f32,             // Activity multiplier per 10°C below the optimum
+    // Share of acetic acid in what is produced: heterofermentative bacteria
+    // make relatively more acetic acid in a cold dough (the tang of a retard)
+    pub acetic_share_warm: f32,
+    pub acetic_share_cold: f32,
+    pub warm_temp: f32, // At or above: warm share
+    pub cold_temp: f32, // At or below: cold share
+    // The pH falls from `fresh_ph` towards `min_ph` (flour buffers the rest),
+    // halfway there once each levain molecule made `ph_half_acid`
+    pub fresh_ph: f32,
+    pub min_ph: f32,
+    pub ph_half_acid: f32,
+    // Below `weakening_onset_ph` the acid attacks the gluten: the strain a
+    // bond withstands shrinks, by up to `max_weakening` at `min_ph`
+    pub weakening_onset_ph: f32,
+    pub max_weakening: f32,
+}
+
+impl Default for AcidSettings {
+    fn default() -> Self {
+        AcidSettings {
+            production_rate: 0.01,
+            optimum_temp: 32.0,
+            q10: 2.5,
+            acetic_share_warm: 0.2,
+            acetic_share_cold: 0.5,
+            warm_temp: 28.0,
+            cold_temp: 6.0,
+            fresh_ph: 6.0,
+            min_ph: 3.4,
+            ph_half_acid: 0.06,
+            weakening_onset_ph: 4.3,
+            max_weakening: 0.8,
+        }
+    }
+}
+
+// Acid accumulated in the dough since the levain went in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Acidity {
+    pub lactic: f64,
+    pub acetic: f64,
+    pub per_levain: f64, // Acid made per levain molecule, what the pH follows
+}
+
+impl Acidity {
+    pub fn total(&self) -> f64 {
+        self.lactic + self.acetic
+    }
+
+    pub fn acetic_fraction(&self) -> f64 {
+        if self.total() > 0.0 {
+            self.acetic / self.total()
+        } else {
+            0.0
+        }
+    }
+
+    // Lactic to acetic ratio bakers call the fermentation quotient (FQ)
+    pub fn fermentation_quotient(&self) -> Option<f64> {
+        (self.acetic > 0.0).then(|| self.lactic / self.acetic)
+    }
+}
+
+impl AcidSettings {
+    // Bacterial activity at a dough temperature, 1 at the optimum
+    pub fn activity(&self, temperature: f32) -> f32 {
+        if temperature >= self.optimum_temp {
+            return 1.0;
+        }
+        self.q10
+            .max(1.0)
+            .powf((temperature - self.optimum_temp) / 10.0)
+    }
+
+    pub fn acetic_share(&self, temperature: f32) -> f32 {
+        let span = (self.warm_temp - self.cold_temp).max(f32::EPSILON);
+        let warmth = ((temperature - self.cold_temp) / span).clamp(0.0, 1.0);
+        self.acetic_share_cold + (self.acetic_share_warm - self.acetic_share_cold) * warmth
+    }
+
+    pub fn ph(&self, acidity: &Acidity) -> f32 {
+        let half = self.ph_half_acid.max(f32::EPSILON) as f64;
+        let remaining = half / (half + acidity.per_levain.max(0.0));
+        self.min_ph + (self.fresh_ph - self.min_ph) * remaining as f32
+    }
+
+    // Share of its strength the gluten keeps at a given pH, 1 above the onset
+    pub fn gluten_strength(&self, ph: f32) -> f32 {
+        let span = (self.weakening_onset_ph - self.min_ph).max(f32::EPSILON);
+        let attack = ((self.weakening_onset_ph - ph) / span).clamp(0.0, 1.0);
+        1.0 - self.max_weakening.clamp(0.0, 1.0) * attack
+    }
+}
+
+impl SimulationState {
+    pub fn dough_ph(&self) -> f32 {
+        self.acid_settings.ph(&self.acidity)
+    }
+
+    pub fn acid_gluten_strength(&self) -> f32 {
+        self.acid_settings.gluten_strength(self.dough_ph())
+    }
+
+    pub(crate) fn produce_acid(&mut self, dt: f32) {
+        let settings = &self.acid_settings;
+        let levain = self
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Yeast))
+            .count();
+        let per_levain =
+            settings.production_rate * settings.activity(self.temperature) * dt / SECONDS_PER_HOUR;
+        // A starter's levain brings its own share of bacteria per yeast
+        let per_levain = per_levain * self.levain.map_or(1.0, |levain| levain.balance.max(0.0));
+        let produced = levain as f32 * per_levain;
+        let acetic = produced * settings.acetic_share(self.temperature);
+        self.acidity.acetic += acetic as f64;
+        self.acidity.lactic += (produced - acetic) as f64;
+        if levain > 0 {
+            self.acidity.per_levain += per_levain as f64;
+        }
+    }
+
+    // Acid made by a reaction rule, shared out over the levain for the pH
+    pub(crate) fn add_acid(&mut self, acetic: f32, lactic: f32) {
+        let levain = self
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Yeast))
+            .count();
+        self.acidity.acetic += acetic as f64;
+        self.acidity.lactic += lactic as f64;
+        self.acidity.per_levain += (acetic + lactic) as f64 / levain.max(1) as f64;
+    }
+}
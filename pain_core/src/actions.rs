@@ -0,0 +1,93 @@
+use crate::{DoughPhase, EnvironmentPreset, KneadAction, Perturbation, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Everything a baker (or a script) can do to the dough, as plain data so it can
+// be scheduled, logged or sent around.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SimAction {
+    AddSalt,
+    AddYeast,
+    AddMalt(f32),         // Share of the flour
+    AddAscorbicAcid(f32), // Share of the flour
+    Fold {
+        center: Vector3<f32>,
+        radius: f32,
+        force: Vector3<f32>,
+    },
+    SetTemperature(f32),
+    // Moves the dough forward through the phases until it reaches this one
+    AdvanceTo(DoughPhase),
+    Bake(f32), // Oven temperature, °C
+    Knead(KneadAction),
+    DustFlour(f32), // Share of the flour already in the dough, while shaping
+    MoveHand {
+        hand: usize, // 0 or 1
+        target: Vector3<f32>,
+        duration: f32,
+    },
+    LiftHands,
+    SetEnvironment(EnvironmentPreset),
+    Perturb(Perturbation),
+    SetDraft {
+        speed: f32,              // m/s, 0 for still air
+        direction: Vector3<f32>, // Horizontal, y ignored
+    },
+}
+
+impl SimAction {
+    // The standard fold: an upward push on a large region around the center
+    pub fn centered_fold(state: &SimulationState) -> Self {
+        SimAction::Fold {
+            center: state.center(),
+            radius: 200.0,
+            force: Vector3::new(0.0, 30.0, 0.0),
+        }
+    }
+}
+
+impl SimulationState {
+    pub fn apply_action(&mut self, action: &SimAction) {
+        match action {
+            SimAction::AddSalt => self.add_salt(),
+            SimAction::AddYeast => self.add_yeast(),
+            SimAction::AddMalt(amount) => {
+                self.add_malt(*amount);
+            }
+            SimAction::AddAscorbicAcid(amount) => {
+                self.add_ascorbic_acid(*amount);
+            }
+            SimAction::Fold {
+                center,
+                radius,
+                force,
+            } => self.apply_force_to_region(*center, *radius, *force),
+            SimAction::SetTemperature(temperature) => self.temperature = *temperature,
+            SimAction::AdvanceTo(phase) => {
+                while self.phase < *phase && self.advance_phase().is_some() {}
+            }
+            SimAction::Bake(oven_temperature) => {
+                self.start_bake(*oven_temperature);
+            }
+            SimAction::Knead(knead) => self.knead(*knead),
+            SimAction::DustFlour(amount) => {
+                self.dust_flour(*amount);
+            }
+            SimAction::MoveHand {
+                hand,
+                target,
+                duration,
+            } => self.move_hand(*hand, *target, *duration),
+            SimAction::LiftHands => self.lift_hands(),
+            SimAction::SetEnvironment(preset) => self.set_environment(*preset),
+            SimAction::Perturb(perturbation) => {
+                self.apply_perturbation(perturbation);
+            }
+            SimAction::SetDraft { speed, direction } => {
+                let airflow = &mut self.environment.airflow;
+                airflow.speed = speed.max(0.0);
+                airflow.direction = Vector3::new(direction.x, 0.0, direction.z);
+            }
+        }
+    }
+}
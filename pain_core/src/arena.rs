@@ -0,0 +1,189 @@
+use crate::Molecule;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Identity of a molecule: a slot in the arena plus the generation of that
+// slot. A removed molecule's slot is reused with the next generation, so a
+// stale id (in an old bond, event or renderer map) never finds the molecule
+// that took its place.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct MoleculeId {
+    index: u32,
+    generation: u32, // 0 for the null id, live slots start at 1
+}
+
+impl MoleculeId {
+    // Never handed out, stands for "no molecule yet"
+    pub const NULL: MoleculeId = MoleculeId {
+        index: 0,
+        generation: 0,
+    };
+
+    pub fn index(self) -> usize {
+        self.index as usize
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+
+    pub fn is_null(self) -> bool {
+        self.generation == 0
+    }
+
+    // Packed into one integer for file formats: generation in the high half,
+    // slot in the low half
+    pub fn to_bits(self) -> u64 {
+        (self.generation as u64) << 32 | self.index as u64
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        MoleculeId {
+            index: bits as u32,
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
+impl fmt::Display for MoleculeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Slot {
+    generation: u32, // Of the molecule in the slot, or of the next one if empty
+    molecule: Option<Molecule>,
+}
+
+// Molecules stored by slot: lookups are array indexing, iteration follows
+// the slots so seeded runs visit molecules in the same order every time.
+// Freed slots are reused last-freed first, so a long ferment that makes and
+// consumes millions of CO2 and sugars keeps as many slots as it ever had
+// molecules at once.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MoleculeArena {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+fn next_generation(generation: u32) -> u32 {
+    // Skips 0, kept for the null id
+    generation.wrapping_add(1).max(1)
+}
+
+impl MoleculeArena {
+    // Stores the molecule and returns its new id, also written into it
+    pub fn insert(&mut self, mut molecule: Molecule) -> MoleculeId {
+        let index = loop {
+            match self.free.pop() {
+                // Taken back by `insert_with_id` since it was freed
+                Some(index) if self.slots[index as usize].molecule.is_some() => continue,
+                Some(index) => break index,
+                None => {
+                    self.slots.push(Slot {
+                        generation: 1,
+                        molecule: None,
+                    });
+                    break (self.slots.len() - 1) as u32;
+                }
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        let id = MoleculeId {
+            index,
+            generation: slot.generation,
+        };
+        molecule.id = id;
+        slot.molecule = Some(molecule);
+        self.len += 1;
+        id
+    }
+
+    // Stores a molecule under the id it already has (trajectory playback,
+    // restored states), replacing whatever held that slot
+    pub fn insert_with_id(&mut self, molecule: Molecule) -> Option<Molecule> {
+        let id = molecule.id;
+        let index = id.index;
+        while self.slots.len() <= index as usize {
+            self.free.push(self.slots.len() as u32);
+            self.slots.push(Slot {
+                generation: 1,
+                molecule: None,
+            });
+        }
+        let slot = &mut self.slots[index as usize];
+        slot.generation = id.generation;
+        let previous = slot.molecule.replace(molecule);
+        // Its entry in `free` goes stale, `insert` skips it
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    pub fn remove(&mut self, id: MoleculeId) -> Option<Molecule> {
+        let slot = self.slots.get_mut(id.index())?;
+        if slot.generation != id.generation || slot.molecule.is_none() {
+            return None;
+        }
+        let molecule = slot.molecule.take();
+        slot.generation = next_generation(slot.generation);
+        self.free.push(id.index);
+        self.len -= 1;
+        molecule
+    }
+
+    pub fn get(&self, id: MoleculeId) -> Option<&Molecule> {
+        self.slots
+            .get(id.index())
+            .filter(|slot| slot.generation == id.generation)?
+            .molecule
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: MoleculeId) -> Option<&mut Molecule> {
+        self.slots
+            .get_mut(id.index())
+            .filter(|slot| slot.generation == id.generation)?
+            .molecule
+            .as_mut()
+    }
+
+    pub fn contains(&self, id: MoleculeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Molecule> + '_ {
+        self.slots.iter().filter_map(|slot| slot.molecule.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Molecule> + '_ {
+        self.slots
+            .iter_mut()
+            .filter_map(|slot| slot.molecule.as_mut())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Slots ever used, the size of arrays indexed by `MoleculeId::index`
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    // Bytes held on the heap by the slots and the free list
+    pub fn heap_bytes(&self) -> usize {
+        self.slots.capacity() * std::mem::size_of::<Slot>()
+            + self.free.capacity() * std::mem::size_of::<u32>()
+    }
+}
@@ -0,0 +1,140 @@
+use crate::SimulationState;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const AUTOSAVE_PREFIX: &str = "autosave-";
+const AUTOSAVE_EXTENSION: &str = "pain";
+
+// When a long run saves itself, and how many of those saves it keeps
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutosaveSettings {
+    pub sim_interval: Option<f32>, // Simulated seconds between two saves
+    pub wall_interval: Option<Duration>, // Wall-clock time between two saves
+    pub keep: usize,               // Newest saves kept, older ones are deleted
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        AutosaveSettings {
+            sim_interval: Some(30.0 * 60.0),
+            wall_interval: Some(Duration::from_secs(10 * 60)),
+            keep: 5,
+        }
+    }
+}
+
+// Numbered saves in a directory (autosave-000001.pain, ...), written
+// whichever interval runs out first, so a multi-hour run that crashes or
+// loses power resumes from the last one instead of starting over
+#[derive(Debug)]
+pub struct Autosaver {
+    dir: PathBuf,
+    settings: AutosaveSettings,
+    last_sim: Option<f32>, // Set by the first poll, a resumed dough does not save right away
+    last_wall: Instant,
+    next: u64,
+}
+
+impl Autosaver {
+    // Numbering goes on after the saves already in `dir`
+    pub fn new(dir: &Path, settings: AutosaveSettings) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let next = autosaves_in(dir)?
+            .last()
+            .map_or(1, |(number, _)| number + 1);
+        Ok(Autosaver {
+            dir: dir.to_path_buf(),
+            settings,
+            last_sim: None,
+            last_wall: Instant::now(),
+            next,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn is_due(&self, state: &SimulationState) -> bool {
+        let by_sim = match (self.settings.sim_interval, self.last_sim) {
+            (Some(interval), Some(last)) => state.time_elapsed - last >= interval,
+            _ => false,
+        };
+        let by_wall = self
+            .settings
+            .wall_interval
+            .is_some_and(|interval| self.last_wall.elapsed() >= interval);
+        by_sim || by_wall
+    }
+
+    // Saves when an interval ran out; the file written, if any
+    pub fn poll(&mut self, state: &SimulationState) -> io::Result<Option<PathBuf>> {
+        self.last_sim.get_or_insert(state.time_elapsed);
+        if !self.is_due(state) {
+            return Ok(None);
+        }
+        self.save_now(state).map(Some)
+    }
+
+    // Written under a temporary name, flushed to the disk, then renamed: a
+    // crash in the middle never leaves a truncated file as the newest save
+    pub fn save_now(&mut self, state: &SimulationState) -> io::Result<PathBuf> {
+        let path = self.dir.join(format!(
+            "{}{:06}.{}",
+            AUTOSAVE_PREFIX, self.next, AUTOSAVE_EXTENSION
+        ));
+        let partial = path.with_extension("part");
+        state.save_to_file(&partial)?;
+        File::open(&partial)?.sync_all()?;
+        fs::rename(&partial, &path)?;
+        self.next += 1;
+        self.last_sim = Some(state.time_elapsed);
+        self.last_wall = Instant::now();
+        self.prune()?;
+        Ok(path)
+    }
+
+    fn prune(&self) -> io::Result<()> {
+        let saves = autosaves_in(&self.dir)?;
+        let excess = saves.len().saturating_sub(self.settings.keep.max(1));
+        for (_, path) in &saves[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+// (number, path) of the autosaves in `dir`, oldest first
+fn autosaves_in(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut saves = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let number = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(AUTOSAVE_PREFIX))
+            .and_then(|name| name.strip_suffix(&format!(".{}", AUTOSAVE_EXTENSION)))
+            .and_then(|number| number.parse::<u64>().ok());
+        if let Some(number) = number {
+            saves.push((number, path));
+        }
+    }
+    saves.sort_by_key(|(number, _)| *number);
+    Ok(saves)
+}
+
+// The newest autosave in `dir` that still loads, passing over any the crash
+// damaged; None when there is nothing to resume
+pub fn load_latest_autosave(dir: &Path) -> io::Result<Option<(PathBuf, SimulationState)>> {
+    for (_, path) in autosaves_in(dir)?.into_iter().rev() {
+        if let Ok(state) = SimulationState::load_from_file(&path) {
+            return Ok(Some((path, state)));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,208 @@
+use crate::{
+    DoughPhase, HeatExchange, MoleculeId, MoleculeType, SimulationEvent, SimulationState,
+};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const KELVIN: f32 = 273.15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BakeSettings {
+    pub oven_temperature: f32, // °C used when the baker just moves on to the bake
+    pub heat_lag: f32,         // Seconds for the dough to close 63% of the gap to the oven
+    pub crumb_max_temp: f32,   // Water in the crumb keeps it just below boiling
+    pub yeast_death_temp: f32, // Yeast dies above this
+    pub denature_start: f32,   // Gluten proteins start to set
+    pub denature_end: f32,     // Fully set: bonds at their stiffest
+    pub gas_push_distance: f32, // Reach of an expanding CO2 bubble
+    pub gas_expansion_gain: f32, // Scale on the displacement from gas expansion
+    pub crust_depth: f32,      // Layer along every wall that dries into a crust
+    pub crust_water_rate: f32, // Chance per second for crust water to evaporate at 100°C
+}
+
+impl Default for BakeSettings {
+    fn default() -> Self {
+        BakeSettings {
+            oven_temperature: 230.0,
+            heat_lag: 900.0,
+            crumb_max_temp: 98.0,
+            yeast_death_temp: 60.0,
+            denature_start: 60.0,
+            denature_end: 80.0,
+            gas_push_distance: 12.0,
+            gas_expansion_gain: 1.0,
+            crust_depth: 15.0,
+            crust_water_rate: 0.02,
+        }
+    }
+}
+
+// The loaf in the oven, kept after the bake as a record of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Oven {
+    pub temperature: f32,
+    pub started_at: f32,
+    pub start_temperature: f32, // Dough temperature when it went in
+    pub yeast_killed: bool,
+    pub denaturation: f32, // 0 raw to 1 fully set, never goes back
+}
+
+impl Oven {
+    // Relative growth of the gas volume since the loaf went in (Charles' law)
+    pub fn gas_expansion(&self, temperature: f32) -> f32 {
+        (temperature + KELVIN) / (self.start_temperature + KELVIN) - 1.0
+    }
+}
+
+impl SimulationState {
+    // Puts the loaf in an oven at `oven_temp` °C. Returns false once the loaf
+    // has cooled; calling it again during the bake changes the oven setting.
+    pub fn start_bake(&mut self, oven_temp: f32) -> bool {
+        if self.phase > DoughPhase::Bake {
+            return false;
+        }
+        if let (DoughPhase::Bake, Some(oven)) = (self.phase, self.oven.as_mut()) {
+            oven.temperature = oven_temp;
+            return true;
+        }
+        self.oven = Some(Oven {
+            temperature: oven_temp,
+            started_at: self.time_elapsed,
+            start_temperature: self.temperature,
+            yeast_killed: false,
+            denaturation: 0.0,
+        });
+        // The oven heat replaces any perturbation in progress
+        self.temperature_spike = None;
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::BakeStarted {
+                oven_temperature: oven_temp,
+            },
+        );
+        self.set_phase(DoughPhase::Bake);
+        true
+    }
+
+    pub fn is_baking(&self) -> bool {
+        self.phase == DoughPhase::Bake && self.oven.is_some()
+    }
+
+    pub fn yeast_alive(&self) -> bool {
+        !self.oven.as_ref().is_some_and(|oven| oven.yeast_killed)
+    }
+
+    // Multiplier on the bond solver correction: set proteins hold their shape
+    pub fn bond_stiffness(&self) -> f32 {
+        1.0 + self.oven.as_ref().map_or(0.0, |oven| oven.denaturation)
+    }
+
+    // The oven heats the crust first, the crumb follows through the dough
+    pub(crate) fn oven_exchange(&self) -> Option<HeatExchange> {
+        if !self.is_baking() {
+            return None;
+        }
+        self.oven.as_ref().map(|oven| HeatExchange {
+            temperature: oven.temperature,
+            lag: self.bake.heat_lag,
+        })
+    }
+
+    // `previous` is the mean dough temperature before the heat of this tick
+    pub(crate) fn update_bake(&mut self, dt: f32, previous: f32) {
+        if !self.is_baking() {
+            return;
+        }
+        let settings = self.bake.clone();
+        // The core is the last to heat: the yeast is dead and the crumb set
+        // once it is
+        let core = self.core_temperature();
+        let Some(oven) = self.oven.as_mut() else {
+            return;
+        };
+
+        if !oven.yeast_killed && core >= settings.yeast_death_temp {
+            oven.yeast_killed = true;
+            self.events
+                .push(self.time_elapsed, SimulationEvent::YeastKilled);
+        }
+
+        let span = (settings.denature_end - settings.denature_start).max(1.0);
+        let set = ((core - settings.denature_start) / span).clamp(0.0, 1.0);
+        oven.denaturation = oven.denaturation.max(set);
+
+        // Heated gas bubbles grow and push the dough around them (oven
+        // spring); they sit in the crumb, which stays below boiling
+        let previous = previous.min(settings.crumb_max_temp);
+        let current = self.temperature.min(settings.crumb_max_temp);
+        let growth = (current - previous) / (previous + KELVIN);
+        if growth > 0.0 {
+            self.expand_gas(
+                growth * settings.gas_expansion_gain,
+                settings.gas_push_distance,
+            );
+        }
+
+        self.dry_crust(settings.crust_water_rate * dt, settings.crust_depth);
+    }
+
+    // Moves every molecule near a CO2 bubble away from it by `growth` of
+    // their distance
+    fn expand_gas(&mut self, growth: f32, reach: f32) {
+        let mut displacements: Vec<(MoleculeId, Vector3<f32>)> = Vec::new();
+        for mol in self.grid.molecules() {
+            if !matches!(mol.mol_type, MoleculeType::CO2) {
+                continue;
+            }
+            for neighbor in self.grid.neighbors_within(mol.pos, reach) {
+                if neighbor.id != mol.id {
+                    displacements.push((neighbor.id, self.grid.displacement(mol.pos, neighbor.pos) * growth));
+                }
+            }
+        }
+
+        let (width, height, depth) = (self.width, self.height, self.depth);
+        for (id, displacement) in displacements {
+            if let Some(mol) = self.grid.get_molecule(id) {
+                let pos = mol.pos + displacement;
+                let pos = Vector3::new(
+                    pos.x.clamp(0.0, width),
+                    pos.y.clamp(0.0, height),
+                    pos.z.clamp(0.0, depth),
+                );
+                self.grid.update_molecule_pos(id, pos);
+            }
+        }
+    }
+
+    // Water along every wall of the tin evaporates into a crust, faster
+    // where the dough is hotter
+    fn dry_crust(&mut self, chance: f32, depth: f32) {
+        if chance <= 0.0 {
+            return;
+        }
+        let mut evaporated = Vec::new();
+        for mol in self.grid.molecules() {
+            let near_wall = mol.pos.x < depth
+                || mol.pos.x > self.width - depth
+                || mol.pos.y < depth
+                || mol.pos.y > self.height - depth
+                || mol.pos.z < depth
+                || mol.pos.z > self.depth - depth;
+            if !near_wall
+                || !matches!(
+                    mol.mol_type,
+                    MoleculeType::Water | MoleculeType::WaterCluster { .. }
+                )
+            {
+                continue;
+            }
+            let heat = ((self.temperature_at(mol.pos) - 25.0) / 75.0).max(0.0);
+            if self.rng.gen::<f32>() < chance * heat * mol.mol_type.water_units() as f32 {
+                evaporated.push(mol.id);
+            }
+        }
+        self.evaporate_water(evaporated);
+    }
+}
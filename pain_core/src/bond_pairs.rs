@@ -0,0 +1,97 @@
+use crate::{Bond, BondColoring, MoleculeId, SimulationState, StableHashSet};
+
+// The molecule pairs of `bonds`, smaller id first, so a new bridge is checked
+// against the network in constant time instead of a scan over every bond.
+// Kept in step with `bonds` by the state's bond mutators, like the coloring.
+#[derive(Debug, Clone, Default)]
+pub struct BondPairs {
+    pairs: StableHashSet<(MoleculeId, MoleculeId)>,
+}
+
+fn pair(a: MoleculeId, b: MoleculeId) -> (MoleculeId, MoleculeId) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn bond_pair(bond: &Bond) -> (MoleculeId, MoleculeId) {
+    pair(bond.molecule_a_id, bond.molecule_b_id)
+}
+
+impl BondPairs {
+    pub fn build(bonds: &[Bond]) -> Self {
+        BondPairs {
+            pairs: bonds.iter().map(bond_pair).collect(),
+        }
+    }
+
+    pub fn contains(&self, a: MoleculeId, b: MoleculeId) -> bool {
+        self.pairs.contains(&pair(a, b))
+    }
+
+    pub fn insert(&mut self, bond: &Bond) {
+        self.pairs.insert(bond_pair(bond));
+    }
+
+    // Drops the bonds not kept, `keep` in the order of `bonds`
+    pub fn retain(&mut self, bonds: &[Bond], keep: &[bool]) {
+        for (bond, &kept) in bonds.iter().zip(keep) {
+            if !kept {
+                self.pairs.remove(&bond_pair(bond));
+            }
+        }
+    }
+}
+
+impl SimulationState {
+    pub fn bonds(&self) -> &[Bond] {
+        &self.bonds
+    }
+
+    // Whether a bond joins these two molecules, either way round
+    pub fn has_bond(&self, a: MoleculeId, b: MoleculeId) -> bool {
+        if self.bonds_indexed() {
+            return self.bond_pairs.contains(a, b);
+        }
+        // Not indexed since the state was loaded or its bonds replaced
+        let wanted = pair(a, b);
+        self.bonds.iter().any(|bond| bond_pair(bond) == wanted)
+    }
+
+    pub fn push_bond(&mut self, bond: Bond) {
+        self.index_bonds();
+        self.bond_coloring.push(&bond);
+        self.bond_pairs.insert(&bond);
+        self.bonds.push(bond);
+        self.bonds_changed_in_step();
+    }
+
+    // Replaces the bonds as a whole (a replayed frame, a reset); they are
+    // indexed again on the next use
+    pub fn set_bonds(&mut self, bonds: Vec<Bond>) {
+        self.bonds = bonds;
+        self.bonds_generation += 1;
+    }
+
+    // Every change to `bonds` moves the generation on. The coloring and the
+    // pair index are valid for the generation they were last brought to, and
+    // built again from scratch when the bonds moved on without them.
+    pub(crate) fn bonds_changed_in_step(&mut self) {
+        self.bonds_generation += 1;
+        self.indexed_generation = Some(self.bonds_generation);
+    }
+
+    fn bonds_indexed(&self) -> bool {
+        self.indexed_generation == Some(self.bonds_generation)
+    }
+
+    pub(crate) fn index_bonds(&mut self) {
+        if !self.bonds_indexed() {
+            self.bond_pairs = BondPairs::build(&self.bonds);
+            self.bond_coloring = BondColoring::build(&self.bonds);
+            self.indexed_generation = Some(self.bonds_generation);
+        }
+    }
+}
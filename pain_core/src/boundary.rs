@@ -0,0 +1,181 @@
+use crate::{Molecule, MoleculeId, MoleculeType, SimulationState, UP_AXIS};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// What a face of the box does to a molecule reaching it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    // Back in with `config.wall_restitution` of the speed it hit with
+    #[default]
+    Bounce,
+    // Same with its own restitution
+    BounceWith(f32),
+    // Stops dead against the face, no sliding (a floured cloth, a damp bowl)
+    Sticky,
+    // Goes through and leaves the dough for good
+    Open,
+    // Comes back in through the opposite face, for a periodic sample of bulk
+    // dough. Bonds, repulsion and neighbor searches reach across the seam
+    // the short way (minimum image).
+    Wrap,
+}
+
+impl BoundaryMode {
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoundaryMode::Bounce | BoundaryMode::BounceWith(_) => "rebond",
+            BoundaryMode::Sticky => "collant",
+            BoundaryMode::Open => "ouvert",
+            BoundaryMode::Wrap => "périodique",
+        }
+    }
+}
+
+// The six faces of the box: `faces[axis]` is the low then the high face
+// along x, y and z, so the floor is `faces[UP_AXIS][0]` and the top
+// `faces[UP_AXIS][1]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Boundaries {
+    pub faces: [[BoundaryMode; 2]; 3],
+}
+
+impl Boundaries {
+    pub fn all(mode: BoundaryMode) -> Self {
+        Boundaries {
+            faces: [[mode; 2]; 3],
+        }
+    }
+
+    // A closed box bouncing on every face, the historical container
+    pub fn closed_box() -> Self {
+        Boundaries::default()
+    }
+
+    // A bowl with no lid: whatever rises past the rim is gone
+    pub fn open_bowl() -> Self {
+        Boundaries::closed_box().with_face(UP_AXIS, true, BoundaryMode::Open)
+    }
+
+    // A covered banneton: the dough clings to the floured cane of the sides
+    // and the bottom, and bounces off the cover
+    pub fn covered_banneton() -> Self {
+        let mut boundaries = Boundaries::all(BoundaryMode::Sticky);
+        boundaries.faces[UP_AXIS][1] = BoundaryMode::Bounce;
+        boundaries
+    }
+
+    // A sample out of a larger mass of dough: periodic sideways, with the
+    // floor and the top kept so gravity still has something to act against
+    pub fn periodic() -> Self {
+        let mut boundaries = Boundaries::all(BoundaryMode::Wrap);
+        boundaries.faces[UP_AXIS] = [BoundaryMode::Bounce; 2];
+        boundaries
+    }
+
+    pub fn with_face(mut self, axis: usize, high: bool, mode: BoundaryMode) -> Self {
+        self.faces[axis][usize::from(high)] = mode;
+        self
+    }
+
+    // Extent of the box along each axis a molecule can wrap around, None
+    // along the others
+    pub fn periods(&self, size: Vector3<f32>) -> [Option<f32>; 3] {
+        std::array::from_fn(|axis| {
+            self.faces[axis]
+                .contains(&BoundaryMode::Wrap)
+                .then_some(size[axis])
+        })
+    }
+
+    // Puts `mol` back in a box of `size` after it moved; false when it left
+    // through an open face
+    pub(crate) fn confine(&self, mol: &mut Molecule, size: Vector3<f32>, restitution: f32) -> bool {
+        let radius = mol.radius();
+        for axis in 0..3 {
+            let [low, high] = self.faces[axis];
+            if mol.pos[axis] < radius && !cross(low, mol, axis, false, size[axis], restitution) {
+                return false;
+            }
+            if mol.pos[axis] > size[axis] - radius
+                && !cross(high, mol, axis, true, size[axis], restitution)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// `mol` touches the low or `high` face of `mode` along `axis`; false when it
+// is gone
+fn cross(
+    mode: BoundaryMode,
+    mol: &mut Molecule,
+    axis: usize,
+    high: bool,
+    extent: f32,
+    restitution: f32,
+) -> bool {
+    let radius = mol.radius();
+    // Where it stops against the face, whether its center went through, and
+    // the jump to the opposite face
+    let (wall, through, shift) = if high {
+        (extent - radius, mol.pos[axis] > extent, -extent)
+    } else {
+        (radius, mol.pos[axis] < 0.0, extent)
+    };
+    match mode {
+        BoundaryMode::Bounce | BoundaryMode::BounceWith(_) => {
+            let restitution = match mode {
+                BoundaryMode::BounceWith(own) => own,
+                _ => restitution,
+            };
+            mol.pos[axis] = wall;
+            mol.velocity[axis] = -mol.velocity[axis] * restitution;
+        }
+        BoundaryMode::Sticky => {
+            mol.pos[axis] = wall;
+            mol.velocity = Vector3::zeros();
+        }
+        BoundaryMode::Open => return !through,
+        BoundaryMode::Wrap if through => mol.pos[axis] += shift,
+        BoundaryMode::Wrap => {}
+    }
+    true
+}
+
+impl SimulationState {
+    // Hands the periodic axes to the grid, whose neighbor searches and
+    // distances wrap around them; the faces can change between ticks
+    pub(crate) fn sync_periods(&mut self) {
+        let periods = self.boundaries.periods(self.container_size());
+        self.grid.set_periods(periods);
+    }
+
+    // Molecules gone through an open face: the gas counts as escaped like at
+    // the surface, everything else in the ledger's `boundary_lost`
+    pub(crate) fn lose_through_boundaries(&mut self, ids: Vec<MoleculeId>) {
+        let mut escaped = Vec::new();
+        for id in ids {
+            let Some(mol) = self.grid.take_everywhere(id) else {
+                continue;
+            };
+            let mass = mol.mass() as f64;
+            match mol.mol_type {
+                MoleculeType::CO2 => {
+                    self.ledger.co2_lost += mass;
+                    self.ledger.co2_escaped += 1;
+                    escaped.push(mol.pos);
+                }
+                MoleculeType::Ethanol => {
+                    self.ledger.ethanol_lost += mass;
+                    self.ledger.ethanol_escaped += 1;
+                }
+                _ => self.ledger.boundary_lost += mass,
+            }
+            self.drop_bonds_of(id);
+        }
+        self.record_burps(&escaped);
+    }
+}
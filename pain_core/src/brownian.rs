@@ -0,0 +1,130 @@
+use crate::container;
+use crate::integrator::drag_rate;
+use crate::kinetics::{arrhenius_factor, kelvin};
+use crate::{MoleculeId, SimulationState, Species};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Thermal jiggling of the small molecules, so they keep diffusing through the
+// dough instead of settling to a standstill under the friction. Each tick adds
+// a random kick sized against the drag (fluctuation-dissipation), so a
+// molecule hovers around its thermal speed: proportional to the temperature
+// in kelvin, over the mass, and to an Arrhenius law for the viscosity of the
+// dough, which thickens in the cold much more than the temperature alone
+// would say. Diffusion at 4°C is about a seventh of what it is at 25°C.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrownianSettings {
+    pub thermal_speed: f32,     // Of a unit mass at the reference, 0 turns it off
+    pub reference_temp: f32,    // °C
+    pub activation_energy: f32, // kJ/mol, of the dough viscosity
+    pub species: Vec<Species>,  // The molecules small enough to jiggle
+}
+
+impl Default for BrownianSettings {
+    fn default() -> Self {
+        BrownianSettings {
+            thermal_speed: 0.05,
+            reference_temp: 25.0,
+            activation_energy: 60.0,
+            species: vec![
+                Species::Water,
+                Species::Salt,
+                Species::Sugar,
+                Species::Ethanol,
+            ],
+        }
+    }
+}
+
+impl BrownianSettings {
+    // Square of the thermal speed of a unit mass at `temperature` (°C),
+    // relative to the reference: how much faster the molecules diffuse
+    pub fn mobility(&self, temperature: f32) -> f32 {
+        kelvin(temperature) / kelvin(self.reference_temp)
+            * arrhenius_factor(self.activation_energy, temperature, self.reference_temp)
+    }
+}
+
+impl SimulationState {
+    pub(crate) fn apply_brownian_motion(&mut self, dt: f32) {
+        let settings = &self.brownian;
+        if settings.thermal_speed <= 0.0 || settings.species.is_empty() || dt <= 0.0 {
+            return;
+        }
+        // A uniform kick of half-width `a` has variance a² / 3, matched to
+        // the 2 * drag * dt * speed² the drag takes out
+        let spread = (6.0 * drag_rate(self.config.friction) * dt).sqrt() * settings.thermal_speed;
+        let jigglers: Vec<(MoleculeId, f32)> = self
+            .grid
+            .molecules()
+            .filter(|mol| settings.species.contains(&mol.mol_type.species()))
+            .map(|mol| {
+                let mobility = settings.mobility(self.temperature_at(mol.pos));
+                (mol.id, spread * (mobility / mol.mass()).sqrt())
+            })
+            .collect();
+        for (id, half_width) in jigglers {
+            let kick = Vector3::new(
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+            ) * half_width;
+            if let Some(mol) = self.grid.get_molecule_mut(id) {
+                mol.velocity += kick;
+            }
+        }
+    }
+    // Where the jiggling takes the small molecules over a long `dt`, without
+    // stepping through it: the spread of a velocity that keeps its thermal
+    // size while the drag forgets it (Ornstein-Uhlenbeck), straight at first,
+    // a random walk once `dt` is well past the drag time. Only the positions
+    // move; the walls hold them as they would at full detail.
+    pub(crate) fn diffuse_over(&mut self, dt: f32) {
+        let settings = &self.brownian;
+        if settings.thermal_speed <= 0.0 || settings.species.is_empty() || dt <= 0.0 {
+            return;
+        }
+        let drag = drag_rate(self.config.friction);
+        let drag_time = drag * dt;
+        // Mean square distance along an axis for a unit velocity variance
+        let spread = if drag_time > 1e-3 {
+            2.0 * (drag_time - 1.0 + (-drag_time).exp()) / (drag * drag)
+        } else {
+            dt * dt
+        };
+        let spread = (3.0 * spread).sqrt() * settings.thermal_speed;
+        let walkers: Vec<(MoleculeId, f32)> = self
+            .grid
+            .molecules()
+            .filter(|mol| settings.species.contains(&mol.mol_type.species()))
+            .map(|mol| {
+                let mobility = settings.mobility(self.temperature_at(mol.pos));
+                (mol.id, spread * (mobility / mol.mass()).sqrt())
+            })
+            .collect();
+        let restitution = self.config.wall_restitution;
+        let size = self.container_size();
+        let vessel = self.container.vessel(size);
+        let mut left = Vec::new();
+        for (id, half_width) in walkers {
+            let step = Vector3::new(
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+                self.rng.gen::<f32>() * 2.0 - 1.0,
+            ) * half_width;
+            let Some(mol) = self.grid.get_molecule_mut(id) else {
+                continue;
+            };
+            let old_pos = mol.pos;
+            mol.pos += step;
+            if !self.boundaries.confine(mol, size, restitution) {
+                left.push(id);
+            } else if let Some(vessel) = &vessel {
+                container::confine_to(vessel, mol, restitution);
+            }
+            self.grid.relocate(id, old_pos);
+        }
+        self.lose_through_boundaries(left);
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+// Share of the budget the flour and water may take when the dough is made,
+// the rest is kept for the salt, the levain and the gas they make
+const RECIPE_SHARE: f32 = 0.6;
+// Reactions adding molecules start slowing down past this share of the budget
+const THROTTLE_START: f32 = 0.9;
+
+// How many molecules the simulation holds: `resolution` scales every count
+// of the recipe and of the salt and levain added later (2 is twice as dense
+// as the demo), and `max_molecules` caps the total, 0 for no limit. Near the
+// cap fermentation and any other reaction making more molecules than it uses
+// slow down, and stop at the cap, so the mass stays balanced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetSettings {
+    pub resolution: f32,
+    pub max_molecules: usize,
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        BudgetSettings {
+            resolution: 1.0,
+            max_molecules: 0,
+        }
+    }
+}
+
+impl BudgetSettings {
+    pub fn scaled(&self, count: usize) -> usize {
+        (count as f32 * self.resolution.max(0.0)).round() as usize
+    }
+
+    // Scale on the `requested` molecules of a recipe: the resolution, less if
+    // they would take more than their share of the cap
+    pub fn recipe_scale(&self, requested: usize) -> f32 {
+        let scale = self.resolution.max(0.0);
+        let wanted = requested as f32 * scale;
+        let share = self.max_molecules as f32 * RECIPE_SHARE;
+        if self.max_molecules > 0 && wanted > share {
+            scale * share / wanted
+        } else {
+            scale
+        }
+    }
+
+    // Molecules that still fit next to `present` ones
+    pub fn room(&self, present: usize) -> usize {
+        if self.max_molecules == 0 {
+            usize::MAX
+        } else {
+            self.max_molecules.saturating_sub(present)
+        }
+    }
+
+    // Share of their chance the reactions adding molecules keep with
+    // `present` molecules: all of it until THROTTLE_START of the cap, down to
+    // none at the cap
+    pub fn throttle(&self, present: usize) -> f32 {
+        if self.max_molecules == 0 {
+            return 1.0;
+        }
+        let fill = present as f32 / self.max_molecules as f32;
+        ((1.0 - fill) / (1.0 - THROTTLE_START)).clamp(0.0, 1.0)
+    }
+}
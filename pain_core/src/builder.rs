@@ -0,0 +1,153 @@
+use crate::{
+    BudgetSettings, ContainerShape, EnvironmentPreset, Levain, PluginSet, Preferment, Recipe,
+    RecipePreset, SimulationConfig, SimulationPlugin, SimulationState, Starter,
+};
+
+// Everything needed to start a dough in one place, for crates embedding the
+// engine without the visualizer:
+//     let mut dough = SimulationState::builder().seed(7).with_yeast().build();
+//     dough.tick(0.25);
+#[derive(Debug, Clone)]
+pub struct SimulationBuilder {
+    size: (f32, f32, f32),
+    seed: Option<u64>,
+    recipe: Recipe,
+    config: SimulationConfig,
+    budget: BudgetSettings,
+    environment: Option<EnvironmentPreset>,
+    container: ContainerShape,
+    preferment: Option<Preferment>,
+    levain: Option<Levain>,
+    salt: bool,
+    yeast: bool,
+    plugins: PluginSet,
+}
+
+impl Default for SimulationBuilder {
+    fn default() -> Self {
+        SimulationBuilder {
+            size: (1000.0, 720.0, 1000.0),
+            seed: None,
+            recipe: RecipePreset::Classic.into(),
+            config: SimulationConfig::default(),
+            budget: BudgetSettings::default(),
+            environment: None,
+            container: ContainerShape::Box,
+            preferment: None,
+            levain: None,
+            salt: false,
+            yeast: false,
+            plugins: PluginSet::default(),
+        }
+    }
+}
+
+impl SimulationBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Container width, height (+y is up) and depth, in simulation units
+    pub fn size(mut self, width: f32, height: f32, depth: f32) -> Self {
+        self.size = (width, height, depth);
+        self
+    }
+
+    // Fixed seed for a reproducible run; random otherwise
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn recipe(mut self, recipe: impl Into<Recipe>) -> Self {
+        self.recipe = recipe.into();
+        self
+    }
+
+    pub fn config(mut self, config: SimulationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    // Denser or lighter than the demo, and a cap on the molecules
+    pub fn budget(mut self, budget: BudgetSettings) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn environment(mut self, preset: EnvironmentPreset) -> Self {
+        self.environment = Some(preset);
+        self
+    }
+
+    // The vessel inside the box, see ContainerShape::bowl and banneton
+    pub fn container(mut self, shape: ContainerShape) -> Self {
+        self.container = shape;
+        self
+    }
+
+    // Part of the flour fermented ahead as a poolish or a biga, see
+    // SimulationState::initialize_with_preferment
+    pub fn preferment(mut self, preferment: Preferment) -> Self {
+        self.preferment = Some(preferment);
+        self
+    }
+
+    // The levain taken from a kept starter as it is now, instead of a fresh
+    // one, for this dough and any levain added to it later
+    pub fn starter(mut self, starter: &Starter) -> Self {
+        self.levain = Some(starter.levain());
+        self
+    }
+
+    // Salt and levain mixed in right away instead of after the autolyse
+    pub fn with_salt(mut self) -> Self {
+        self.salt = true;
+        self
+    }
+
+    pub fn with_yeast(mut self) -> Self {
+        self.yeast = true;
+        self
+    }
+
+    pub fn plugin(mut self, plugin: impl SimulationPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    pub fn build(self) -> SimulationState {
+        let (width, height, depth) = self.size;
+        let mut state = match self.seed {
+            Some(seed) => SimulationState::new_with_seed(width, height, depth, seed),
+            None => SimulationState::new(width, height, depth),
+        };
+        // The grid is rebuilt by the recipe with the configured cell size
+        state.config = self.config;
+        state.budget = self.budget;
+        match &self.preferment {
+            Some(preferment) => state.initialize_with_preferment(self.recipe, preferment),
+            None => state.initialize_recipe(self.recipe),
+        }
+        if let Some(preset) = self.environment {
+            state.set_environment(preset);
+        }
+        state.levain = self.levain;
+        if self.salt {
+            state.add_salt();
+        }
+        if self.yeast {
+            state.add_yeast();
+        }
+        // Once everything is in, so nothing starts outside the wall
+        state.set_container(self.container);
+        state.set_plugins(self.plugins);
+        state
+    }
+}
+
+impl SimulationState {
+    pub fn builder() -> SimulationBuilder {
+        SimulationBuilder::new()
+    }
+}
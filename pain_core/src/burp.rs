@@ -0,0 +1,87 @@
+use crate::{SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// How the CO2 escaping through the surface is grouped into burps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurpSettings {
+    pub radius: f32,        // CO2 escaping this close together in one tick make one burp
+    pub min_molecules: u32, // Fewer escaping together seep out unnoticed
+}
+
+impl Default for BurpSettings {
+    fn default() -> Self {
+        BurpSettings {
+            radius: 15.0,
+            min_molecules: 1,
+        }
+    }
+}
+
+// Gas bursting through the top of the dough, the sign bakers read as an
+// active fermentation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Burp {
+    pub pos: Vector3<f32>, // Mean position of the escaping molecules
+    pub molecules: u32,
+}
+
+// Each position joins the first burp whose center is within `radius`
+pub fn group_burps(escaped: &[Vector3<f32>], settings: &BurpSettings) -> Vec<Burp> {
+    let mut burps: Vec<Burp> = Vec::new();
+    for &pos in escaped {
+        match burps
+            .iter_mut()
+            .find(|burp| (burp.pos - pos).magnitude() <= settings.radius)
+        {
+            Some(burp) => {
+                burp.molecules += 1;
+                burp.pos += (pos - burp.pos) / burp.molecules as f32;
+            }
+            None => burps.push(Burp { pos, molecules: 1 }),
+        }
+    }
+    burps.retain(|burp| burp.molecules >= settings.min_molecules);
+    burps
+}
+
+impl SimulationState {
+    // Logs the gas that left through the surface this tick as burps
+    pub(crate) fn record_burps(&mut self, escaped: &[Vector3<f32>]) {
+        for burp in group_burps(escaped, &self.burp_settings) {
+            self.ledger.burps += 1;
+            self.events
+                .push(self.time_elapsed, SimulationEvent::GasBurp(burp));
+        }
+    }
+
+    // Burps still in the event log that happened after `time`, oldest first
+    pub fn burps_since(&self, time: f32) -> impl Iterator<Item = (f32, Burp)> + '_ {
+        self.events
+            .iter()
+            .filter(move |entry| entry.time > time)
+            .filter_map(|entry| match entry.event {
+                SimulationEvent::GasBurp(burp) => Some((entry.time, burp)),
+                _ => None,
+            })
+    }
+
+    // Burps per minute over the last `window` seconds of metrics samples
+    pub fn burp_rate(&self, window: f32) -> f32 {
+        let Some(latest) = self.metrics.latest() else {
+            return 0.0;
+        };
+        let Some(first) = self
+            .metrics
+            .samples()
+            .find(|sample| sample.time >= latest.time - window)
+        else {
+            return 0.0;
+        };
+        let span = latest.time - first.time;
+        if span <= 0.0 {
+            return 0.0;
+        }
+        (latest.burps - first.burps) as f32 / span * 60.0
+    }
+}
@@ -0,0 +1,149 @@
+use crate::flour::wheat_absorption;
+use crate::{DoughPhase, Recipe, RecipePreset, SimulationState};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Hydration the player starts from, before adjusting it to the flour
+const START_HYDRATION: f32 = 0.70;
+// Off by this much from the flour's absorption, the hydration earns nothing
+const HYDRATION_TOLERANCE: f32 = 0.10;
+// Volume ratio of a well risen loaf
+const TARGET_VOLUME_RATIO: f32 = 2.0;
+
+// A flour whose properties are drawn at random and kept from the player, who
+// has to work them out from how the dough behaves
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MysteryFlour {
+    pub protein_content: f32,
+    pub glutenin_fraction: f32,
+    pub amylase_activity: f32,
+}
+
+impl MysteryFlour {
+    // From a weak rye-like flour to a strong bread flour
+    pub fn draw(rng: &mut impl Rng) -> Self {
+        MysteryFlour {
+            protein_content: rng.gen_range(0.08..0.145),
+            glutenin_fraction: rng.gen_range(0.3..0.7),
+            amylase_activity: rng.gen_range(0.5..2.5),
+        }
+    }
+
+    // Hydration the flour takes up: stronger flours drink more water, the
+    // classic 12% flour wants 72%
+    pub fn absorption(&self) -> f32 {
+        wheat_absorption(self.protein_content)
+    }
+
+    // The classic recipe made with this flour and the player's hydration
+    pub fn recipe(&self, hydration: f32) -> Recipe {
+        Recipe {
+            name: "Farine mystère".to_string(),
+            hydration,
+            protein_content: self.protein_content,
+            glutenin_fraction: self.glutenin_fraction,
+            amylase_activity: self.amylase_activity,
+            absorption: self.absorption(),
+            ..RecipePreset::Classic.recipe()
+        }
+    }
+
+    // Shown once the challenge is over
+    pub fn describe(&self) -> String {
+        format!(
+            "protéines {:.1} %, gluténines {:.0} %, amylase x{:.1}, hydratation idéale {:.0} %",
+            self.protein_content * 100.0,
+            self.glutenin_fraction * 100.0,
+            self.amylase_activity,
+            self.absorption() * 100.0
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Challenge {
+    pub flour: MysteryFlour,
+    pub hydration: f32, // Chosen by the player
+    pub revealed: bool,
+}
+
+// The final loaf judged on what a baker would look at, each part from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoafScore {
+    pub hydration: f32,   // How close the water was to what the flour takes up
+    pub development: f32, // Gluten development
+    pub volume: f32,      // Rise, nothing if the dough collapsed
+    pub crumb: f32,       // Open-crumb score
+    pub points: u32,      // Out of 100
+}
+
+impl LoafScore {
+    pub fn verdict(&self) -> &'static str {
+        match self.points {
+            0..=39 => "Pavé",
+            40..=59 => "Pain honnête",
+            60..=79 => "Belle miche",
+            _ => "Pain de concours",
+        }
+    }
+}
+
+impl SimulationState {
+    // Starts a fresh dough from a newly drawn mystery flour
+    pub fn start_challenge(&mut self) {
+        let flour = MysteryFlour::draw(&mut self.rng);
+        self.challenge = Some(Challenge {
+            flour,
+            hydration: START_HYDRATION,
+            revealed: false,
+        });
+        self.initialize_recipe(flour.recipe(START_HYDRATION));
+    }
+
+    // Remakes the dough with more or less water; only possible before salt
+    // and yeast go in
+    pub fn set_challenge_hydration(&mut self, hydration: f32) -> bool {
+        let Some(challenge) = self.challenge.as_mut() else {
+            return false;
+        };
+        if self.salt_added || self.yeast_added {
+            return false;
+        }
+        challenge.hydration = hydration.clamp(0.5, 0.95);
+        let recipe = challenge.flour.recipe(challenge.hydration);
+        self.initialize_recipe(recipe);
+        true
+    }
+
+    pub fn reveal_challenge(&mut self) -> Option<MysteryFlour> {
+        let challenge = self.challenge.as_mut()?;
+        challenge.revealed = true;
+        Some(challenge.flour)
+    }
+
+    // Scored once the loaf has baked and cooled
+    pub fn challenge_score(&self) -> Option<LoafScore> {
+        let challenge = self.challenge.as_ref()?;
+        if self.phase != DoughPhase::Cooled {
+            return None;
+        }
+        let gap = (challenge.hydration - challenge.flour.absorption()).abs();
+        let hydration = (1.0 - gap / HYDRATION_TOLERANCE).clamp(0.0, 1.0);
+        let development = self.gluten_development().total.clamp(0.0, 1.0);
+        let volume = if self.collapse.record.is_some() {
+            0.0
+        } else {
+            let ratio = self.rise_metrics().volume_ratio;
+            ((ratio - 1.0) / (TARGET_VOLUME_RATIO - 1.0)).clamp(0.0, 1.0)
+        };
+        let crumb = self.crumb_analysis().open_crumb_score;
+        let points = 25.0 * hydration + 30.0 * development + 30.0 * volume + 15.0 * crumb;
+        Some(LoafScore {
+            hydration,
+            development,
+            volume,
+            crumb,
+            points: points.round() as u32,
+        })
+    }
+}
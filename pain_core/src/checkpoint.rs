@@ -0,0 +1,157 @@
+use crate::snapshot::{decode_state, encode_state};
+use crate::{DoughPhase, SimulationEvent, SimulationState};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::mem;
+use std::sync::Arc;
+
+// Fast rather than small: checkpoints are taken while the dough runs
+const CHECKPOINT_ZSTD_LEVEL: i32 = 1;
+
+// How often the running dough checkpoints itself, and how far back it keeps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointSettings {
+    pub interval: Option<f32>, // Sim seconds between two checkpoints, None for none
+    pub keep: usize,           // Newest checkpoints kept, older ones are dropped
+}
+
+impl Default for CheckpointSettings {
+    fn default() -> Self {
+        CheckpointSettings {
+            interval: Some(10.0 * 60.0),
+            keep: 48, // Eight hours of ferment
+        }
+    }
+}
+
+// The whole dough at one moment, compressed in memory. Cheap to clone: the
+// copies share the bytes.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    time: f32,
+    phase: DoughPhase,
+    bytes: Arc<[u8]>,
+}
+
+impl Checkpoint {
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn phase(&self) -> DoughPhase {
+        self.phase
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+// The automatic checkpoints, oldest first. Not saved: a save file holds one
+// dough, not its past.
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointRing {
+    checkpoints: VecDeque<Checkpoint>,
+    last_at: Option<f32>,
+}
+
+impl CheckpointRing {
+    pub fn iter(&self) -> impl Iterator<Item = &Checkpoint> {
+        self.checkpoints.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+    }
+
+    // The checkpoint taken nearest to `time`
+    pub fn nearest(&self, time: f32) -> Option<&Checkpoint> {
+        self.checkpoints
+            .iter()
+            .min_by(|a, b| (a.time - time).abs().total_cmp(&(b.time - time).abs()))
+    }
+
+    fn push(&mut self, checkpoint: Checkpoint, keep: usize) {
+        self.last_at = Some(checkpoint.time);
+        self.checkpoints.push_back(checkpoint);
+        while self.checkpoints.len() > keep.max(1) {
+            self.checkpoints.pop_front();
+        }
+    }
+
+    // Back at `time`, the checkpoints taken later belong to a future that
+    // will not happen
+    fn rewind_to(&mut self, time: f32) {
+        self.checkpoints
+            .retain(|checkpoint| checkpoint.time <= time);
+        self.last_at = Some(time);
+    }
+}
+
+impl SimulationState {
+    pub fn checkpoint(&self) -> io::Result<Checkpoint> {
+        let bytes = zstd::encode_all(&encode_state(self)?[..], CHECKPOINT_ZSTD_LEVEL)?;
+        Ok(Checkpoint {
+            time: self.time_elapsed,
+            phase: self.phase,
+            bytes: bytes.into(),
+        })
+    }
+
+    // Puts the dough back as it was at `checkpoint`. The checkpoints and the
+    // plugins stay, and the event log notes the jump back.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> io::Result<()> {
+        let mut restored = decode_state(&zstd::decode_all(&checkpoint.bytes[..])?)?;
+        let from = self.time_elapsed;
+        restored.checkpoints = mem::take(&mut self.checkpoints);
+        restored.checkpoints.rewind_to(checkpoint.time);
+        restored.plugins = mem::take(&mut self.plugins);
+        restored.events.push(
+            restored.time_elapsed,
+            SimulationEvent::Rewound {
+                from,
+                to: restored.time_elapsed,
+            },
+        );
+        *self = restored;
+        Ok(())
+    }
+
+    // Back to the checkpoint nearest to `seconds` ago; the time it went back
+    // to, None without any checkpoint
+    pub fn rewind(&mut self, seconds: f32) -> io::Result<Option<f32>> {
+        let Some(checkpoint) = self
+            .checkpoints
+            .nearest(self.time_elapsed - seconds.max(0.0))
+            .cloned()
+        else {
+            return Ok(None);
+        };
+        self.restore(&checkpoint)?;
+        Ok(Some(checkpoint.time))
+    }
+
+    // The first tick takes one, so there is always a start to go back to
+    pub(crate) fn checkpoint_if_due(&mut self) {
+        let Some(interval) = self.checkpoint_settings.interval else {
+            return;
+        };
+        if self
+            .checkpoints
+            .last_at
+            .is_some_and(|last| self.time_elapsed - last < interval)
+        {
+            return;
+        }
+        // Encoding into memory does not fail on a state that ticks
+        if let Ok(checkpoint) = self.checkpoint() {
+            let keep = self.checkpoint_settings.keep;
+            self.checkpoints.push(checkpoint, keep);
+        }
+    }
+}
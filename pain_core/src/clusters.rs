@@ -0,0 +1,179 @@
+use crate::{Molecule, MoleculeId, MoleculeType, SimulationState};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Largest cluster allowed at accuracy 0.0
+const MAX_CLUSTER_SIZE: u8 = 8;
+
+// Quiet bulk water is merged into heavier cluster particles to save work, and
+// split back into individual molecules as soon as it gets sheared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaterClusterSettings {
+    pub accuracy: f32,       // 1.0 = never merge, 0.0 = clusters of up to 8 molecules
+    pub quiet_speed: f32,    // Molecules slower than this may merge
+    pub merge_distance: f32, // Max distance between merging molecules
+    pub shear_speed: f32,    // Speed relative to the neighborhood that splits a cluster
+}
+
+impl Default for WaterClusterSettings {
+    fn default() -> Self {
+        WaterClusterSettings {
+            accuracy: 0.5,
+            quiet_speed: 0.1,
+            merge_distance: 6.0,
+            shear_speed: 0.5,
+        }
+    }
+}
+
+impl WaterClusterSettings {
+    pub fn max_cluster_size(&self) -> u8 {
+        let accuracy = self.accuracy.clamp(0.0, 1.0);
+        (1.0 + (1.0 - accuracy) * (MAX_CLUSTER_SIZE - 1) as f32).round() as u8
+    }
+}
+
+impl MoleculeType {
+    // Number of water molecules this particle stands for
+    pub fn water_units(&self) -> usize {
+        match self {
+            MoleculeType::Water => 1,
+            MoleculeType::WaterCluster { size } => *size as usize,
+            _ => 0,
+        }
+    }
+}
+
+struct Merge {
+    host: MoleculeId,
+    guests: Vec<MoleculeId>,
+    size: u8,
+    pos: Vector3<f32>,
+    velocity: Vector3<f32>,
+}
+
+impl SimulationState {
+    pub fn water_count(&self) -> usize {
+        self.grid
+            .get_all_molecules()
+            .iter()
+            .map(|mol| mol.mol_type.water_units())
+            .sum()
+    }
+
+    pub(crate) fn update_water_clusters(&mut self) {
+        self.split_sheared_clusters();
+
+        let max_size = self.water_clustering.max_cluster_size();
+        if max_size > 1 {
+            self.merge_quiet_water(max_size);
+        }
+    }
+
+    fn merge_quiet_water(&mut self, max_size: u8) {
+        let settings = &self.water_clustering;
+        let mut taken: HashSet<MoleculeId> = HashSet::new();
+        let mut merges = Vec::new();
+
+        for mol in self.grid.molecules() {
+            let units = mol.mol_type.water_units();
+            if units == 0
+                || units >= max_size as usize
+                || taken.contains(&mol.id)
+                || mol.velocity.magnitude() > settings.quiet_speed
+            {
+                continue;
+            }
+
+            // Mass-weighted centroid and momentum so merging is invisible to
+            // the physics, the centroid taken from the host across any seam
+            let mut size = units;
+            let mut weighted_offset = Vector3::zeros();
+            let mut momentum = mol.velocity * units as f32;
+            let mut guests = Vec::new();
+
+            for neighbor in self.grid.neighbors(mol.pos) {
+                let neighbor_units = neighbor.mol_type.water_units();
+                if neighbor.id == mol.id
+                    || neighbor_units == 0
+                    || size + neighbor_units > max_size as usize
+                    || taken.contains(&neighbor.id)
+                    || neighbor.velocity.magnitude() > settings.quiet_speed
+                    || self.grid.displacement(mol.pos, neighbor.pos).magnitude()
+                        > settings.merge_distance
+                {
+                    continue;
+                }
+                size += neighbor_units;
+                weighted_offset +=
+                    self.grid.displacement(mol.pos, neighbor.pos) * neighbor_units as f32;
+                momentum += neighbor.velocity * neighbor_units as f32;
+                guests.push(neighbor.id);
+            }
+
+            if guests.is_empty() {
+                continue;
+            }
+            taken.insert(mol.id);
+            taken.extend(guests.iter().copied());
+            merges.push(Merge {
+                host: mol.id,
+                guests,
+                size: size as u8,
+                pos: mol.pos + weighted_offset / size as f32,
+                velocity: momentum / size as f32,
+            });
+        }
+
+        for merge in merges {
+            for guest in merge.guests {
+                self.grid.remove(guest);
+            }
+            self.grid.update_molecule_pos(merge.host, merge.pos);
+            self.grid
+                .set_mol_type(merge.host, MoleculeType::WaterCluster { size: merge.size });
+            if let Some(host) = self.grid.get_molecule_mut(merge.host) {
+                host.velocity = merge.velocity;
+            }
+        }
+    }
+
+    fn split_sheared_clusters(&mut self) {
+        let max_size = self.water_clustering.max_cluster_size() as usize;
+        let shear_speed = self.water_clustering.shear_speed;
+        let mut to_split = Vec::new();
+
+        for mol in self.grid.molecules() {
+            let MoleculeType::WaterCluster { size } = mol.mol_type else {
+                continue;
+            };
+
+            // Shear is measured against the average motion of the surroundings
+            let (velocity_sum, count) = self
+                .grid
+                .neighbors(mol.pos)
+                .fold((Vector3::zeros(), 0), |(acc, count), n| (acc + n.velocity, count + 1));
+            let mean_velocity = velocity_sum / count.max(1) as f32;
+            let shear = (mol.velocity - mean_velocity).magnitude();
+
+            if shear > shear_speed || size as usize > max_size {
+                to_split.push((mol.id, size, mol.pos, mol.velocity));
+            }
+        }
+
+        for (id, size, pos, velocity) in to_split {
+            self.grid.remove(id);
+            for _ in 0..size {
+                let offset = Vector3::new(
+                    self.rng.gen_range(-1.5..1.5),
+                    self.rng.gen_range(-1.5..1.5),
+                    self.rng.gen_range(-1.5..1.5),
+                );
+                self.grid
+                    .insert(Molecule::new(MoleculeType::Water, pos + offset, velocity));
+            }
+        }
+    }
+}
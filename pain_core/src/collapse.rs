@@ -0,0 +1,122 @@
+use crate::{SimulationEvent, SimulationState};
+use serde::{Deserialize, Serialize};
+
+// A collapse is a sudden loss of rise together with a burst of torn bonds:
+// the network gives way and the dough slumps, like an over-proofed loaf
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollapseSettings {
+    pub window: f32,      // Sim seconds between two checks
+    pub min_rise: f32,    // Rise (sim units) the dough must have reached first
+    pub height_drop: f32, // Share of the peak rise lost within one window
+    pub break_spike: f32, // Bonds torn within the window, as a share of those at its start
+}
+
+impl Default for CollapseSettings {
+    fn default() -> Self {
+        CollapseSettings {
+            window: 10.0,
+            min_rise: 5.0,
+            height_drop: 0.3,
+            break_spike: 0.15,
+        }
+    }
+}
+
+// When and how the dough collapsed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollapseRecord {
+    pub time: f32,
+    pub peak_rise: f32,
+    pub height_drop: f32, // Height lost within the window
+    pub bonds_torn: u64,  // Bonds torn within the window
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CollapseWindow {
+    time: f32,
+    height: f32,
+    bonds_broken: u64, // Ledger count at the start of the window
+    bonds: usize,
+}
+
+// Follows the rise window by window; only the first collapse is recorded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollapseMonitor {
+    start_height: Option<f32>,
+    start_volume: Option<f32>,
+    peak_rise: f32,
+    window: Option<CollapseWindow>,
+    pub record: Option<CollapseRecord>,
+}
+
+impl CollapseMonitor {
+    pub fn peak_rise(&self) -> f32 {
+        self.peak_rise
+    }
+
+    // Dough height at the first check, what "50% rise" is measured against
+    pub fn start_height(&self) -> Option<f32> {
+        self.start_height
+    }
+
+    // Dough volume at the first check, what "doubled" is measured against
+    pub fn start_volume(&self) -> Option<f32> {
+        self.start_volume
+    }
+}
+
+impl SimulationState {
+    // Rise of the dough since the monitor first measured it
+    pub fn rise(&self) -> f32 {
+        self.collapse
+            .start_height
+            .map_or(0.0, |start| self.dough_height() - start)
+    }
+
+    pub(crate) fn watch_for_collapse(&mut self) {
+        let settings = &self.collapse_settings;
+        if self
+            .collapse
+            .window
+            .is_some_and(|window| self.time_elapsed - window.time < settings.window)
+        {
+            return;
+        }
+        let height = self.dough_height();
+        let start_height = *self.collapse.start_height.get_or_insert(height);
+        if self.collapse.start_volume.is_none() {
+            self.collapse.start_volume = Some(self.dough_volume());
+        }
+        let now = CollapseWindow {
+            time: self.time_elapsed,
+            height,
+            bonds_broken: self.reactions.bonds_broken,
+            bonds: self.bonds.len(),
+        };
+        let previous = self.collapse.window.replace(now);
+        let monitor = &mut self.collapse;
+        monitor.peak_rise = monitor.peak_rise.max(height - start_height);
+        let Some(previous) = previous else {
+            return;
+        };
+        if monitor.record.is_some() || monitor.peak_rise < settings.min_rise {
+            return;
+        }
+
+        let height_drop = previous.height - height;
+        let bonds_torn = now.bonds_broken - previous.bonds_broken;
+        let slumped = height_drop >= settings.height_drop * monitor.peak_rise;
+        let torn = bonds_torn as f32 >= settings.break_spike * previous.bonds.max(1) as f32;
+        if slumped && torn {
+            let record = CollapseRecord {
+                time: self.time_elapsed,
+                peak_rise: monitor.peak_rise,
+                height_drop,
+                bonds_torn,
+            };
+            monitor.record = Some(record);
+            self.events
+                .push(self.time_elapsed, SimulationEvent::DoughCollapsed(record));
+        }
+    }
+}
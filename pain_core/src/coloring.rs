@@ -0,0 +1,144 @@
+use crate::{Bond, MoleculeId, SimulationState, StableHashMap, StableHashSet};
+use smallvec::SmallVec;
+
+// Bonds split into independent sets by greedy graph coloring: two bonds of
+// the same color never share a molecule, so the bond solver can work out a
+// whole color at once (on several threads with the `parallel` feature) and
+// write the corrections back without two bonds touching the same molecule.
+// Kept up to date as bonds form and tear instead of recolored every tick,
+// see `SimulationState::push_bond`.
+#[derive(Debug, Clone, Default)]
+pub struct BondColoring {
+    colors: Vec<u32>, // Color of each bond, in the order of `bonds`
+    taken: StableHashMap<MoleculeId, SmallVec<[u32; 4]>>, // Colors of the bonds at each molecule
+    sizes: Vec<usize>, // Bonds of each color
+}
+
+impl BondColoring {
+    pub fn build(bonds: &[Bond]) -> Self {
+        let mut coloring = BondColoring::default();
+        for bond in bonds {
+            coloring.push(bond);
+        }
+        coloring
+    }
+
+    // Lowest color free at both ends of the bond
+    pub fn push(&mut self, bond: &Bond) -> u32 {
+        let empty = SmallVec::new();
+        let at_a = self.taken.get(&bond.molecule_a_id).unwrap_or(&empty);
+        let at_b = self.taken.get(&bond.molecule_b_id).unwrap_or(&empty);
+        let color = (0..)
+            .find(|color| !at_a.contains(color) && !at_b.contains(color))
+            .unwrap_or_default();
+        for id in [bond.molecule_a_id, bond.molecule_b_id] {
+            self.taken.entry(id).or_default().push(color);
+        }
+        if self.sizes.len() <= color as usize {
+            self.sizes.resize(color as usize + 1, 0);
+        }
+        self.sizes[color as usize] += 1;
+        self.colors.push(color);
+        color
+    }
+
+    // Drops the colors of the bonds not kept, `keep` in the order of `bonds`.
+    // The other bonds keep their color: the coloring stays valid, if no
+    // longer the smallest possible.
+    pub fn retain(&mut self, bonds: &[Bond], keep: &[bool]) {
+        let mut colors = Vec::with_capacity(self.colors.len());
+        for ((bond, &color), &kept) in bonds.iter().zip(&self.colors).zip(keep) {
+            if kept {
+                colors.push(color);
+                continue;
+            }
+            for id in [bond.molecule_a_id, bond.molecule_b_id] {
+                if let Some(taken) = self.taken.get_mut(&id) {
+                    taken.retain(|taken| *taken != color);
+                    if taken.is_empty() {
+                        self.taken.remove(&id);
+                    }
+                }
+            }
+            self.sizes[color as usize] -= 1;
+        }
+        self.colors = colors;
+        while self.sizes.last() == Some(&0) {
+            self.sizes.pop();
+        }
+    }
+
+    pub fn color(&self, bond_index: usize) -> Option<u32> {
+        self.colors.get(bond_index).copied()
+    }
+
+    pub fn color_count(&self) -> usize {
+        self.sizes.iter().filter(|&&size| size > 0).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    // Bond indices of each color, in color order
+    pub fn batches(&self) -> Vec<Vec<usize>> {
+        let mut batches: Vec<Vec<usize>> = self
+            .sizes
+            .iter()
+            .map(|&size| Vec::with_capacity(size))
+            .collect();
+        for (index, &color) in self.colors.iter().enumerate() {
+            batches[color as usize].push(index);
+        }
+        batches.retain(|batch| !batch.is_empty());
+        batches
+    }
+
+    // No two bonds of a color share a molecule
+    pub fn is_valid(&self, bonds: &[Bond]) -> bool {
+        let mut seen: StableHashSet<(MoleculeId, u32)> = StableHashSet::default();
+        self.colors.len() == bonds.len()
+            && bonds.iter().zip(&self.colors).all(|(bond, &color)| {
+                seen.insert((bond.molecule_a_id, color)) && seen.insert((bond.molecule_b_id, color))
+            })
+    }
+}
+
+impl SimulationState {
+    pub fn bond_coloring(&self) -> &BondColoring {
+        &self.bond_coloring
+    }
+
+    // Takes the bonds `remove` picks out of `bonds`, in order, without
+    // recoloring the ones left
+    pub(crate) fn take_bonds_where(
+        &mut self,
+        remove: impl Fn(&SimulationState, &Bond) -> bool,
+    ) -> Vec<Bond> {
+        let keep: Vec<bool> = self.bonds.iter().map(|bond| !remove(self, bond)).collect();
+        self.take_bonds(&keep)
+    }
+
+    // Same with `keep` in the order of `bonds`
+    pub(crate) fn take_bonds(&mut self, keep: &[bool]) -> Vec<Bond> {
+        self.index_bonds();
+        self.bond_coloring.retain(&self.bonds, keep);
+        self.bond_pairs.retain(&self.bonds, keep);
+        let mut taken = Vec::new();
+        let mut kept = Vec::with_capacity(self.bonds.len());
+        for (bond, &keep) in std::mem::take(&mut self.bonds).into_iter().zip(keep) {
+            if keep {
+                kept.push(bond);
+            } else {
+                taken.push(bond);
+            }
+        }
+        self.bonds = kept;
+        self.bonds_changed_in_step();
+        taken
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{Falloff, Integrator, UP};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Physics and chemistry knobs that used to be hardcoded in the tick. Missing
+// keys in a TOML file keep their default, so a file can tune just one value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    // Spatial grid cell, at least the largest interaction distance
+    pub grid_cell_size: f32,
+    // Fraction of the velocity kept when bouncing off a wall
+    pub wall_restitution: f32,
+    // Velocity multiplier applied every tick
+    pub friction: f32,
+    // How positions follow velocities, and friction with them (see Integrator)
+    pub integrator: Integrator,
+    // Clamp on the velocity after folds and kneading forces
+    pub force_max_velocity: f32,
+    // How a fold fades from its center to the edge of its radius
+    pub force_falloff: Falloff,
+    // Disulfide bridges: max distance between two free thiols, base chance per
+    // close pair, overall scale per tick, temperature (°C) of the base chance,
+    // activation energy (kJ/mol) of the Arrhenius law around it and
+    // multiplier at the reference salt (see SaltSettings)
+    pub bond_distance: f32,
+    pub bond_probability: f32,
+    pub bond_rate_scale: f32,
+    pub bond_reference_temp: f32,
+    pub bond_activation_energy: f32,
+    pub salt_bond_boost: f32,
+    // Bonds tear when stretched this fraction past their rest length; heavily
+    // worked dough tears sooner, down to (1 - overmix_weakening) of the strain
+    pub bond_break_strain: f32,
+    pub overmix_weakening: f32,
+    // Fermentation: max yeast-sugar distance, chance per second, temperature
+    // (°C) of the base metabolism, activation energy (kJ/mol) of its
+    // Arrhenius law, and the deactivation enthalpy (kJ/mol) and temperature
+    // (°C) at which half the yeast enzymes have unfolded. A 4°C retard runs
+    // about six times slower than the counter, the yeast peaks near 40°C and
+    // stops past 50°C.
+    pub fermentation_distance: f32,
+    pub fermentation_rate: f32,
+    pub metabolism_reference_temp: f32,
+    pub metabolism_activation_energy: f32,
+    pub metabolism_deactivation_energy: f32,
+    pub metabolism_deactivation_temp: f32,
+    // Amylase: max enzyme-starch distance, chance per second at 25°C, optimum
+    // temperature (°C) and temperature where the enzyme is fully denatured
+    pub amylase_distance: f32,
+    pub amylase_rate: f32,
+    pub amylase_optimum_temp: f32,
+    pub amylase_denature_temp: f32,
+    // Gravity (units/s², pointing down, -UP), density of the dough relative
+    // to water, and the acceleration the gluten matrix holds without giving
+    // (see SimulationState::buoyant_acceleration)
+    pub gravity: Vector3<f32>,
+    pub dough_density: f32,
+    pub matrix_yield: f32,
+    // Random sideways drift of the CO2 bubbles
+    pub co2_jitter: f32,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            grid_cell_size: 15.0,
+            wall_restitution: 0.8,
+            friction: 0.999,
+            integrator: Integrator::Euler,
+            force_max_velocity: 5.0,
+            force_falloff: Falloff::Constant,
+            bond_distance: 8.0,
+            bond_probability: 0.20,
+            bond_rate_scale: 0.1,
+            bond_reference_temp: 25.0,
+            bond_activation_energy: 40.0,
+            salt_bond_boost: 1.2,
+            bond_break_strain: 1.0,
+            overmix_weakening: 0.5,
+            fermentation_distance: 5.0,
+            fermentation_rate: 0.01,
+            metabolism_reference_temp: 20.0,
+            metabolism_activation_energy: 75.0,
+            metabolism_deactivation_energy: 450.0,
+            metabolism_deactivation_temp: 42.0,
+            amylase_distance: 12.0,
+            amylase_rate: 0.002,
+            amylase_optimum_temp: 62.0,
+            amylase_denature_temp: 80.0,
+            // Bubbles rise about 0.05 faster every 0.25 s tick, as they
+            // used to when their lift was set directly
+            gravity: -UP * 0.13,
+            dough_density: 1.2,
+            matrix_yield: 0.06,
+            co2_jitter: 0.02,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let config: SimulationConfig =
+            toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // The grid is allocated from the cell size, a zero cell would be unbounded
+        if config.grid_cell_size < 1.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "grid_cell_size must be at least 1.0",
+            ));
+        }
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> io::Result<String> {
+        toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        SimulationConfig::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_toml()?)
+    }
+}
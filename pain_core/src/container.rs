@@ -0,0 +1,203 @@
+use crate::{Molecule, SimulationState, UP};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Step of the finite differences of the default `Container::normal`
+const NORMAL_STEP: f32 = 0.01;
+
+// The vessel the dough sits in, as a signed distance to its wall: negative
+// inside, positive outside. Anything with a distance can hold the dough.
+pub trait Container {
+    fn signed_distance(&self, pos: Vector3<f32>) -> f32;
+
+    // Outward unit normal of the wall nearest `pos`
+    fn normal(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        let axis = |direction: Vector3<f32>| {
+            self.signed_distance(pos + direction * NORMAL_STEP)
+                - self.signed_distance(pos - direction * NORMAL_STEP)
+        };
+        Vector3::new(axis(Vector3::x()), axis(Vector3::y()), axis(Vector3::z()))
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(UP)
+    }
+
+    // Whether a molecule of `radius` at `pos` is clear of the wall
+    fn contains(&self, pos: Vector3<f32>, radius: f32) -> bool {
+        self.signed_distance(pos) <= -radius
+    }
+}
+
+// The whole simulation box
+pub struct BoxContainer {
+    pub size: Vector3<f32>,
+}
+
+impl Container for BoxContainer {
+    fn signed_distance(&self, pos: Vector3<f32>) -> f32 {
+        let half = self.size / 2.0;
+        let offset = (pos - half).abs() - half;
+        let outside = offset.map(|c| c.max(0.0)).magnitude();
+        outside + offset.max().min(0.0)
+    }
+}
+
+// A round vessel standing on the floor of the box, centered in it: a
+// cylinder, or a cone cut flat when the radii differ. Only its side is a
+// wall; the floor and the top are the box faces (see Boundaries).
+pub struct Vessel {
+    pub axis: Vector3<f32>, // A point of the axis on the floor
+    pub height: f32,
+    pub bottom_radius: f32,
+    pub top_radius: f32,
+}
+
+impl Vessel {
+    // Radius of the wall at `height` above the floor
+    pub fn radius_at(&self, height: f32) -> f32 {
+        let along = (height / self.height.max(f32::EPSILON)).clamp(0.0, 1.0);
+        self.bottom_radius + (self.top_radius - self.bottom_radius) * along
+    }
+
+    // Horizontal offset from the axis, and height above the floor
+    fn split(&self, pos: Vector3<f32>) -> (Vector3<f32>, f32) {
+        let relative = pos - self.axis;
+        let height = relative.dot(&UP);
+        (relative - UP * height, height)
+    }
+}
+
+impl Container for Vessel {
+    fn signed_distance(&self, pos: Vector3<f32>) -> f32 {
+        let (across, height) = self.split(pos);
+        // Distance to the slanted side, straight across for a cylinder
+        let flare = self.top_radius - self.bottom_radius;
+        let slope = self.height / flare.hypot(self.height).max(f32::EPSILON);
+        (across.magnitude() - self.radius_at(height)) * slope
+    }
+
+    fn normal(&self, pos: Vector3<f32>) -> Vector3<f32> {
+        let (across, _) = self.split(pos);
+        let Some(out) = across.try_normalize(f32::EPSILON) else {
+            return Vector3::x();
+        };
+        // Tilted down where the vessel widens upward, like a banneton
+        let flare = self.top_radius - self.bottom_radius;
+        (out * self.height - UP * flare).normalize()
+    }
+}
+
+// Shapes the simulation knows how to save; the box is the historical one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ContainerShape {
+    #[default]
+    Box,
+    Cylinder {
+        radius: f32,
+    },
+    // Narrow at the bottom, wide at the top
+    TruncatedCone {
+        bottom_radius: f32,
+        top_radius: f32,
+    },
+}
+
+impl ContainerShape {
+    // A straight bowl as wide as a box of `width` by `depth`
+    pub fn bowl(width: f32, depth: f32) -> Self {
+        ContainerShape::Cylinder {
+            radius: width.min(depth) / 2.0,
+        }
+    }
+
+    // A banneton as wide as the box at the top, flaring out from 60% of that
+    pub fn banneton(width: f32, depth: f32) -> Self {
+        let radius = width.min(depth) / 2.0;
+        ContainerShape::TruncatedCone {
+            bottom_radius: radius * 0.6,
+            top_radius: radius,
+        }
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContainerShape::Box => "boîte",
+            ContainerShape::Cylinder { .. } => "bol",
+            ContainerShape::TruncatedCone { .. } => "banneton",
+        }
+    }
+
+    // The round wall placed in a box of `size`; none for the box, whose
+    // faces already hold the dough
+    pub fn vessel(&self, size: Vector3<f32>) -> Option<Vessel> {
+        let (bottom_radius, top_radius) = match *self {
+            ContainerShape::Box => return None,
+            ContainerShape::Cylinder { radius } => (radius, radius),
+            ContainerShape::TruncatedCone {
+                bottom_radius,
+                top_radius,
+            } => (bottom_radius, top_radius),
+        };
+        let height = size.dot(&UP);
+        Some(Vessel {
+            axis: size / 2.0 - UP * (height / 2.0),
+            height,
+            bottom_radius: bottom_radius.max(0.0),
+            top_radius: top_radius.max(0.0),
+        })
+    }
+}
+
+// Keeps `mol` inside the wall of `container`, bouncing off it with
+// `restitution` of the speed it hit with
+pub(crate) fn confine_to(container: &dyn Container, mol: &mut Molecule, restitution: f32) {
+    let depth = container.signed_distance(mol.pos) + mol.radius();
+    if depth <= 0.0 {
+        return;
+    }
+    let normal = container.normal(mol.pos);
+    mol.pos -= normal * depth;
+    let outward = mol.velocity.dot(&normal);
+    if outward > 0.0 {
+        mol.velocity -= normal * outward * (1.0 + restitution);
+    }
+}
+
+impl SimulationState {
+    // Puts the dough in `shape`: the molecules are squeezed into it, each
+    // keeping its height and direction from the axis and the same share of
+    // the way to the wall as it had to the sides of the box
+    pub fn set_container(&mut self, shape: ContainerShape) {
+        self.container = shape;
+        let Some(vessel) = shape.vessel(self.container_size()) else {
+            return;
+        };
+        let half = Vector3::new(self.width, self.height, self.depth) / 2.0;
+        let moves: Vec<_> = self
+            .grid
+            .molecules()
+            .filter_map(|mol| {
+                let (across, height) = vessel.split(mol.pos);
+                let share = across
+                    .iter()
+                    .zip(half.iter())
+                    .map(|(c, h)| c.abs() / h.max(f32::EPSILON))
+                    .fold(0.0, f32::max)
+                    .min(1.0);
+                let direction = across.try_normalize(f32::EPSILON)?;
+                let reach = (vessel.radius_at(height) - mol.radius()).max(0.0);
+                Some((
+                    mol.id,
+                    mol.pos,
+                    mol.pos - across + direction * share * reach,
+                ))
+            })
+            .collect();
+        for (id, old_pos, pos) in moves {
+            if let Some(mol) = self.grid.get_molecule_mut(id) {
+                mol.pos = pos;
+            }
+            self.grid.relocate(id, old_pos);
+        }
+    }
+}
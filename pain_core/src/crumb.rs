@@ -0,0 +1,155 @@
+use crate::network::Components;
+use crate::{MoleculeId, MoleculeType, SimulationState};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrumbSettings {
+    pub link_distance: f32, // CO2 molecules closer than this share a bubble (two radii: touching)
+    pub open_bubble_size: usize, // Molecules in a bubble big enough to count as an open alveolus
+}
+
+impl Default for CrumbSettings {
+    fn default() -> Self {
+        CrumbSettings {
+            link_distance: 16.0,
+            open_bubble_size: 8,
+        }
+    }
+}
+
+// Bubbles of `min_size` to `max_size` CO2 molecules
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BubbleBin {
+    pub min_size: usize,
+    pub max_size: usize,
+    pub bubbles: usize,
+}
+
+// The alveoli of the crumb: CO2 clustered into bubbles, their size
+// distribution in doubling bins (1, 2-3, 4-7, ...) and how open the crumb
+// is, from a tight sandwich loaf towards a ciabatta
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrumbAnalysis {
+    pub bubble_sizes: Vec<usize>, // Largest first
+    pub histogram: Vec<BubbleBin>,
+    pub open_crumb_score: f32, // Share of the gas held in open alveoli, 0 to 1
+}
+
+impl CrumbAnalysis {
+    pub fn bubble_count(&self) -> usize {
+        self.bubble_sizes.len()
+    }
+
+    pub fn gas(&self) -> usize {
+        self.bubble_sizes.iter().sum()
+    }
+
+    // Size of the bubble an average CO2 molecule sits in: the big alveoli
+    // weigh as much as the gas they hold
+    pub fn mean_bubble_size(&self) -> f32 {
+        let gas = self.gas();
+        if gas == 0 {
+            return 0.0;
+        }
+        let weighted: usize = self.bubble_sizes.iter().map(|size| size * size).sum();
+        weighted as f32 / gas as f32
+    }
+
+    // Word shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self.open_crumb_score {
+            score if score < 0.15 => "mie serrée",
+            score if score < 0.4 => "mie régulière",
+            score if score < 0.7 => "mie alvéolée",
+            _ => "mie très ouverte",
+        }
+    }
+
+    // "1:42 2-3:9 4-7:3", bubbles per size bin
+    pub fn histogram_text(&self) -> String {
+        self.histogram
+            .iter()
+            .map(|bin| {
+                if bin.min_size == bin.max_size {
+                    format!("{}:{}", bin.min_size, bin.bubbles)
+                } else {
+                    format!("{}-{}:{}", bin.min_size, bin.max_size, bin.bubbles)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl SimulationState {
+    pub fn crumb_analysis(&self) -> CrumbAnalysis {
+        let settings = &self.crumb_settings;
+        let mut gas: Vec<MoleculeId> = self
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::CO2))
+            .map(|mol| mol.id)
+            .collect();
+        gas.sort_unstable();
+
+        let mut components = Components::new(gas.len());
+        for (i, id) in gas.iter().enumerate() {
+            let Some(mol) = self.grid.get_molecule(*id) else {
+                continue;
+            };
+            self.grid
+                .for_each_neighbor(mol.pos, settings.link_distance, |neighbor| {
+                    if let Ok(j) = gas.binary_search(&neighbor.id) {
+                        components.union(i, j);
+                    }
+                });
+        }
+
+        let mut sizes = vec![0; gas.len()];
+        for i in 0..gas.len() {
+            sizes[components.find(i)] += 1;
+        }
+        let mut bubble_sizes: Vec<usize> = sizes.into_iter().filter(|&size| size > 0).collect();
+        bubble_sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut histogram: Vec<BubbleBin> = Vec::new();
+        for &size in bubble_sizes.iter().rev() {
+            let min_size = 1 << size.ilog2();
+            match histogram.last_mut() {
+                Some(bin) if bin.min_size == min_size => bin.bubbles += 1,
+                _ => {
+                    // Empty bins in between keep the doubling steps readable
+                    let mut next = histogram.last().map_or(1, |bin| bin.min_size * 2);
+                    while next < min_size {
+                        histogram.push(BubbleBin {
+                            min_size: next,
+                            max_size: next * 2 - 1,
+                            bubbles: 0,
+                        });
+                        next *= 2;
+                    }
+                    histogram.push(BubbleBin {
+                        min_size,
+                        max_size: min_size * 2 - 1,
+                        bubbles: 1,
+                    });
+                }
+            }
+        }
+
+        let total: usize = bubble_sizes.iter().sum();
+        let open: usize = bubble_sizes
+            .iter()
+            .filter(|&&size| size >= settings.open_bubble_size.max(1))
+            .sum();
+        CrumbAnalysis {
+            open_crumb_score: if total == 0 {
+                0.0
+            } else {
+                open as f32 / total as f32
+            },
+            bubble_sizes,
+            histogram,
+        }
+    }
+}
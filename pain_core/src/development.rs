@@ -0,0 +1,105 @@
+use crate::{GlutenNetwork, MoleculeType, SimulationState};
+use serde::{Deserialize, Serialize};
+
+// How the gluten development gauge weighs its three parts; the weights are
+// relative, they do not need to add up to 1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevelopmentSettings {
+    pub bond_weight: f32,
+    pub network_weight: f32,
+    pub hydration_weight: f32,
+    pub hydration_distance: f32, // A protein with water this close counts as hydrated
+}
+
+impl Default for DevelopmentSettings {
+    fn default() -> Self {
+        DevelopmentSettings {
+            bond_weight: 0.4,
+            network_weight: 0.4,
+            hydration_weight: 0.2,
+            hydration_distance: 10.0,
+        }
+    }
+}
+
+// Gluten development between 0 and 1, with the parts it is made of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlutenDevelopment {
+    pub bonds: f32,   // Bonds formed out of the most the glutenins allow (one thiol each)
+    pub network: f32, // Share of the glutenins in the largest component, 1 once it percolates
+    pub hydration: f32, // Share of the proteins with water nearby
+    pub total: f32,
+}
+
+impl GlutenDevelopment {
+    pub fn percent(&self) -> f32 {
+        self.total * 100.0
+    }
+}
+
+impl SimulationState {
+    pub fn gluten_development(&self) -> GlutenDevelopment {
+        self.development_of(&self.gluten_network())
+    }
+
+    // For callers that already built the network
+    pub fn development_of(&self, network: &GlutenNetwork) -> GlutenDevelopment {
+        let settings = &self.development_settings;
+        let max_bonds = network.glutenin_count / 2;
+        let bonds = if max_bonds > 0 {
+            (self.bonds.len() as f32 / max_bonds as f32).min(1.0)
+        } else {
+            0.0
+        };
+        let network_score = if network.percolates() {
+            1.0
+        } else {
+            network.largest_fraction
+        };
+        let hydration = self.hydrated_fraction();
+
+        let weights = [
+            settings.bond_weight.max(0.0),
+            settings.network_weight.max(0.0),
+            settings.hydration_weight.max(0.0),
+        ];
+        let weight_sum: f32 = weights.iter().sum();
+        let total = if weight_sum > 0.0 {
+            (weights[0] * bonds + weights[1] * network_score + weights[2] * hydration) / weight_sum
+        } else {
+            0.0
+        };
+        GlutenDevelopment {
+            bonds,
+            network: network_score,
+            hydration,
+            total,
+        }
+    }
+
+    fn hydrated_fraction(&self) -> f32 {
+        let distance = self.development_settings.hydration_distance;
+        let (mut proteins, mut hydrated) = (0, 0);
+        for mol in self.grid.molecules() {
+            if !matches!(
+                mol.mol_type,
+                MoleculeType::Gliadin | MoleculeType::Glutenin { .. }
+            ) {
+                continue;
+            }
+            proteins += 1;
+            let wet = self
+                .grid
+                .neighbors_within(mol.pos, distance)
+                .any(|other| other.mol_type.water_units() > 0);
+            if wet {
+                hydrated += 1;
+            }
+        }
+        if proteins > 0 {
+            hydrated as f32 / proteins as f32
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{HeatExchange, SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+const HOURS_PER_DAY: f32 = 24.0;
+
+// Room temperature around the dough over the day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum AmbientTemperature {
+    // The dough keeps whatever temperature it is set to
+    #[default]
+    Constant,
+    // Sinusoid between mean - amplitude (at the coldest hour) and mean + amplitude
+    Daily {
+        mean: f32,
+        amplitude: f32,
+        coldest_hour: f32,
+    },
+    // (hour of day, °C) points, interpolated linearly and wrapping at midnight
+    Schedule(Vec<(f32, f32)>),
+    // Thermostat-controlled enclosure (fridge, proofer) at a steady °C
+    Fixed(f32),
+}
+
+// Where the dough rests: a cold retard in the fridge, a warm proofer, or the
+// counter at a given room temperature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EnvironmentPreset {
+    Fridge,
+    Proofer,
+    RoomTemp { temp: f32 },
+}
+
+impl EnvironmentPreset {
+    pub fn temperature(&self) -> f32 {
+        match self {
+            EnvironmentPreset::Fridge => 4.0,
+            EnvironmentPreset::Proofer => 27.0,
+            EnvironmentPreset::RoomTemp { temp } => *temp,
+        }
+    }
+
+    // Still fridge air exchanges heat slowly: a loaf takes hours to chill through
+    pub fn thermal_lag(&self) -> f32 {
+        match self {
+            EnvironmentPreset::Fridge => 3600.0,
+            EnvironmentPreset::Proofer | EnvironmentPreset::RoomTemp { .. } => 1800.0,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            EnvironmentPreset::Fridge => "Réfrigérateur".to_string(),
+            EnvironmentPreset::Proofer => "Étuve".to_string(),
+            EnvironmentPreset::RoomTemp { temp } => format!("Pièce à {:.0}°C", temp),
+        }
+    }
+}
+
+// Draft over the dough (a nearby window): the windward side of the surface
+// dries faster and the dough loses heat faster to the room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Airflow {
+    pub direction: Vector3<f32>, // Horizontal direction the air moves to (y ignored)
+    pub speed: f32,              // m/s, 0 = still air
+    pub drying_gain: f32,        // Extra evaporation per m/s on the windward edge
+    pub chill: f32,              // °C below the room temperature per m/s (evaporative cooling)
+}
+
+impl Default for Airflow {
+    fn default() -> Self {
+        Airflow {
+            direction: Vector3::new(1.0, 0.0, 0.0),
+            speed: 0.0,
+            drying_gain: 1.5,
+            chill: 1.0,
+        }
+    }
+}
+
+impl Airflow {
+    pub fn horizontal_direction(&self) -> Option<Vector3<f32>> {
+        Vector3::new(self.direction.x, 0.0, self.direction.z).try_normalize(1e-6)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentSettings {
+    pub ambient: AmbientTemperature,
+    pub airflow: Airflow,
+    pub start_hour: f32,  // Hour of day at sim time 0
+    pub thermal_lag: f32, // Seconds for the dough to close 63% of the gap to the room
+    pub preset: Option<EnvironmentPreset>, // Last one set with `set_environment`
+}
+
+impl Default for EnvironmentSettings {
+    fn default() -> Self {
+        EnvironmentSettings {
+            ambient: AmbientTemperature::Constant,
+            airflow: Airflow::default(),
+            start_hour: 8.0,
+            thermal_lag: 1800.0,
+            preset: None,
+        }
+    }
+}
+
+impl AmbientTemperature {
+    // A kitchen that cools at night: 19-25°C, coldest at 5 am
+    pub fn kitchen() -> Self {
+        AmbientTemperature::Daily {
+            mean: 22.0,
+            amplitude: 3.0,
+            coldest_hour: 5.0,
+        }
+    }
+
+    pub fn at_hour(&self, hour: f32) -> Option<f32> {
+        let hour = hour.rem_euclid(HOURS_PER_DAY);
+        match self {
+            AmbientTemperature::Constant => None,
+            AmbientTemperature::Fixed(temp) => Some(*temp),
+            AmbientTemperature::Daily {
+                mean,
+                amplitude,
+                coldest_hour,
+            } => {
+                let phase = (hour - coldest_hour) / HOURS_PER_DAY * TAU;
+                Some(mean - amplitude * phase.cos())
+            }
+            AmbientTemperature::Schedule(points) => {
+                let mut points = points.clone();
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let first = *points.first()?;
+                let last = *points.last()?;
+                // Segment containing the hour, wrapping from the last point to the first
+                let (from, to) = points
+                    .windows(2)
+                    .map(|pair| (pair[0], pair[1]))
+                    .find(|(from, to)| hour >= from.0 && hour < to.0)
+                    .unwrap_or((last, (first.0 + HOURS_PER_DAY, first.1)));
+                let hour = if hour < from.0 {
+                    hour + HOURS_PER_DAY
+                } else {
+                    hour
+                };
+                let span = to.0 - from.0;
+                if span <= 0.0 {
+                    return Some(from.1);
+                }
+                Some(from.1 + (to.1 - from.1) * (hour - from.0) / span)
+            }
+        }
+    }
+}
+
+impl SimulationState {
+    pub fn hour_of_day(&self) -> f32 {
+        (self.environment.start_hour + self.time_elapsed / SECONDS_PER_HOUR)
+            .rem_euclid(HOURS_PER_DAY)
+    }
+
+    pub fn ambient_temperature(&self) -> Option<f32> {
+        self.environment.ambient.at_hour(self.hour_of_day())
+    }
+
+    // Moves the dough into the fridge, a proofer or a room: it drifts towards
+    // the new temperature with the thermal lag of that place, and the yeast
+    // and bacteria follow the dough temperature
+    pub fn set_environment(&mut self, preset: EnvironmentPreset) {
+        self.environment.ambient = AmbientTemperature::Fixed(preset.temperature());
+        self.environment.thermal_lag = preset.thermal_lag();
+        self.environment.preset = Some(preset);
+        self.events
+            .push(self.time_elapsed, SimulationEvent::EnvironmentChanged(preset));
+    }
+
+    // Evaporation multiplier at a surface position: 1 in still air, highest
+    // on the windward edge and back to 1 on the leeward edge
+    pub fn draft_factor(&self, pos: Vector3<f32>) -> f32 {
+        let airflow = &self.environment.airflow;
+        let Some(direction) = airflow.horizontal_direction() else {
+            return 1.0;
+        };
+        if airflow.speed <= 0.0 {
+            return 1.0;
+        }
+        let half_extent =
+            (direction.x.abs() * self.width + direction.z.abs() * self.depth).max(1.0) / 2.0;
+        let downwind = ((pos - self.center()).dot(&direction) / half_extent).clamp(-1.0, 1.0);
+        let exposure = (1.0 - downwind) / 2.0;
+        1.0 + airflow.drying_gain * airflow.speed * exposure
+    }
+
+    // The dough surface drifts towards the room temperature with some thermal
+    // lag; a draft chills it below the room and speeds up the exchange
+    pub(crate) fn ambient_exchange(&self) -> Option<HeatExchange> {
+        let ambient = self.ambient_temperature()?;
+        let airflow = &self.environment.airflow;
+        let wind = airflow.speed.max(0.0);
+        Some(HeatExchange {
+            temperature: ambient - airflow.chill * wind,
+            lag: self.environment.thermal_lag.max(1.0) / (1.0 + wind),
+        })
+    }
+}
@@ -0,0 +1,21 @@
+use crate::SimulationState;
+
+impl SimulationState {
+    // Relative amylase activity: doubles every 10°C up to the optimum, then
+    // drops to nothing as the enzyme denatures
+    pub fn amylase_activity(&self) -> f32 {
+        self.amylase_activity_at_temperature(self.temperature)
+    }
+
+    pub fn amylase_activity_at_temperature(&self, temperature: f32) -> f32 {
+        let config = &self.config;
+        let optimum = config.amylase_optimum_temp;
+        let denature = config.amylase_denature_temp.max(optimum + 1.0);
+        let at = |temp: f32| 2f32.powf((temp - 25.0) / 10.0);
+        if temperature <= optimum {
+            at(temperature)
+        } else {
+            at(optimum) * ((denature - temperature) / (denature - optimum)).max(0.0)
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::arena::MoleculeId;
+use crate::burp::Burp;
+use crate::collapse::CollapseRecord;
+use crate::environment::EnvironmentPreset;
+use crate::kneading::KneadAction;
+use crate::perturbation::Perturbation;
+use crate::phase::DoughPhase;
+use crate::preferment::PrefermentKind;
+use crate::proofing::OverproofSigns;
+use crate::sanity::QuarantineReason;
+use crate::species::Species;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+// Only the most recent events are kept; this is a diagnostic trail, not a history
+const EVENT_LOG_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SimulationEvent {
+    RecipeInitialized,
+    SaltAdded,
+    YeastAdded,
+    ForceApplied {
+        center: Vector3<f32>,
+        radius: f32,
+        force: Vector3<f32>,
+    },
+    InstabilityDetected {
+        molecule_id: MoleculeId,
+    },
+    Perturbed(Perturbation),
+    SolverNotConverged {
+        iterations: u32,
+        residual: f32,
+    },
+    PhaseChanged {
+        from: DoughPhase,
+        to: DoughPhase,
+    },
+    BakeStarted {
+        oven_temperature: f32,
+    },
+    YeastKilled,
+    MoleculeQuarantined {
+        molecule_id: MoleculeId,
+        species: Species,
+        pos: Vector3<f32>,
+        reason: QuarantineReason,
+        removed: bool, // Otherwise put back inside the container
+    },
+    EnvironmentChanged(EnvironmentPreset),
+    DoughCollapsed(CollapseRecord),
+    Kneaded(KneadAction),
+    Annotated(String),
+    ScheduledStep {
+        label: String,
+        timed_out: bool, // Fired by its timeout rather than its condition
+    },
+    GasBurp(Burp),
+    FlourDusted {
+        amount: f32, // Share of the flour already in the dough
+    },
+    PrefermentPoured {
+        kind: PrefermentKind,
+        hours: f32, // How long it fermented
+    },
+    MaltAdded {
+        amount: f32, // Share of the flour
+    },
+    AscorbicAcidAdded {
+        amount: f32, // Share of the flour
+    },
+    ReadyToShape {
+        windowpane: f32,
+        poke: f32,
+    },
+    OverproofWarning(OverproofSigns),
+    Rewound {
+        from: f32, // Sim time the dough was at
+        to: f32,
+    },
+    FastForwarded {
+        from: f32,
+        to: f32,
+        leaps: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub time: f32,
+    pub event: SimulationEvent,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    entries: VecDeque<LoggedEvent>,
+}
+
+impl fmt::Display for SimulationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationEvent::RecipeInitialized => write!(f, "recipe initialized"),
+            SimulationEvent::SaltAdded => write!(f, "salt added"),
+            SimulationEvent::YeastAdded => write!(f, "yeast added"),
+            SimulationEvent::ForceApplied {
+                center,
+                radius,
+                force,
+            } => write!(
+                f,
+                "force ({:.1}, {:.1}, {:.1}) applied at ({:.1}, {:.1}, {:.1}) r={:.1}",
+                force.x, force.y, force.z, center.x, center.y, center.z, radius
+            ),
+            SimulationEvent::InstabilityDetected { molecule_id } => {
+                write!(f, "non-finite state on molecule {}", molecule_id)
+            }
+            SimulationEvent::Perturbed(perturbation) => write!(f, "perturbation {:?}", perturbation),
+            SimulationEvent::SolverNotConverged {
+                iterations,
+                residual,
+            } => write!(
+                f,
+                "bond solver did not converge ({} iterations, residual {:.3})",
+                iterations, residual
+            ),
+            SimulationEvent::PhaseChanged { from, to } => {
+                write!(f, "phase {:?} -> {:?}", from, to)
+            }
+            SimulationEvent::BakeStarted { oven_temperature } => {
+                write!(f, "bake started at {:.0}°C", oven_temperature)
+            }
+            SimulationEvent::YeastKilled => write!(f, "yeast killed by the oven heat"),
+            SimulationEvent::MoleculeQuarantined {
+                molecule_id,
+                species,
+                pos,
+                reason,
+                removed,
+            } => write!(
+                f,
+                "{:?} molecule {} quarantined at ({:.1}, {:.1}, {:.1}): {}, {}",
+                species,
+                molecule_id,
+                pos.x,
+                pos.y,
+                pos.z,
+                reason,
+                if *removed { "removed" } else { "put back inside" }
+            ),
+            SimulationEvent::EnvironmentChanged(preset) => write!(
+                f,
+                "dough moved to {:?} ({:.0}°C)",
+                preset,
+                preset.temperature()
+            ),
+            SimulationEvent::DoughCollapsed(record) => write!(
+                f,
+                "dough collapsed: lost {:.1} of its {:.1} rise, {} bonds torn",
+                record.height_drop, record.peak_rise, record.bonds_torn
+            ),
+            SimulationEvent::Kneaded(action) => match action {
+                KneadAction::StretchAndFold => write!(f, "stretch and fold"),
+                KneadAction::CoilFold => write!(f, "coil fold"),
+                KneadAction::SlapAndFold => write!(f, "slap and fold"),
+            },
+            SimulationEvent::Annotated(text) => write!(f, "note: {}", text),
+            SimulationEvent::ScheduledStep { label, timed_out } => write!(
+                f,
+                "scheduled step \"{}\"{}",
+                label,
+                if *timed_out { " (timed out)" } else { "" }
+            ),
+            SimulationEvent::GasBurp(burp) => write!(
+                f,
+                "gas burp: {} CO2 escaped at ({:.1}, {:.1}, {:.1})",
+                burp.molecules, burp.pos.x, burp.pos.y, burp.pos.z
+            ),
+            SimulationEvent::FlourDusted { amount } => {
+                write!(f, "dusted with {:.1}% more flour", amount * 100.0)
+            }
+            SimulationEvent::PrefermentPoured { kind, hours } => {
+                write!(f, "{:?} poured in after {:.1} h", kind, hours)
+            }
+            SimulationEvent::MaltAdded { amount } => {
+                write!(f, "{:.2}% diastatic malt added", amount * 100.0)
+            }
+            SimulationEvent::AscorbicAcidAdded { amount } => {
+                write!(f, "{:.0} ppm ascorbic acid added", amount * 1e6)
+            }
+            SimulationEvent::ReadyToShape { windowpane, poke } => write!(
+                f,
+                "ready to shape: windowpane {:.0}%, poke {:.0}%",
+                windowpane * 100.0,
+                poke * 100.0
+            ),
+            SimulationEvent::OverproofWarning(signs) => write!(
+                f,
+                "over-proofing: {:.0}% sugar left, {:.0}% of the CO2 escaping, {:.0}% of the network left",
+                signs.sugar_left * 100.0,
+                signs.escape_rate * 100.0,
+                signs.network_left * 100.0
+            ),
+            SimulationEvent::Rewound { from, to } => {
+                write!(f, "rewound from {:.0} s to {:.0} s", from, to)
+            }
+            SimulationEvent::FastForwarded { from, to, leaps } => write!(
+                f,
+                "fast-forwarded from {:.0} s to {:.0} s in {} leaps",
+                from, to, leaps
+            ),
+        }
+    }
+}
+
+impl EventLog {
+    pub fn push(&mut self, time: f32, event: SimulationEvent) {
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedEvent { time, event });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Human-readable dump, one event per line, used for crash reports
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&format!("[{:>10.1} s] {}\n", entry.time, entry.event));
+        }
+        text
+    }
+}
@@ -0,0 +1,120 @@
+use crate::plugin::PluginHook;
+use crate::rules::RuleStep;
+use crate::{DoughPhase, SimulationEvent, SimulationState};
+use serde::{Deserialize, Serialize};
+
+// The tick the per-tick rates and the physics were tuned at
+pub(crate) const TUNED_TICK: f32 = 0.25;
+
+// Time-lapse through a long ferment. Each round runs a few ticks at full
+// detail, so the dough keeps moving, rising and losing its gas, then one leap
+// of chemistry only: the reactions as first-order kinetics over the whole
+// leap, the heat and the salt as usual (they step through it themselves) and
+// the small molecules carried as far as they would have diffused. The gluten
+// network and the bubbles only move in the full ticks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FastForwardSettings {
+    pub leap: f32,       // Sim seconds of chemistry only, per round
+    pub full_steps: u32, // Ticks at full detail before each leap
+}
+
+impl Default for FastForwardSettings {
+    fn default() -> Self {
+        FastForwardSettings {
+            leap: 30.0,
+            full_steps: 1,
+        }
+    }
+}
+
+// What a fast-forward went through
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FastForwardReport {
+    pub from: f32,
+    pub to: f32,
+    pub leaps: u32,
+    pub sugars_fermented: u64,
+    pub co2_produced: u64,
+    pub reached_bake: bool, // Stopped at the oven, which is never skipped
+}
+
+impl SimulationState {
+    // Moves the dough `sim_seconds` on in time-lapse, then hands it back for
+    // full-detail ticks. Stops short when the bake starts: the oven is too
+    // quick and too hot for leaps.
+    pub fn fast_forward(&mut self, sim_seconds: f32) -> FastForwardReport {
+        let settings = self.fast_forward_settings.clone();
+        let leap = settings.leap.max(TUNED_TICK);
+        let end = self.time_elapsed + sim_seconds.max(0.0);
+        let mut report = FastForwardReport {
+            from: self.time_elapsed,
+            ..FastForwardReport::default()
+        };
+        let fermented = self.reactions.sugars_fermented;
+        let co2 = self.reactions.co2_produced;
+
+        while self.time_elapsed < end {
+            if self.phase >= DoughPhase::Bake {
+                report.reached_bake = true;
+                break;
+            }
+            for _ in 0..settings.full_steps {
+                let dt = (end - self.time_elapsed).min(TUNED_TICK);
+                if dt <= 0.0 {
+                    break;
+                }
+                self.tick(dt);
+            }
+            let dt = (end - self.time_elapsed).min(leap);
+            if dt > 0.0 && self.leap(dt) {
+                report.leaps += 1;
+            }
+        }
+
+        report.to = self.time_elapsed;
+        report.sugars_fermented = self.reactions.sugars_fermented - fermented;
+        report.co2_produced = self.reactions.co2_produced - co2;
+        if report.leaps > 0 {
+            self.events.push(
+                self.time_elapsed,
+                SimulationEvent::FastForwarded {
+                    from: report.from,
+                    to: report.to,
+                    leaps: report.leaps,
+                },
+            );
+        }
+        report
+    }
+
+    // One leap of `dt` seconds; false if a scheduled bake got there first
+    fn leap(&mut self, dt: f32) -> bool {
+        self.run_plugins(PluginHook::BeforeTick, dt);
+        self.run_scheduled_actions();
+        if self.phase >= DoughPhase::Bake {
+            return false;
+        }
+        self.sync_periods();
+        self.remember_step_origins();
+        self.time_elapsed += dt;
+        self.update_temperature_spike();
+        self.update_thermal_field(dt);
+        self.mixing.decay(dt, &self.mixing_settings);
+
+        self.diffuse_over(dt);
+        self.update_observation_volumes();
+        self.handle_chemistry(dt, RuleStep::Leap);
+        self.handle_surface_losses(dt);
+        self.update_water_clusters();
+
+        self.quarantine_degenerate_molecules();
+        self.watch_for_collapse();
+        self.watch_proofing();
+        self.record_metrics_if_due();
+        self.debug_check_ledgers();
+        self.run_plugins(PluginHook::AfterTick, dt);
+        self.record_changes();
+        self.checkpoint_if_due();
+        true
+    }
+}
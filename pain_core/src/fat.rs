@@ -0,0 +1,42 @@
+use crate::{MoleculeType, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Butter or oil in an enriched dough: the fat coats the glutenin, so fewer
+// disulfide bridges form next to it, and the ones that do are softer. A
+// brioche stays tender where a lean dough would turn elastic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FatSettings {
+    pub reach: f32,     // Distance at which a fat molecule coats a forming bond
+    pub coating: f32,   // Bond slowdown per fat molecule in reach, 0 turns it off
+    pub softening: f32, // Stiffness loss per fat molecule in reach, 0 turns it off
+}
+
+impl Default for FatSettings {
+    fn default() -> Self {
+        FatSettings {
+            reach: 25.0,
+            coating: 0.8,
+            softening: 0.4,
+        }
+    }
+}
+
+impl SimulationState {
+    fn fat_around(&self, pos: Vector3<f32>) -> usize {
+        self.grid
+            .neighbors_within(pos, self.fat.reach)
+            .filter(|mol| mol.mol_type == MoleculeType::Fat)
+            .count()
+    }
+
+    // Share of the bond chance left by the fat around `pos`
+    pub fn fat_coating_factor_at(&self, pos: Vector3<f32>) -> f32 {
+        1.0 / (1.0 + self.fat.coating.max(0.0) * self.fat_around(pos) as f32)
+    }
+
+    // Share of the stiffness a bond forming at `pos` keeps
+    pub fn fat_softening_at(&self, pos: Vector3<f32>) -> f32 {
+        1.0 / (1.0 + self.fat.softening.max(0.0) * self.fat_around(pos) as f32)
+    }
+}
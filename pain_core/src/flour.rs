@@ -0,0 +1,129 @@
+use crate::Recipe;
+use serde::{Deserialize, Serialize};
+
+// Water / flour a white wheat flour of 12% protein takes up, the reference of
+// the stickiness thresholds
+pub const REFERENCE_ABSORPTION: f32 = 0.72;
+
+// Water a wheat flour takes up: stronger flours drink more
+pub fn wheat_absorption(protein_content: f32) -> f32 {
+    0.60 + 3.0 * (protein_content - 0.08)
+}
+
+// The flours a baker buys, with what sets them apart in the dough: how much
+// protein and how much of it forms gluten, the minerals (ash) left by the
+// bran, the water they drink and their enzymes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FlourProfile {
+    BreadFlour, // Strong white flour (gruau)
+    T65,
+    WholeWheat, // T150
+    Rye,        // T130
+    Spelt,      // T70
+}
+
+impl FlourProfile {
+    pub fn all() -> [FlourProfile; 5] {
+        [
+            FlourProfile::BreadFlour,
+            FlourProfile::T65,
+            FlourProfile::WholeWheat,
+            FlourProfile::Rye,
+            FlourProfile::Spelt,
+        ]
+    }
+
+    // Identifier used on the command line
+    pub fn id(&self) -> &'static str {
+        match self {
+            FlourProfile::BreadFlour => "bread",
+            FlourProfile::T65 => "t65",
+            FlourProfile::WholeWheat => "whole",
+            FlourProfile::Rye => "rye",
+            FlourProfile::Spelt => "spelt",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        FlourProfile::all()
+            .into_iter()
+            .find(|flour| flour.id().eq_ignore_ascii_case(id.trim()))
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            FlourProfile::BreadFlour => "farine de force",
+            FlourProfile::T65 => "farine T65",
+            FlourProfile::WholeWheat => "farine complète",
+            FlourProfile::Rye => "farine de seigle",
+            FlourProfile::Spelt => "farine d'épeautre",
+        }
+    }
+
+    // Protein / flour
+    pub fn protein_content(&self) -> f32 {
+        match self {
+            FlourProfile::BreadFlour => 0.135,
+            FlourProfile::T65 => 0.115,
+            FlourProfile::WholeWheat => 0.13,
+            FlourProfile::Rye => 0.09,
+            FlourProfile::Spelt => 0.125,
+        }
+    }
+
+    // Share of the proteins that are glutenin: the bran cuts the strands of
+    // a whole flour, spelt is rich in gliadin, rye barely forms gluten
+    pub fn glutenin_fraction(&self) -> f32 {
+        match self {
+            FlourProfile::BreadFlour => 0.65,
+            FlourProfile::T65 => 0.6,
+            FlourProfile::WholeWheat => 0.55,
+            FlourProfile::Rye => 0.3,
+            FlourProfile::Spelt => 0.45,
+        }
+    }
+
+    // Ash / flour, the French type number over 100000
+    pub fn ash_content(&self) -> f32 {
+        match self {
+            FlourProfile::BreadFlour => 0.0045,
+            FlourProfile::T65 => 0.0065,
+            FlourProfile::WholeWheat => 0.015,
+            FlourProfile::Rye => 0.013,
+            FlourProfile::Spelt => 0.007,
+        }
+    }
+
+    // Water / flour it takes up: the bran and the rye pentosans drink a lot,
+    // spelt less than wheat
+    pub fn absorption(&self) -> f32 {
+        match self {
+            FlourProfile::WholeWheat => 0.80,
+            FlourProfile::Rye => 0.82,
+            FlourProfile::Spelt => 0.66,
+            _ => wheat_absorption(self.protein_content()),
+        }
+    }
+
+    // Enzymes relative to a white wheat flour, higher in the bran and in rye
+    pub fn amylase_activity(&self) -> f32 {
+        match self {
+            FlourProfile::WholeWheat => 1.3,
+            FlourProfile::Rye => 2.0,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Recipe {
+    // The same recipe made with `flour`
+    pub fn with_flour(mut self, flour: FlourProfile) -> Self {
+        self.protein_content = flour.protein_content();
+        self.glutenin_fraction = flour.glutenin_fraction();
+        self.ash_content = flour.ash_content();
+        self.absorption = flour.absorption();
+        self.amylase_activity = flour.amylase_activity();
+        self
+    }
+}
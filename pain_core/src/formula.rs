@@ -0,0 +1,105 @@
+use crate::Recipe;
+use serde::{Deserialize, Serialize};
+
+// How much dough a recipe makes: grams of flour, and how many molecules stand
+// for a gram of anything. Every ingredient then gets its grams' worth: the
+// flour split into proteins and starch by the protein content, the water, the
+// salt, and a yeast (with its sugar) per gram of levain.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GramScale {
+    pub flour_g: f32,
+    pub particles_per_gram: f32,
+}
+
+impl GramScale {
+    // One molecule per gram, about a thousand for a 500 g loaf
+    pub fn new(flour_g: f32) -> Self {
+        GramScale {
+            flour_g,
+            particles_per_gram: 1.0,
+        }
+    }
+
+    // Molecules for an ingredient weighing `share` of the flour
+    pub fn count(&self, share: f32) -> usize {
+        (self.flour_g * share.max(0.0) * self.particles_per_gram.max(0.0)).round() as usize
+    }
+}
+
+// A recipe the way a baker writes it: grams of flour and everything else as a
+// percentage of it (baker's percentages)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BakersFormula {
+    pub name: String,
+    pub flour_g: f32,
+    pub hydration: f32,   // Water, % of the flour
+    pub salt_pct: f32,    // % of the flour
+    pub levain_pct: f32,  // % of the flour
+    pub protein_pct: f32, // Protein in the flour, %
+    pub particles_per_gram: f32,
+}
+
+impl Default for BakersFormula {
+    // The classic recipe for one 500 g loaf of flour
+    fn default() -> Self {
+        BakersFormula::from_recipe(&Recipe::default(), GramScale::new(500.0))
+    }
+}
+
+impl BakersFormula {
+    pub fn from_recipe(recipe: &Recipe, scale: GramScale) -> Self {
+        BakersFormula {
+            name: recipe.name.clone(),
+            flour_g: scale.flour_g,
+            hydration: recipe.hydration * 100.0,
+            salt_pct: recipe.salt * 100.0,
+            levain_pct: recipe.yeast * 100.0,
+            protein_pct: recipe.protein_content * 100.0,
+            particles_per_gram: scale.particles_per_gram,
+        }
+    }
+
+    pub fn scale(&self) -> GramScale {
+        GramScale {
+            flour_g: self.flour_g,
+            particles_per_gram: self.particles_per_gram,
+        }
+    }
+
+    pub fn water_g(&self) -> f32 {
+        self.flour_g * self.hydration / 100.0
+    }
+
+    pub fn salt_g(&self) -> f32 {
+        self.flour_g * self.salt_pct / 100.0
+    }
+
+    pub fn levain_g(&self) -> f32 {
+        self.flour_g * self.levain_pct / 100.0
+    }
+
+    pub fn total_g(&self) -> f32 {
+        self.flour_g + self.water_g() + self.salt_g() + self.levain_g()
+    }
+
+    // The simulated recipe; what a baker's formula does not say (glutenin,
+    // autolyse, temperature, amylase) comes from the classic recipe
+    pub fn recipe(&self) -> Recipe {
+        Recipe {
+            name: self.name.clone(),
+            hydration: self.hydration / 100.0,
+            salt: self.salt_pct / 100.0,
+            yeast: self.levain_pct / 100.0,
+            protein_content: self.protein_pct / 100.0,
+            scale: Some(self.scale()),
+            ..Recipe::default()
+        }
+    }
+}
+
+impl From<BakersFormula> for Recipe {
+    fn from(formula: BakersFormula) -> Self {
+        formula.recipe()
+    }
+}
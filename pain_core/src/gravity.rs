@@ -0,0 +1,42 @@
+use crate::{SimulationState, Species, SPECIES};
+use nalgebra::Vector3;
+
+// Up in the dough, for the physics and for every renderer: the bowl floor is
+// at y = 0 and the surface the dough rises through is at the top of the box
+pub const UP: Vector3<f32> = Vector3::new(0.0, 1.0, 0.0);
+// Index of the up axis in a position, for code that works axis by axis
+pub const UP_AXIS: usize = 1;
+
+// Share of the displaced dough a molecule drags along as it moves (added
+// mass of a sphere); it keeps a bubble, with next to no mass of its own, at a
+// finite acceleration
+const ADDED_MASS: f32 = 0.5;
+
+impl SimulationState {
+    // Acceleration of a molecule of `species` from gravity and the buoyancy
+    // of the dough it displaces, for its density relative to the dough's:
+    // denser molecules sink, lighter ones rise. The gluten matrix takes up to
+    // `matrix_yield` of it without giving, so with the default settings the
+    // solids stay in place and only the gas bubbles rise.
+    pub fn buoyant_acceleration(&self, species: Species) -> Vector3<f32> {
+        let config = &self.config;
+        let density = species.info().density;
+        let medium = config.dough_density;
+        let relative = (density - medium) / (density + ADDED_MASS * medium).max(f32::EPSILON);
+        let pull = config.gravity * relative;
+        let excess = pull.magnitude() - config.matrix_yield.max(0.0);
+        if excess <= 0.0 {
+            return Vector3::zeros();
+        }
+        pull.normalize() * excess
+    }
+
+    // `buoyant_acceleration` of every species, in registry order
+    pub(crate) fn buoyancy_table(&self) -> [Vector3<f32>; SPECIES.len()] {
+        let mut table = [Vector3::zeros(); SPECIES.len()];
+        for (slot, info) in table.iter_mut().zip(SPECIES.iter()) {
+            *slot = self.buoyant_acceleration(info.species);
+        }
+        table
+    }
+}
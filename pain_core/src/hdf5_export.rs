@@ -0,0 +1,139 @@
+use crate::snapshot::decode_state;
+use crate::trajectory::{apply_record, Trajectory, TrajectoryRecord};
+use crate::SimulationState;
+use hdf5::types::VarLenUnicode;
+use std::io;
+use std::path::Path;
+
+// HDF5 layout, for analysis tools that do not read our own formats:
+//
+//   /                attrs: recipe, seed, width, height, depth, species (names
+//                    indexed by the type codes below)
+//   /frames/000042   attrs: time (s), temperature (°C)
+//     ids            u64 [n]     MoleculeId::to_bits, generation << 32 | slot
+//     positions      f32 [n, 3]
+//     types          u8  [n]     Species code
+//     bonds          u64 [m, 2]  molecule ids of each disulfide bridge
+
+fn hdf5_error(err: hdf5::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+fn unicode(text: &str) -> io::Result<VarLenUnicode> {
+    text.parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err)))
+}
+
+fn write_frame(frames: &hdf5::Group, index: usize, state: &SimulationState) -> hdf5::Result<()> {
+    let frame = frames.create_group(&format!("{:06}", index))?;
+    frame
+        .new_attr::<f32>()
+        .create("time")?
+        .write_scalar(&state.time_elapsed)?;
+    frame
+        .new_attr::<f32>()
+        .create("temperature")?
+        .write_scalar(&state.temperature)?;
+
+    let mut molecules = state.grid.get_all_molecules();
+    molecules.sort_by_key(|mol| mol.id);
+    let ids: Vec<u64> = molecules.iter().map(|mol| mol.id.to_bits()).collect();
+    let positions: Vec<f32> = molecules
+        .iter()
+        .flat_map(|mol| [mol.pos.x, mol.pos.y, mol.pos.z])
+        .collect();
+    let types: Vec<u8> = molecules
+        .iter()
+        .map(|mol| mol.mol_type.species() as u8)
+        .collect();
+    let bonds: Vec<u64> = state
+        .bonds
+        .iter()
+        .flat_map(|bond| [bond.molecule_a_id.to_bits(), bond.molecule_b_id.to_bits()])
+        .collect();
+
+    frame
+        .new_dataset::<u64>()
+        .shape(ids.len())
+        .create("ids")?
+        .write_raw(&ids)?;
+    frame
+        .new_dataset::<f32>()
+        .shape((ids.len(), 3))
+        .create("positions")?
+        .write_raw(&positions)?;
+    frame
+        .new_dataset::<u8>()
+        .shape(types.len())
+        .create("types")?
+        .write_raw(&types)?;
+    frame
+        .new_dataset::<u64>()
+        .shape((state.bonds.len(), 2))
+        .create("bonds")?
+        .write_raw(&bonds)?;
+    Ok(())
+}
+
+fn write_metadata(file: &hdf5::File, state: &SimulationState) -> io::Result<()> {
+    let recipe = unicode(&state.recipe_name)?;
+    let species = crate::Species::all()
+        .map(|info| unicode(info.name))
+        .collect::<io::Result<Vec<_>>>()?;
+    let write = || -> hdf5::Result<()> {
+        file.new_attr::<VarLenUnicode>()
+            .create("recipe")?
+            .write_scalar(&recipe)?;
+        file.new_attr::<u64>()
+            .create("seed")?
+            .write_scalar(&state.seed)?;
+        file.new_attr::<f32>()
+            .create("width")?
+            .write_scalar(&state.width)?;
+        file.new_attr::<f32>()
+            .create("height")?
+            .write_scalar(&state.height)?;
+        file.new_attr::<f32>()
+            .create("depth")?
+            .write_scalar(&state.depth)?;
+        file.new_attr::<VarLenUnicode>()
+            .shape(species.len())
+            .create("species")?
+            .write_raw(&species)
+    };
+    write().map_err(hdf5_error)
+}
+
+// Replays the trajectory and writes every recorded frame
+pub fn export_trajectory_hdf5(trajectory: &Trajectory, path: &Path) -> io::Result<()> {
+    let records = trajectory.records();
+    let Some(TrajectoryRecord::Keyframe { state: bytes, .. }) = records.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "trajectory does not start with a savepoint",
+        ));
+    };
+    let mut state = decode_state(bytes)?;
+
+    let file = hdf5::File::create(path).map_err(hdf5_error)?;
+    write_metadata(&file, &state)?;
+    let frames = file.create_group("frames").map_err(hdf5_error)?;
+    let mut index = 0;
+    for record in records {
+        apply_record(&mut state, record)?;
+        if record.is_frame() {
+            write_frame(&frames, index, &state).map_err(hdf5_error)?;
+            index += 1;
+        }
+    }
+    file.flush().map_err(hdf5_error)
+}
+
+// A single frame of a live simulation
+pub fn export_state_hdf5(state: &SimulationState, path: &Path) -> io::Result<()> {
+    let file = hdf5::File::create(path).map_err(hdf5_error)?;
+    write_metadata(&file, state)?;
+    let frames = file.create_group("frames").map_err(hdf5_error)?;
+    write_frame(&frames, 0, state).map_err(hdf5_error)?;
+    file.flush().map_err(hdf5_error)
+}
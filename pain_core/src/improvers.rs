@@ -0,0 +1,121 @@
+use crate::{Molecule, MoleculeType, SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Improvers a baker adds to the flour, as shares of it. Diastatic malt brings
+// amylase, which frees more sugar from the starch for the yeast, and some
+// maltose the yeast eat right away. Ascorbic acid (vitamin C) oxidizes while
+// mixing and helps the glutenin form disulfide bridges: the network comes
+// sooner and holds firmer.
+// Doses are shares of the flour; the gains are per unit of that share and 0
+// turns them off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImproverSettings {
+    pub malt_amylase: f32,       // Amylase per flour amylase: 100 doubles it at 1%
+    pub malt_sugar: f32,         // Share of the malt that is sugar
+    pub ascorbic_bond: f32,      // Bond chance gain
+    pub ascorbic_stiffness: f32, // Bond stiffness gain
+    pub malt_amount: f32,        // Usual dose of malt
+    pub ascorbic_amount: f32,    // Usual dose of ascorbic acid, 100 ppm
+}
+
+impl Default for ImproverSettings {
+    fn default() -> Self {
+        ImproverSettings {
+            malt_amylase: 100.0,
+            malt_sugar: 0.5,
+            ascorbic_bond: 5000.0,
+            ascorbic_stiffness: 2000.0,
+            malt_amount: 0.005,
+            ascorbic_amount: 0.0001,
+        }
+    }
+}
+
+// What went into the dough so far, shares of the flour
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Improvers {
+    pub malt: f32,
+    pub ascorbic_acid: f32,
+}
+
+impl SimulationState {
+    // Adds `amount` of diastatic malt: amylase in proportion to the flour's
+    // own, and maltose, spread through the dough
+    pub fn add_malt(&mut self, amount: f32) -> bool {
+        if !amount.is_finite() || amount <= 0.0 {
+            return false;
+        }
+        let settings = &self.improver_settings;
+        let amylase = self
+            .grid
+            .molecules()
+            .filter(|mol| mol.mol_type == MoleculeType::Amylase)
+            .count();
+        let flour = self
+            .grid
+            .molecules()
+            .filter(|mol| {
+                matches!(
+                    mol.mol_type,
+                    MoleculeType::Gliadin
+                        | MoleculeType::Glutenin { .. }
+                        | MoleculeType::Starch
+                        | MoleculeType::Amylase
+                        | MoleculeType::Ash
+                )
+            })
+            .count();
+        let enzymes = (amylase as f32 * settings.malt_amylase.max(0.0) * amount).round() as usize;
+        let sugars = (flour as f32 * settings.malt_sugar.clamp(0.0, 1.0) * amount).round() as usize;
+        let room = self.budget.room(self.grid.len());
+        let enzymes = enzymes.min(room);
+        let sugars = sugars.min(room - enzymes);
+
+        let mass_before = self.total_mass();
+        for (mol_type, count) in [
+            (MoleculeType::Amylase, enzymes),
+            (MoleculeType::Sugar, sugars),
+        ] {
+            for _ in 0..count {
+                let pos = Vector3::new(
+                    self.rng.gen_range(0.0..self.width),
+                    self.rng.gen_range(0.0..self.height),
+                    self.rng.gen_range(0.0..self.depth),
+                );
+                self.grid
+                    .insert(Molecule::new(mol_type.clone(), pos, Vector3::zeros()));
+            }
+        }
+        self.ledger.added_mass += self.total_mass() - mass_before;
+        self.improvers.malt += amount;
+        self.events
+            .push(self.time_elapsed, SimulationEvent::MaltAdded { amount });
+        self.record_metrics();
+        true
+    }
+
+    // Adds `amount` of ascorbic acid, too little to count as molecules
+    pub fn add_ascorbic_acid(&mut self, amount: f32) -> bool {
+        if !amount.is_finite() || amount <= 0.0 {
+            return false;
+        }
+        self.improvers.ascorbic_acid += amount;
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::AscorbicAcidAdded { amount },
+        );
+        true
+    }
+
+    // Multiplier on the disulfide bridge chance
+    pub fn ascorbic_bond_factor(&self) -> f32 {
+        1.0 + self.improver_settings.ascorbic_bond.max(0.0) * self.improvers.ascorbic_acid
+    }
+
+    // Multiplier on the stiffness of a new bridge
+    pub fn ascorbic_stiffening(&self) -> f32 {
+        1.0 + self.improver_settings.ascorbic_stiffness.max(0.0) * self.improvers.ascorbic_acid
+    }
+}
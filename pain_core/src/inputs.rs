@@ -0,0 +1,274 @@
+use crate::snapshot::{decode_state, encode_state, SAVE_VERSION};
+use crate::{SimAction, SimulationState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+// The start state is a save in all but name: the header holds the save
+// format version, and a replay reads where its saves would
+const INPUTS_MAGIC: &[u8; 8] = b"HOPINPT\0";
+const ZSTD_LEVEL: i32 = 3;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+// The ticks a frame of `duration` seconds is cut into, none longer than
+// `max_tick`: how the visualizer moves the dough on, and how a replay does
+pub fn frame_ticks(duration: f32, max_tick: f32) -> impl Iterator<Item = f32> {
+    let mut remaining = duration;
+    std::iter::from_fn(move || {
+        if remaining <= 0.0 || max_tick <= 0.0 {
+            return None;
+        }
+        let dt = remaining.min(max_tick);
+        remaining -= dt;
+        Some(dt)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEntry {
+    // `count` frames in a row of the same length
+    Frames {
+        duration: f32,
+        max_tick: f32,
+        count: u32,
+    },
+    // A gesture, with the hash of the dough just before it: a replay that no
+    // longer matches there has drifted from the recording
+    Action {
+        time: f32,
+        action: SimAction,
+        state_hash: u64,
+    },
+}
+
+// Everything the baker did to a dough, frame by frame, from the state it
+// started in. Played back on that state it makes the same bake, bit for bit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputReplay {
+    pub seed: u64,
+    pub recipe_name: String,
+    start: Vec<u8>, // Encoded state, compressed with the rest of the file
+    pub entries: Vec<InputEntry>,
+    pub start_time: f32,
+    pub end_time: f32,
+    pub end_hash: u64, // Of the dough when the recording stopped
+}
+
+impl InputReplay {
+    pub fn start_state(&self) -> io::Result<SimulationState> {
+        decode_state(&self.start)
+    }
+
+    // Sim seconds recorded
+    pub fn duration(&self) -> f32 {
+        self.end_time - self.start_time
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry, InputEntry::Action { .. }))
+            .count()
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|entry| match entry {
+                InputEntry::Frames { count, .. } => u64::from(*count),
+                InputEntry::Action { .. } => 0,
+            })
+            .sum()
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        file.write_all(INPUTS_MAGIC)?;
+        file.write_all(&SAVE_VERSION.to_le_bytes())?;
+        let mut encoder = zstd::Encoder::new(file, ZSTD_LEVEL)?;
+        bincode::serialize_into(&mut encoder, self).map_err(invalid_data)?;
+        encoder.finish()?.flush()
+    }
+
+    pub fn load_from_file(path: &Path) -> io::Result<Self> {
+        let mut file = io::BufReader::new(fs::File::open(path)?);
+        let mut magic = [0u8; 8];
+        let mut version = [0u8; 4];
+        if file.read_exact(&mut magic).is_err() || &magic != INPUTS_MAGIC {
+            return Err(invalid_data("not a house_of_pain input replay"));
+        }
+        file.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SAVE_VERSION {
+            return Err(invalid_data(format!(
+                "input replay made with save format version {}",
+                version
+            )));
+        }
+        bincode::deserialize_from(zstd::Decoder::with_buffer(file)?).map_err(invalid_data)
+    }
+}
+
+// Notes the frames and gestures of a running dough. The frames must be the
+// ones the dough really ran, each as `frame_ticks(duration, max_tick)`.
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    replay: InputReplay,
+    frames: u64,
+    actions: usize,
+}
+
+impl InputRecorder {
+    pub fn start(state: &SimulationState) -> io::Result<Self> {
+        Ok(InputRecorder {
+            replay: InputReplay {
+                seed: state.seed,
+                recipe_name: state.recipe_name.clone(),
+                start: encode_state(state)?,
+                entries: Vec::new(),
+                start_time: state.time_elapsed,
+                end_time: state.time_elapsed,
+                end_hash: state.state_hash(),
+            },
+            frames: 0,
+            actions: 0,
+        })
+    }
+
+    pub fn frame(&mut self, duration: f32, max_tick: f32) {
+        self.frames += 1;
+        if let Some(InputEntry::Frames {
+            duration: last,
+            max_tick: last_max,
+            count,
+        }) = self.replay.entries.last_mut()
+        {
+            if *last == duration && *last_max == max_tick && *count < u32::MAX {
+                *count += 1;
+                return;
+            }
+        }
+        self.replay.entries.push(InputEntry::Frames {
+            duration,
+            max_tick,
+            count: 1,
+        });
+    }
+
+    // Notes the gesture and makes it
+    pub fn apply(&mut self, state: &mut SimulationState, action: SimAction) {
+        self.actions += 1;
+        self.replay.entries.push(InputEntry::Action {
+            time: state.time_elapsed,
+            action: action.clone(),
+            state_hash: state.state_hash(),
+        });
+        state.apply_action(&action);
+    }
+
+    pub fn actions_recorded(&self) -> usize {
+        self.actions
+    }
+
+    pub fn frames_recorded(&self) -> u64 {
+        self.frames
+    }
+
+    // `state` is the dough as it is once the last frame has run
+    pub fn finish(mut self, state: &SimulationState) -> InputReplay {
+        self.replay.end_time = state.time_elapsed;
+        self.replay.end_hash = state.state_hash();
+        self.replay
+    }
+}
+
+// Plays a replay back on its start state: the gestures at their place
+// between the frames, the frames for the caller to run
+#[derive(Debug, Clone)]
+pub struct InputPlayer {
+    replay: InputReplay,
+    next: usize,       // Next entry
+    frames_left: u32,  // Of the Frames entry before `next`
+    frame: (f32, f32), // Its duration and max tick
+    diverged_at: Option<f32>,
+}
+
+impl InputPlayer {
+    pub fn new(replay: InputReplay) -> Self {
+        InputPlayer {
+            replay,
+            next: 0,
+            frames_left: 0,
+            frame: (0.0, 0.0),
+            diverged_at: None,
+        }
+    }
+
+    pub fn replay(&self) -> &InputReplay {
+        &self.replay
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames_left == 0 && self.next >= self.replay.entries.len()
+    }
+
+    // Sim time of the first gesture made on a dough that no longer matched
+    // the recording, if any
+    pub fn diverged_at(&self) -> Option<f32> {
+        self.diverged_at
+    }
+
+    // Makes the gestures due before the next frame and gives its duration
+    // and max tick; None once the replay is over
+    pub fn next_frame(&mut self, state: &mut SimulationState) -> Option<(f32, f32)> {
+        while self.frames_left == 0 {
+            let entry = self.replay.entries.get(self.next)?;
+            self.next += 1;
+            match entry {
+                InputEntry::Frames {
+                    duration,
+                    max_tick,
+                    count,
+                } => {
+                    self.frame = (*duration, *max_tick);
+                    self.frames_left = *count;
+                }
+                InputEntry::Action {
+                    action, state_hash, ..
+                } => {
+                    if self.diverged_at.is_none() && state.state_hash() != *state_hash {
+                        self.diverged_at = Some(state.time_elapsed);
+                    }
+                    state.apply_action(action);
+                }
+            }
+        }
+        self.frames_left -= 1;
+        Some(self.frame)
+    }
+
+    // Whether `state`, played to the end, is the dough the recording ended on
+    pub fn matches_end(&self, state: &SimulationState) -> bool {
+        self.is_finished()
+            && self.diverged_at.is_none()
+            && state.time_elapsed == self.replay.end_time
+            && state.state_hash() == self.replay.end_hash
+    }
+
+    // Plays what is left in one go; whether the dough ended as recorded
+    pub fn play(&mut self, state: &mut SimulationState) -> bool {
+        while let Some((duration, max_tick)) = self.next_frame(state) {
+            for dt in frame_ticks(duration, max_tick) {
+                state.tick(dt);
+            }
+        }
+        self.matches_end(state)
+    }
+}
@@ -0,0 +1,27 @@
+// Temperature dependence of the reaction rates, as Arrhenius laws relative
+// to a reference temperature instead of fixed multipliers
+
+// Gas constant in kJ/(mol·K), activation energies are in kJ/mol
+pub const GAS_CONSTANT: f32 = 8.314e-3;
+
+pub(crate) fn kelvin(celsius: f32) -> f32 {
+    // Clamped just above absolute zero, the law divides by it
+    (celsius + 273.15).max(1.0)
+}
+
+// exp(-Ea/R (1/T - 1/Tref)): 1 at the reference temperature, growing with
+// the temperature, faster the higher the activation energy
+pub fn arrhenius_factor(activation_energy: f32, temperature: f32, reference_temp: f32) -> f32 {
+    let exponent = -activation_energy / GAS_CONSTANT
+        * (1.0 / kelvin(temperature) - 1.0 / kelvin(reference_temp));
+    exponent.min(80.0).exp()
+}
+
+// Share of an enzyme still folded (Sharpe-Schoolfield): half at
+// `deactivation_temp`, dropping steeply above it with a large deactivation
+// enthalpy
+pub fn active_fraction(deactivation_energy: f32, deactivation_temp: f32, temperature: f32) -> f32 {
+    let exponent = deactivation_energy / GAS_CONSTANT
+        * (1.0 / kelvin(deactivation_temp) - 1.0 / kelvin(temperature));
+    1.0 / (1.0 + exponent.min(80.0).exp())
+}
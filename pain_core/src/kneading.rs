@@ -0,0 +1,144 @@
+use crate::{SimulationEvent, SimulationState, UP};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// The baker's hand techniques. Each one moves the dough its own way and
+// works it more or less, so the bonds develop at a different pace:
+//
+// - stretch and fold: one side is pulled out and laid back over the top, the
+//   bowl turning a quarter between two folds
+// - coil fold: the middle is lifted so both ends tuck under, gentle
+// - slap and fold: the dough is slapped down and sheared, the top thrown
+//   forward over a held bottom, the most vigorous
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KneadAction {
+    StretchAndFold,
+    CoilFold,
+    SlapAndFold,
+}
+
+impl KneadAction {
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            KneadAction::StretchAndFold => "Étirer et rabattre",
+            KneadAction::CoilFold => "Rabat en bobine",
+            KneadAction::SlapAndFold => "Frapper et rabattre",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KneadSettings {
+    pub radius: f32,        // Region worked around the dough center
+    pub stretch_force: f32, // Pull on the stretched side
+    pub coil_lift: f32,     // Lift at the middle of a coil fold
+    pub slap_shear: f32,    // Shear between the top and the bottom of a slap
+    // Agitation left in the mixing field per unit of force: how much each
+    // technique speeds up bond formation (and over-kneading)
+    pub stretch_work: f32,
+    pub coil_work: f32,
+    pub slap_work: f32,
+}
+
+impl Default for KneadSettings {
+    fn default() -> Self {
+        KneadSettings {
+            radius: 200.0,
+            stretch_force: 30.0,
+            coil_lift: 25.0,
+            slap_shear: 40.0,
+            stretch_work: 1.0,
+            coil_work: 0.5,
+            slap_work: 2.0,
+        }
+    }
+}
+
+// How many times each technique was used on this dough
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KneadCounts {
+    pub stretch_and_folds: u32,
+    pub coil_folds: u32,
+    pub slap_and_folds: u32,
+}
+
+impl SimulationState {
+    pub fn knead(&mut self, action: KneadAction) {
+        let settings = self.knead_settings.clone();
+        let center = self.center();
+        let radius = settings.radius.max(f32::EPSILON);
+        // A quarter turn of the bowl after every stretch
+        let quarter = self.kneading.stretch_and_folds % 4;
+        let stretch_direction = match quarter {
+            0 => Vector3::new(1.0, 0.0, 0.0),
+            1 => Vector3::new(0.0, 0.0, 1.0),
+            2 => Vector3::new(-1.0, 0.0, 0.0),
+            _ => Vector3::new(0.0, 0.0, -1.0),
+        };
+
+        let (force, work) = match action {
+            KneadAction::StretchAndFold => {
+                self.kneading.stretch_and_folds += 1;
+                (settings.stretch_force, settings.stretch_work)
+            }
+            KneadAction::CoilFold => {
+                self.kneading.coil_folds += 1;
+                (settings.coil_lift, settings.coil_work)
+            }
+            KneadAction::SlapAndFold => {
+                self.kneading.slap_and_folds += 1;
+                (settings.slap_shear, settings.slap_work)
+            }
+        };
+
+        self.start_poke(center, radius);
+        let mut nearby = Vec::new();
+        self.grid.query_radius_into(center, radius, &mut nearby);
+        let max_velocity = self.config.force_max_velocity;
+        for id in nearby {
+            let Some(mol) = self.grid.get_molecule_mut(id) else {
+                continue;
+            };
+            let offset = (mol.pos - center) / radius;
+            let kick = match action {
+                // The far side goes out and up, the near side is held back
+                KneadAction::StretchAndFold => {
+                    let along = offset.dot(&stretch_direction);
+                    if along > 0.0 {
+                        (stretch_direction + UP * 0.5) * force * along
+                    } else {
+                        -stretch_direction * force * 0.2 * -along
+                    }
+                }
+                // Lifted most at the middle, the ends drawn in under it
+                KneadAction::CoilFold => {
+                    let horizontal = Vector3::new(offset.x, 0.0, offset.z);
+                    let spread = horizontal.magnitude().min(1.0);
+                    UP * force * (1.0 - spread) - horizontal * force * 0.3
+                }
+                // Top thrown forward and slapped down, bottom thrown back
+                KneadAction::SlapAndFold => {
+                    let shear = Vector3::new(1.0, 0.0, 0.0) * force * offset.y;
+                    let slap = if offset.y > 0.0 {
+                        -UP * force * 0.3 * offset.y
+                    } else {
+                        Vector3::zeros()
+                    };
+                    shear + slap
+                }
+            };
+            mol.velocity += kick / mol.mass();
+            let speed = mol.velocity.magnitude();
+            if speed > max_velocity {
+                mol.velocity *= max_velocity / speed;
+            }
+        }
+
+        self.mixing
+            .deposit(center, radius, force * work, &self.mixing_settings);
+        self.last_force_time = Some(self.time_elapsed);
+        self.events
+            .push(self.time_elapsed, SimulationEvent::Kneaded(action));
+    }
+}
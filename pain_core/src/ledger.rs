@@ -0,0 +1,256 @@
+use crate::{MoleculeId, MoleculeType, SimulationState};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Relative mismatch between the ledger and the molecules tolerated by the
+// conservation check (float sums over thousands of molecules)
+const CONSERVATION_TOLERANCE: f64 = 1e-4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaporationSettings {
+    pub surface_depth: f32, // Thickness of the top layer exposed to air
+    pub water_rate: f32,    // Chance per second for a surface water molecule to evaporate at 25°C
+    pub ethanol_rate: f32,  // Same for ethanol, which is more volatile
+    pub co2_rate: f32,      // Same for a CO2 bubble, which also escapes on reaching the top
+    pub doubling_temp: f32, // Degrees for the evaporation rate to double
+}
+
+impl Default for EvaporationSettings {
+    fn default() -> Self {
+        EvaporationSettings {
+            surface_depth: 20.0,
+            water_rate: 0.0005,
+            ethanol_rate: 0.002,
+            co2_rate: 0.01,
+            doubling_temp: 10.0,
+        }
+    }
+}
+
+// Running account of the dough mass (in molecule mass units): what went in,
+// what left through the surface and what the chemistry created or destroyed.
+// At any time the molecules must add up to `expected_mass()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MassLedger {
+    pub initial_mass: f64,
+    pub added_mass: f64,      // Salt, levain and anything added after the recipe
+    pub reaction_delta: f64,  // Net mass created by reactions (0 for balanced chemistry)
+    pub water_evaporated: f64,
+    pub co2_lost: f64,
+    pub ethanol_lost: f64,
+    pub quarantined_mass: f64, // Degenerate molecules removed by the sanity checks
+    pub burps: u64, // Bursts of CO2 through the surface, see `record_burps`
+    pub co2_escaped: u64, // Molecules, against `ReactionLedger::co2_produced`
+    pub ethanol_escaped: u64,
+    pub boundary_lost: f64, // Dough, not gas, gone through an open face of the box
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassReport {
+    pub initial_mass: f64,
+    pub current_mass: f64,
+    pub water_evaporated: f64,
+    pub co2_lost: f64,
+    pub ethanol_lost: f64,
+    pub loss_percent: f64, // Bakers aim for roughly 10-15% at the end of the bake
+}
+
+impl MassLedger {
+    pub fn starting_at(initial_mass: f64) -> Self {
+        MassLedger {
+            initial_mass,
+            ..MassLedger::default()
+        }
+    }
+
+    pub fn total_lost(&self) -> f64 {
+        self.water_evaporated + self.co2_lost + self.ethanol_lost
+    }
+
+    pub fn expected_mass(&self) -> f64 {
+        self.initial_mass + self.added_mass + self.reaction_delta
+            - self.total_lost()
+            - self.quarantined_mass
+            - self.boundary_lost
+    }
+}
+
+impl SimulationState {
+    pub fn total_mass(&self) -> f64 {
+        self.grid
+            .get_all_molecules()
+            .iter()
+            .map(|mol| mol.mass() as f64)
+            .sum::<f64>()
+            + self.salt.dissolved_mass()
+    }
+
+    pub fn mass_report(&self) -> MassReport {
+        let ledger = &self.ledger;
+        let input = ledger.initial_mass + ledger.added_mass;
+        MassReport {
+            initial_mass: ledger.initial_mass,
+            current_mass: self.total_mass(),
+            water_evaporated: ledger.water_evaporated,
+            co2_lost: ledger.co2_lost,
+            ethanol_lost: ledger.ethanol_lost,
+            loss_percent: if input > 0.0 {
+                ledger.total_lost() / input * 100.0
+            } else {
+                0.0
+            },
+        }
+    }
+
+    // Relative difference between the molecules and the ledger; ~0 when mass is conserved
+    pub fn conservation_error(&self) -> f64 {
+        let expected = self.ledger.expected_mass();
+        (self.total_mass() - expected).abs() / expected.abs().max(1.0)
+    }
+
+    pub fn is_mass_conserved(&self) -> bool {
+        self.conservation_error() <= CONSERVATION_TOLERANCE
+    }
+
+    // Water and ethanol evaporate from the top layer and CO2 bubbles in it
+    // escape, all faster when warm; bubbles reaching the top always escape
+    // (+y is up).
+    pub(crate) fn handle_surface_losses(&mut self, dt: f32) {
+        let settings = &self.evaporation;
+        let surface = self.height - settings.surface_depth;
+        let temp_factor = 2f32.powf((self.temperature - 25.0) / settings.doubling_temp.max(1.0));
+        // A covered or cooled loaf no longer dries
+        let exposure = if self.phase.loses_moisture() { 1.0 } else { 0.0 };
+        let water_chance = settings.water_rate * temp_factor * exposure * dt;
+        let ethanol_chance = settings.ethanol_rate * temp_factor * exposure * dt;
+        // A cover keeps the moisture in, not the gas
+        let co2_chance = settings.co2_rate * temp_factor * dt;
+
+        let mut evaporated = Vec::new();
+        let mut removed = Vec::new();
+        for mol in self.grid.get_all_molecules() {
+            if mol.pos.y < surface {
+                continue;
+            }
+            // A draft dries the windward side of the surface faster
+            let draft = self.draft_factor(mol.pos);
+            match mol.mol_type {
+                MoleculeType::Water | MoleculeType::WaterCluster { .. }
+                    if self.rng.gen::<f32>()
+                        < water_chance * draft * mol.mol_type.water_units() as f32 =>
+                {
+                    evaporated.push(mol.id);
+                }
+                MoleculeType::Ethanol if self.rng.gen::<f32>() < ethanol_chance * draft => {
+                    removed.push((mol.id, mol.mass()));
+                }
+                MoleculeType::CO2
+                    if mol.pos.y >= self.height - mol.radius() - 1.0
+                        || self.rng.gen::<f32>() < co2_chance * draft =>
+                {
+                    removed.push((mol.id, mol.mass()));
+                }
+                _ => {}
+            }
+        }
+
+        self.evaporate_water(evaporated);
+
+        let mut escaped = Vec::new();
+        for (id, mass) in removed {
+            if let Some(mol) = self.grid.get_molecule(id) {
+                match mol.mol_type {
+                    MoleculeType::CO2 => {
+                        self.ledger.co2_lost += mass as f64;
+                        self.ledger.co2_escaped += 1;
+                        escaped.push(mol.pos);
+                    }
+                    _ => {
+                        self.ledger.ethanol_lost += mass as f64;
+                        self.ledger.ethanol_escaped += 1;
+                    }
+                }
+                self.grid.remove(id);
+            }
+        }
+        self.record_burps(&escaped);
+    }
+
+    // One water molecule leaves each of `ids`; a cluster loses one molecule at a time
+    pub(crate) fn evaporate_water(&mut self, ids: Vec<MoleculeId>) {
+        for id in ids {
+            let Some(mol) = self.grid.get_molecule(id) else {
+                continue;
+            };
+            let unit_mass = mol.mass() / mol.mol_type.water_units() as f32;
+            match mol.mol_type {
+                MoleculeType::WaterCluster { size } if size > 2 => {
+                    let smaller = MoleculeType::WaterCluster { size: size - 1 };
+                    self.grid.set_mol_type(id, smaller);
+                }
+                MoleculeType::WaterCluster { .. } => self.grid.set_mol_type(id, MoleculeType::Water),
+                _ => self.grid.remove(id),
+            }
+            self.ledger.water_evaporated += unit_mass as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DoughPhase, SimulationState};
+
+    // A small loaf through its ferment and into the oven, always the same one
+    fn baked_loaf() -> SimulationState {
+        let mut state = SimulationState::builder()
+            .seed(42)
+            .size(150.0, 150.0, 150.0)
+            .build();
+        state.add_salt();
+        state.add_yeast();
+        assert_eq!(state.phase, DoughPhase::BulkFerment);
+        // Two hours of bulk in time-lapse, for some gas and ethanol to lose
+        state.fast_forward(2.0 * 3600.0);
+        assert!(state.start_bake(230.0));
+        for _ in 0..120 {
+            state.tick(0.5);
+        }
+        state
+    }
+
+    #[test]
+    fn losses_account_for_the_lost_dough() {
+        let state = baked_loaf();
+        let ledger = &state.ledger;
+        assert!(ledger.water_evaporated > 0.0);
+        assert!(ledger.co2_lost > 0.0);
+        assert!(ledger.ethanol_lost > 0.0);
+
+        let input = ledger.initial_mass + ledger.added_mass;
+        // Whatever did not leave through the surface is still in the dough
+        let other = ledger.reaction_delta - ledger.quarantined_mass - ledger.boundary_lost;
+        let drop = input + other - state.total_mass();
+        let lost = ledger.water_evaporated + ledger.co2_lost + ledger.ethanol_lost;
+        assert!(
+            (lost - drop).abs() <= 1e-4 * input,
+            "lost {lost}, dough down {drop}"
+        );
+        assert!(state.is_mass_conserved());
+    }
+
+    #[test]
+    fn bake_loss_comes_from_the_totals() {
+        let state = baked_loaf();
+        let ledger = &state.ledger;
+        let report = state.mass_report();
+        let lost = ledger.water_evaporated + ledger.co2_lost + ledger.ethanol_lost;
+        let input = ledger.initial_mass + ledger.added_mass;
+
+        assert_eq!(report.water_evaporated, ledger.water_evaporated);
+        assert_eq!(report.co2_lost, ledger.co2_lost);
+        assert_eq!(report.ethanol_lost, ledger.ethanol_lost);
+        assert_eq!(report.current_mass, state.total_mass());
+        assert!((report.loss_percent - lost / input * 100.0).abs() < 1e-9);
+        assert!(report.loss_percent > 0.0);
+    }
+}
@@ -1,63 +1,357 @@
 use nalgebra::Vector3;
-use rand::Rng;
-use std::collections::HashMap;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasherDefault;
+
+pub mod acidity;
+pub mod arena;
+pub mod autosave;
+pub mod actions;
+pub mod bake;
+pub mod bond_pairs;
+pub mod boundary;
+pub mod brownian;
+pub mod budget;
+pub mod burp;
+pub mod builder;
+pub mod challenge;
+pub mod checkpoint;
+pub mod collapse;
+pub mod clusters;
+pub mod coloring;
+pub mod config;
+pub mod container;
+pub mod crumb;
+pub mod development;
+pub mod enzymes;
+pub mod gravity;
+#[cfg(feature = "hdf5")]
+pub mod hdf5_export;
+pub mod improvers;
+pub mod inputs;
+pub mod integrator;
+pub mod environment;
+pub mod events;
+pub mod fast_forward;
+pub mod fat;
+pub mod flour;
+pub mod formula;
+pub mod kinetics;
+pub mod kneading;
+pub mod ledger;
+pub mod metrics;
+pub mod mixing;
+pub mod network;
+pub mod notebook;
+pub mod observation;
+pub mod particle_export;
+pub mod perturbation;
+pub mod phase;
+pub mod plugin;
+pub mod preferment;
+pub mod profile;
+pub mod proofing;
+pub mod protease;
+pub mod readiness;
+pub mod reactions;
+pub mod repulsion;
+pub mod recipe;
+pub mod region;
+pub mod rules;
+pub mod rise;
+pub mod salt;
+pub mod sanity;
+pub mod schedule;
+pub mod section;
+pub mod sensitivity;
+pub mod session;
+pub mod snapshot;
+pub mod solver;
+pub mod species;
+pub mod starter;
+pub mod stats;
+pub mod stickiness;
+pub mod summary;
+pub mod sync;
+pub mod thermal;
+pub mod timeline;
+pub mod timestep;
+pub mod tools;
+pub mod trajectory;
+pub mod viscosity;
+pub mod web_export;
+
+pub use acidity::{AcidSettings, Acidity};
+pub use arena::{MoleculeArena, MoleculeId};
+pub use autosave::{AutosaveSettings, Autosaver};
+pub use actions::SimAction;
+pub use bake::{BakeSettings, Oven};
+pub use bond_pairs::BondPairs;
+pub use checkpoint::{Checkpoint, CheckpointRing, CheckpointSettings};
+pub use boundary::{Boundaries, BoundaryMode};
+pub use brownian::BrownianSettings;
+pub use budget::BudgetSettings;
+pub use burp::{Burp, BurpSettings};
+pub use builder::SimulationBuilder;
+pub use challenge::{Challenge, LoafScore, MysteryFlour};
+pub use collapse::{CollapseMonitor, CollapseRecord, CollapseSettings};
+pub use development::{DevelopmentSettings, GlutenDevelopment};
+pub use clusters::WaterClusterSettings;
+pub use coloring::BondColoring;
+pub use config::SimulationConfig;
+pub use container::{BoxContainer, Container, ContainerShape, Vessel};
+pub use crumb::{BubbleBin, CrumbAnalysis, CrumbSettings};
+pub use environment::{Airflow, AmbientTemperature, EnvironmentPreset, EnvironmentSettings};
+pub use events::{EventLog, LoggedEvent, SimulationEvent};
+pub use fast_forward::{FastForwardReport, FastForwardSettings};
+pub use fat::FatSettings;
+pub use flour::{FlourProfile, REFERENCE_ABSORPTION};
+pub use formula::{BakersFormula, GramScale};
+pub use gravity::{UP, UP_AXIS};
+pub use improvers::{ImproverSettings, Improvers};
+pub use inputs::{frame_ticks, InputEntry, InputPlayer, InputRecorder, InputReplay};
+pub use integrator::Integrator;
+pub use kneading::{KneadAction, KneadCounts, KneadSettings};
+pub use ledger::{EvaporationSettings, MassLedger, MassReport};
+pub use metrics::{MetricsRecorder, MetricsSample};
+pub use mixing::{MixingField, MixingSettings};
+pub use network::GlutenNetwork;
+pub use notebook::{LabNotebook, Note};
+pub use observation::{ObservationCounters, ObservationVolume};
+pub use profile::TickProfiler;
+pub use proofing::{OverproofSigns, ProofMonitor, ProofSettings, ProofState};
+pub use protease::ProteaseSettings;
+pub use readiness::{DoughReadiness, PokeResponse, ReadinessSettings};
+pub use reactions::ReactionLedger;
+pub use recipe::{Recipe, RecipePreset};
+pub use region::{Falloff, Region};
+pub use rise::{RiseMetrics, RiseSettings};
+pub use rules::{Fate, Product, RateFactor, ReactionRule, RuleCondition, RuleEffect};
+pub use repulsion::RepulsionSettings;
+pub use salt::{SaltField, SaltSettings};
+pub use sanity::{EscapePolicy, QuarantineCounters, QuarantineReason, SanitySettings};
+pub use perturbation::{Perturbation, RecoveryMetrics, RecoveryReport, TemperatureSpike};
+pub use phase::DoughPhase;
+pub use plugin::{PluginSet, SimulationPlugin};
+pub use preferment::{Preferment, PrefermentKind};
+pub use schedule::{DoughSummary, ProcessSchedule, ScheduleStep, StepCondition, StepNotification};
+pub use section::CrossSection;
+pub use sensitivity::{Outcome, RecipeParameter, SensitivityReport, SensitivitySettings};
+pub use session::{CameraCursor, SessionClient, SessionEntry, SessionServer};
+pub use snapshot::EmergencySnapshot;
+pub use solver::{SolverReport, SolverSettings};
+pub use species::{Species, SpeciesInfo, SPECIES};
+pub use starter::{diagnose_starter, Feeding, Levain, Starter, StarterHealth, StarterReport};
+pub use stats::{SimStats, SpeciesCounts};
+pub use stickiness::{Stickiness, StickinessSettings};
+pub use summary::{TickStage, TickSummary};
+pub use sync::{ChangeJournal, SimChanges, SyncToken};
+pub use thermal::{HeatExchange, ThermalField, ThermalSettings};
+pub use timeline::ActionScheduler;
+pub use timestep::FixedTimestep;
+pub use tools::{Hand, ShapingTools, WorkSurface};
+use kinetics::{active_fraction, arrhenius_factor};
+use integrator::SlotHistory;
+use plugin::PluginHook;
+use rules::RuleStep;
+use summary::StageTimer;
+pub use trajectory::{Trajectory, TrajectoryPlayer, TrajectoryRecorder, TrajectoryWriter};
+pub use viscosity::ViscositySettings;
+
+// What an embedding crate needs: `use pain_core::prelude::*;`
+pub mod prelude {
+    pub use crate::{
+        Bond, DoughPhase, EnvironmentPreset, GlutenDevelopment, GlutenNetwork, KneadAction, Molecule, MoleculeType, Recipe,
+        RecipePreset, SimAction, SimStats, SimulationBuilder, SimulationConfig, SimulationEvent,
+        SimulationState, Species, TickSummary,
+    };
+}
+
+// HashMap with a fixed hasher: iteration order only depends on the insertion
+// history, so seeded runs visit molecules in the same order every time.
+pub type StableHashMap<K, V> = HashMap<K, V, BuildHasherDefault<DefaultHasher>>;
+pub type StableHashSet<T> = HashSet<T, BuildHasherDefault<DefaultHasher>>;
+
+// Ids kept inline in a grid cell before it spills to the heap; a 15-unit
+// cell of kneaded dough rarely holds more
+const CELL_INLINE_IDS: usize = 16;
+type CellIds<const N: usize> = SmallVec<[MoleculeId; N]>;
 
-#[derive(Debug, Clone)]
+// Same generator as rand's StdRng, but serializable so a saved run resumes
+// the exact random stream.
+pub type SimRng = ChaCha12Rng;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MoleculeType {
     Gliadin,
     Glutenin { has_free_thiol: bool },
     Water,
+    WaterCluster { size: u8 }, // Several quiet water molecules moving as one
     Yeast,
     CO2,
     Ethanol,
     Sugar,
     Salt,
     Ash,
+    Starch,  // Damaged starch, broken down into sugar by amylase
+    Amylase, // Flour enzyme feeding the yeast over long ferments
+    Fat,     // Butter or oil of an enriched dough
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Molecule {
-    pub id: u64,
+    pub id: MoleculeId,
     pub pos: Vector3<f32>,
     pub velocity: Vector3<f32>,
     pub mol_type: MoleculeType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bond {
-    pub molecule_a_id: u64,
-    pub molecule_b_id: u64,
+    pub molecule_a_id: MoleculeId,
+    pub molecule_b_id: MoleculeId,
     pub target_distance: f32,
+    pub stiffness: f32, // Spring constant along the bond, see `SolverSettings`
+    pub damping: f32,   // Resistance to the ends moving apart or together
 }
 
-#[derive(Debug)]
+// Cells along one axis: a range, and a second one when it wraps around
+type CellRange = std::iter::Chain<std::ops::Range<i32>, std::ops::Range<i32>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpatialGrid3D {
     cell_size: f32,
-    grid: HashMap<(i32, i32, i32), Vec<u64>>,
-    molecules: HashMap<u64, Molecule>,
-    next_id: u64,
+    dims: (i32, i32, i32),   // Number of cells along x, y, z
+    cells: Vec<CellIds<CELL_INLINE_IDS>>, // Dense cell array indexed by `cell_index`
+    molecules: MoleculeArena,
+    inserted: u64, // Molecules ever inserted
+    species: SpeciesCounts, // Follows `molecules`
+    #[serde(skip)]
+    periods: [Option<f32>; 3], // Box extent along the axes that wrap, see `Boundaries::periods`
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SimulationState {
     pub grid: SpatialGrid3D,
-    pub bonds: Vec<Bond>,
+    bonds: Vec<Bond>, // See `push_bond` and `set_bonds`
     pub width: f32,
     pub height: f32,
     pub depth: f32,
-    pub temperature: f32,      // Influences reaction rates
+    pub temperature: f32,      // Mean dough temperature, `thermal` has the local values
     pub time_elapsed: f32,     // Time elapsed in seconds
     pub recipe_hydration: f32, // Hydration percentage (0.65 to 0.90)
     pub recipe_salt: f32,      // Salt percentage (0.0 to 0.03)
     pub recipe_yeast: f32,     // Yeast/levain percentage (0.10 to 0.30)
     pub autolyse_time: f32,    // Duration of autolyse phase in seconds
+    pub recipe_name: String,   // Name of the recipe the dough was started from
+    pub recipe_scale: Option<GramScale>, // Grams the recipe was weighed in, if any
+    pub recipe_absorption: f32, // Water / flour the flour takes up
+    pub recipe_fat: f32, // Butter or oil / flour, 0 for a lean dough
+    pub levain: Option<Levain>, // Taken from a fed starter, None for a fresh levain
     pub salt_added: bool,      // Track if salt has been added
     pub yeast_added: bool,     // Track if yeast has been added
+    pub phase: DoughPhase,
+    pub phase_started: f32, // Sim time the current phase began
+    pub bake: BakeSettings,
+    pub oven: Option<Oven>, // Set once the loaf goes in the oven
+    #[serde(skip)]
+    pub last_tick: TickSummary,
+    pub events: EventLog,      // Recent actions and incidents, kept for crash reports
+    pub temperature_spike: Option<TemperatureSpike>, // Active perturbation to undo
+    pub solver: SolverSettings,
+    pub last_solver_report: SolverReport,
+    pub last_force_time: Option<f32>, // When a fold/kneading force was last applied
+    pub repulsion: RepulsionSettings, // Keeps molecules from overlapping
+    pub brownian: BrownianSettings, // Thermal jiggling of the small molecules
+    pub viscosity: ViscositySettings, // Drag of the gluten around each molecule
+    pub boundaries: Boundaries, // What each face of the box does to the molecules
+    pub container: ContainerShape, // Vessel in the box, change it with `set_container`
+    pub budget: BudgetSettings, // How many molecules the recipe and the reactions make
+    pub fat: FatSettings, // How butter or oil weakens the gluten around it
+    pub improvers: Improvers, // Malt and ascorbic acid added so far
+    pub improver_settings: ImproverSettings,
+    pub protease_settings: ProteaseSettings, // How fast the flour's proteases cut the gluten
+    pub gluten_digested: f32, // Share of the glutenin chains the proteases cut
+    pub water_clustering: WaterClusterSettings,
+    pub observations: Vec<ObservationVolume>,
+    pub mixing: MixingField, // Recent mechanical agitation, drives bond formation
+    pub mixing_settings: MixingSettings,
+    pub ledger: MassLedger, // Where the dough mass went: evaporation, gas, reactions
+    pub evaporation: EvaporationSettings,
+    pub reactions: ReactionLedger, // Atom bookkeeping for the chemistry
+    pub environment: EnvironmentSettings, // Room temperature over the day
+    pub config: SimulationConfig, // Physics and chemistry constants
+    pub acidity: Acidity, // Lactic and acetic acid made by the levain bacteria
+    pub acid_settings: AcidSettings,
+    pub sanity: SanitySettings,
+    pub quarantine: QuarantineCounters, // NaN or escaped molecules caught so far
+    pub collapse: CollapseMonitor, // Rise and torn bonds, to catch the dough slumping
+    pub collapse_settings: CollapseSettings,
+    pub proofing: ProofMonitor, // Readiness and over-proofing, warned once each
+    pub proof_settings: ProofSettings,
+    pub checkpoint_settings: CheckpointSettings,
+    // Automatic checkpoints to rewind to, not saved
+    #[serde(skip)]
+    pub checkpoints: CheckpointRing,
+    pub fast_forward_settings: FastForwardSettings,
+    pub metrics: MetricsRecorder, // Recent samples of counts, bonds and network, for the frontends
+    pub development_settings: DevelopmentSettings, // Weights of the gluten development gauge
+    pub scheduler: ActionScheduler, // Timeline of actions fired by the ticks themselves
+    pub knead_settings: KneadSettings,
+    pub kneading: KneadCounts, // Folds of each technique on this dough
+    pub notebook: LabNotebook, // The baker's timestamped notes on this dough
+    pub thermal: ThermalField, // Temperature across the dough, reactions read it locally
+    pub thermal_settings: ThermalSettings,
+    pub rise_settings: RiseSettings, // How the dough volume is measured
+    pub crumb_settings: CrumbSettings, // How gas is grouped into alveoli
+    pub challenge: Option<Challenge>, // Mystery flour game, hidden from the player
+    pub salt: SaltField, // Dissolved salt across the dough
+    pub salt_settings: SaltSettings,
+    pub burp_settings: BurpSettings, // How escaping gas is grouped into burps
+    pub stickiness_settings: StickinessSettings,
+    pub readiness_settings: ReadinessSettings,
+    pub poke: Option<PokeResponse>, // Proteins pushed by the last force, for the poke test
+    pub tools: ShapingTools, // Bench and hands the dough is shaped with
+    pub extra_reactions: Vec<ReactionRule>, // User chemistry, run after the built-in reactions
+    pub timestep: FixedTimestep,
+    step_accumulator: f32, // Seconds received but not stepped yet, see `timestep`
+
+    pub seed: u64, // Seed the run was started with, to reproduce it
+    pub rng: SimRng,
+
+    // Independent sets of bonds for the solver, follows `bonds`
+    #[serde(skip)]
+    bond_coloring: BondColoring,
+    // Molecule pairs already bonded, follows `bonds`
+    #[serde(skip)]
+    bond_pairs: BondPairs,
+    // Changes to `bonds`, and the one the two indexes above are valid for
+    #[serde(skip)]
+    bonds_generation: u64,
+    #[serde(skip)]
+    indexed_generation: Option<u64>,
+    // Velocities of the last tick, for the Verlet integrator
+    #[serde(skip)]
+    velocity_history: SlotHistory,
+    // Positions before the last fixed step, for the renderers
+    #[serde(skip)]
+    step_origins: SlotHistory,
+    // What changed step by step, for the renderers that ask
+    #[serde(skip)]
+    journal: ChangeJournal,
+    // Custom models hooked into the tick, not saved
+    #[serde(skip)]
+    plugins: PluginSet,
 }
 
 impl Molecule {
     pub fn new(mol_type: MoleculeType, pos: Vector3<f32>, velocity: Vector3<f32>) -> Self {
         Molecule {
-            id: 0, // Will be assigned by SpatialGrid3D
+            id: MoleculeId::NULL, // Will be assigned by SpatialGrid3D
             pos,
             velocity,
             mol_type,
@@ -65,60 +359,78 @@ impl Molecule {
     }
 
     pub fn radius(&self) -> f32 {
+        let radius = self.mol_type.species().info().radius;
         match self.mol_type {
-            MoleculeType::Gliadin => 3.0,
-            MoleculeType::Glutenin { .. } => 4.0,
-            MoleculeType::Water => 1.5,
-            MoleculeType::Yeast => 5.0,
-            MoleculeType::CO2 => 8.0,
-            MoleculeType::Ethanol => 2.0,
-            MoleculeType::Sugar => 2.5,
-            MoleculeType::Salt => 1.8,
-            MoleculeType::Ash => 2.0,
+            // A cluster keeps the total volume of its molecules
+            MoleculeType::WaterCluster { size } => radius * (size as f32).cbrt(),
+            _ => radius,
         }
     }
 
     pub fn mass(&self) -> f32 {
+        let mass = self.mol_type.species().info().mass;
         match self.mol_type {
-            MoleculeType::Gliadin => 10.0,
-            MoleculeType::Glutenin { .. } => 12.0,
-            MoleculeType::Water => 1.0,
-            MoleculeType::Yeast => 15.0,
-            MoleculeType::CO2 => 2.0,
-            MoleculeType::Ethanol => 3.0,
-            MoleculeType::Sugar => 4.0,
-            MoleculeType::Salt => 2.0,
-            MoleculeType::Ash => 2.0,
+            MoleculeType::WaterCluster { size } => mass * size as f32,
+            _ => mass,
         }
     }
 }
 
 impl SpatialGrid3D {
-    pub fn new(_width: f32, _height: f32, _depth: f32, cell_size: f32) -> Self {
+    pub fn new(width: f32, height: f32, depth: f32, cell_size: f32) -> Self {
+        let cells_along = |extent: f32| ((extent / cell_size).ceil() as i32).max(1);
+        let dims = (cells_along(width), cells_along(height), cells_along(depth));
         SpatialGrid3D {
             cell_size,
-            grid: HashMap::new(),
-            molecules: HashMap::new(),
-            next_id: 1,
+            dims,
+            cells: vec![CellIds::new(); (dims.0 * dims.1 * dims.2) as usize],
+            molecules: MoleculeArena::default(),
+            inserted: 0,
+            species: SpeciesCounts::default(),
+            periods: [None; 3],
         }
     }
 
-    pub fn insert(&mut self, mut molecule: Molecule) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        molecule.id = id;
-
-        self.molecules.insert(id, molecule.clone());
+    pub(crate) fn set_periods(&mut self, periods: [Option<f32>; 3]) {
+        self.periods = periods;
+    }
 
-        let cell_coords = self.get_cell_coords(molecule.pos);
-        self.grid
-            .entry(cell_coords)
-            .or_insert_with(Vec::new)
-            .push(id);
+    // Shortest way from `from` to `to`: across the seam of a periodic axis
+    // when that is nearer than through the box
+    pub fn displacement(&self, from: Vector3<f32>, to: Vector3<f32>) -> Vector3<f32> {
+        let mut offset = to - from;
+        for (axis, period) in self.periods.iter().enumerate() {
+            if let Some(period) = *period {
+                offset[axis] -= period * (offset[axis] / period).round();
+            }
+        }
+        offset
+    }
 
+    pub fn insert(&mut self, molecule: Molecule) -> MoleculeId {
+        let index = self.cell_index(molecule.pos);
+        self.species.add(&molecule.mol_type);
+        let id = self.molecules.insert(molecule);
+        self.cells[index].push(id);
+        self.inserted += 1;
         id
     }
 
+    // Inserts a molecule keeping its id (trajectory playback, restored
+    // states), replacing the molecule that held its slot
+    pub fn insert_with_id(&mut self, molecule: Molecule) {
+        let id = molecule.id;
+        let index = self.cell_index(molecule.pos);
+        self.species.add(&molecule.mol_type);
+        if let Some(previous) = self.molecules.insert_with_id(molecule) {
+            self.species.remove(&previous.mol_type);
+            let previous_index = self.cell_index(previous.pos);
+            self.cells[previous_index].retain(|mol_id| *mol_id != previous.id);
+        }
+        self.cells[index].push(id);
+        self.inserted += 1;
+    }
+
     pub fn get_cell_coords(&self, pos: Vector3<f32>) -> (i32, i32, i32) {
         (
             (pos.x / self.cell_size).floor() as i32,
@@ -127,67 +439,232 @@ impl SpatialGrid3D {
         )
     }
 
+    // Cell coordinates clamped to the domain: molecules that stray outside
+    // the box are kept in the nearest border cell
+    fn clamped_cell_coords(&self, pos: Vector3<f32>) -> (i32, i32, i32) {
+        let (x, y, z) = self.get_cell_coords(pos);
+        (
+            x.clamp(0, self.dims.0 - 1),
+            y.clamp(0, self.dims.1 - 1),
+            z.clamp(0, self.dims.2 - 1),
+        )
+    }
+
+    fn linear_index(&self, (x, y, z): (i32, i32, i32)) -> usize {
+        ((z * self.dims.1 + y) * self.dims.0 + x) as usize
+    }
+
+    fn cell_index(&self, pos: Vector3<f32>) -> usize {
+        self.linear_index(self.clamped_cell_coords(pos))
+    }
+
+    // Cells along `axis` from the one holding `low` to the one holding
+    // `high`: clamped to the grid, or on a periodic axis wrapped around, the
+    // part past a face coming back in from the other one
+    fn axis_cells(&self, axis: usize, low: f32, high: f32) -> CellRange {
+        let count = [self.dims.0, self.dims.1, self.dims.2][axis];
+        let cell = |at: f32| ((at / self.cell_size).floor() as i32).clamp(0, count - 1);
+        let (inside, wrapped) = match self.periods[axis] {
+            Some(period) if high - low >= period => (0..count, 0..0),
+            Some(period) if low < 0.0 => (0..cell(high) + 1, cell(low + period)..count),
+            Some(period) if high >= period => (cell(low)..count, 0..cell(high - period) + 1),
+            _ => (cell(low)..cell(high) + 1, 0..0),
+        };
+        // Both ends of a short axis meet in the same cells
+        if inside.start.max(wrapped.start) < inside.end.min(wrapped.end) {
+            return (0..count).chain(0..0);
+        }
+        inside.chain(wrapped)
+    }
+
+    // Id lists of the cells within `span` cells of `pos`, z-major like the dense array
+    fn cells_around(&self, pos: Vector3<f32>, span: i32) -> impl Iterator<Item = &[MoleculeId]> + '_ {
+        let center = self.clamped_cell_coords(pos);
+        let center = [center.0, center.1, center.2];
+        let dims = [self.dims.0, self.dims.1, self.dims.2];
+        let reach = span as f32 * self.cell_size;
+        let around = move |axis: usize| match self.periods[axis] {
+            Some(_) => self.axis_cells(axis, pos[axis] - reach, pos[axis] + reach),
+            None => ((center[axis] - span).max(0)..(center[axis] + span + 1).min(dims[axis]))
+                .chain(0..0),
+        };
+        around(2).flat_map(move |z| {
+            around(1).flat_map(move |y| {
+                around(0).map(move |x| self.cells[self.linear_index((x, y, z))].as_slice())
+            })
+        })
+    }
+
+    // Molecules in the center cell and the surrounding cells (3x3x3 cube) that exist
+    pub fn neighbors(&self, pos: Vector3<f32>) -> impl Iterator<Item = &Molecule> + '_ {
+        self.cells_around(pos, 1)
+            .flatten()
+            .filter_map(|&id| self.molecules.get(id))
+    }
+
+    // The whole 3x3x3 block whatever the distance, see `get_neighbors_within`
     pub fn get_neighbors(&self, pos: Vector3<f32>) -> Vec<&Molecule> {
-        let center_cell = self.get_cell_coords(pos);
-        let mut neighbors = Vec::new();
-
-        // Check the center cell and all 26 surrounding cells (3x3x3 cube)
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                for dz in -1..=1 {
-                    let cell_coords = (center_cell.0 + dx, center_cell.1 + dy, center_cell.2 + dz);
-
-                    if let Some(ids) = self.grid.get(&cell_coords) {
-                        for &id in ids {
-                            if let Some(mol) = self.molecules.get(&id) {
-                                neighbors.push(mol);
+        self.neighbors(pos).collect()
+    }
+
+    pub fn get_neighbors_within(&self, pos: Vector3<f32>, radius: f32) -> Vec<&Molecule> {
+        self.neighbors_within(pos, radius).collect()
+    }
+
+    // Same molecules as `neighbors`, as ids written into a reusable buffer
+    pub fn neighbors_into(&self, pos: Vector3<f32>, out: &mut Vec<MoleculeId>) {
+        out.clear();
+        out.extend(self.cells_around(pos, 1).flatten().copied());
+    }
+
+    // Cells to scan on each side to reach `radius`, never more than the grid
+    fn span_for(&self, radius: f32) -> i32 {
+        let widest = self.dims.0.max(self.dims.1).max(self.dims.2);
+        ((radius / self.cell_size).ceil() as i32).clamp(1, widest)
+    }
+
+    // Molecules strictly closer than `radius` to `pos`, in the same order as
+    // `neighbors`, read straight from the cells without allocating
+    pub fn neighbors_within(&self, pos: Vector3<f32>, radius: f32) -> impl Iterator<Item = &Molecule> + '_ {
+        self.cells_around(pos, self.span_for(radius))
+            .flatten()
+            .filter_map(|&id| self.molecules.get(id))
+            .filter(move |mol| self.displacement(pos, mol.pos).magnitude() < radius)
+    }
+
+    // Same molecules and order as `neighbors_within`, for the passes that
+    // visit every molecule each tick: only the cells the sphere of `radius`
+    // reaches are scanned, often just the molecule's own for short radii
+    pub fn for_each_neighbor(&self, pos: Vector3<f32>, radius: f32, mut f: impl FnMut(&Molecule)) {
+        let cells = |axis: usize| self.axis_cells(axis, pos[axis] - radius, pos[axis] + radius);
+        for z in cells(2) {
+            for y in cells(1) {
+                for x in cells(0) {
+                    for &id in &self.cells[self.linear_index((x, y, z))] {
+                        if let Some(mol) = self.molecules.get(id) {
+                            if self.displacement(pos, mol.pos).magnitude() < radius {
+                                f(mol);
                             }
                         }
                     }
                 }
             }
         }
+    }
 
-        neighbors
+    // The `k` molecules closest to `pos`, nearest first, for analysis tools.
+    // The block of cells scanned doubles until the molecules within its inner
+    // radius are enough, so a query in a dense dough stays local.
+    pub fn k_nearest(&self, pos: Vector3<f32>, k: usize) -> Vec<&Molecule> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let widest = self.dims.0.max(self.dims.1).max(self.dims.2);
+        let mut span = 1;
+        loop {
+            let covers_grid = span >= widest;
+            let reach = span as f32 * self.cell_size;
+            let mut found: Vec<&Molecule> = self
+                .cells_around(pos, span)
+                .flatten()
+                .filter_map(|&id| self.molecules.get(id))
+                .filter(|mol| covers_grid || self.displacement(pos, mol.pos).magnitude() < reach)
+                .collect();
+            if found.len() >= k || covers_grid {
+                found.sort_by(|a, b| {
+                    self.displacement(pos, a.pos)
+                        .magnitude_squared()
+                        .total_cmp(&self.displacement(pos, b.pos).magnitude_squared())
+                });
+                found.truncate(k);
+                return found;
+            }
+            span = (span * 2).min(widest);
+        }
     }
 
-    pub fn remove(&mut self, id: u64) {
-        if let Some(molecule) = self.molecules.remove(&id) {
-            let cell_coords = self.get_cell_coords(molecule.pos);
+    // Ids of `neighbors_within`, for callers that change the molecules
+    // afterwards; `out` is cleared first and keeps its capacity
+    pub fn query_radius_into(&self, pos: Vector3<f32>, radius: f32, out: &mut Vec<MoleculeId>) {
+        out.clear();
+        out.extend(self.neighbors_within(pos, radius).map(|mol| mol.id));
+    }
 
-            if let Some(ids) = self.grid.get_mut(&cell_coords) {
-                ids.retain(|&mol_id| mol_id != id);
-            }
+    pub fn remove(&mut self, id: MoleculeId) {
+        if let Some(molecule) = self.molecules.remove(id) {
+            self.species.remove(&molecule.mol_type);
+            let index = self.cell_index(molecule.pos);
+            self.cells[index].retain(|mol_id| *mol_id != id);
         }
     }
 
-    pub fn update_molecule_pos(&mut self, id: u64, new_pos: Vector3<f32>) {
-        if let Some(mol) = self.molecules.get_mut(&id) {
+    pub fn update_molecule_pos(&mut self, id: MoleculeId, new_pos: Vector3<f32>) {
+        if let Some(mol) = self.molecules.get_mut(id) {
             let old_pos = mol.pos;
-            // Update position
             mol.pos = new_pos;
+            self.relocate(id, old_pos);
+        }
+    }
 
-            // Remove from old cell
-            let old_cell_coords = self.get_cell_coords(old_pos);
-            if let Some(ids) = self.grid.get_mut(&old_cell_coords) {
-                ids.retain(|&mol_id| mol_id != id);
-            }
+    // Moves the id of a molecule whose position was changed in place from the
+    // cell of `old_pos` to the cell of its current position
+    pub fn relocate(&mut self, id: MoleculeId, old_pos: Vector3<f32>) {
+        let Some(new_pos) = self.molecules.get(id).map(|mol| mol.pos) else {
+            return;
+        };
+        let old_index = self.cell_index(old_pos);
+        let new_index = self.cell_index(new_pos);
+        if old_index == new_index {
+            return;
+        }
+        self.cells[old_index].retain(|mol_id| *mol_id != id);
+        self.cells[new_index].push(id);
+    }
 
-            // Insert into new cell
-            let new_cell_coords = self.get_cell_coords(new_pos);
-            self.grid
-                .entry(new_cell_coords)
-                .or_insert_with(Vec::new)
-                .push(id);
+    pub fn get_molecule(&self, id: MoleculeId) -> Option<&Molecule> {
+        self.molecules.get(id)
+    }
+
+    // Change the type with `set_mol_type`, the species counts follow it
+    pub fn get_molecule_mut(&mut self, id: MoleculeId) -> Option<&mut Molecule> {
+        self.molecules.get_mut(id)
+    }
+
+    pub fn set_mol_type(&mut self, id: MoleculeId, mol_type: MoleculeType) {
+        if let Some(mol) = self.molecules.get_mut(id) {
+            self.species.remove(&mol.mol_type);
+            self.species.add(&mol_type);
+            mol.mol_type = mol_type;
+        }
+    }
+
+    pub fn species_counts(&self) -> &SpeciesCounts {
+        &self.species
+    }
+
+    // The counts from scratch, to check the kept ones
+    pub(crate) fn recount_species(&self) -> SpeciesCounts {
+        let mut counts = SpeciesCounts::default();
+        for mol in self.molecules.values() {
+            counts.add(&mol.mol_type);
         }
+        counts
     }
 
-    pub fn get_molecule(&self, id: u64) -> Option<&Molecule> {
-        self.molecules.get(&id)
+    // Removes a molecule and its id from every cell, for molecules whose
+    // position may no longer match the cell they were filed in
+    pub(crate) fn take_everywhere(&mut self, id: MoleculeId) -> Option<Molecule> {
+        let molecule = self.molecules.remove(id)?;
+        self.species.remove(&molecule.mol_type);
+        for cell in &mut self.cells {
+            cell.retain(|mol_id| *mol_id != id);
+        }
+        Some(molecule)
     }
 
-    pub fn get_molecule_mut(&mut self, id: u64) -> Option<&mut Molecule> {
-        self.molecules.get_mut(&id)
+    // Every molecule, in the stable iteration order, without collecting them
+    pub fn molecules(&self) -> impl Iterator<Item = &Molecule> + '_ {
+        self.molecules.values()
     }
 
     pub fn get_all_molecules(&self) -> Vec<&Molecule> {
@@ -197,12 +674,54 @@ impl SpatialGrid3D {
     pub fn get_all_molecules_mut(&mut self) -> Vec<&mut Molecule> {
         self.molecules.values_mut().collect()
     }
+
+    pub fn len(&self) -> usize {
+        self.molecules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.molecules.is_empty()
+    }
+
+    // Molecules inserted since the grid was made, to count additions
+    pub(crate) fn inserted(&self) -> u64 {
+        self.inserted
+    }
+
+    // Size of arrays indexed by `MoleculeId::index`
+    pub fn id_capacity(&self) -> usize {
+        self.molecules.capacity()
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Bytes held on the heap by the cells, the ids spilled out of them and
+    // the molecules
+    pub fn heap_bytes(&self) -> usize {
+        let spilled: usize = self
+            .cells
+            .iter()
+            .filter(|ids| ids.spilled())
+            .map(|ids| ids.capacity() * std::mem::size_of::<MoleculeId>())
+            .sum();
+        self.cells.capacity() * std::mem::size_of::<CellIds<CELL_INLINE_IDS>>()
+            + spilled
+            + self.molecules.heap_bytes()
+    }
 }
 
 impl SimulationState {
+    // Starts from a random seed; use `new_with_seed` for reproducible runs
     pub fn new(width: f32, height: f32, depth: f32) -> Self {
+        Self::new_with_seed(width, height, depth, rand::random())
+    }
+
+    // Same seed and same inputs give identical molecule trajectories
+    pub fn new_with_seed(width: f32, height: f32, depth: f32, seed: u64) -> Self {
         SimulationState {
-            grid: SpatialGrid3D::new(width, height, depth, 15.0),
+            grid: SpatialGrid3D::new(width, height, depth, SimulationConfig::default().grid_cell_size),
             bonds: Vec::new(),
             width,
             height,
@@ -213,111 +732,157 @@ impl SimulationState {
             recipe_salt: 0.02,      // 2% salt
             recipe_yeast: 0.20,     // 20% yeast/levain
             autolyse_time: 1800.0,  // 30 minutes of autolyse (in seconds)
+            recipe_name: String::new(),
+            recipe_scale: None,
+            recipe_absorption: REFERENCE_ABSORPTION,
+            recipe_fat: 0.0,
+            levain: None,
             salt_added: true,       // Initially true for new simulation, but will be managed by UI
             yeast_added: false,     // Initially false until user adds yeast
+            phase: DoughPhase::default(),
+            phase_started: 0.0,
+            bake: BakeSettings::default(),
+            oven: None,
+            last_tick: TickSummary::default(),
+            events: EventLog::default(),
+            temperature_spike: None,
+            solver: SolverSettings::default(),
+            last_solver_report: SolverReport::default(),
+            last_force_time: None,
+            repulsion: RepulsionSettings::default(),
+            brownian: BrownianSettings::default(),
+            viscosity: ViscositySettings::default(),
+            boundaries: Boundaries::default(),
+            container: ContainerShape::Box,
+            budget: BudgetSettings::default(),
+            fat: FatSettings::default(),
+            improvers: Improvers::default(),
+            improver_settings: ImproverSettings::default(),
+            protease_settings: ProteaseSettings::default(),
+            gluten_digested: 0.0,
+            water_clustering: WaterClusterSettings::default(),
+            observations: Vec::new(),
+            mixing: MixingField::default(),
+            mixing_settings: MixingSettings::default(),
+            ledger: MassLedger::default(),
+            evaporation: EvaporationSettings::default(),
+            reactions: ReactionLedger::default(),
+            environment: EnvironmentSettings::default(),
+            config: SimulationConfig::default(),
+            acidity: Acidity::default(),
+            acid_settings: AcidSettings::default(),
+            sanity: SanitySettings::default(),
+            quarantine: QuarantineCounters::default(),
+            collapse: CollapseMonitor::default(),
+            collapse_settings: CollapseSettings::default(),
+            proofing: ProofMonitor::default(),
+            proof_settings: ProofSettings::default(),
+            checkpoint_settings: CheckpointSettings::default(),
+            checkpoints: CheckpointRing::default(),
+            fast_forward_settings: FastForwardSettings::default(),
+            metrics: MetricsRecorder::default(),
+            development_settings: DevelopmentSettings::default(),
+            scheduler: ActionScheduler::default(),
+            knead_settings: KneadSettings::default(),
+            kneading: KneadCounts::default(),
+            notebook: LabNotebook::default(),
+            thermal: ThermalField::uniform(ThermalSettings::default().resolution, 25.0),
+            thermal_settings: ThermalSettings::default(),
+            rise_settings: RiseSettings::default(),
+            crumb_settings: CrumbSettings::default(),
+            challenge: None,
+            salt: SaltField::empty(SaltSettings::default().resolution),
+            salt_settings: SaltSettings::default(),
+            burp_settings: BurpSettings::default(),
+            stickiness_settings: StickinessSettings::default(),
+            readiness_settings: ReadinessSettings::default(),
+            poke: None,
+            tools: ShapingTools::default(),
+            extra_reactions: Vec::new(),
+            timestep: FixedTimestep::default(),
+            step_accumulator: 0.0,
+            seed,
+            rng: SimRng::seed_from_u64(seed),
+            bond_coloring: BondColoring::default(),
+            bond_pairs: BondPairs::default(),
+            bonds_generation: 0,
+            indexed_generation: Some(0),
+            velocity_history: SlotHistory::default(),
+            step_origins: SlotHistory::default(),
+            journal: ChangeJournal::default(),
+            plugins: PluginSet::default(),
         }
     }
 
-    pub fn initialize_classic_recipe(&mut self) {
-        self.recipe_hydration = 0.72;
-        self.recipe_salt = 0.02;
-        self.recipe_yeast = 0.20;
-        self.autolyse_time = 1800.0; // 30 minutes
-        self.temperature = 25.0;
-
-        // Reset simulation state
-        self.grid = SpatialGrid3D::new(self.width, self.height, self.depth, 15.0);
-        self.bonds.clear();
-        self.time_elapsed = 0.0;
-        self.salt_added = false; // We'll add salt later
-        self.yeast_added = false;
-
-        // Add initial flour components: gliadin and glutenin proteins
-        let flour_proteins = 200; // Limite stricte pour la démo
-        for _ in 0..flour_proteins {
-            let x = rand::thread_rng().gen_range(0.0..self.width);
-            let y = rand::thread_rng().gen_range(0.0..self.height);
-            let z = rand::thread_rng().gen_range(0.0..self.depth);
-            let pos = Vector3::new(x, y, z);
-
-            // Randomly distribute gliadins and glutens
-            let vel_x = rand::thread_rng().gen_range(-0.1..0.1);
-            let vel_y = rand::thread_rng().gen_range(-0.1..0.1);
-            let vel_z = rand::thread_rng().gen_range(-0.1..0.1);
-            let velocity = Vector3::new(vel_x, vel_y, vel_z);
-
-            let protein_choice = rand::thread_rng().gen_range(0..100);
-            if protein_choice < 40 {
-                // 40% gliadin
-                let molecule = Molecule::new(MoleculeType::Gliadin, pos, velocity);
-                self.grid.insert(molecule);
-            } else {
-                // 60% glutenin
-                let molecule = Molecule::new(
-                    MoleculeType::Glutenin {
-                        has_free_thiol: true,
-                    },
-                    pos,
-                    velocity,
-                );
-                self.grid.insert(molecule);
-            }
-        }
-
-        // Add water based on hydration percentage
-        let water_amount = 200; // Limite stricte pour la démo
-        for _ in 0..water_amount as usize {
-            let x = rand::thread_rng().gen_range(0.0..self.width);
-            let y = rand::thread_rng().gen_range(0.0..self.height);
-            let z = rand::thread_rng().gen_range(0.0..self.depth);
-            let pos = Vector3::new(x, y, z);
-
-            let vel_x = rand::thread_rng().gen_range(-0.2..0.2);
-            let vel_y = rand::thread_rng().gen_range(-0.2..0.2);
-            let vel_z = rand::thread_rng().gen_range(-0.2..0.2);
-            let velocity = Vector3::new(vel_x, vel_y, vel_z);
-
-            let molecule = Molecule::new(MoleculeType::Water, pos, velocity);
-            self.grid.insert(molecule);
-        }
+    pub fn center(&self) -> Vector3<f32> {
+        Vector3::new(self.width / 2.0, self.height / 2.0, self.depth / 2.0)
     }
 
     pub fn add_salt(&mut self) {
         if !self.salt_added {
-            let salt_amount = (self.width * self.height * self.depth * 0.00005 * self.recipe_salt) as usize;
+            let mass_before = self.total_mass();
+            let salt_amount = match self.recipe_scale {
+                Some(scale) => scale.count(self.recipe_salt),
+                None => (self.width * self.height * self.depth * 0.00005 * self.recipe_salt) as usize,
+            };
+            let salt_amount = self
+                .budget
+                .scaled(salt_amount)
+                .min(self.budget.room(self.grid.len()));
 
             for _ in 0..salt_amount {
-                let x = rand::thread_rng().gen_range(0.0..self.width);
-                let y = rand::thread_rng().gen_range(0.0..self.height);
-                let z = rand::thread_rng().gen_range(0.0..self.depth);
+                let x = self.rng.gen_range(0.0..self.width);
+                let y = self.rng.gen_range(0.0..self.height);
+                let z = self.rng.gen_range(0.0..self.depth);
                 let pos = Vector3::new(x, y, z);
 
-                let vel_x = rand::thread_rng().gen_range(-0.2..0.2);
-                let vel_y = rand::thread_rng().gen_range(-0.2..0.2);
-                let vel_z = rand::thread_rng().gen_range(-0.2..0.2);
+                let vel_x = self.rng.gen_range(-0.2..0.2);
+                let vel_y = self.rng.gen_range(-0.2..0.2);
+                let vel_z = self.rng.gen_range(-0.2..0.2);
                 let velocity = Vector3::new(vel_x, vel_y, vel_z);
 
                 let molecule = Molecule::new(MoleculeType::Salt, pos, velocity);
                 self.grid.insert(molecule);
             }
 
+            let salt_mass = self.total_mass() - mass_before;
+            self.ledger.added_mass += salt_mass;
+            self.expect_salt(salt_mass as f32);
             self.salt_added = true;
+            self.events.push(self.time_elapsed, SimulationEvent::SaltAdded);
+            self.update_phase_after_addition();
+            self.record_metrics();
         }
     }
 
     pub fn add_yeast(&mut self) {
         if !self.yeast_added {
-            let yeast_amount = (self.width * self.height * self.depth * 0.00002 * self.recipe_yeast) as usize;
+            let mass_before = self.total_mass();
+            // A yeast per gram of levain when the recipe is weighed
+            let yeast_amount = match self.recipe_scale {
+                Some(scale) => scale.count(self.recipe_yeast),
+                None => (self.width * self.height * self.depth * 0.00002 * self.recipe_yeast) as usize,
+            };
+            // A starter's levain holds as many yeast as it grew
+            let yeast_amount = match self.levain {
+                Some(levain) => (yeast_amount as f32 * levain.vigor.max(0.0)).round() as usize,
+                None => yeast_amount,
+            };
+            // Each yeast comes with a sugar
+            let yeast_amount = self
+                .budget
+                .scaled(yeast_amount)
+                .min(self.budget.room(self.grid.len()) / 2);
 
             for _ in 0..yeast_amount {
-                let x = rand::thread_rng().gen_range(0.0..self.width);
-                let y = rand::thread_rng().gen_range(0.0..self.height);
-                let z = rand::thread_rng().gen_range(0.0..self.depth);
+                let x = self.rng.gen_range(0.0..self.width);
+                let y = self.rng.gen_range(0.0..self.height);
+                let z = self.rng.gen_range(0.0..self.depth);
                 let pos = Vector3::new(x, y, z);
 
-                let vel_x = rand::thread_rng().gen_range(-0.1..0.1);
-                let vel_y = rand::thread_rng().gen_range(-0.1..0.1);
-                let vel_z = rand::thread_rng().gen_range(-0.1..0.1);
+                let vel_x = self.rng.gen_range(-0.1..0.1);
+                let vel_y = self.rng.gen_range(-0.1..0.1);
+                let vel_z = self.rng.gen_range(-0.1..0.1);
                 let velocity = Vector3::new(vel_x, vel_y, vel_z);
 
                 let molecule = Molecule::new(MoleculeType::Yeast, pos, velocity);
@@ -325,23 +890,34 @@ impl SimulationState {
 
                 // Also add sugar for the yeast to consume
                 let sugar_x =
-                    rand::thread_rng().gen_range((x - 20.0).max(0.0)..(x + 20.0).min(self.width));
+                    self.rng.gen_range((x - 20.0).max(0.0)..(x + 20.0).min(self.width));
                 let sugar_y =
-                    rand::thread_rng().gen_range((y - 20.0).max(0.0)..(y + 20.0).min(self.height));
+                    self.rng.gen_range((y - 20.0).max(0.0)..(y + 20.0).min(self.height));
                 let sugar_z =
-                    rand::thread_rng().gen_range((z - 20.0).max(0.0)..(z + 20.0).min(self.depth));
+                    self.rng.gen_range((z - 20.0).max(0.0)..(z + 20.0).min(self.depth));
                 let sugar_pos = Vector3::new(sugar_x, sugar_y, sugar_z);
 
-                let sugar_vel_x = rand::thread_rng().gen_range(-0.1..0.1);
-                let sugar_vel_y = rand::thread_rng().gen_range(-0.1..0.1);
-                let sugar_vel_z = rand::thread_rng().gen_range(-0.1..0.1);
+                let sugar_vel_x = self.rng.gen_range(-0.1..0.1);
+                let sugar_vel_y = self.rng.gen_range(-0.1..0.1);
+                let sugar_vel_z = self.rng.gen_range(-0.1..0.1);
                 let sugar_velocity = Vector3::new(sugar_vel_x, sugar_vel_y, sugar_vel_z);
 
                 let sugar_molecule = Molecule::new(MoleculeType::Sugar, sugar_pos, sugar_velocity);
                 self.grid.insert(sugar_molecule);
             }
 
+            // And the acid it already made
+            if let Some(levain) = self.levain {
+                let acid = levain.acid * 2.0 * yeast_amount as f32;
+                let acetic = acid * levain.acetic_fraction;
+                self.add_acid(acetic, acid - acetic);
+            }
+
+            self.ledger.added_mass += self.total_mass() - mass_before;
             self.yeast_added = true;
+            self.events.push(self.time_elapsed, SimulationEvent::YeastAdded);
+            self.update_phase_after_addition();
+            self.record_metrics();
         }
     }
 
@@ -351,21 +927,34 @@ impl SimulationState {
         radius: f32,
         force: Vector3<f32>,
     ) {
-        let mut mol_ids_to_update = Vec::new();
-        let neighbors = self.grid.get_neighbors(center);
-
-        for mol in neighbors {
-            if (mol.pos - center).magnitude() < radius {
-                mol_ids_to_update.push(mol.id);
-            }
-        }
-
-        for id in mol_ids_to_update {
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::ForceApplied {
+                center,
+                radius,
+                force,
+            },
+        );
+        self.last_force_time = Some(self.time_elapsed);
+        self.start_poke(center, radius);
+        self.mixing
+            .deposit(center, radius, force.magnitude(), &self.mixing_settings);
+
+        // However many cells the radius spans, fading out toward the edge
+        // with `config.force_falloff`
+        let falloff = self.config.force_falloff;
+        let mol_ids_to_update: Vec<(MoleculeId, f32)> = self
+            .grid
+            .query_region(&Region::Sphere { center, radius })
+            .map(|mol| (mol.id, falloff.weight((mol.pos - center).magnitude(), radius)))
+            .collect();
+
+        for (id, weight) in mol_ids_to_update {
             if let Some(mol_mut) = self.grid.get_molecule_mut(id) {
-                mol_mut.velocity += force / mol_mut.mass();
+                mol_mut.velocity += force * weight / mol_mut.mass();
 
                 // Limit max velocity to prevent particles from flying away too fast
-                let max_vel = 5.0;
+                let max_vel = self.config.force_max_velocity;
                 let vel_mag = mol_mut.velocity.magnitude();
                 if vel_mag > max_vel {
                     mol_mut.velocity = mol_mut.velocity.normalize() * max_vel;
@@ -374,290 +963,201 @@ impl SimulationState {
         }
     }
 
-    pub fn tick(&mut self, dt: f32) {
+    // Advances the simulation by `dt` seconds and reports what happened; the
+    // report is also kept in `last_tick`. With fixed steps (`timestep`) the
+    // tick runs the steps that fit and a tick too short for one leaves the
+    // last report in place.
+    pub fn tick(&mut self, dt: f32) -> TickSummary {
+        let mut start = self.tick_start();
+        let plan = self.plan_steps(dt);
+        for _ in 0..plan.steps {
+            self.remember_step_origins();
+            self.step(plan.dt, &mut start.stages);
+        }
+        let summary = self.tick_summary(start, &plan);
+        if plan.steps > 0 {
+            self.last_tick = summary;
+            self.checkpoint_if_due();
+        }
+        summary
+    }
+
+    fn step(&mut self, dt: f32, stages: &mut StageTimer) {
+        self.run_plugins(PluginHook::BeforeTick, dt);
+        // Actions due at the start of the tick act on this tick
+        self.run_scheduled_actions();
+        self.sync_periods();
         // Update time elapsed
         self.time_elapsed += dt;
+        // Molecules corrupted between ticks (edits, perturbations, loaded
+        // states) are caught before the wall clamp hides them in the wrong cell
+        self.quarantine_degenerate_molecules();
+        self.update_temperature_spike();
+        let previous_temperature = self.temperature;
+        self.update_thermal_field(dt);
+        self.update_bake(dt, previous_temperature);
+        self.mixing.decay(dt, &self.mixing_settings);
+        stages.lap(TickStage::Environment);
+
+        // Forces from the plugins move the molecules this tick
+        self.run_plugins(PluginHook::ApplyForces, dt);
+        self.tools.advance(dt);
+        // The small molecules keep diffusing, faster when warm
+        self.apply_brownian_motion(dt);
+        // And everything is held back where the gluten is thick
+        self.apply_local_viscosity(dt);
 
         // Update molecule positions and apply physics
+        let restitution = self.config.wall_restitution;
+        let friction = self.config.friction;
+        let integrator = self.config.integrator;
+        let buoyancy = self.buoyancy_table();
+        let size = self.container_size();
+        let vessel = self.container.vessel(size);
         let mut molecules_to_update = Vec::new();
+        let mut left = Vec::new();
         for mol in self.grid.get_all_molecules_mut() {
-            // Apply velocity
-            mol.pos += mol.velocity * dt;
+            let old_pos = mol.pos;
 
-            // Boundary conditions (bounce off walls)
-            if mol.pos.x < mol.radius() {
-                mol.pos.x = mol.radius();
-                mol.velocity.x = -mol.velocity.x * 0.8; // Dampening
-            }
-            if mol.pos.x > self.width - mol.radius() {
-                mol.pos.x = self.width - mol.radius();
-                mol.velocity.x = -mol.velocity.x * 0.8;
-            }
-            if mol.pos.y < mol.radius() {
-                mol.pos.y = mol.radius();
-                mol.velocity.y = -mol.velocity.y * 0.8;
-            }
-            if mol.pos.y > self.height - mol.radius() {
-                mol.pos.y = self.height - mol.radius();
-                mol.velocity.y = -mol.velocity.y * 0.8;
-            }
-            if mol.pos.z < mol.radius() {
-                mol.pos.z = mol.radius();
-                mol.velocity.z = -mol.velocity.z * 0.8; // Dampening
-            }
-            if mol.pos.z > self.depth - mol.radius() {
-                mol.pos.z = self.depth - mol.radius();
-                mol.velocity.z = -mol.velocity.z * 0.8;
+            // Gravity, less the lift of the dough around it
+            mol.velocity += buoyancy[mol.mol_type.species() as usize] * dt;
+
+            // Apply velocity
+            integrator.advance(mol, dt, friction, &self.velocity_history);
+
+            // Boundary conditions (bounce off walls, or whatever each face does)
+            if !self.boundaries.confine(mol, size, restitution) {
+                left.push(mol.id);
+            } else if let Some(vessel) = &vessel {
+                // And the round wall of a bowl or a banneton
+                container::confine_to(vessel, mol, restitution);
             }
+            // The bench and the hands, while shaping
+            self.tools.collide(mol);
 
             // Apply some friction to slow down movement gradually
-            mol.velocity *= 0.999;
+            integrator.settle(mol, friction, &mut self.velocity_history);
 
             // Store for updating spatial grid
-            molecules_to_update.push((mol.id, mol.pos));
+            molecules_to_update.push((mol.id, old_pos));
         }
+        stages.lap(TickStage::Integration);
 
         // Update spatial grid with new positions
-        for (id, pos) in molecules_to_update {
-            self.grid.update_molecule_pos(id, pos);
+        // Positions were already written, so move each id out of the cell it was in
+        for (id, old_pos) in molecules_to_update {
+            self.grid.relocate(id, old_pos);
         }
+        self.lose_through_boundaries(left);
+        self.update_observation_volumes();
+        stages.lap(TickStage::Grid);
+
+        // Overlapping molecules push each other apart
+        self.apply_repulsion(dt);
+        stages.lap(TickStage::Repulsion);
 
         // Handle chemical reactions and yeast activity
-        self.handle_chemistry(dt);
+        self.handle_chemistry(dt, RuleStep::Tick);
+        stages.lap(TickStage::Chemistry);
 
-        // Apply bond constraints
-        self.apply_bond_constraints();
-    }
+        // Evaporation and gas escaping through the top surface
+        self.handle_surface_losses(dt);
+        stages.lap(TickStage::SurfaceLosses);
 
-    fn handle_chemistry(&mut self, dt: f32) {
-        // Formation of disulfide bridges between glutenins
-        self.form_disulfide_bridges();
+        // Merge quiet bulk water and split sheared clusters
+        self.update_water_clusters();
+        stages.lap(TickStage::WaterClusters);
 
-        // Yeast activity (consuming sugar and producing CO2 and ethanol)
-        if self.yeast_added {
-            self.handle_yeast_activity(dt);
-        }
+        // Apply bond constraints
+        self.apply_bond_constraints(dt);
+        stages.lap(TickStage::BondSolver);
+
+        // And those the tick itself produced, before anyone reads the state
+        self.quarantine_degenerate_molecules();
+        self.watch_for_collapse();
+        self.follow_poke();
+        self.watch_proofing();
+        self.record_metrics_if_due();
+
+        self.debug_check_ledgers();
+        self.run_plugins(PluginHook::AfterTick, dt);
+        self.record_changes();
     }
 
-    fn form_disulfide_bridges(&mut self) {
-        let mut new_bonds = Vec::new();
-        let mut mol_ids_to_update = Vec::new();
-
-        for mol in self.grid.get_all_molecules() {
-            if let MoleculeType::Glutenin {
-                has_free_thiol: true,
-            } = mol.mol_type
-            {
-                let neighbors = self.grid.get_neighbors(mol.pos);
-
-                for neighbor in neighbors {
-                    if neighbor.id == mol.id {
-                        continue; // Skip self
-                    }
-
-                    if let MoleculeType::Glutenin {
-                        has_free_thiol: true,
-                    } = neighbor.mol_type
-                    {
-                        let dist = (mol.pos - neighbor.pos).magnitude();
-
-                        // Check if they're close enough to react
-                        if dist < 8.0 {
-                            // Reaction distance threshold
-                            // Probability of reaction based on temperature and presence of salt
-                            let mut reaction_prob = 0.20; // Augmented base probability (was 0.05)
-
-                            // Increase probability with temperature
-                            reaction_prob *= (self.temperature / 25.0).max(0.1); // Normalize to 25°C base
-
-                            // Check if there's salt nearby to catalyze the reaction
-                            let salt_neighbors = self.grid.get_neighbors(mol.pos);
-                            for salt_neighbor in salt_neighbors {
-                                if matches!(salt_neighbor.mol_type, MoleculeType::Salt) {
-                                    reaction_prob *= 1.2; // Salt increases reaction rate
-                                    break;
-                                }
-                            }
+    fn debug_check_ledgers(&self) {
+        debug_assert!(
+            self.reaction_violation().is_none(),
+            "reaction ledger out of balance: {}",
+            self.reaction_violation().unwrap_or_default()
+        );
+        debug_assert!(
+            self.is_mass_conserved(),
+            "mass ledger out of balance (relative error {})",
+            self.conservation_error()
+        );
+        debug_assert_eq!(
+            *self.grid.species_counts(),
+            self.grid.recount_species(),
+            "species counts out of step with the molecules"
+        );
+    }
 
-                            if rand::thread_rng().gen::<f32>() < reaction_prob * 0.1 {
-                                // Scale down frequency
-                                // Create a bond between the two molecules
-                                new_bonds.push(Bond {
-                                    molecule_a_id: mol.id,
-                                    molecule_b_id: neighbor.id,
-                                    target_distance: dist,
-                                });
-
-                                // Schedule molecules to update their thiol state
-                                mol_ids_to_update.push(mol.id);
-                                mol_ids_to_update.push(neighbor.id);
-                            }
-                        }
-                    }
-                }
-            }
+    fn handle_chemistry(&mut self, dt: f32, kinetics: RuleStep) {
+        // Salt grains dissolve and the dissolved salt spreads
+        self.update_salt(dt);
+
+        // Disulfide bridges, starch hydrolysis, fermentation and any reaction
+        // added by the user, from the rule table
+        let mass_before = self.total_mass();
+        self.run_reaction_rules(dt, kinetics);
+        self.run_plugins(PluginHook::React, dt);
+        self.ledger.reaction_delta += self.total_mass() - mass_before;
+
+        // The flour's proteases slowly cut the bridges, most in a long, warm,
+        // sour ferment
+        if self.phase.enzymes_active() {
+            self.digest_gluten(dt);
         }
 
-        // Add new bonds to our bonds list
-        for bond in new_bonds {
-            // Check if this bond already exists to avoid duplicates
-            if !self.bonds.iter().any(|b| {
-                (b.molecule_a_id == bond.molecule_a_id && b.molecule_b_id == bond.molecule_b_id)
-                    || (b.molecule_a_id == bond.molecule_b_id
-                        && b.molecule_b_id == bond.molecule_a_id)
-            }) {
-                self.bonds.push(bond);
-            }
-        }
+        if self.yeast_added && self.phase.ferments() && self.yeast_alive() {
+            // CO2 released by the fermentation wanders through the dough
+            self.move_co2_bubbles();
 
-        // Update thiol states after bond creation
-        for id in mol_ids_to_update {
-            if let Some(mol_mut) = self.grid.get_molecule_mut(id) {
-                if let MoleculeType::Glutenin {
-                    ref mut has_free_thiol,
-                } = mol_mut.mol_type
-                {
-                    *has_free_thiol = false;
-                }
-            }
+            // The bacteria of the levain sour the dough alongside the yeast
+            self.produce_acid(dt);
         }
     }
 
-    fn handle_yeast_activity(&mut self, dt: f32) {
-        // Process yeast metabolism
-        let mut consumed_sugars = Vec::new();
-        let mut new_molecules = Vec::new();
-
-        for mol in self.grid.get_all_molecules() {
-            if let MoleculeType::Yeast = mol.mol_type {
-                // Look for nearby sugar to consume
-                let neighbors = self.grid.get_neighbors(mol.pos);
-
-                for neighbor in neighbors {
-                    if neighbor.id == mol.id {
-                        continue; // Skip self
-                    }
-
-                    if matches!(neighbor.mol_type, MoleculeType::Sugar) {
-                        // Calculate distance
-                        let dist = (mol.pos - neighbor.pos).magnitude();
-                        if dist < 5.0 {
-                            // Within reaction distance
-                            // Consume the sugar
-                            consumed_sugars.push(neighbor.id);
-
-                            // Increase yeast metabolism rate based on temperature
-                            let metabolism_rate = (self.temperature / 20.0).max(0.1); // Normalized to 20°C base
-
-                            // Random chance to produce CO2 based on metabolism rate
-                            if rand::thread_rng().gen::<f32>() < 0.01 * metabolism_rate * dt {
-                                // Produce CO2 bubble
-                                let co2_pos = Vector3::new(
-                                    mol.pos.x + rand::thread_rng().gen_range(-3.0..3.0),
-                                    mol.pos.y + rand::thread_rng().gen_range(-3.0..3.0),
-                                    mol.pos.z + rand::thread_rng().gen_range(-3.0..3.0),
-                                );
-
-                                let co2_vel = Vector3::new(
-                                    rand::thread_rng().gen_range(-0.2..0.2),
-                                    rand::thread_rng().gen_range(-0.2..0.2),
-                                    rand::thread_rng().gen_range(-0.2..0.2),
-                                );
-
-                                let co2_molecule =
-                                    Molecule::new(MoleculeType::CO2, co2_pos, co2_vel);
-                                new_molecules.push(co2_molecule);
-
-                                // Occasionally produce ethanol too
-                                if rand::thread_rng().gen::<f32>() < 0.3 {
-                                    let ethanol_pos = Vector3::new(
-                                        mol.pos.x + rand::thread_rng().gen_range(-2.0..2.0),
-                                        mol.pos.y + rand::thread_rng().gen_range(-2.0..2.0),
-                                        mol.pos.z + rand::thread_rng().gen_range(-2.0..2.0),
-                                    );
-
-                                    let ethanol_vel = Vector3::new(
-                                        rand::thread_rng().gen_range(-0.1..0.1),
-                                        rand::thread_rng().gen_range(-0.1..0.1),
-                                        rand::thread_rng().gen_range(-0.1..0.1),
-                                    );
-
-                                    let ethanol_molecule = Molecule::new(
-                                        MoleculeType::Ethanol,
-                                        ethanol_pos,
-                                        ethanol_vel,
-                                    );
-                                    new_molecules.push(ethanol_molecule);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Add new molecules to the simulation
-        for mol in new_molecules {
-            self.grid.insert(mol);
-        }
+    // Yeast metabolism relative to the reference temperature: 1 there, an
+    // Arrhenius rise with the temperature, cut by the unfolding of the yeast
+    // enzymes above the deactivation temperature
+    pub fn yeast_metabolism(&self) -> f32 {
+        self.yeast_metabolism_at_temperature(self.temperature)
+    }
 
-        // Remove consumed sugars after we're done checking neighbors
-        for sugar_id in consumed_sugars {
-            self.grid.remove(sugar_id);
-        }
+    pub fn yeast_metabolism_at_temperature(&self, temperature: f32) -> f32 {
+        let config = &self.config;
+        let rate = |temp: f32| {
+            arrhenius_factor(
+                config.metabolism_activation_energy,
+                temp,
+                config.metabolism_reference_temp,
+            ) * active_fraction(
+                config.metabolism_deactivation_energy,
+                config.metabolism_deactivation_temp,
+                temp,
+            )
+        };
+        rate(temperature) / rate(config.metabolism_reference_temp).max(f32::EPSILON)
+    }
 
-        // Handle CO2 bubble behavior - they tend to rise
+    fn move_co2_bubbles(&mut self) {
+        // CO2 bubbles wander as they rise; the rise itself is buoyancy
+        let jitter = self.config.co2_jitter;
         for mol in self.grid.get_all_molecules_mut() {
             if let MoleculeType::CO2 = mol.mol_type {
-                // CO2 bubbles rise due to their lower density
-                mol.velocity.y -= 0.05; // Apply upward force
-
                 // Apply some random motion for realism
-                mol.velocity.x += rand::thread_rng().gen_range(-0.02..0.02);
-            }
-        }
-    }
-
-    fn apply_bond_constraints(&mut self) {
-        let mut forces: HashMap<u64, Vector3<f32>> = HashMap::new();
-
-        for bond in &self.bonds {
-            if let (Some(mol_a), Some(mol_b)) = (
-                self.grid.get_molecule(bond.molecule_a_id),
-                self.grid.get_molecule(bond.molecule_b_id),
-            ) {
-                let diff = mol_b.pos - mol_a.pos;
-                let current_dist = diff.magnitude();
-
-                if current_dist > 0.0 {
-                    let correction = (bond.target_distance - current_dist) / current_dist * 0.5;
-                    let correction_vec = diff * correction;
-
-                    // Apply correction forces (but store them to apply later to avoid borrow checker issues)
-                    forces
-                        .entry(mol_a.id)
-                        .and_modify(|f| *f += correction_vec)
-                        .or_insert(correction_vec);
-
-                    forces
-                        .entry(mol_b.id)
-                        .and_modify(|f| *f -= correction_vec)
-                        .or_insert(-correction_vec);
-                }
-            }
-        }
-
-        // Apply accumulated forces to molecules
-        for (mol_id, force) in forces {
-            if let Some(mol) = self.grid.get_molecule_mut(mol_id) {
-                mol.velocity += force / mol.mass();
-
-                // Limit max velocity to prevent instability
-                let max_vel = 3.0;
-                let vel_mag = mol.velocity.magnitude();
-                if vel_mag > max_vel {
-                    mol.velocity = mol.velocity.normalize() * max_vel;
-                }
+                mol.velocity.x += self.rng.gen_range(-jitter..jitter);
             }
         }
     }
@@ -677,6 +1177,18 @@ impl SimulationState {
         bond_lines
     }
 
+    // Returns the first molecule whose position or velocity is no longer finite,
+    // the usual symptom of a numerical explosion
+    pub fn first_non_finite(&self) -> Option<MoleculeId> {
+        self.grid
+            .get_all_molecules()
+            .into_iter()
+            .find(|mol| {
+                !(mol.pos.iter().all(|c| c.is_finite()) && mol.velocity.iter().all(|c| c.is_finite()))
+            })
+            .map(|mol| mol.id)
+    }
+
     pub fn get_molecules_by_type(&self, mol_type: &MoleculeType) -> Vec<&Molecule> {
         self.grid.get_all_molecules()
             .into_iter()
@@ -0,0 +1,167 @@
+use crate::species::SPECIES;
+use crate::{SimulationState, Species};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// What the dough looked like at one moment, cheap to read every frame
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub time: f32,
+    pub temperature: f32,
+    pub counts: [usize; SPECIES.len()], // Molecules per species, indexed like `Species`
+    pub water_units: usize,             // Water molecules, clusters counted by their size
+    pub bonds: usize,
+    pub largest_component: usize,
+    pub largest_fraction: f32, // Share of the glutenins in the largest network component
+    pub percolates: bool,
+    pub development: f32, // Gluten development gauge, 0 to 1
+    pub rise: f32,
+    pub volume: f32,
+    pub volume_ratio: f32, // Volume over the first measure
+    pub burps: u64, // Gas burps through the surface so far
+    pub stickiness: f32, // 0 smooth, 1 sticks to everything
+    pub co2_produced: u64, // By the fermentation so far
+    pub co2_escaped: u64, // Through the surface so far
+    pub ethanol_produced: u64,
+    pub ethanol_escaped: u64,
+    pub windowpane: f32, // Windowpane test, 0 tears at once, 1 a thin pane
+    pub poke: Option<f32>, // Share of the last dent taken back
+}
+
+impl MetricsSample {
+    pub fn count(&self, species: Species) -> usize {
+        self.counts[species as usize]
+    }
+
+    // Gliadin and glutenin, bonded or not
+    pub fn proteins(&self) -> usize {
+        self.count(Species::Gliadin)
+            + self.count(Species::ReactiveGlutenin)
+            + self.count(Species::BondedGlutenin)
+    }
+
+    // CO2 the fermentation produced that has not escaped
+    pub fn co2_retained(&self) -> u64 {
+        self.co2_produced.saturating_sub(self.co2_escaped)
+    }
+
+    // Share of the CO2 produced still in the dough, 1 before any fermentation
+    pub fn gas_retention(&self) -> f32 {
+        if self.co2_produced == 0 {
+            return 1.0;
+        }
+        self.co2_retained() as f32 / self.co2_produced as f32
+    }
+}
+
+// Samples the dough every `interval` sim seconds and keeps the last
+// `capacity` samples, so frontends can show counts and curves without
+// walking every molecule each frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsRecorder {
+    pub interval: f32,
+    pub capacity: usize,
+    samples: VecDeque<MetricsSample>,
+}
+
+impl Default for MetricsRecorder {
+    fn default() -> Self {
+        MetricsRecorder::new(1.0, 3600)
+    }
+}
+
+impl MetricsRecorder {
+    pub fn new(interval: f32, capacity: usize) -> Self {
+        MetricsRecorder {
+            interval,
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn latest(&self) -> Option<&MetricsSample> {
+        self.samples.back()
+    }
+
+    // Oldest first
+    pub fn samples(&self) -> impl DoubleEndedIterator<Item = &MetricsSample> + '_ {
+        self.samples.iter()
+    }
+
+    // (time, value) pairs of one metric, oldest first
+    pub fn series(&self, metric: impl Fn(&MetricsSample) -> f32) -> Vec<(f32, f32)> {
+        self.samples
+            .iter()
+            .map(|sample| (sample.time, metric(sample)))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn is_due(&self, time: f32) -> bool {
+        self.latest()
+            .is_none_or(|last| time < last.time || time - last.time >= self.interval)
+    }
+
+    fn push(&mut self, sample: MetricsSample) {
+        // Time went backwards: a new dough or a reloaded one, the old curves no longer apply
+        if self.latest().is_some_and(|last| sample.time < last.time) {
+            self.samples.clear();
+        }
+        // A second sample at the same moment replaces the first
+        if self.latest().is_some_and(|last| sample.time == last.time) {
+            self.samples.pop_back();
+        }
+        while self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+}
+
+impl SimulationState {
+    pub fn sample_metrics(&self) -> MetricsSample {
+        let species = self.grid.species_counts();
+        let network = self.gluten_network();
+        let rise = self.rise_metrics();
+        let development = self.development_of(&network).total;
+        let readiness = self.readiness_of(&network);
+        MetricsSample {
+            time: self.time_elapsed,
+            temperature: self.temperature,
+            counts: species.counts,
+            water_units: species.water_units,
+            bonds: self.bonds.len(),
+            largest_component: network.largest_component(),
+            largest_fraction: network.largest_fraction,
+            percolates: network.percolates(),
+            development,
+            rise: rise.rise,
+            volume: rise.volume,
+            volume_ratio: rise.volume_ratio,
+            burps: self.ledger.burps,
+            stickiness: self.stickiness_of(development).score,
+            co2_produced: self.reactions.co2_produced,
+            co2_escaped: self.ledger.co2_escaped,
+            ethanol_produced: self.reactions.ethanol_produced,
+            ethanol_escaped: self.ledger.ethanol_escaped,
+            windowpane: readiness.windowpane,
+            poke: readiness.poke,
+        }
+    }
+
+    // Sampled after every tick once the interval has passed, and right away
+    // when the recipe, salt or yeast change the dough between ticks
+    pub fn record_metrics(&mut self) {
+        let sample = self.sample_metrics();
+        self.metrics.push(sample);
+    }
+
+    pub(crate) fn record_metrics_if_due(&mut self) {
+        if self.metrics.is_due(self.time_elapsed) {
+            self.record_metrics();
+        }
+    }
+}
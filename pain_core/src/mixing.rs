@@ -0,0 +1,104 @@
+use crate::{SimulationState, StableHashMap};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Cells whose agitation falls below this are forgotten
+const MIN_AGITATION: f32 = 1e-3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixingSettings {
+    pub cell_size: f32,
+    pub decay_time: f32,      // Seconds for the accumulated agitation to fall by 1/e
+    pub untouched_rate: f32,  // Fraction of the full bond rate in dough never worked
+    pub half_saturation: f32, // Agitation giving half of the extra rate
+}
+
+impl Default for MixingSettings {
+    fn default() -> Self {
+        MixingSettings {
+            cell_size: 50.0,
+            decay_time: 900.0,
+            untouched_rate: 0.2,
+            half_saturation: 30.0,
+        }
+    }
+}
+
+// Recent mechanical work (folds, kneading) per coarse cell; disulfide bonds
+// form faster where the dough has been worked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MixingField {
+    cells: StableHashMap<(i32, i32, i32), f32>,
+}
+
+impl MixingField {
+    fn cell_of(pos: Vector3<f32>, cell_size: f32) -> (i32, i32, i32) {
+        (
+            (pos.x / cell_size).floor() as i32,
+            (pos.y / cell_size).floor() as i32,
+            (pos.z / cell_size).floor() as i32,
+        )
+    }
+
+    pub fn agitation_at(&self, pos: Vector3<f32>, settings: &MixingSettings) -> f32 {
+        self.cells
+            .get(&Self::cell_of(pos, settings.cell_size))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    // Spreads `amount` over every cell whose center lies inside the sphere
+    pub fn deposit(&mut self, center: Vector3<f32>, radius: f32, amount: f32, settings: &MixingSettings) {
+        let size = settings.cell_size;
+        let min = Self::cell_of(center - Vector3::repeat(radius), size);
+        let max = Self::cell_of(center + Vector3::repeat(radius), size);
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    let cell_center = Vector3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * size;
+                    if (cell_center - center).magnitude() <= radius.max(size / 2.0) {
+                        *self.cells.entry((x, y, z)).or_default() += amount;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn decay(&mut self, dt: f32, settings: &MixingSettings) {
+        let factor = (-dt / settings.decay_time.max(f32::EPSILON)).exp();
+        self.cells.retain(|_, agitation| {
+            *agitation *= factor;
+            *agitation > MIN_AGITATION
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+impl SimulationState {
+    // How worked the dough is around `pos`, from 0 (untouched) towards 1
+    pub fn worked_fraction_at(&self, pos: Vector3<f32>) -> f32 {
+        let settings = &self.mixing_settings;
+        let agitation = self.mixing.agitation_at(pos, settings);
+        agitation / (agitation + settings.half_saturation.max(f32::EPSILON))
+    }
+
+    // Multiplier on the disulfide formation rate, from `untouched_rate` up to 1.0
+    pub fn mixing_factor_at(&self, pos: Vector3<f32>) -> f32 {
+        let untouched = self.mixing_settings.untouched_rate;
+        untouched + (1.0 - untouched) * self.worked_fraction_at(pos)
+    }
+
+    // Relative stretch at which a bond tears; over-kneaded or over-fermented
+    // (too acidic, digested by the proteases) dough tears sooner
+    pub fn bond_break_strain_at(&self, pos: Vector3<f32>) -> f32 {
+        let weakening = self.config.overmix_weakening.clamp(0.0, 1.0);
+        self.config.bond_break_strain
+            * (1.0 - weakening * self.worked_fraction_at(pos))
+            * self.acid_gluten_strength()
+            * self.protease_gluten_strength()
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{MoleculeId, MoleculeType, SimulationState, StableHashMap};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+const DOUGH_TOP_QUANTILE: f32 = 0.95;
+
+// Dough development as a graph: glutenins are the nodes, disulfide bridges
+// the edges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlutenNetwork {
+    pub glutenin_count: usize,
+    pub component_sizes: Vec<usize>, // Largest first, unbonded glutenins count as 1
+    pub largest_fraction: f32,       // Share of the glutenins in the largest component
+    pub spans: [bool; 3],            // Largest component touches both walls along x, y, z
+}
+
+impl GlutenNetwork {
+    // A component reaching across the dough: the network holds together
+    pub fn percolates(&self) -> bool {
+        self.spans.iter().any(|&spans| spans)
+    }
+
+    pub fn largest_component(&self) -> usize {
+        self.component_sizes.first().copied().unwrap_or(0)
+    }
+}
+
+// Union-find over molecule indices
+pub(crate) struct Components {
+    parent: Vec<usize>,
+}
+
+impl Components {
+    pub(crate) fn new(len: usize) -> Self {
+        Components {
+            parent: (0..len).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut index = index;
+        while self.parent[index] != root {
+            let next = self.parent[index];
+            self.parent[index] = root;
+            index = next;
+        }
+        root
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+impl SimulationState {
+    pub fn gluten_network(&self) -> GlutenNetwork {
+        let mut glutenins: Vec<(MoleculeId, Vector3<f32>)> = self
+            .grid
+            .get_all_molecules()
+            .iter()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Glutenin { .. }))
+            .map(|mol| (mol.id, mol.pos))
+            .collect();
+        glutenins.sort_by_key(|(id, _)| *id);
+        let index: StableHashMap<MoleculeId, usize> = glutenins
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (*id, i))
+            .collect();
+
+        let mut components = Components::new(glutenins.len());
+        for bond in &self.bonds {
+            if let (Some(&a), Some(&b)) = (
+                index.get(&bond.molecule_a_id),
+                index.get(&bond.molecule_b_id),
+            ) {
+                components.union(a, b);
+            }
+        }
+
+        // Size and bounding box of every component
+        let mut extents: StableHashMap<usize, (usize, Vector3<f32>, Vector3<f32>)> =
+            StableHashMap::default();
+        for (i, (_, pos)) in glutenins.iter().enumerate() {
+            let root = components.find(i);
+            let extent = extents.entry(root).or_insert((0, *pos, *pos));
+            extent.0 += 1;
+            extent.1 = extent.1.inf(pos);
+            extent.2 = extent.2.sup(pos);
+        }
+
+        let mut sorted: Vec<(usize, Vector3<f32>, Vector3<f32>)> = extents.into_values().collect();
+        sorted.sort_by_key(|(count, _, _)| std::cmp::Reverse(*count));
+
+        // Close enough to a wall for a bond to reach it
+        let margin = self.config.bond_distance;
+        let size = Vector3::new(self.width, self.height, self.depth);
+        let spans = match sorted.first() {
+            Some((count, min, max)) if *count > 1 => {
+                [0, 1, 2].map(|axis| min[axis] <= margin && max[axis] >= size[axis] - margin)
+            }
+            _ => [false; 3],
+        };
+
+        let glutenin_count = glutenins.len();
+        let component_sizes: Vec<usize> = sorted.iter().map(|(count, _, _)| *count).collect();
+        GlutenNetwork {
+            glutenin_count,
+            largest_fraction: match component_sizes.first() {
+                Some(&largest) => largest as f32 / glutenin_count as f32,
+                None => 0.0,
+            },
+            component_sizes,
+            spans,
+        }
+    }
+    // Top of the dough (+y is up): height under which 95% of the proteins
+    // sit, so a few stray molecules do not count as rise. 0 without flour.
+    pub fn dough_height(&self) -> f32 {
+        let mut heights: Vec<f32> = self
+            .grid
+            .molecules()
+            .filter(|mol| {
+                matches!(
+                    mol.mol_type,
+                    MoleculeType::Gliadin | MoleculeType::Glutenin { .. }
+                )
+            })
+            .map(|mol| mol.pos.y)
+            .collect();
+        if heights.is_empty() {
+            return 0.0;
+        }
+        let rank = ((heights.len() - 1) as f32 * DOUGH_TOP_QUANTILE).round() as usize;
+        *heights.select_nth_unstable_by(rank, f32::total_cmp).1
+    }
+}
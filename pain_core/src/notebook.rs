@@ -0,0 +1,121 @@
+use crate::timeline::format_duration;
+use crate::{MetricsSample, SimulationEvent, SimulationState};
+use serde::{Deserialize, Serialize};
+
+// A remark the baker attached to the run at a given sim time, "dough felt
+// slack here, added a fold"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub time: f32,
+    pub text: String,
+}
+
+// The notes of one dough in time order; saved with the state and recorded in
+// trajectories, so a run can be reviewed like a lab notebook entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LabNotebook {
+    notes: Vec<Note>,
+}
+
+impl LabNotebook {
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    // Kept sorted by time; a note already there is not added twice
+    pub fn add(&mut self, note: Note) {
+        if self.notes.contains(&note) {
+            return;
+        }
+        let index = self.notes.partition_point(|other| other.time <= note.time);
+        self.notes.insert(index, note);
+    }
+
+    pub fn clear(&mut self) {
+        self.notes.clear();
+    }
+}
+
+fn sample_line(sample: &MetricsSample) -> String {
+    format!(
+        "{:.1} °C, {} liaisons, développement {:.0} %, levée {:.1}",
+        sample.temperature,
+        sample.bonds,
+        sample.development * 100.0,
+        sample.rise
+    )
+}
+
+impl SimulationState {
+    // Adds a note at the current sim time; blank text is ignored
+    pub fn annotate(&mut self, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty() {
+            return false;
+        }
+        self.notebook.add(Note {
+            time: self.time_elapsed,
+            text: text.to_string(),
+        });
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::Annotated(text.to_string()),
+        );
+        true
+    }
+
+    // The run as a Markdown notebook entry: the notes with the dough as the
+    // metrics saw it at that moment, then how it ended
+    pub fn notebook_report(&self) -> String {
+        let mut report = format!(
+            "# {}\n\nGraine {}, {} simulées, phase {}\n\n## Notes\n\n",
+            self.recipe_name,
+            self.seed,
+            format_duration(self.time_elapsed.round()),
+            self.phase
+        );
+        if self.notebook.is_empty() {
+            report.push_str("Aucune note.\n");
+        }
+        for note in self.notebook.notes() {
+            report.push_str(&format!(
+                "- **{}** {}",
+                format_duration(note.time.round()),
+                note.text
+            ));
+            // The closest sample at or before the note, when the history still has it
+            if let Some(sample) = self
+                .metrics
+                .samples()
+                .rev()
+                .find(|sample| sample.time <= note.time)
+            {
+                report.push_str(&format!(" ({})", sample_line(sample)));
+            }
+            report.push('\n');
+        }
+        report.push_str(&format!(
+            "\n## Fin\n\n{} : pH {:.2}, {} liaisons, développement {:.0} %, levée {:.1}\n",
+            format_duration(self.time_elapsed.round()),
+            self.dough_ph(),
+            self.bonds.len(),
+            self.gluten_development().percent(),
+            self.rise()
+        ));
+        if let Some(record) = self.collapse.record {
+            report.push_str(&format!(
+                "Pâte effondrée à {}\n",
+                format_duration(record.time.round())
+            ));
+        }
+        report
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{Bond, Molecule, MoleculeId, MoleculeType, Region, SimulationState, StableHashSet};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObservationCounters {
+    pub co2_produced: u64,
+    pub ethanol_produced: u64,
+    pub bonds_formed: u64,
+    pub molecules_entered: u64,
+    pub molecules_exited: u64,
+}
+
+// Named axis-aligned box that counts what happens inside it, so localized
+// questions (center vs edge, top vs bottom) can be answered with numbers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservationVolume {
+    pub name: String,
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+    pub counters: ObservationCounters,
+    inside: StableHashSet<MoleculeId>, // Molecules inside at the last update
+}
+
+impl ObservationVolume {
+    pub fn new(name: &str, min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        ObservationVolume {
+            name: name.to_string(),
+            min: min.inf(&max),
+            max: max.sup(&min),
+            counters: ObservationCounters::default(),
+            inside: StableHashSet::default(),
+        }
+    }
+
+    pub fn contains(&self, pos: Vector3<f32>) -> bool {
+        self.region().contains(pos)
+    }
+
+    pub fn region(&self) -> Region {
+        Region::Aabb {
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    // Number of molecules currently inside
+    pub fn population(&self) -> usize {
+        self.inside.len()
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.counters = ObservationCounters::default();
+    }
+}
+
+impl SimulationState {
+    pub fn add_observation_volume(&mut self, name: &str, min: Vector3<f32>, max: Vector3<f32>) {
+        let mut volume = ObservationVolume::new(name, min, max);
+        // Molecules already there when the box is placed do not count as entering
+        volume.inside = self
+            .grid
+            .get_all_molecules()
+            .iter()
+            .filter(|mol| volume.contains(mol.pos))
+            .map(|mol| mol.id)
+            .collect();
+        self.observations.push(volume);
+    }
+
+    pub fn observation_volume(&self, name: &str) -> Option<&ObservationVolume> {
+        self.observations.iter().find(|volume| volume.name == name)
+    }
+
+    pub(crate) fn update_observation_volumes(&mut self) {
+        if self.observations.is_empty() {
+            return;
+        }
+        for volume in &mut self.observations {
+            let now_inside: StableHashSet<MoleculeId> = self
+                .grid
+                .query_region(&volume.region())
+                .map(|mol| mol.id)
+                .collect();
+
+            volume.counters.molecules_entered += now_inside.difference(&volume.inside).count() as u64;
+            // Molecules consumed inside the box (sugar eaten by yeast) also leave it
+            volume.counters.molecules_exited += volume.inside.difference(&now_inside).count() as u64;
+            volume.inside = now_inside;
+        }
+    }
+
+    // Called for molecules created by reactions, before they are inserted
+    pub(crate) fn observe_produced(&mut self, molecule: &Molecule) {
+        for volume in &mut self.observations {
+            if volume.contains(molecule.pos) {
+                match molecule.mol_type {
+                    MoleculeType::CO2 => volume.counters.co2_produced += 1,
+                    MoleculeType::Ethanol => volume.counters.ethanol_produced += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // A bond counts for a volume when its midpoint lies inside
+    pub(crate) fn observe_bond(&mut self, bond: &Bond) {
+        if self.observations.is_empty() {
+            return;
+        }
+        let (Some(mol_a), Some(mol_b)) = (
+            self.grid.get_molecule(bond.molecule_a_id),
+            self.grid.get_molecule(bond.molecule_b_id),
+        ) else {
+            return;
+        };
+        let midpoint = (mol_a.pos + mol_b.pos) / 2.0;
+
+        for volume in &mut self.observations {
+            if volume.contains(midpoint) {
+                volume.counters.bonds_formed += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::snapshot::decode_state;
+use crate::trajectory::{apply_record, Trajectory, TrajectoryRecord};
+use crate::{Molecule, MoleculeId, SimulationState, Species, StableHashMap};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Plain text particle formats for ParaView and OVITO:
+//
+// - extended XYZ: every frame appended to one file, one line per molecule
+//   with its species name, position, id and species code; the comment line
+//   holds the box (Lattice), time and temperature. OVITO rebuilds bonds from
+//   distances, the disulfide bridges themselves are only in VTK.
+// - legacy VTK polydata: one file per frame (name_000042.vtk, which ParaView
+//   opens as a time series), molecules as points, bonds as lines, species
+//   and id as point data and the time as TIME field data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleFormat {
+    ExtendedXyz,
+    Vtk,
+}
+
+impl ParticleFormat {
+    // From the extension of the output path: .xyz / .extxyz or .vtk
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "xyz" | "extxyz" => Some(ParticleFormat::ExtendedXyz),
+            "vtk" => Some(ParticleFormat::Vtk),
+            _ => None,
+        }
+    }
+}
+
+fn sorted_molecules(state: &SimulationState) -> Vec<&Molecule> {
+    let mut molecules: Vec<&Molecule> = state.grid.molecules().collect();
+    molecules.sort_by_key(|mol| mol.id);
+    molecules
+}
+
+fn species_label(species: Species) -> String {
+    // Names without spaces or accents, XYZ columns are whitespace separated
+    format!("{:?}", species)
+}
+
+pub fn write_xyz_frame<W: Write>(writer: &mut W, state: &SimulationState) -> io::Result<()> {
+    let molecules = sorted_molecules(state);
+    writeln!(writer, "{}", molecules.len())?;
+    writeln!(
+        writer,
+        "Lattice=\"{} 0 0 0 {} 0 0 0 {}\" Properties=species:S:1:pos:R:3:id:I:1:type:I:1 \
+         Time={} Temperature={} pbc=\"F F F\"",
+        state.width, state.height, state.depth, state.time_elapsed, state.temperature
+    )?;
+    for mol in molecules {
+        let species = mol.mol_type.species();
+        writeln!(
+            writer,
+            "{} {} {} {} {} {}",
+            species_label(species),
+            mol.pos.x,
+            mol.pos.y,
+            mol.pos.z,
+            mol.id.to_bits(),
+            species as u8
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_vtk_frame<W: Write>(writer: &mut W, state: &SimulationState) -> io::Result<()> {
+    let molecules = sorted_molecules(state);
+    let index: StableHashMap<MoleculeId, usize> = molecules
+        .iter()
+        .enumerate()
+        .map(|(i, mol)| (mol.id, i))
+        .collect();
+    let bonds: Vec<(usize, usize)> = state
+        .bonds
+        .iter()
+        .filter_map(|bond| {
+            Some((
+                *index.get(&bond.molecule_a_id)?,
+                *index.get(&bond.molecule_b_id)?,
+            ))
+        })
+        .collect();
+
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(
+        writer,
+        "{} t={} s",
+        state.recipe_name.replace('\n', " "),
+        state.time_elapsed
+    )?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET POLYDATA")?;
+    writeln!(writer, "FIELD FieldData 2")?;
+    writeln!(writer, "TIME 1 1 double")?;
+    writeln!(writer, "{}", state.time_elapsed)?;
+    writeln!(writer, "TEMPERATURE 1 1 double")?;
+    writeln!(writer, "{}", state.temperature)?;
+
+    writeln!(writer, "POINTS {} float", molecules.len())?;
+    for mol in &molecules {
+        writeln!(writer, "{} {} {}", mol.pos.x, mol.pos.y, mol.pos.z)?;
+    }
+    writeln!(writer, "LINES {} {}", bonds.len(), bonds.len() * 3)?;
+    for (a, b) in &bonds {
+        writeln!(writer, "2 {} {}", a, b)?;
+    }
+
+    writeln!(writer, "POINT_DATA {}", molecules.len())?;
+    writeln!(writer, "SCALARS species int 1")?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for mol in &molecules {
+        writeln!(writer, "{}", mol.mol_type.species() as u8)?;
+    }
+    writeln!(writer, "SCALARS id unsigned_long 1")?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for mol in &molecules {
+        writeln!(writer, "{}", mol.id.to_bits())?;
+    }
+    Ok(())
+}
+
+// name.vtk -> name_000042.vtk
+fn numbered_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_{:06}.vtk", stem, index))
+}
+
+// Writes particle frames as they come, to one XYZ file or numbered VTK files
+pub struct ParticleExporter {
+    path: PathBuf,
+    xyz: Option<BufWriter<fs::File>>,
+    frames: usize,
+}
+
+impl ParticleExporter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let format = ParticleFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: expected a .xyz or .vtk file", path.display()),
+            )
+        })?;
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let xyz = match format {
+            ParticleFormat::ExtendedXyz => Some(BufWriter::new(fs::File::create(path)?)),
+            ParticleFormat::Vtk => None,
+        };
+        Ok(ParticleExporter {
+            path: path.to_path_buf(),
+            xyz,
+            frames: 0,
+        })
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.frames
+    }
+
+    pub fn write_frame(&mut self, state: &SimulationState) -> io::Result<()> {
+        match &mut self.xyz {
+            Some(writer) => write_xyz_frame(writer, state)?,
+            None => {
+                let path = numbered_path(&self.path, self.frames);
+                let mut writer = BufWriter::new(fs::File::create(path)?);
+                write_vtk_frame(&mut writer, state)?;
+                writer.flush()?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<usize> {
+        if let Some(writer) = &mut self.xyz {
+            writer.flush()?;
+        }
+        Ok(self.frames)
+    }
+}
+
+// Replays the trajectory and writes every recorded frame, returns the frame count
+pub fn export_trajectory_particles(trajectory: &Trajectory, path: &Path) -> io::Result<usize> {
+    let records = trajectory.records();
+    let Some(TrajectoryRecord::Keyframe { state: bytes, .. }) = records.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "trajectory does not start with a savepoint",
+        ));
+    };
+    let mut state = decode_state(bytes)?;
+    let mut exporter = ParticleExporter::create(path)?;
+    for record in records {
+        apply_record(&mut state, record)?;
+        if record.is_frame() {
+            exporter.write_frame(&state)?;
+        }
+    }
+    exporter.finish()
+}
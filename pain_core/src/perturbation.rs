@@ -0,0 +1,172 @@
+use crate::{SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use rand::seq::index::sample;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Controlled disturbances used to probe how well the dough (and the physics
+// model) recovers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Perturbation {
+    VelocityNoise { magnitude: f32 },
+    RemoveBonds { fraction: f32 },
+    TemperatureSpike { delta: f32, duration: f32 },
+}
+
+// Pending restoration of the temperature after a spike
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TemperatureSpike {
+    pub baseline: f32,
+    pub ends_at: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecoveryMetrics {
+    pub kinetic_energy: f32,
+    pub bond_count: usize,
+    pub temperature: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecoveryReport {
+    pub perturbation: Perturbation,
+    pub baseline: RecoveryMetrics,
+    pub after_perturbation: RecoveryMetrics,
+    pub peak_kinetic_energy: f32,
+    pub recovery_time: Option<f32>, // Sim seconds until energy settles back near baseline
+    pub bond_recovery: f32,         // Fraction of the lost bonds formed again by the end
+    pub final_metrics: RecoveryMetrics,
+}
+
+impl SimulationState {
+    pub fn kinetic_energy(&self) -> f32 {
+        self.grid
+            .get_all_molecules()
+            .iter()
+            .map(|mol| 0.5 * mol.mass() * mol.velocity.magnitude_squared())
+            .sum()
+    }
+
+    pub fn recovery_metrics(&self) -> RecoveryMetrics {
+        RecoveryMetrics {
+            kinetic_energy: self.kinetic_energy(),
+            bond_count: self.bonds.len(),
+            temperature: self.temperature,
+        }
+    }
+
+    // Applies the perturbation and returns how many molecules or bonds it touched
+    pub fn apply_perturbation(&mut self, perturbation: &Perturbation) -> usize {
+        let affected = match *perturbation {
+            Perturbation::VelocityNoise { magnitude } if magnitude > 0.0 && magnitude.is_finite() => {
+                let rng = &mut self.rng;
+                let molecules = self.grid.get_all_molecules_mut();
+                let count = molecules.len();
+                for mol in molecules {
+                    mol.velocity += Vector3::new(
+                        rng.gen_range(-magnitude..=magnitude),
+                        rng.gen_range(-magnitude..=magnitude),
+                        rng.gen_range(-magnitude..=magnitude),
+                    );
+                }
+                count
+            }
+            // No range to draw from for a negative, NaN or infinite magnitude
+            Perturbation::VelocityNoise { .. } => 0,
+            Perturbation::RemoveBonds { fraction } => {
+                let to_remove = (self.bonds.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+                // Bonds picked at random, the others keep their order (and colors)
+                let mut keep = vec![true; self.bonds.len()];
+                for index in sample(&mut self.rng, keep.len(), to_remove) {
+                    keep[index] = false;
+                }
+                let removed = self.take_bonds(&keep);
+
+                // Torn bonds free their thiols so the network can heal
+                for bond in &removed {
+                    self.release_bond(bond);
+                }
+                removed.len()
+            }
+            Perturbation::TemperatureSpike { delta, duration } => {
+                let baseline = match self.temperature_spike {
+                    Some(spike) => spike.baseline,
+                    None => self.temperature,
+                };
+                self.temperature = baseline + delta;
+                self.temperature_spike = Some(TemperatureSpike {
+                    baseline,
+                    ends_at: self.time_elapsed + duration,
+                });
+                0
+            }
+        };
+
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::Perturbed(perturbation.clone()),
+        );
+        affected
+    }
+
+    // Scriptable robustness trial: measures a baseline, perturbs, then ticks for
+    // up to `max_duration` sim seconds tracking how the dough settles.
+    // `tolerance` is the relative kinetic energy excess still considered recovered.
+    pub fn run_perturbation_trial(
+        &mut self,
+        perturbation: Perturbation,
+        dt: f32,
+        max_duration: f32,
+        tolerance: f32,
+    ) -> RecoveryReport {
+        let baseline = self.recovery_metrics();
+        self.apply_perturbation(&perturbation);
+        let after_perturbation = self.recovery_metrics();
+
+        let start = self.time_elapsed;
+        let mut peak_kinetic_energy = after_perturbation.kinetic_energy;
+        let mut recovery_time = None;
+        let settled_energy = baseline.kinetic_energy * (1.0 + tolerance);
+        // A tick that does not move the clock on would never reach the end
+        let advances = dt > 0.0 && dt.is_finite();
+
+        while advances && self.time_elapsed - start < max_duration {
+            self.tick(dt);
+            let energy = self.kinetic_energy();
+            peak_kinetic_energy = peak_kinetic_energy.max(energy);
+            if recovery_time.is_none() && energy <= settled_energy && self.temperature_spike.is_none() {
+                recovery_time = Some(self.time_elapsed - start);
+            }
+        }
+
+        let final_metrics = self.recovery_metrics();
+        let lost_bonds = baseline.bond_count.saturating_sub(after_perturbation.bond_count);
+        let bond_recovery = if lost_bonds == 0 {
+            1.0
+        } else {
+            let regained = final_metrics
+                .bond_count
+                .saturating_sub(after_perturbation.bond_count);
+            (regained as f32 / lost_bonds as f32).min(1.0)
+        };
+
+        RecoveryReport {
+            perturbation,
+            baseline,
+            after_perturbation,
+            peak_kinetic_energy,
+            recovery_time,
+            bond_recovery,
+            final_metrics,
+        }
+    }
+
+    pub(crate) fn update_temperature_spike(&mut self) {
+        if let Some(spike) = self.temperature_spike {
+            if self.time_elapsed >= spike.ends_at {
+                self.temperature = spike.baseline;
+                self.temperature_spike = None;
+            }
+        }
+    }
+}
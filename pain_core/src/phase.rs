@@ -0,0 +1,141 @@
+use crate::{SimulationEvent, SimulationState, WorkSurface};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Where the dough is in the bread-making process. Phases only move forward,
+// one at a time, except that a dough without autolyse starts at Mix.
+// Ordered like the process
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum DoughPhase {
+    // Flour and water only, proteins hydrate
+    #[default]
+    Autolyse,
+    // Salt and/or yeast being worked in
+    Mix,
+    // First rise, folds strengthen the network
+    BulkFerment,
+    Shape,
+    // Final rise of the shaped loaf, covered
+    Proof,
+    Bake,
+    // Crumb set, no more reactions
+    Cooled,
+}
+
+impl DoughPhase {
+    pub fn all() -> [DoughPhase; 7] {
+        [
+            DoughPhase::Autolyse,
+            DoughPhase::Mix,
+            DoughPhase::BulkFerment,
+            DoughPhase::Shape,
+            DoughPhase::Proof,
+            DoughPhase::Bake,
+            DoughPhase::Cooled,
+        ]
+    }
+
+    pub fn next(&self) -> Option<DoughPhase> {
+        let all = DoughPhase::all();
+        let index = all.iter().position(|phase| phase == self)?;
+        all.get(index + 1).copied()
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            DoughPhase::Autolyse => "Autolyse",
+            DoughPhase::Mix => "Pétrissage",
+            DoughPhase::BulkFerment => "Pointage",
+            DoughPhase::Shape => "Façonnage",
+            DoughPhase::Proof => "Apprêt",
+            DoughPhase::Bake => "Cuisson",
+            DoughPhase::Cooled => "Refroidi",
+        }
+    }
+
+    // Yeast and amylase work from the mix until the loaf has cooled
+    pub fn ferments(&self) -> bool {
+        !matches!(self, DoughPhase::Autolyse | DoughPhase::Cooled)
+    }
+
+    // Flour enzymes already work during the autolyse
+    pub fn enzymes_active(&self) -> bool {
+        !matches!(self, DoughPhase::Cooled)
+    }
+
+    // New disulfide bridges stop once the crumb is set
+    pub fn forms_bonds(&self) -> bool {
+        !matches!(self, DoughPhase::Cooled)
+    }
+
+    // The shaped loaf proofs covered, and a cooled loaf no longer dries
+    pub fn loses_moisture(&self) -> bool {
+        !matches!(self, DoughPhase::Proof | DoughPhase::Cooled)
+    }
+}
+
+impl fmt::Display for DoughPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl SimulationState {
+    pub fn can_transition_to(&self, phase: DoughPhase) -> bool {
+        self.phase.next() == Some(phase)
+    }
+
+    // Moves to `phase` if it directly follows the current one; returns whether
+    // the transition happened. The bake starts at the default oven setting.
+    pub fn transition_to(&mut self, phase: DoughPhase) -> bool {
+        if !self.can_transition_to(phase) {
+            return false;
+        }
+        if phase == DoughPhase::Bake {
+            return self.start_bake(self.bake.oven_temperature);
+        }
+        self.set_phase(phase);
+        true
+    }
+
+    pub fn advance_phase(&mut self) -> Option<DoughPhase> {
+        let next = self.phase.next()?;
+        self.transition_to(next).then_some(next)
+    }
+
+    // Seconds spent in the current phase
+    pub fn phase_elapsed(&self) -> f32 {
+        self.time_elapsed - self.phase_started
+    }
+
+    pub(crate) fn set_phase(&mut self, phase: DoughPhase) {
+        if phase == self.phase {
+            return;
+        }
+        let from = self.phase;
+        // Shaping happens on the bench
+        if phase == DoughPhase::Shape {
+            self.tools.surface.get_or_insert_with(WorkSurface::default);
+        }
+        self.phase = phase;
+        self.phase_started = self.time_elapsed;
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::PhaseChanged { from, to: phase },
+        );
+    }
+
+    // Adding salt or yeast ends the autolyse; once both are in, the bulk
+    // fermentation starts
+    pub(crate) fn update_phase_after_addition(&mut self) {
+        if self.phase == DoughPhase::Autolyse {
+            self.set_phase(DoughPhase::Mix);
+        }
+        if self.phase == DoughPhase::Mix && self.salt_added && self.yeast_added {
+            self.set_phase(DoughPhase::BulkFerment);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::SimulationState;
+use std::fmt;
+
+// Custom models run inside the tick without touching the core crate (a
+// protease cutting gluten, an extra force field...). Every hook gets the
+// whole state and does nothing by default. Plugins are not saved with the
+// state: register them again after loading a save.
+pub trait SimulationPlugin: PluginClone + Send + Sync {
+    fn name(&self) -> &str;
+
+    // Start of the tick, before the scheduled actions and the environment
+    fn before_tick(&mut self, _state: &mut SimulationState, _dt: f32) {}
+
+    // Before the positions are integrated, so velocity changes move the
+    // molecules this very tick
+    fn apply_forces(&mut self, _state: &mut SimulationState, _dt: f32) {}
+
+    // After the reaction rules. Molecules added or removed here are counted
+    // as reactions by the mass ledger, anywhere else they unbalance it
+    fn react(&mut self, _state: &mut SimulationState, _dt: f32) {}
+
+    // End of the tick, after the bond solver and the checks
+    fn after_tick(&mut self, _state: &mut SimulationState, _dt: f32) {}
+}
+
+// Lets a cloned state carry its own copy of each plugin; implemented for
+// every plugin that is `Clone`
+pub trait PluginClone {
+    fn clone_box(&self) -> Box<dyn SimulationPlugin>;
+}
+
+impl<T: SimulationPlugin + Clone + 'static> PluginClone for T {
+    fn clone_box(&self) -> Box<dyn SimulationPlugin> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PluginHook {
+    BeforeTick,
+    ApplyForces,
+    React,
+    AfterTick,
+}
+
+// Plugins in the order they were registered, which is the order they run in
+#[derive(Default)]
+pub struct PluginSet {
+    plugins: Vec<Box<dyn SimulationPlugin>>,
+}
+
+impl Clone for PluginSet {
+    fn clone(&self) -> Self {
+        PluginSet {
+            plugins: self
+                .plugins
+                .iter()
+                .map(|plugin| plugin.clone_box())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for PluginSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl PluginSet {
+    pub fn push(&mut self, plugin: Box<dyn SimulationPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.plugins.iter().map(|plugin| plugin.name())
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+impl SimulationState {
+    pub fn add_plugin(&mut self, plugin: impl SimulationPlugin + 'static) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    // Drops the plugins with that name, false if there was none
+    pub fn remove_plugin(&mut self, name: &str) -> bool {
+        let before = self.plugins.len();
+        self.plugins.plugins.retain(|plugin| plugin.name() != name);
+        self.plugins.len() != before
+    }
+
+    pub fn plugins(&self) -> &PluginSet {
+        &self.plugins
+    }
+
+    // Replaces every registered plugin, e.g. after loading a save
+    pub fn set_plugins(&mut self, plugins: PluginSet) {
+        self.plugins = plugins;
+    }
+
+    pub(crate) fn run_plugins(&mut self, hook: PluginHook, dt: f32) {
+        if self.plugins.is_empty() {
+            return;
+        }
+        // Out of the state while they run, since each hook borrows it whole
+        let mut plugins = std::mem::take(&mut self.plugins);
+        for plugin in &mut plugins.plugins {
+            match hook {
+                PluginHook::BeforeTick => plugin.before_tick(self, dt),
+                PluginHook::ApplyForces => plugin.apply_forces(self, dt),
+                PluginHook::React => plugin.react(self, dt),
+                PluginHook::AfterTick => plugin.after_tick(self, dt),
+            }
+        }
+        // Plugins registered by a hook run from the next one on
+        plugins
+            .plugins
+            .append(&mut std::mem::take(&mut self.plugins).plugins);
+        self.plugins = plugins;
+    }
+}
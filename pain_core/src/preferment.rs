@@ -0,0 +1,180 @@
+use crate::{
+    Bond, BudgetSettings, DoughPhase, Molecule, Recipe, SimAction, SimulationBuilder,
+    SimulationEvent, SimulationState, StableHashMap,
+};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+// Tick of the preferment: hours of a slow ferment, no baker working it
+const PREFERMENT_STEP: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrefermentKind {
+    Poolish, // As much water as flour, runny
+    Biga,    // Stiff, about half as much water as flour
+}
+
+// Part of the flour mixed the day before with water and a trace of yeast and
+// left to ferment on its own, then mixed into the dough: it brings a yeast
+// population already at work, acids, ethanol and some gluten network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preferment {
+    pub kind: PrefermentKind,
+    pub flour_share: f32, // Of the flour of the whole dough
+    pub hydration: f32,   // Water / flour of the preferment
+    pub yeast: f32,       // Levain / flour of the preferment, a trace
+    pub hours: f32,
+    pub temperature: f32, // °C
+}
+
+impl Preferment {
+    // A third of the flour, overnight at a cool room temperature
+    pub fn poolish() -> Self {
+        Preferment {
+            kind: PrefermentKind::Poolish,
+            flour_share: 0.3,
+            hydration: 1.0,
+            yeast: 0.01,
+            hours: 12.0,
+            temperature: 20.0,
+        }
+    }
+
+    // Half the flour, for the better part of a day in a cool place
+    pub fn biga() -> Self {
+        Preferment {
+            kind: PrefermentKind::Biga,
+            flour_share: 0.5,
+            hydration: 0.5,
+            yeast: 0.01,
+            hours: 16.0,
+            temperature: 18.0,
+        }
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            PrefermentKind::Poolish => "poolish",
+            PrefermentKind::Biga => "biga",
+        }
+    }
+
+    fn share(&self) -> f32 {
+        self.flour_share.clamp(0.0, 1.0)
+    }
+
+    // The preferment's own recipe: the flour of `recipe`, its water and
+    // yeast, no salt and no autolyse
+    pub fn recipe(&self, recipe: &Recipe) -> Recipe {
+        Recipe {
+            name: self.label().to_string(),
+            hydration: self.hydration,
+            salt: 0.0,
+            yeast: self.yeast,
+            autolyse_time: 0.0,
+            temperature: self.temperature,
+            ..recipe.clone()
+        }
+    }
+
+    // Mixes the preferment for a dough of `recipe` like `dough`'s (same box,
+    // config and budget, its share of the molecules) and lets it ferment
+    pub fn ferment(&self, recipe: &Recipe, dough: &SimulationState, seed: u64) -> SimulationState {
+        let budget = BudgetSettings {
+            resolution: dough.budget.resolution * self.share(),
+            max_molecules: (dough.budget.max_molecules as f32 * self.share()).round() as usize,
+        };
+        let mut preferment = SimulationBuilder::new()
+            .size(dough.width, dough.height, dough.depth)
+            .seed(seed)
+            .recipe(self.recipe(recipe))
+            .config(dough.config.clone())
+            .budget(budget)
+            .with_yeast()
+            .build();
+        preferment.apply_action(&SimAction::AdvanceTo(DoughPhase::BulkFerment));
+        let ticks = (self.hours.max(0.0) * SECONDS_PER_HOUR / PREFERMENT_STEP).round() as usize;
+        for _ in 0..ticks {
+            preferment.tick(PREFERMENT_STEP);
+        }
+        preferment
+    }
+}
+
+impl SimulationState {
+    // Starts the dough of `recipe` with part of its flour as `preferment`:
+    // the preferment ferments first, the rest of the flour and the water it
+    // did not take are mixed, and the preferment is poured in. The salt and
+    // the levain still to add go in later as usual; the preferment's yeast
+    // starts working in the dough once they have.
+    pub fn initialize_with_preferment(&mut self, recipe: Recipe, preferment: &Preferment) {
+        let share = preferment.share();
+        let seed = self.rng.gen();
+        let fermented = preferment.ferment(&recipe, self, seed);
+
+        let budget = self.budget.clone();
+        let rest = 1.0 - share;
+        self.budget.resolution *= rest;
+        let hydration = (recipe.hydration - preferment.hydration * share).max(0.0);
+        self.initialize_recipe(Recipe {
+            hydration: hydration / rest.max(f32::EPSILON),
+            ..recipe.clone()
+        });
+        self.budget = budget;
+        // The ratios of the whole dough from here on
+        self.recipe_hydration = recipe.hydration;
+        self.recipe_yeast = (recipe.yeast - preferment.yeast * share).max(0.0);
+        self.pour_preferment(&fermented, preferment);
+    }
+
+    // Mixes a fermented preferment into the dough, see `pour`
+    pub fn pour_preferment(&mut self, fermented: &SimulationState, preferment: &Preferment) {
+        // Part of the recipe, not an addition
+        self.ledger.initial_mass += self.pour(fermented);
+        self.events.push(
+            self.time_elapsed,
+            SimulationEvent::PrefermentPoured {
+                kind: preferment.kind,
+                hours: preferment.hours,
+            },
+        );
+        self.record_metrics();
+    }
+
+    // Mixes another dough into this one: its molecules at the same place in
+    // the box, its gluten bonds, its acid. Returns the mass poured, for the
+    // caller to enter in the ledger.
+    pub(crate) fn pour(&mut self, other: &SimulationState) -> f64 {
+        let mass_before = self.total_mass();
+        let scale = Vector3::new(
+            self.width / other.width.max(f32::EPSILON),
+            self.height / other.height.max(f32::EPSILON),
+            self.depth / other.depth.max(f32::EPSILON),
+        );
+        let mut poured = StableHashMap::default();
+        for mol in other.grid.molecules() {
+            let pos = mol.pos.component_mul(&scale);
+            let id = self
+                .grid
+                .insert(Molecule::new(mol.mol_type.clone(), pos, mol.velocity));
+            poured.insert(mol.id, id);
+        }
+        for bond in &other.bonds {
+            if let (Some(&a), Some(&b)) = (
+                poured.get(&bond.molecule_a_id),
+                poured.get(&bond.molecule_b_id),
+            ) {
+                self.push_bond(Bond {
+                    molecule_a_id: a,
+                    molecule_b_id: b,
+                    ..bond.clone()
+                });
+            }
+        }
+        self.add_acid(other.acidity.acetic as f32, other.acidity.lactic as f32);
+        self.total_mass() - mass_before
+    }
+}
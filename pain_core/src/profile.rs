@@ -0,0 +1,83 @@
+use crate::summary::TICK_STAGES;
+use crate::{DoughPhase, TickStage, TickSummary};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// Root frame of the folded stacks
+const ROOT_FRAME: &str = "simulation";
+
+// Wall time of the ticks added up per dough phase and tick stage, to see
+// where a run spends its time (chemistry, integration, grid, bond solver...)
+// and in which part of the bake
+#[derive(Debug, Clone, Default)]
+pub struct TickProfiler {
+    phases: Vec<(DoughPhase, [Duration; TICK_STAGES])>, // In order of first appearance
+    ticks: u64,
+}
+
+impl TickProfiler {
+    pub fn new() -> Self {
+        TickProfiler::default()
+    }
+
+    // Charges a tick to the phase the dough was in at its end
+    pub fn record(&mut self, phase: DoughPhase, summary: &TickSummary) {
+        let index = match self.phases.iter().position(|(seen, _)| *seen == phase) {
+            Some(index) => index,
+            None => {
+                self.phases.push((phase, [Duration::ZERO; TICK_STAGES]));
+                self.phases.len() - 1
+            }
+        };
+        let times = &mut self.phases[index].1;
+        for (total, time) in times.iter_mut().zip(summary.stage_times) {
+            *total += time;
+        }
+        self.ticks += 1;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn stage_time(&self, phase: DoughPhase, stage: TickStage) -> Duration {
+        self.phases
+            .iter()
+            .find(|(seen, _)| *seen == phase)
+            .map_or(Duration::ZERO, |(_, times)| times[stage as usize])
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().flat_map(|(_, times)| times.iter()).sum()
+    }
+
+    // Folded stacks as read by inferno and flamegraph.pl, one line per phase
+    // and stage with the time in microseconds:
+    //   simulation;Pointage;chimie 123456
+    pub fn write_folded<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (phase, times) in &self.phases {
+            for stage in TickStage::all() {
+                let micros = times[stage as usize].as_micros();
+                if micros > 0 {
+                    writeln!(
+                        writer,
+                        "{};{};{} {}",
+                        ROOT_FRAME,
+                        phase.label(),
+                        stage.label(),
+                        micros
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save_folded(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        self.write_folded(&mut file)?;
+        file.flush()
+    }
+}
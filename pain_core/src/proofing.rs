@@ -0,0 +1,175 @@
+use crate::{DoughPhase, SimulationEvent, SimulationState, Species};
+use serde::{Deserialize, Serialize};
+
+// When the rising dough is worth a look from the baker: ready to shape once
+// it passes the windowpane and poke tests during the bulk ferment, over-proofed
+// once the yeast has run out of sugar, the gas leaks out much faster than the
+// dough once held it or the network it built falls apart. The collapse itself is caught by the
+// collapse monitor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofSettings {
+    pub window: f32,          // Sim seconds between two checks
+    pub sugar_exhausted: f32, // Share of the most sugar the dough held, left
+    pub min_co2: u64,         // CO2 made between two measures of the escape rate
+    pub leak_rise: f32,       // Escape rate above the lowest one measured
+    pub network_left: f32,    // Share of the most bonds, weakened by the proteases
+    pub min_bonds: usize,     // A network worth watching
+}
+
+impl Default for ProofSettings {
+    fn default() -> Self {
+        ProofSettings {
+            window: 60.0,
+            sugar_exhausted: 0.1,
+            min_co2: 20,
+            leak_rise: 0.3,
+            network_left: 0.5,
+            min_bonds: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofState {
+    #[default]
+    Underproofed,
+    Ready,
+    Overproofed,
+    Collapsed,
+}
+
+impl ProofState {
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProofState::Underproofed => "pas encore prête",
+            ProofState::Ready => "prête à façonner",
+            ProofState::Overproofed => "trop poussée",
+            ProofState::Collapsed => "effondrée",
+        }
+    }
+}
+
+// What an over-proofed dough looked like when it was caught
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverproofSigns {
+    pub time: f32,
+    pub sugar_left: f32,   // Share of the most sugar the dough held
+    pub escape_rate: f32,  // Share of the CO2 made since the last measure that escaped
+    pub network_left: f32, // Share of the most bonds, weakened by the proteases
+}
+
+// CO2 counts when the escape rate was last measured
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct GasMark {
+    co2_produced: u64,
+    co2_escaped: u64,
+}
+
+// Follows the ferment window by window; each warning is sent once
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProofMonitor {
+    checked_at: Option<f32>,
+    gas_mark: Option<GasMark>,
+    escape_rate: f32,      // Last measured
+    tightest: Option<f32>, // Lowest escape rate measured
+    peak_sugar: usize,
+    peak_bonds: usize,
+    pub ready_at: Option<f32>,
+    pub overproof: Option<OverproofSigns>,
+}
+
+impl SimulationState {
+    pub fn proof_state(&self) -> ProofState {
+        if self.collapse.record.is_some() {
+            ProofState::Collapsed
+        } else if self.proofing.overproof.is_some() {
+            ProofState::Overproofed
+        } else if self.proofing.ready_at.is_some() {
+            ProofState::Ready
+        } else {
+            ProofState::Underproofed
+        }
+    }
+
+    pub(crate) fn watch_proofing(&mut self) {
+        let settings = &self.proof_settings;
+        if !self.yeast_added
+            || !(DoughPhase::Mix..=DoughPhase::Proof).contains(&self.phase)
+            || self
+                .proofing
+                .checked_at
+                .is_some_and(|time| self.time_elapsed - time < settings.window)
+        {
+            return;
+        }
+        let sugar = self.grid.species_counts().count(Species::Sugar);
+        let now = GasMark {
+            co2_produced: self.reactions.co2_produced,
+            co2_escaped: self.ledger.co2_escaped,
+        };
+        let min_co2 = settings.min_co2.max(1);
+        let monitor = &mut self.proofing;
+        monitor.checked_at = Some(self.time_elapsed);
+        monitor.peak_sugar = monitor.peak_sugar.max(sugar);
+        monitor.peak_bonds = monitor.peak_bonds.max(self.bonds.len());
+        let mark = *monitor.gas_mark.get_or_insert(now);
+        let produced = now.co2_produced - mark.co2_produced;
+        if produced >= min_co2 {
+            let escaped = now.co2_escaped.saturating_sub(mark.co2_escaped);
+            monitor.escape_rate = escaped as f32 / produced as f32;
+            monitor.tightest = Some(monitor.tightest.map_or(monitor.escape_rate, |tightest| {
+                tightest.min(monitor.escape_rate)
+            }));
+            monitor.gas_mark = Some(now);
+        }
+
+        if monitor.ready_at.is_none()
+            && monitor.overproof.is_none()
+            && self.phase == DoughPhase::BulkFerment
+            && now.co2_produced > 0
+        {
+            let readiness = self.dough_readiness();
+            if readiness.ready {
+                self.proofing.ready_at = Some(self.time_elapsed);
+                self.events.push(
+                    self.time_elapsed,
+                    SimulationEvent::ReadyToShape {
+                        windowpane: readiness.windowpane,
+                        poke: readiness.poke.unwrap_or_default(),
+                    },
+                );
+            }
+        }
+
+        let settings = &self.proof_settings;
+        let monitor = &self.proofing;
+        if monitor.overproof.is_some() || monitor.peak_bonds < settings.min_bonds.max(1) {
+            return;
+        }
+        let sugar_left = if monitor.peak_sugar > 0 {
+            sugar as f32 / monitor.peak_sugar as f32
+        } else {
+            1.0
+        };
+        let leaking = monitor
+            .tightest
+            .is_some_and(|tightest| monitor.escape_rate - tightest >= settings.leak_rise);
+        let network_left =
+            self.bonds.len() as f32 / monitor.peak_bonds as f32 * self.protease_gluten_strength();
+        if sugar_left <= settings.sugar_exhausted
+            || leaking
+            || network_left <= settings.network_left
+        {
+            let signs = OverproofSigns {
+                time: self.time_elapsed,
+                sugar_left,
+                escape_rate: monitor.escape_rate,
+                network_left,
+            };
+            self.proofing.overproof = Some(signs);
+            self.events
+                .push(self.time_elapsed, SimulationEvent::OverproofWarning(signs));
+        }
+    }
+}
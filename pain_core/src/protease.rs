@@ -0,0 +1,123 @@
+use crate::kinetics::arrhenius_factor;
+use crate::{MoleculeType, SimulationState};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+
+// The flour's proteases slowly cut the gluten: a bridge gets cut at `rate`
+// per hour once they are awake, faster when warm and as the dough sours,
+// slower where the salt is. Every cut shortens the chains: over a long or warm
+// ferment the network bonds again less and tears sooner, and an
+// over-fermented dough slackens and collapses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProteaseSettings {
+    pub rate: f32,              // Cuts per bridge per hour at the reference, 0 turns it off
+    pub activation_energy: f32, // kJ/mol
+    pub reference_temp: f32,    // °C, unsalted fresh dough
+    pub denature_temp: f32,     // °C, no more cutting above
+    pub wake_hours: f32,        // Time for the proteases to reach full activity
+    pub acid_gain: f32,         // Extra activity in the most sour dough
+    pub salt_inhibition: f32,   // Slowdown at the reference salt: 1 halves the rate
+    pub max_weakening: f32,     // Loss of the stretch a bridge withstands, all chains cut
+}
+
+impl Default for ProteaseSettings {
+    fn default() -> Self {
+        ProteaseSettings {
+            rate: 0.05,
+            activation_energy: 50.0,
+            reference_temp: 25.0,
+            denature_temp: 70.0,
+            wake_hours: 2.0,
+            acid_gain: 4.0,
+            salt_inhibition: 1.0,
+            max_weakening: 0.6,
+        }
+    }
+}
+
+impl SimulationState {
+    // Protease activity over the whole dough: the time they had to wake up
+    // and the acidity, 0 once `rate` is off
+    pub fn protease_activity(&self) -> f32 {
+        let settings = &self.protease_settings;
+        let wake = settings.wake_hours.max(f32::EPSILON) * SECONDS_PER_HOUR;
+        let awake = 1.0 - (-self.time_elapsed.max(0.0) / wake).exp();
+        let acid = &self.acid_settings;
+        let span = (acid.fresh_ph - acid.min_ph).max(f32::EPSILON);
+        let sourness = ((acid.fresh_ph - self.dough_ph()) / span).clamp(0.0, 1.0);
+        settings.rate.max(0.0) * awake * (1.0 + settings.acid_gain.max(0.0) * sourness)
+    }
+
+    // Cuts per bridge per hour at a place of the dough
+    fn protease_rate_at(&self, activity: f32, pos: nalgebra::Vector3<f32>) -> f32 {
+        let settings = &self.protease_settings;
+        let temperature = self.temperature_at(pos);
+        if temperature >= settings.denature_temp {
+            return 0.0;
+        }
+        let reference = self.salt_settings.reference_salt.max(f32::EPSILON);
+        let salt = self.salt_concentration_at(pos) / reference;
+        activity
+            * arrhenius_factor(
+                settings.activation_energy,
+                temperature,
+                settings.reference_temp,
+            )
+            / (1.0 + settings.salt_inhibition.max(0.0) * salt)
+    }
+
+    // Cuts the bridges the proteases got to during `dt`, freeing their thiols
+    pub(crate) fn digest_gluten(&mut self, dt: f32) {
+        let activity = self.protease_activity();
+        if activity <= 0.0 || self.bonds.is_empty() {
+            return;
+        }
+        let chances: Vec<f32> = self
+            .bonds
+            .iter()
+            .map(|bond| {
+                let (Some(a), Some(b)) = (
+                    self.grid.get_molecule(bond.molecule_a_id),
+                    self.grid.get_molecule(bond.molecule_b_id),
+                ) else {
+                    return 0.0;
+                };
+                self.protease_rate_at(activity, (a.pos + b.pos) / 2.0) * dt / SECONDS_PER_HOUR
+            })
+            .collect();
+        let keep: Vec<bool> = chances
+            .iter()
+            .map(|&chance| self.rng.gen::<f32>() >= chance)
+            .collect();
+        if keep.iter().all(|&kept| kept) {
+            return;
+        }
+        let cut = self.take_bonds(&keep);
+        for bond in &cut {
+            self.release_bond(bond);
+        }
+        self.reactions.bonds_digested += cut.len() as u64;
+        // Each cut shortens a glutenin chain for good: the freed thiols can
+        // bond again, but into a looser network
+        let glutenin = self
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Glutenin { .. }))
+            .count();
+        let digested = cut.len() as f32 / glutenin.max(1) as f32;
+        self.gluten_digested = (self.gluten_digested + digested).min(1.0);
+    }
+
+    // Share of the bond chance left to the cut chains
+    pub fn protease_bond_factor(&self) -> f32 {
+        1.0 - self.gluten_digested.clamp(0.0, 1.0)
+    }
+
+    // Share of the stretch a bridge withstands left by the cuts
+    pub fn protease_gluten_strength(&self) -> f32 {
+        let weakening = self.protease_settings.max_weakening.clamp(0.0, 1.0);
+        1.0 - weakening * self.gluten_digested.clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Bond, MoleculeType, SimulationState, Species};
+use serde::{Deserialize, Serialize};
+
+// Tolerance on mass sums (f64 accumulation of f32 masses)
+const MASS_TOLERANCE: f64 = 1e-3;
+
+// Running tally of every reaction, used to check that the chemistry neither
+// creates nor destroys atoms: fermented sugar must reappear as CO2 + ethanol,
+// and every disulfide bridge uses exactly two thiols.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReactionLedger {
+    pub sugars_fermented: u64,
+    pub sugar_mass_consumed: f64,
+    pub co2_produced: u64,
+    pub ethanol_produced: u64,
+    pub product_mass: f64, // CO2 + ethanol produced by fermentation
+    pub bonds_formed: u64,
+    pub bonds_broken: u64,
+    pub thiols_consumed: u64,
+    pub thiols_freed: u64,
+    pub starch_hydrolyzed: u64,
+    pub starch_mass_consumed: f64,
+    pub sugar_mass_released: f64, // Sugar produced by amylase
+    pub other_reactions: u64, // Fired by user reaction rules
+    pub bonds_digested: u64, // Cut by proteases, also in bonds_broken
+}
+
+impl ReactionLedger {
+    // One sugar fermented into one CO2 and one ethanol
+    pub(crate) fn record_fermentation(&mut self) {
+        self.sugars_fermented += 1;
+        self.sugar_mass_consumed += Species::Sugar.info().mass as f64;
+        self.co2_produced += 1;
+        self.ethanol_produced += 1;
+        self.product_mass += (Species::CO2.info().mass + Species::Ethanol.info().mass) as f64;
+    }
+
+    // One starch broken down into one sugar
+    pub(crate) fn record_hydrolysis(&mut self) {
+        self.starch_hydrolyzed += 1;
+        self.starch_mass_consumed += Species::Starch.info().mass as f64;
+        self.sugar_mass_released += Species::Sugar.info().mass as f64;
+    }
+
+    pub(crate) fn record_other_reaction(&mut self) {
+        self.other_reactions += 1;
+    }
+
+    pub(crate) fn record_bond_formed(&mut self) {
+        self.bonds_formed += 1;
+        self.thiols_consumed += 2;
+    }
+
+    pub(crate) fn record_bond_broken(&mut self, thiols_freed: u64) {
+        self.bonds_broken += 1;
+        self.thiols_freed += thiols_freed;
+    }
+
+    pub fn net_bonds(&self) -> i64 {
+        self.bonds_formed as i64 - self.bonds_broken as i64
+    }
+
+    pub fn net_thiols_consumed(&self) -> i64 {
+        self.thiols_consumed as i64 - self.thiols_freed as i64
+    }
+
+    // First broken invariant, if any
+    pub fn violation(&self) -> Option<String> {
+        if (self.sugar_mass_consumed - self.product_mass).abs() > MASS_TOLERANCE {
+            return Some(format!(
+                "sugar mass consumed {} != product mass {}",
+                self.sugar_mass_consumed, self.product_mass
+            ));
+        }
+        if (self.starch_mass_consumed - self.sugar_mass_released).abs() > MASS_TOLERANCE {
+            return Some(format!(
+                "starch mass consumed {} != sugar mass released {}",
+                self.starch_mass_consumed, self.sugar_mass_released
+            ));
+        }
+        if self.net_thiols_consumed() != 2 * self.net_bonds() {
+            return Some(format!(
+                "thiols consumed {} != 2 x bonds {}",
+                self.net_thiols_consumed(),
+                self.net_bonds()
+            ));
+        }
+        None
+    }
+}
+
+impl SimulationState {
+    // Frees the thiols of a bond already taken out of `bonds` so they can bond again
+    pub(crate) fn release_bond(&mut self, bond: &Bond) {
+        let mut freed = 0;
+        for id in [bond.molecule_a_id, bond.molecule_b_id] {
+            if let Some(MoleculeType::Glutenin {
+                has_free_thiol: false,
+            }) = self.grid.get_molecule(id).map(|mol| &mol.mol_type)
+            {
+                self.grid.set_mol_type(
+                    id,
+                    MoleculeType::Glutenin {
+                        has_free_thiol: true,
+                    },
+                );
+                freed += 1;
+            }
+        }
+        self.reactions.record_bond_broken(freed);
+    }
+
+    // Breaks a bond and frees its thiols, for models cutting the gluten from
+    // outside the core (a protease plugin)
+    pub fn break_bond(&mut self, index: usize) -> Option<Bond> {
+        if index >= self.bonds.len() {
+            return None;
+        }
+        let keep: Vec<bool> = (0..self.bonds.len()).map(|i| i != index).collect();
+        let bond = self.take_bonds(&keep).pop()?;
+        self.release_bond(&bond);
+        Some(bond)
+    }
+
+    // Cross-checks the reaction ledger against the molecules themselves
+    pub fn reaction_violation(&self) -> Option<String> {
+        if let Some(violation) = self.reactions.violation() {
+            return Some(violation);
+        }
+        let bound_thiols = self
+            .grid
+            .get_all_molecules()
+            .iter()
+            .filter(|mol| {
+                matches!(
+                    mol.mol_type,
+                    MoleculeType::Glutenin {
+                        has_free_thiol: false
+                    }
+                )
+            })
+            .count();
+        if bound_thiols != 2 * self.bonds.len() {
+            return Some(format!(
+                "{} glutenins without a free thiol for {} bonds",
+                bound_thiols,
+                self.bonds.len()
+            ));
+        }
+        None
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{GlutenNetwork, MoleculeId, MoleculeType, SimulationState, StableHashSet};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// The baker's two checks before shaping. The windowpane: a piece of dough
+// stretched thin lets light through instead of tearing once the gluten is a
+// connected, well bridged network. The poke: a finger dent that springs back
+// shows a network with the elasticity to hold the shape; here, the bridges of
+// the glutenins the last force pushed are followed to see how much of their
+// stretch they take back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessSettings {
+    pub windowpane_ready: f32, // Windowpane score from which the dough is ready
+    pub poke_ready: f32,       // Poke recovery from which the dough is ready
+    pub poke_window: f32,      // Seconds the pushed glutenins are followed
+    pub poke_samples: usize,   // Glutenins followed, at most
+}
+
+impl Default for ReadinessSettings {
+    fn default() -> Self {
+        ReadinessSettings {
+            windowpane_ready: 0.6,
+            poke_ready: 0.5,
+            poke_window: 30.0,
+            poke_samples: 64,
+        }
+    }
+}
+
+impl ReadinessSettings {
+    // Both tests passed: time to shape
+    pub fn passes(&self, windowpane: f32, poke: Option<f32>) -> bool {
+        windowpane >= self.windowpane_ready && poke.is_some_and(|poke| poke >= self.poke_ready)
+    }
+}
+
+// The glutenins pushed by the last force and how stretched their bridges
+// were since
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PokeResponse {
+    pub time: f32,
+    pushed: Vec<MoleculeId>,
+    pub bonded: f32,      // Share of them bridged, at the last tick followed
+    pub peak_strain: f32, // Mean relative stretch of their bridges, the most
+    pub strain: f32,      // Same at the last tick followed
+}
+
+impl PokeResponse {
+    // Share of the dent taken back: the stretch the bridges relaxed, over
+    // the glutenins that have one to spring back with
+    pub fn recovery(&self) -> f32 {
+        if self.peak_strain <= f32::EPSILON {
+            return 0.0;
+        }
+        self.bonded * (1.0 - self.strain / self.peak_strain).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DoughReadiness {
+    pub windowpane: f32,   // 0 tears at once, 1 stretches into a thin pane
+    pub poke: Option<f32>, // Share of the last dent taken back, None before any force
+    pub ready: bool,       // Both tests passed: time to shape
+}
+
+impl SimulationState {
+    pub fn dough_readiness(&self) -> DoughReadiness {
+        self.readiness_of(&self.gluten_network())
+    }
+
+    // For callers that already built the network
+    pub fn readiness_of(&self, network: &GlutenNetwork) -> DoughReadiness {
+        let windowpane = self.windowpane_of(network);
+        let poke = self.poke.as_ref().map(PokeResponse::recovery);
+        DoughReadiness {
+            windowpane,
+            poke,
+            ready: self.readiness_settings.passes(windowpane, poke),
+        }
+    }
+
+    // A pane needs bridges enough to spread the stretch, or a network
+    // reaching across the dough, and bridges that still hold: acid and
+    // proteases make it tear. Each glutenin bonds once, so short of
+    // percolating the network is mostly pairs and the bridges count.
+    fn windowpane_of(&self, network: &GlutenNetwork) -> f32 {
+        let connected = if network.percolates() {
+            1.0
+        } else {
+            network.largest_fraction
+        };
+        let max_bonds = network.glutenin_count / 2;
+        let bridged = if max_bonds > 0 {
+            (self.bonds.len() as f32 / max_bonds as f32).min(1.0)
+        } else {
+            0.0
+        };
+        connected.max(bridged) * self.acid_gluten_strength() * self.protease_gluten_strength()
+    }
+
+    // Starts following the glutenins a force around `center` is about to push
+    pub(crate) fn start_poke(&mut self, center: Vector3<f32>, radius: f32) {
+        let mut pushed: Vec<MoleculeId> = self
+            .grid
+            .neighbors_within(center, radius)
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Glutenin { .. }))
+            .map(|mol| mol.id)
+            .collect();
+        pushed.sort();
+        pushed.truncate(self.readiness_settings.poke_samples);
+        self.poke = Some(PokeResponse {
+            time: self.time_elapsed,
+            pushed,
+            ..Default::default()
+        });
+    }
+
+    // How stretched the pushed glutenins' bridges are now, until the poke
+    // window closes
+    pub(crate) fn follow_poke(&mut self) {
+        let window = self.readiness_settings.poke_window;
+        let Some(poke) = &self.poke else {
+            return;
+        };
+        if self.time_elapsed - poke.time > window || poke.pushed.is_empty() {
+            return;
+        }
+        let pushed: StableHashSet<MoleculeId> = poke.pushed.iter().copied().collect();
+        let (mut strain, mut bridges, mut bonded) = (0.0, 0, 0);
+        for bond in &self.bonds {
+            let ends = [bond.molecule_a_id, bond.molecule_b_id];
+            let held = ends.iter().filter(|id| pushed.contains(id)).count();
+            if held == 0 {
+                continue;
+            }
+            bonded += held;
+            if let (Some(a), Some(b)) = (
+                self.grid.get_molecule(bond.molecule_a_id),
+                self.grid.get_molecule(bond.molecule_b_id),
+            ) {
+                let length = self.grid.displacement(a.pos, b.pos).magnitude();
+                strain +=
+                    (length - bond.target_distance).abs() / bond.target_distance.max(f32::EPSILON);
+                bridges += 1;
+            }
+        }
+        let count = pushed.len();
+        if let Some(poke) = &mut self.poke {
+            poke.bonded = (bonded as f32 / count as f32).min(1.0);
+            poke.strain = if bridges > 0 {
+                strain / bridges as f32
+            } else {
+                0.0
+            };
+            poke.peak_strain = poke.peak_strain.max(poke.strain);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use crate::flour::wheat_absorption;
+use crate::{
+    CollapseMonitor, FlourProfile, GramScale, DoughPhase, KneadCounts, MassLedger, MixingField, Molecule, MoleculeType, ReactionLedger, SaltField, SimulationEvent,
+    SimulationState, SpatialGrid3D, ThermalField,
+};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Molecule counts of the classic recipe, kept small for the demo; other
+// recipes scale them by their protein content and hydration
+const CLASSIC_PROTEINS: f32 = 200.0;
+const CLASSIC_WATER: f32 = 200.0;
+const CLASSIC_PROTEIN_CONTENT: f32 = 0.12;
+const CLASSIC_HYDRATION: f32 = 0.72;
+const CLASSIC_STARCH: f32 = 100.0;
+const CLASSIC_AMYLASE: f32 = 10.0;
+// Amylase sits on the starch granules it digests
+const AMYLASE_SPREAD: f32 = 10.0;
+
+// Missing keys in a recipe file keep the classic recipe's value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Recipe {
+    pub name: String,
+    pub hydration: f32,         // Water / flour (0.55 to 0.90)
+    pub salt: f32,              // Salt / flour (0.0 to 0.03)
+    pub yeast: f32,             // Yeast/levain / flour (0.10 to 0.30)
+    pub protein_content: f32,   // Protein / flour (0.08 rye to 0.14 strong flour)
+    pub glutenin_fraction: f32, // Share of the proteins able to form disulfide bridges
+    pub autolyse_time: f32,     // Seconds of autolyse before salt and yeast
+    pub temperature: f32,       // Starting dough temperature in Celsius
+    pub amylase_activity: f32,  // Enzyme content relative to a white wheat flour
+    pub ash_content: f32,       // Minerals / flour (0.005 white to 0.015 whole)
+    pub absorption: f32,        // Water / flour the flour takes up (see FlourProfile)
+    pub fat: f32,               // Butter or oil / flour (0 lean, 0.5 brioche)
+    // Grams of flour and molecules per gram (see BakersFormula); without one
+    // the molecule counts are the small ones of the demo
+    pub scale: Option<GramScale>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RecipePreset {
+    Classic,
+    Baguette,
+    Ciabatta,
+    Rye,
+    Brioche,
+    Pizza,
+    Challah,
+}
+
+impl RecipePreset {
+    pub fn all() -> [RecipePreset; 7] {
+        [
+            RecipePreset::Classic,
+            RecipePreset::Baguette,
+            RecipePreset::Ciabatta,
+            RecipePreset::Rye,
+            RecipePreset::Brioche,
+            RecipePreset::Pizza,
+            RecipePreset::Challah,
+        ]
+    }
+
+    // Identifier used in preferences and on the command line
+    pub fn id(&self) -> &'static str {
+        match self {
+            RecipePreset::Classic => "classic",
+            RecipePreset::Baguette => "baguette",
+            RecipePreset::Ciabatta => "ciabatta",
+            RecipePreset::Rye => "rye",
+            RecipePreset::Brioche => "brioche",
+            RecipePreset::Pizza => "pizza",
+            RecipePreset::Challah => "challah",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        RecipePreset::all()
+            .into_iter()
+            .find(|preset| preset.id().eq_ignore_ascii_case(id.trim()))
+    }
+
+    pub fn next(&self) -> Self {
+        let all = RecipePreset::all();
+        let index = all.iter().position(|preset| preset == self).unwrap_or(0);
+        all[(index + 1) % all.len()]
+    }
+
+    pub fn recipe(&self) -> Recipe {
+        let (name, hydration, salt, yeast, protein_content, glutenin_fraction, autolyse_time) =
+            match self {
+                RecipePreset::Classic => ("Pain classique", 0.72, 0.02, 0.20, 0.12, 0.6, 1800.0),
+                RecipePreset::Baguette => ("Baguette", 0.68, 0.02, 0.15, 0.115, 0.6, 1800.0),
+                RecipePreset::Ciabatta => ("Ciabatta", 0.80, 0.022, 0.20, 0.13, 0.6, 2400.0),
+                // Rye proteins barely form gluten
+                RecipePreset::Rye => ("Seigle", 0.85, 0.018, 0.25, 0.09, 0.3, 0.0),
+                // Eggs and milk replace most of the water, no autolyse
+                RecipePreset::Brioche => ("Brioche", 0.60, 0.018, 0.12, 0.115, 0.6, 0.0),
+                RecipePreset::Pizza => ("Pizza napolitaine", 0.65, 0.028, 0.10, 0.125, 0.6, 1200.0),
+                // Eggs for most of the liquid, like the brioche
+                RecipePreset::Challah => ("Hallah", 0.55, 0.02, 0.12, 0.12, 0.6, 0.0),
+            };
+        Recipe {
+            name: name.to_string(),
+            hydration,
+            salt,
+            yeast,
+            protein_content,
+            glutenin_fraction,
+            autolyse_time,
+            temperature: 25.0,
+            // Rye flour is much richer in amylase than wheat
+            amylase_activity: match self {
+                RecipePreset::Rye => 2.0,
+                _ => 1.0,
+            },
+            // The type of flour each is usually made with
+            ash_content: match self {
+                RecipePreset::Rye => FlourProfile::Rye.ash_content(),
+                RecipePreset::Brioche | RecipePreset::Pizza | RecipePreset::Challah => {
+                    FlourProfile::BreadFlour.ash_content()
+                }
+                _ => FlourProfile::T65.ash_content(),
+            },
+            absorption: match self {
+                RecipePreset::Rye => FlourProfile::Rye.absorption(),
+                _ => wheat_absorption(protein_content),
+            },
+            // Butter in the brioche, oil in the challah
+            fat: match self {
+                RecipePreset::Brioche => 0.5,
+                RecipePreset::Challah => 0.12,
+                _ => 0.0,
+            },
+            scale: None,
+        }
+    }
+}
+
+impl Default for Recipe {
+    fn default() -> Self {
+        RecipePreset::Classic.recipe()
+    }
+}
+
+impl From<RecipePreset> for Recipe {
+    fn from(preset: RecipePreset) -> Self {
+        preset.recipe()
+    }
+}
+
+impl Recipe {
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let recipe: Recipe =
+            toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !(recipe.hydration > 0.0 && recipe.protein_content > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "hydration and protein_content must be positive",
+            ));
+        }
+        Ok(recipe)
+    }
+
+    pub fn to_toml(&self) -> io::Result<String> {
+        toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        Recipe::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_toml()?)
+    }
+
+    pub fn protein_count(&self) -> usize {
+        match self.scale {
+            Some(scale) => scale.count(self.protein_content),
+            None => (CLASSIC_PROTEINS * self.protein_content / CLASSIC_PROTEIN_CONTENT).round() as usize,
+        }
+    }
+
+    pub fn water_count(&self) -> usize {
+        match self.scale {
+            Some(scale) => scale.count(self.hydration),
+            None => (CLASSIC_WATER * self.hydration / CLASSIC_HYDRATION).round() as usize,
+        }
+    }
+
+    // Starch makes up the flour that is neither protein nor ash
+    pub fn starch_count(&self) -> usize {
+        self.flour_share_count(1.0 - self.protein_content - self.ash_content)
+    }
+
+    pub fn ash_count(&self) -> usize {
+        self.flour_share_count(self.ash_content)
+    }
+
+    // As many molecules per gram as the water
+    pub fn fat_count(&self) -> usize {
+        match self.scale {
+            Some(scale) => scale.count(self.fat),
+            None => (CLASSIC_WATER * self.fat.max(0.0) / CLASSIC_HYDRATION).round() as usize,
+        }
+    }
+
+    // Molecules for `share` of the flour besides the proteins, counted like
+    // the starch of the classic recipe
+    fn flour_share_count(&self, share: f32) -> usize {
+        match self.scale {
+            Some(scale) => scale.count(share),
+            None => (CLASSIC_STARCH * share.max(0.0) / (1.0 - CLASSIC_PROTEIN_CONTENT)).round() as usize,
+        }
+    }
+
+    // As much per starch granule as in the classic recipe when weighed
+    pub fn amylase_count(&self) -> usize {
+        let per_classic = match self.scale {
+            Some(_) => self.starch_count() as f32 / CLASSIC_STARCH,
+            None => 1.0,
+        };
+        (CLASSIC_AMYLASE * per_classic * self.amylase_activity).round() as usize
+    }
+}
+
+impl SimulationState {
+    // Starts a fresh dough from a recipe: flour (proteins, starch, amylase)
+    // and water only, salt and yeast are added later by the baker
+    pub fn initialize_recipe(&mut self, recipe: Recipe) {
+        self.recipe_hydration = recipe.hydration;
+        self.recipe_salt = recipe.salt;
+        self.recipe_yeast = recipe.yeast;
+        self.autolyse_time = recipe.autolyse_time;
+        self.temperature = recipe.temperature;
+        self.recipe_scale = recipe.scale;
+        self.recipe_absorption = recipe.absorption;
+        self.recipe_fat = recipe.fat;
+
+        // Reset simulation state
+        self.grid = SpatialGrid3D::new(
+            self.width,
+            self.height,
+            self.depth,
+            self.config.grid_cell_size,
+        );
+        self.set_bonds(Vec::new());
+        self.mixing = MixingField::default();
+        self.reactions = ReactionLedger::default();
+        self.collapse = CollapseMonitor::default();
+        self.scheduler.restart();
+        self.kneading = KneadCounts::default();
+        self.notebook.clear();
+        self.thermal = ThermalField::uniform(self.thermal_settings.resolution, recipe.temperature);
+        self.salt = SaltField::empty(self.salt_settings.resolution);
+        self.time_elapsed = 0.0;
+        self.salt_added = false; // We'll add salt later
+        self.yeast_added = false;
+        // Without autolyse the baker mixes everything right away
+        self.phase = if recipe.autolyse_time > 0.0 {
+            DoughPhase::Autolyse
+        } else {
+            DoughPhase::Mix
+        };
+        self.phase_started = 0.0;
+        self.events
+            .push(self.time_elapsed, SimulationEvent::RecipeInitialized);
+
+        // Every count at the resolution, within the molecule budget
+        let requested = recipe.protein_count()
+            + recipe.water_count()
+            + recipe.starch_count()
+            + recipe.amylase_count()
+            + recipe.ash_count()
+            + recipe.fat_count();
+        let scale = self.budget.recipe_scale(requested);
+        let scaled = move |count: usize| (count as f32 * scale).round() as usize;
+
+        // Add initial flour components: gliadin and glutenin proteins
+        let glutenin_percent = (recipe.glutenin_fraction * 100.0).round() as u32;
+        for _ in 0..scaled(recipe.protein_count()) {
+            let x = self.rng.gen_range(0.0..self.width);
+            let y = self.rng.gen_range(0.0..self.height);
+            let z = self.rng.gen_range(0.0..self.depth);
+            let pos = Vector3::new(x, y, z);
+
+            // Randomly distribute gliadins and glutens
+            let vel_x = self.rng.gen_range(-0.1..0.1);
+            let vel_y = self.rng.gen_range(-0.1..0.1);
+            let vel_z = self.rng.gen_range(-0.1..0.1);
+            let velocity = Vector3::new(vel_x, vel_y, vel_z);
+
+            let protein_choice = self.rng.gen_range(0..100);
+            let mol_type = if protein_choice < 100 - glutenin_percent {
+                MoleculeType::Gliadin
+            } else {
+                MoleculeType::Glutenin {
+                    has_free_thiol: true,
+                }
+            };
+            self.grid.insert(Molecule::new(mol_type, pos, velocity));
+        }
+
+        // Add water based on hydration percentage
+        for _ in 0..scaled(recipe.water_count()) {
+            let x = self.rng.gen_range(0.0..self.width);
+            let y = self.rng.gen_range(0.0..self.height);
+            let z = self.rng.gen_range(0.0..self.depth);
+            let pos = Vector3::new(x, y, z);
+
+            let vel_x = self.rng.gen_range(-0.2..0.2);
+            let vel_y = self.rng.gen_range(-0.2..0.2);
+            let vel_z = self.rng.gen_range(-0.2..0.2);
+            let velocity = Vector3::new(vel_x, vel_y, vel_z);
+
+            let molecule = Molecule::new(MoleculeType::Water, pos, velocity);
+            self.grid.insert(molecule);
+        }
+
+        // Add starch, with amylase spread over some of the granules
+        let mut starch_positions = Vec::new();
+        for _ in 0..scaled(recipe.starch_count()) {
+            let x = self.rng.gen_range(0.0..self.width);
+            let y = self.rng.gen_range(0.0..self.height);
+            let z = self.rng.gen_range(0.0..self.depth);
+            let pos = Vector3::new(x, y, z);
+
+            let vel_x = self.rng.gen_range(-0.1..0.1);
+            let vel_y = self.rng.gen_range(-0.1..0.1);
+            let vel_z = self.rng.gen_range(-0.1..0.1);
+            let velocity = Vector3::new(vel_x, vel_y, vel_z);
+
+            self.grid
+                .insert(Molecule::new(MoleculeType::Starch, pos, velocity));
+            starch_positions.push(pos);
+        }
+        if !starch_positions.is_empty() {
+            for _ in 0..scaled(recipe.amylase_count()) {
+                let granule = starch_positions[self.rng.gen_range(0..starch_positions.len())];
+                let x = self.rng.gen_range(
+                    (granule.x - AMYLASE_SPREAD).max(0.0)
+                        ..(granule.x + AMYLASE_SPREAD).min(self.width),
+                );
+                let y = self.rng.gen_range(
+                    (granule.y - AMYLASE_SPREAD).max(0.0)
+                        ..(granule.y + AMYLASE_SPREAD).min(self.height),
+                );
+                let z = self.rng.gen_range(
+                    (granule.z - AMYLASE_SPREAD).max(0.0)
+                        ..(granule.z + AMYLASE_SPREAD).min(self.depth),
+                );
+                let pos = Vector3::new(x, y, z);
+                self.grid
+                    .insert(Molecule::new(MoleculeType::Amylase, pos, Vector3::zeros()));
+            }
+        }
+
+        // Minerals of the bran, scattered through the flour
+        for _ in 0..scaled(recipe.ash_count()) {
+            let pos = Vector3::new(
+                self.rng.gen_range(0.0..self.width),
+                self.rng.gen_range(0.0..self.height),
+                self.rng.gen_range(0.0..self.depth),
+            );
+            self.grid
+                .insert(Molecule::new(MoleculeType::Ash, pos, Vector3::zeros()));
+        }
+
+        // Butter or oil, worked into the flour from the start
+        for _ in 0..scaled(recipe.fat_count()) {
+            let pos = Vector3::new(
+                self.rng.gen_range(0.0..self.width),
+                self.rng.gen_range(0.0..self.height),
+                self.rng.gen_range(0.0..self.depth),
+            );
+            self.grid
+                .insert(Molecule::new(MoleculeType::Fat, pos, Vector3::zeros()));
+        }
+
+        self.recipe_name = recipe.name;
+        self.ledger = MassLedger::starting_at(self.total_mass());
+        self.metrics.clear();
+        self.record_metrics();
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{Molecule, SpatialGrid3D};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// A volume of the dough to select molecules in, for the tools that push on
+// part of it and for analysis
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Region {
+    // Strictly closer than `radius` to `center`
+    Sphere {
+        center: Vector3<f32>,
+        radius: f32,
+    },
+    // Axis-aligned box, faces included
+    Aabb {
+        min: Vector3<f32>,
+        max: Vector3<f32>,
+    },
+    // Round column from the center of its `base` along `axis` for `height`,
+    // the caps included
+    Cylinder {
+        base: Vector3<f32>,
+        axis: Vector3<f32>,
+        radius: f32,
+        height: f32,
+    },
+}
+
+impl Region {
+    pub fn contains(&self, pos: Vector3<f32>) -> bool {
+        match *self {
+            Region::Sphere { center, radius } => (pos - center).magnitude() < radius,
+            Region::Aabb { min, max } => (0..3).all(|i| pos[i] >= min[i] && pos[i] <= max[i]),
+            Region::Cylinder {
+                base,
+                axis,
+                radius,
+                height,
+            } => {
+                let axis = axis.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+                let offset = pos - base;
+                let along = offset.dot(&axis);
+                (0.0..=height).contains(&along) && (offset - axis * along).magnitude() < radius
+            }
+        }
+    }
+
+    // Corners of a box holding the whole region
+    pub fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+        match *self {
+            Region::Sphere { center, radius } => {
+                let reach = Vector3::repeat(radius.max(0.0));
+                (center - reach, center + reach)
+            }
+            Region::Aabb { min, max } => (min.inf(&max), max.sup(&min)),
+            Region::Cylinder {
+                base,
+                axis,
+                radius,
+                height,
+            } => {
+                let axis = axis.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+                let top = base + axis * height;
+                let reach = Vector3::repeat(radius.max(0.0));
+                (base.inf(&top) - reach, base.sup(&top) + reach)
+            }
+        }
+    }
+}
+
+// How a push on a sphere fades from its center to its edge, as a share of
+// the full force
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Falloff {
+    // The whole force everywhere inside, the historical fold
+    #[default]
+    Constant,
+    // Down to nothing at the edge in a straight line
+    Linear,
+    // Flat at the center and at the edge, like a hand pressing in
+    Smoothstep,
+}
+
+impl Falloff {
+    pub fn all() -> [Falloff; 3] {
+        [Falloff::Constant, Falloff::Linear, Falloff::Smoothstep]
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            Falloff::Constant => "uniforme",
+            Falloff::Linear => "linéaire",
+            Falloff::Smoothstep => "douce",
+        }
+    }
+
+    // Share of the force at `distance` from the center of a sphere of `radius`
+    pub fn weight(&self, distance: f32, radius: f32) -> f32 {
+        let t = (distance / radius.max(f32::EPSILON)).clamp(0.0, 1.0);
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Smoothstep => 1.0 - t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl SpatialGrid3D {
+    // Molecules inside `region`, from the cells its bounds overlap only, in
+    // the z-major order of the cells; any size of region, however many cells
+    // it spans
+    pub fn query_region<'a>(&'a self, region: &Region) -> impl Iterator<Item = &'a Molecule> + 'a {
+        let region = *region;
+        let (min, max) = region.bounds();
+        let (low_x, low_y, low_z) = self.clamped_cell_coords(min);
+        let (high_x, high_y, high_z) = self.clamped_cell_coords(max);
+        (low_z..=high_z)
+            .flat_map(move |z| {
+                (low_y..=high_y).flat_map(move |y| {
+                    (low_x..=high_x)
+                        .map(move |x| self.cells[self.linear_index((x, y, z))].as_slice())
+                })
+            })
+            .flatten()
+            .filter_map(|&id| self.molecules.get(id))
+            .filter(move |mol| region.contains(mol.pos))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{MoleculeId, SimulationState};
+use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Soft repulsion between molecules closer than their cores: every molecule
+// is pushed away from each neighbor it overlaps, in proportion to the
+// overlap, so the dough takes up room and rising CO2 shoves the matrix aside
+// instead of passing through it. The species radii are also how far the
+// reactions reach, so only the inner `core_fraction` of them is solid and
+// neighbors still come close enough to react.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepulsionSettings {
+    pub stiffness: f32,     // Force per unit of overlap, 0 turns it off
+    pub core_fraction: f32, // Share of each radius that is solid
+    pub max_push: f32,      // Clamp on the velocity one tick of repulsion adds
+}
+
+impl Default for RepulsionSettings {
+    fn default() -> Self {
+        RepulsionSettings {
+            stiffness: 4.0,
+            core_fraction: 0.5,
+            max_push: 1.0,
+        }
+    }
+}
+
+impl SimulationState {
+    // Pushes overlapping molecules apart; bonded pairs are left to the bond
+    // solver. All pushes are worked out from the same positions, then added
+    // to the velocities.
+    pub(crate) fn apply_repulsion(&mut self, dt: f32) {
+        let settings = self.repulsion.clone();
+        if settings.stiffness <= 0.0 || settings.core_fraction <= 0.0 || dt <= 0.0 {
+            return;
+        }
+        self.index_bonds();
+        let widest_core = self
+            .grid
+            .molecules()
+            .map(|mol| mol.radius() * settings.core_fraction)
+            .fold(0.0, f32::max);
+        let ids: Vec<MoleculeId> = self.grid.molecules().map(|mol| mol.id).collect();
+        let push = |&id: &MoleculeId| self.repulsion_push(id, widest_core, &settings, dt);
+        #[cfg(feature = "parallel")]
+        let pushes: Vec<(MoleculeId, Vector3<f32>)> = ids.par_iter().filter_map(push).collect();
+        #[cfg(not(feature = "parallel"))]
+        let pushes: Vec<(MoleculeId, Vector3<f32>)> = ids.iter().filter_map(push).collect();
+
+        for (id, kick) in pushes {
+            if let Some(mol) = self.grid.get_molecule_mut(id) {
+                mol.velocity += kick;
+            }
+        }
+    }
+
+    // Velocity change of one molecule from the neighbors its core overlaps
+    fn repulsion_push(
+        &self,
+        id: MoleculeId,
+        widest_core: f32,
+        settings: &RepulsionSettings,
+        dt: f32,
+    ) -> Option<(MoleculeId, Vector3<f32>)> {
+        let mol = self.grid.get_molecule(id)?;
+        let core = mol.radius() * settings.core_fraction;
+        let mut force = Vector3::zeros();
+        self.grid.for_each_neighbor(mol.pos, core + widest_core, |other| {
+            if other.id == id {
+                return;
+            }
+            let contact = core + other.radius() * settings.core_fraction;
+            let offset = self.grid.displacement(other.pos, mol.pos);
+            let distance = offset.magnitude();
+            if distance >= contact || self.has_bond(id, other.id) {
+                return;
+            }
+            // Molecules on the same spot part along x, each its own way
+            let away = if distance > f32::EPSILON {
+                offset / distance
+            } else if id > other.id {
+                Vector3::x()
+            } else {
+                -Vector3::x()
+            };
+            force += away * (contact - distance) * settings.stiffness;
+        });
+        if force == Vector3::zeros() {
+            return None;
+        }
+        let kick = force / mol.mass() * dt;
+        let limit = settings.max_push.max(0.0);
+        let kick = if kick.magnitude() > limit {
+            kick.normalize() * limit
+        } else {
+            kick
+        };
+        Some((id, kick))
+    }
+}
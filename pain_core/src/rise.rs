@@ -0,0 +1,83 @@
+use crate::{MoleculeType, SimulationState, StableHashMap};
+use serde::{Deserialize, Serialize};
+
+// Share of a column's molecules under its top surface, a few strays above
+// do not lift it
+const COLUMN_TOP_QUANTILE: f32 = 0.95;
+
+// The volume is measured column by column: the floor of the container is cut
+// into square columns and each one holds dough up to its own top surface
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiseSettings {
+    pub column_size: f32,     // Side of the columns
+    pub min_molecules: usize, // Molecules a column needs to hold any dough
+}
+
+impl Default for RiseSettings {
+    fn default() -> Self {
+        RiseSettings {
+            column_size: 100.0,
+            min_molecules: 3,
+        }
+    }
+}
+
+// How far the dough has risen, the baker's rise curve read at one moment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiseMetrics {
+    pub time: f32,
+    pub height: f32,       // Top surface of the dough
+    pub rise: f32,         // Height gained since the first measure
+    pub volume: f32,       // Space under the top surface, gas bubbles included
+    pub volume_ratio: f32, // Volume over the first measure: 2 once it has doubled
+}
+
+impl SimulationState {
+    // Every column's top surface times its floor area: the dough and the
+    // bubbles it holds. Free gas does not count.
+    pub fn dough_volume(&self) -> f32 {
+        let size = self.rise_settings.column_size.max(f32::EPSILON);
+        let mut columns: StableHashMap<(i32, i32), Vec<f32>> = StableHashMap::default();
+        for mol in self.grid.molecules() {
+            if matches!(mol.mol_type, MoleculeType::CO2) {
+                continue;
+            }
+            let column = (
+                (mol.pos.x / size).floor() as i32,
+                (mol.pos.z / size).floor() as i32,
+            );
+            columns.entry(column).or_default().push(mol.pos.y);
+        }
+        let min_molecules = self.rise_settings.min_molecules.max(1);
+        let heights: f32 = columns
+            .into_values()
+            .filter(|heights| heights.len() >= min_molecules)
+            .map(|mut heights| {
+                let rank = ((heights.len() - 1) as f32 * COLUMN_TOP_QUANTILE).round() as usize;
+                *heights.select_nth_unstable_by(rank, f32::total_cmp).1
+            })
+            .sum();
+        heights * size * size
+    }
+
+    // Measured against the height and volume the collapse monitor took at its
+    // first check
+    pub fn rise_metrics(&self) -> RiseMetrics {
+        let volume = self.dough_volume();
+        let volume_ratio = match self.collapse.start_volume() {
+            Some(start) if start > 0.0 => volume / start,
+            _ => 1.0,
+        };
+        let height = self.dough_height();
+        RiseMetrics {
+            time: self.time_elapsed,
+            height,
+            rise: self
+                .collapse
+                .start_height()
+                .map_or(0.0, |start| height - start),
+            volume,
+            volume_ratio,
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use crate::fast_forward::TUNED_TICK;
+use crate::kinetics::arrhenius_factor;
+use crate::{Molecule, MoleculeId, SimulationState, Species, StableHashSet};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Multiplies the base chance of a rule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RateFactor {
+    // Arrhenius law at the temperature between the two reactants
+    Arrhenius {
+        activation_energy: f32, // kJ/mol
+        reference_temp: f32,    // °C where the factor is 1
+    },
+    // Temperature response of the yeast (config), around the first reactant
+    YeastMetabolism,
+    // Temperature response of the amylase (config), around the first reactant
+    AmylaseActivity,
+    // Slowdown by the dissolved salt around the first reactant
+    Osmotic,
+    // Recent kneading between the two reactants
+    Mixing,
+    // Gluten tightening by the dissolved salt between the two reactants
+    SaltBond,
+    // Gluten coated by the fat between the two reactants
+    FatCoating,
+    // Bridges helped by the ascorbic acid added to the dough
+    AscorbicAcid,
+    // Glutenin chains cut by the proteases
+    Protease,
+}
+
+impl RateFactor {
+    // Evaluated once per first reactant rather than per pair
+    fn at_first(&self) -> bool {
+        matches!(
+            self,
+            RateFactor::YeastMetabolism
+                | RateFactor::AmylaseActivity
+                | RateFactor::Osmotic
+                | RateFactor::AscorbicAcid
+                | RateFactor::Protease
+        )
+    }
+}
+
+// What becomes of a reactant when the rule fires
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Fate {
+    #[default]
+    Kept, // A catalyst: can react again in the same tick
+    Becomes(Species),
+    Consumed,
+}
+
+// A molecule spawned around the first reactant
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Product {
+    pub species: Species,
+    pub spread: f32, // Offset from the first reactant, per axis
+    pub speed: f32,  // Random initial velocity, per axis
+}
+
+// Bookkeeping besides the molecules themselves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum RuleEffect {
+    #[default]
+    None,
+    DisulfideBridge, // Links the reactants with a bond
+    Fermentation,    // Counted in the reaction ledger as sugar -> CO2 + ethanol
+    Hydrolysis,      // Counted in the reaction ledger as starch -> sugar
+    // Acid added to the dough per firing, lowering its pH
+    Acid {
+        acetic: f32,
+        lactic: f32,
+    },
+}
+
+// When the rule runs at all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum RuleCondition {
+    #[default]
+    Always,
+    FormsBonds,    // Phases where the gluten network develops
+    EnzymesActive, // Phases where the flour enzymes work
+    Fermenting,    // Yeast added, alive and in a fermenting phase
+}
+
+// One chemical reaction between two species: every molecule of `first`
+// looks for `second` molecules within `distance` and reacts with each of
+// them at the chance `rate` (per tick, or per second with `per_second`)
+// times its factors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReactionRule {
+    pub name: String,
+    pub first: Species,
+    pub second: Species,
+    pub distance: f32,
+    pub rate: f32,
+    #[serde(default)]
+    pub per_second: bool,
+    #[serde(default)]
+    pub factors: Vec<RateFactor>,
+    #[serde(default)]
+    pub first_fate: Fate,
+    #[serde(default)]
+    pub second_fate: Fate,
+    #[serde(default)]
+    pub products: Vec<Product>,
+    #[serde(default)]
+    pub effect: RuleEffect,
+    #[serde(default)]
+    pub when: RuleCondition,
+}
+
+// `[[reaction]]` tables of a TOML file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    reaction: Vec<ReactionRule>,
+}
+
+impl ReactionRule {
+    pub fn from_toml(text: &str) -> io::Result<Vec<ReactionRule>> {
+        let file: RuleFile =
+            toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(file.reaction)
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Vec<ReactionRule>> {
+        ReactionRule::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+// How a pass turns rates into chances: as they are over a regular tick, or
+// as the share of first-order kinetics done over a fast-forward leap, which
+// tops out at certain instead of going past it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RuleStep {
+    Tick,
+    Leap,
+}
+
+// A successful draw, applied once the pass over the molecules is done
+struct Firing {
+    first: MoleculeId,
+    second: MoleculeId,
+    distance: f32,
+}
+
+impl SimulationState {
+    // The dough's own chemistry, from the config: disulfide bridges, starch
+    // hydrolysis and fermentation, in that order
+    pub fn builtin_reactions(&self) -> Vec<ReactionRule> {
+        let config = &self.config;
+        let mut bond_factors = vec![
+            RateFactor::Arrhenius {
+                activation_energy: config.bond_activation_energy,
+                reference_temp: config.bond_reference_temp,
+            },
+            RateFactor::Mixing,
+            RateFactor::SaltBond,
+        ];
+        // Only an enriched dough pays for looking for fat
+        if self.recipe_fat > 0.0 {
+            bond_factors.push(RateFactor::FatCoating);
+        }
+        if self.improvers.ascorbic_acid > 0.0 {
+            bond_factors.push(RateFactor::AscorbicAcid);
+        }
+        if self.gluten_digested > 0.0 {
+            bond_factors.push(RateFactor::Protease);
+        }
+        vec![
+            ReactionRule {
+                name: "pont disulfure".to_string(),
+                first: Species::ReactiveGlutenin,
+                second: Species::ReactiveGlutenin,
+                distance: config.bond_distance,
+                rate: config.bond_probability * config.bond_rate_scale,
+                per_second: false,
+                factors: bond_factors,
+                first_fate: Fate::Becomes(Species::BondedGlutenin),
+                second_fate: Fate::Becomes(Species::BondedGlutenin),
+                products: Vec::new(),
+                effect: RuleEffect::DisulfideBridge,
+                when: RuleCondition::FormsBonds,
+            },
+            ReactionRule {
+                name: "hydrolyse de l'amidon".to_string(),
+                first: Species::Amylase,
+                second: Species::Starch,
+                distance: config.amylase_distance,
+                rate: config.amylase_rate,
+                per_second: true,
+                factors: vec![RateFactor::AmylaseActivity],
+                first_fate: Fate::Kept,
+                second_fate: Fate::Becomes(Species::Sugar),
+                products: Vec::new(),
+                effect: RuleEffect::Hydrolysis,
+                when: RuleCondition::EnzymesActive,
+            },
+            ReactionRule {
+                name: "fermentation".to_string(),
+                first: Species::Yeast,
+                second: Species::Sugar,
+                distance: config.fermentation_distance,
+                rate: config.fermentation_rate,
+                per_second: true,
+                factors: vec![RateFactor::YeastMetabolism, RateFactor::Osmotic],
+                first_fate: Fate::Kept,
+                second_fate: Fate::Consumed,
+                products: vec![
+                    Product {
+                        species: Species::CO2,
+                        spread: 3.0,
+                        speed: 0.2,
+                    },
+                    Product {
+                        species: Species::Ethanol,
+                        spread: 2.0,
+                        speed: 0.1,
+                    },
+                ],
+                effect: RuleEffect::Fermentation,
+                when: RuleCondition::Fermenting,
+            },
+        ]
+    }
+
+    // Built-in reactions followed by the ones added to `extra_reactions`
+    pub fn reaction_rules(&self) -> Vec<ReactionRule> {
+        let mut rules = self.builtin_reactions();
+        rules.extend(self.extra_reactions.iter().cloned());
+        rules
+    }
+
+    fn rule_runs(&self, condition: RuleCondition) -> bool {
+        match condition {
+            RuleCondition::Always => true,
+            RuleCondition::FormsBonds => self.phase.forms_bonds(),
+            RuleCondition::EnzymesActive => self.phase.enzymes_active(),
+            RuleCondition::Fermenting => {
+                self.yeast_added && self.phase.ferments() && self.yeast_alive()
+            }
+        }
+    }
+
+    fn rate_factor(&self, factor: &RateFactor, first: Vector3<f32>, second: Vector3<f32>) -> f32 {
+        let midpoint = (first + second) / 2.0;
+        match *factor {
+            RateFactor::Arrhenius {
+                activation_energy,
+                reference_temp,
+            } => arrhenius_factor(
+                activation_energy,
+                self.temperature_at(midpoint),
+                reference_temp,
+            ),
+            RateFactor::YeastMetabolism => {
+                self.yeast_metabolism_at_temperature(self.temperature_at(first))
+            }
+            RateFactor::AmylaseActivity => {
+                self.amylase_activity_at_temperature(self.temperature_at(first))
+            }
+            RateFactor::Osmotic => self.osmotic_factor_at(first),
+            RateFactor::Mixing => self.mixing_factor_at(midpoint),
+            RateFactor::SaltBond => self.salt_bond_factor_at(midpoint),
+            RateFactor::FatCoating => self.fat_coating_factor_at(midpoint),
+            RateFactor::AscorbicAcid => self.ascorbic_bond_factor(),
+            RateFactor::Protease => self.protease_bond_factor(),
+        }
+    }
+
+    // Runs every rule whose condition holds, in table order
+    pub(crate) fn run_reaction_rules(&mut self, dt: f32, kinetics: RuleStep) {
+        for rule in self.reaction_rules() {
+            if self.rule_runs(rule.when) {
+                self.run_rule(&rule, dt, kinetics);
+            }
+        }
+    }
+
+    fn run_rule(&mut self, rule: &ReactionRule, dt: f32, kinetics: RuleStep) {
+        let base = match (rule.per_second, kinetics) {
+            (true, _) => rule.rate * dt,
+            (false, RuleStep::Tick) => rule.rate,
+            // A per-tick rate was tuned at the usual tick
+            (false, RuleStep::Leap) => rule.rate * dt / TUNED_TICK,
+        };
+        // Rules making more molecules than they use hold back near the
+        // budget (see BudgetSettings) and stop once it is full
+        let consumed = [rule.first_fate, rule.second_fate]
+            .iter()
+            .filter(|fate| **fate == Fate::Consumed)
+            .count();
+        let added = rule.products.len().saturating_sub(consumed);
+        let mut room = self.budget.room(self.grid.len());
+        let base = if added > 0 {
+            base * self.budget.throttle(self.grid.len())
+        } else {
+            base
+        };
+        let (first_factors, pair_factors): (Vec<RateFactor>, Vec<RateFactor>) =
+            rule.factors.iter().partition(|factor| factor.at_first());
+        // Reactants that were used up or changed can only react once per tick
+        let mut used: StableHashSet<MoleculeId> = StableHashSet::default();
+        let mut firings = Vec::new();
+        let mut spawned = Vec::new();
+
+        for mol in self.grid.molecules() {
+            if mol.mol_type.species() != rule.first || used.contains(&mol.id) {
+                continue;
+            }
+            let chance_here = first_factors.iter().fold(base, |chance, factor| {
+                chance * self.rate_factor(factor, mol.pos, mol.pos)
+            });
+            if chance_here <= 0.0 {
+                continue;
+            }
+            for neighbor in self.grid.neighbors_within(mol.pos, rule.distance) {
+                let neighbor_id = neighbor.id;
+                if neighbor_id == mol.id || used.contains(&neighbor_id) {
+                    continue;
+                }
+                if neighbor.mol_type.species() != rule.second {
+                    continue;
+                }
+                let chance = pair_factors.iter().fold(chance_here, |chance, factor| {
+                    chance * self.rate_factor(factor, mol.pos, neighbor.pos)
+                });
+                let chance = match kinetics {
+                    RuleStep::Tick => chance,
+                    RuleStep::Leap => 1.0 - (-chance).exp(),
+                };
+                if added > room {
+                    break;
+                }
+                if self.rng.gen::<f32>() >= chance {
+                    continue;
+                }
+                room -= added;
+
+                for product in &rule.products {
+                    let spread = product.spread.max(f32::EPSILON);
+                    let speed = product.speed.max(f32::EPSILON);
+                    let pos = mol.pos
+                        + Vector3::new(
+                            self.rng.gen_range(-spread..spread),
+                            self.rng.gen_range(-spread..spread),
+                            self.rng.gen_range(-spread..spread),
+                        );
+                    let velocity = Vector3::new(
+                        self.rng.gen_range(-speed..speed),
+                        self.rng.gen_range(-speed..speed),
+                        self.rng.gen_range(-speed..speed),
+                    );
+                    spawned.push(Molecule::new(
+                        product.species.molecule_type(),
+                        pos,
+                        velocity,
+                    ));
+                }
+                firings.push(Firing {
+                    first: mol.id,
+                    second: neighbor_id,
+                    distance: self.grid.displacement(mol.pos, neighbor.pos).magnitude(),
+                });
+                if rule.second_fate != Fate::Kept {
+                    used.insert(neighbor_id);
+                }
+                if rule.first_fate != Fate::Kept {
+                    used.insert(mol.id);
+                    break;
+                }
+            }
+        }
+
+        // Products go in before the reactants leave, so they do not take
+        // over the slots freed this tick
+        for mol in spawned {
+            self.observe_produced(&mol);
+            self.grid.insert(mol);
+        }
+        for firing in firings {
+            self.apply_firing(rule, &firing);
+        }
+    }
+
+    fn apply_firing(&mut self, rule: &ReactionRule, firing: &Firing) {
+        match rule.effect {
+            RuleEffect::DisulfideBridge => {
+                self.index_bonds();
+                if self.bond_pairs.contains(firing.first, firing.second) {
+                    return;
+                }
+                let mut bond = self
+                    .solver
+                    .new_bond(firing.first, firing.second, firing.distance);
+                if self.recipe_fat > 0.0 {
+                    if let (Some(a), Some(b)) = (
+                        self.grid.get_molecule(firing.first),
+                        self.grid.get_molecule(firing.second),
+                    ) {
+                        bond.stiffness *= self.fat_softening_at((a.pos + b.pos) / 2.0);
+                    }
+                }
+                if self.improvers.ascorbic_acid > 0.0 {
+                    bond.stiffness *= self.ascorbic_stiffening();
+                }
+                self.reactions.record_bond_formed();
+                self.observe_bond(&bond);
+                self.push_bond(bond);
+            }
+            RuleEffect::Fermentation => self.reactions.record_fermentation(),
+            RuleEffect::Hydrolysis => self.reactions.record_hydrolysis(),
+            RuleEffect::Acid { acetic, lactic } => {
+                self.add_acid(acetic, lactic);
+                self.reactions.record_other_reaction();
+            }
+            RuleEffect::None => self.reactions.record_other_reaction(),
+        }
+        for (id, fate) in [
+            (firing.first, rule.first_fate),
+            (firing.second, rule.second_fate),
+        ] {
+            match fate {
+                Fate::Kept => {}
+                Fate::Becomes(species) => self.grid.set_mol_type(id, species.molecule_type()),
+                Fate::Consumed => self.grid.remove(id),
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{MoleculeId, MoleculeType, SimulationState};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaltSettings {
+    pub resolution: usize,     // Cells along each side of the dough
+    pub dissolution_time: f32, // Mean seconds for a salt grain to dissolve
+    // Seconds for dissolved salt to spread from the surface to the center,
+    // much slower than heat
+    pub diffusion_time: f32,
+    pub reference_salt: f32, // Salt / flour at which the bond boost is the configured one
+    pub osmotic_half: f32,   // Salt / flour at which the yeast works at half speed
+}
+
+impl Default for SaltSettings {
+    fn default() -> Self {
+        SaltSettings {
+            resolution: 6,
+            dissolution_time: 300.0,
+            diffusion_time: 5400.0,
+            reference_salt: 0.02,
+            osmotic_half: 0.05,
+        }
+    }
+}
+
+// Salt dissolved in the dough water, as mass per cell of a coarse grid over
+// the container. Grains dissolve into the cell they sit in and spread out
+// from there.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaltField {
+    resolution: usize,
+    cells: Vec<f32>,
+    total: f32, // Mass of all the salt added, dissolved or not
+}
+
+impl SaltField {
+    pub fn empty(resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        SaltField {
+            resolution,
+            cells: vec![0.0; resolution.pow(3)],
+            total: 0.0,
+        }
+    }
+
+    pub fn dissolved_mass(&self) -> f64 {
+        self.cells.iter().map(|&cell| cell as f64).sum()
+    }
+
+    pub fn total(&self) -> f32 {
+        self.total
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * self.resolution + y) * self.resolution + z
+    }
+
+    fn cell_of(&self, pos: Vector3<f32>, size: Vector3<f32>) -> usize {
+        let n = self.resolution;
+        let axis = |coord: f32, extent: f32| {
+            ((coord / extent.max(f32::EPSILON) * n as f32).max(0.0) as usize).min(n - 1)
+        };
+        self.index(
+            axis(pos.x, size.x),
+            axis(pos.y, size.y),
+            axis(pos.z, size.z),
+        )
+    }
+
+    // Mass of the cell around `pos`
+    pub fn mass_at(&self, pos: Vector3<f32>, size: Vector3<f32>) -> f32 {
+        if self.cells.is_empty() {
+            return 0.0;
+        }
+        self.cells[self.cell_of(pos, size)]
+    }
+
+    fn dissolve(&mut self, pos: Vector3<f32>, size: Vector3<f32>, mass: f32) {
+        let cell = self.cell_of(pos, size);
+        self.cells[cell] += mass;
+    }
+
+    // Explicit diffusion between neighboring cells, sub-stepped to stay
+    // stable; nothing crosses the walls of the container
+    fn diffuse(&mut self, dt: f32, size: Vector3<f32>, diffusion_time: f32) {
+        let n = self.resolution;
+        if n < 2 || dt <= 0.0 {
+            return;
+        }
+        let half_side = size.x.min(size.y).min(size.z).max(f32::EPSILON) / 2.0;
+        let diffusivity = half_side * half_side / diffusion_time.max(1.0);
+        let rates = size.map(|extent| {
+            let cell = extent.max(f32::EPSILON) / n as f32;
+            diffusivity / (cell * cell)
+        });
+        let total_rate = 2.0 * (rates.x + rates.y + rates.z);
+        let substeps = ((dt * total_rate).ceil() as usize).max(1);
+        let sub_dt = dt / substeps as f32;
+
+        let mut next = self.cells.clone();
+        for _ in 0..substeps {
+            for x in 0..n {
+                for y in 0..n {
+                    for z in 0..n {
+                        let here = self.cells[self.index(x, y, z)];
+                        let mut flow = 0.0;
+                        let mut neighbor = |nx: usize, ny: usize, nz: usize, rate: f32| {
+                            flow += rate * (self.cells[self.index(nx, ny, nz)] - here);
+                        };
+                        if x > 0 {
+                            neighbor(x - 1, y, z, rates.x);
+                        }
+                        if x + 1 < n {
+                            neighbor(x + 1, y, z, rates.x);
+                        }
+                        if y > 0 {
+                            neighbor(x, y - 1, z, rates.y);
+                        }
+                        if y + 1 < n {
+                            neighbor(x, y + 1, z, rates.y);
+                        }
+                        if z > 0 {
+                            neighbor(x, y, z - 1, rates.z);
+                        }
+                        if z + 1 < n {
+                            neighbor(x, y, z + 1, rates.z);
+                        }
+                        next[self.index(x, y, z)] = here + flow * sub_dt;
+                    }
+                }
+            }
+            std::mem::swap(&mut self.cells, &mut next);
+        }
+    }
+}
+
+impl SimulationState {
+    fn salt_container(&self) -> Vector3<f32> {
+        Vector3::new(self.width, self.height, self.depth)
+    }
+
+    // Remembers how much salt went in, the scale of the concentrations
+    pub(crate) fn expect_salt(&mut self, mass: f32) {
+        if self.salt.cells.is_empty() {
+            self.salt = SaltField::empty(self.salt_settings.resolution);
+        }
+        self.salt.total += mass;
+    }
+
+    // Dissolved salt around `pos` as salt / flour, like the recipe: the
+    // recipe's salt once it has spread evenly
+    pub fn salt_concentration_at(&self, pos: Vector3<f32>) -> f32 {
+        if self.salt.total <= 0.0 {
+            return 0.0;
+        }
+        let even = self.salt.total / self.salt.cells.len() as f32;
+        self.recipe_salt * self.salt.mass_at(pos, self.salt_container()) / even
+    }
+
+    // Share of the salt added that has dissolved
+    pub fn dissolved_salt_fraction(&self) -> f32 {
+        if self.salt.total <= 0.0 {
+            return 0.0;
+        }
+        (self.salt.dissolved_mass() as f32 / self.salt.total).min(1.0)
+    }
+
+    // Gluten tightening by the salt around a forming bond: none without salt,
+    // the configured boost at the reference salt, at most twice that
+    pub fn salt_bond_factor_at(&self, pos: Vector3<f32>) -> f32 {
+        let reference = self.salt_settings.reference_salt.max(f32::EPSILON);
+        let relative = (self.salt_concentration_at(pos) / reference).min(2.0);
+        1.0 + (self.config.salt_bond_boost - 1.0) * relative
+    }
+
+    // Osmotic stress: salty water draws water out of the yeast cells and
+    // slows them down, to half at `osmotic_half`
+    pub fn osmotic_factor_at(&self, pos: Vector3<f32>) -> f32 {
+        let half = self.salt_settings.osmotic_half.max(f32::EPSILON);
+        let relative = self.salt_concentration_at(pos) / half;
+        1.0 / (1.0 + relative * relative)
+    }
+
+    // Grains dissolve into the field, the field spreads out. The field's
+    // mass is part of the dough, so the mass ledger does not move.
+    pub(crate) fn update_salt(&mut self, dt: f32) {
+        if self.salt.total <= 0.0 {
+            return;
+        }
+        let size = self.salt_container();
+        let chance = dt / self.salt_settings.dissolution_time.max(f32::EPSILON);
+        let grains: Vec<(MoleculeId, Vector3<f32>, f32)> = self
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Salt))
+            .map(|mol| (mol.id, mol.pos, mol.mass()))
+            .collect();
+        for (id, pos, mass) in grains {
+            if self.rng.gen::<f32>() < chance {
+                self.grid.remove(id);
+                self.salt.dissolve(pos, size, mass);
+            }
+        }
+        let diffusion_time = self.salt_settings.diffusion_time;
+        self.salt.diffuse(dt, size, diffusion_time);
+    }
+}
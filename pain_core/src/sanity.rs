@@ -0,0 +1,317 @@
+use crate::{Molecule, MoleculeId, SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Quarantined molecules logged one by one per tick; the counters keep the rest
+const QUARANTINE_LOG_LIMIT: usize = 8;
+
+// What happens to a finite molecule found outside the container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EscapePolicy {
+    // Put it back just inside the nearest wall
+    #[default]
+    Reproject,
+    Remove,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SanitySettings {
+    pub escape_policy: EscapePolicy,
+    pub escape_margin: f32, // Distance past a wall tolerated before a molecule counts as escaped
+}
+
+impl Default for SanitySettings {
+    fn default() -> Self {
+        SanitySettings {
+            escape_policy: EscapePolicy::Reproject,
+            escape_margin: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuarantineReason {
+    NonFinite, // NaN or infinite position or velocity, always removed
+    Escaped,
+}
+
+// How many degenerate molecules were caught since the start of the run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineCounters {
+    pub non_finite_removed: u64,
+    pub escaped_reprojected: u64,
+    pub escaped_removed: u64,
+    pub bonds_dropped: u64, // Bonds lost with a removed glutenin
+}
+
+impl QuarantineCounters {
+    pub fn total(&self) -> u64 {
+        self.non_finite_removed + self.escaped_reprojected + self.escaped_removed
+    }
+}
+
+impl fmt::Display for QuarantineCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} non-finite removed, {} escaped put back, {} escaped removed, {} bonds dropped",
+            self.non_finite_removed, self.escaped_reprojected, self.escaped_removed, self.bonds_dropped
+        )
+    }
+}
+
+impl fmt::Display for QuarantineReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuarantineReason::NonFinite => write!(f, "non-finite state"),
+            QuarantineReason::Escaped => write!(f, "escaped the container"),
+        }
+    }
+}
+
+fn is_finite(mol: &Molecule) -> bool {
+    mol.pos.iter().all(|c| c.is_finite()) && mol.velocity.iter().all(|c| c.is_finite())
+}
+
+impl SimulationState {
+    fn has_escaped(&self, pos: Vector3<f32>) -> bool {
+        let margin = self.sanity.escape_margin;
+        let extents = [self.width, self.height, self.depth];
+        pos.iter()
+            .zip(extents)
+            .any(|(&c, extent)| c < -margin || c > extent + margin)
+    }
+
+    // Catches molecules with a NaN/infinite state or outside the box before
+    // they corrupt the grid: each one is logged with its position and either
+    // removed (with its bonds) or put back inside, depending on the policy
+    pub(crate) fn quarantine_degenerate_molecules(&mut self) {
+        let suspects: Vec<(MoleculeId, QuarantineReason)> = self
+            .grid
+            .molecules()
+            .filter_map(|mol| {
+                if !is_finite(mol) {
+                    Some((mol.id, QuarantineReason::NonFinite))
+                } else if self.has_escaped(mol.pos) {
+                    Some((mol.id, QuarantineReason::Escaped))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for (logged, (id, reason)) in suspects.into_iter().enumerate() {
+            // Wherever its id ended up in the grid, the molecule is taken out of every cell
+            let Some(mut mol) = self.grid.take_everywhere(id) else {
+                continue;
+            };
+            let species = mol.mol_type.species();
+            let pos = mol.pos;
+            let removed = reason == QuarantineReason::NonFinite
+                || self.sanity.escape_policy == EscapePolicy::Remove;
+
+            if removed {
+                self.ledger.quarantined_mass += mol.mass() as f64;
+                self.quarantine.bonds_dropped += self.drop_bonds_of(id);
+                match reason {
+                    QuarantineReason::NonFinite => self.quarantine.non_finite_removed += 1,
+                    QuarantineReason::Escaped => self.quarantine.escaped_removed += 1,
+                }
+            } else {
+                let radius = mol.radius();
+                mol.pos = Vector3::new(
+                    pos.x.clamp(radius, (self.width - radius).max(radius)),
+                    pos.y.clamp(radius, (self.height - radius).max(radius)),
+                    pos.z.clamp(radius, (self.depth - radius).max(radius)),
+                );
+                mol.velocity = Vector3::zeros();
+                self.grid.insert_with_id(mol);
+                self.quarantine.escaped_reprojected += 1;
+            }
+
+            if logged < QUARANTINE_LOG_LIMIT {
+                self.events.push(
+                    self.time_elapsed,
+                    SimulationEvent::MoleculeQuarantined {
+                        molecule_id: id,
+                        species,
+                        pos,
+                        reason,
+                        removed,
+                    },
+                );
+            }
+        }
+    }
+
+    // Bonds to a molecule that is gone: its thiol leaves with it, the partner's is freed
+    pub(crate) fn drop_bonds_of(&mut self, id: MoleculeId) -> u64 {
+        let dropped = self
+            .take_bonds_where(|_, bond| bond.molecule_a_id == id || bond.molecule_b_id == id);
+        for bond in &dropped {
+            self.release_bond(bond);
+            // The removed glutenin's thiol is no longer bound either
+            self.reactions.thiols_freed += 1;
+        }
+        dropped.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MoleculeType, Species};
+
+    fn dough() -> SimulationState {
+        SimulationState::builder()
+            .seed(7)
+            .size(200.0, 200.0, 200.0)
+            .build()
+    }
+
+    fn first_of(state: &SimulationState, species: Species) -> MoleculeId {
+        state
+            .grid
+            .molecules()
+            .find(|mol| mol.mol_type.species() == species)
+            .map(|mol| mol.id)
+            .expect("the dough holds that species")
+    }
+
+    // (molecule, reason, removed) for every quarantine logged
+    fn quarantine_events(state: &SimulationState) -> Vec<(MoleculeId, QuarantineReason, bool)> {
+        state
+            .events
+            .iter()
+            .filter_map(|entry| match entry.event {
+                SimulationEvent::MoleculeQuarantined {
+                    molecule_id,
+                    reason,
+                    removed,
+                    ..
+                } => Some((molecule_id, reason, removed)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn assert_grid_consistent(state: &SimulationState) {
+        assert_eq!(*state.grid.species_counts(), state.grid.recount_species());
+        assert!(state.first_non_finite().is_none());
+    }
+
+    #[test]
+    fn non_finite_molecule_is_removed() {
+        let mut state = dough();
+        let id = first_of(&state, Species::Water);
+        let count = state.grid.len();
+        state.grid.get_molecule_mut(id).unwrap().pos.x = f32::NAN;
+
+        state.quarantine_degenerate_molecules();
+
+        assert_eq!(state.quarantine.non_finite_removed, 1);
+        assert_eq!(state.quarantine.total(), 1);
+        assert_eq!(
+            quarantine_events(&state),
+            vec![(id, QuarantineReason::NonFinite, true)]
+        );
+        assert!(state.grid.get_molecule(id).is_none());
+        assert!(state.grid.molecules().all(|mol| mol.id != id));
+        assert_eq!(state.grid.len(), count - 1);
+        assert!(state.ledger.quarantined_mass > 0.0);
+        assert!(state.is_mass_conserved());
+        assert_grid_consistent(&state);
+    }
+
+    #[test]
+    fn escaped_molecule_is_put_back_or_removed() {
+        let mut state = dough();
+        let id = first_of(&state, Species::Water);
+        let outside = Vector3::new(state.width + 50.0, 100.0, 100.0);
+        {
+            let mol = state.grid.get_molecule_mut(id).unwrap();
+            mol.pos = outside;
+            mol.velocity = Vector3::new(3.0, 0.0, 0.0);
+        }
+
+        state.quarantine_degenerate_molecules();
+
+        assert_eq!(state.quarantine.escaped_reprojected, 1);
+        assert_eq!(state.quarantine.escaped_removed, 0);
+        assert_eq!(
+            quarantine_events(&state),
+            vec![(id, QuarantineReason::Escaped, false)]
+        );
+        let mol = state.grid.get_molecule(id).expect("put back in the grid");
+        assert!(mol.pos.x <= state.width - mol.radius());
+        assert_eq!(mol.velocity, Vector3::zeros());
+        let pos = mol.pos;
+        assert!(state
+            .grid
+            .neighbors_within(pos, 1.0)
+            .any(|mol| mol.id == id));
+        assert_grid_consistent(&state);
+
+        // The same escape with the remove policy
+        let mut state = dough();
+        state.sanity.escape_policy = EscapePolicy::Remove;
+        let id = first_of(&state, Species::Water);
+        state.grid.get_molecule_mut(id).unwrap().pos = outside;
+
+        state.quarantine_degenerate_molecules();
+
+        assert_eq!(state.quarantine.escaped_removed, 1);
+        assert_eq!(state.quarantine.escaped_reprojected, 0);
+        assert_eq!(
+            quarantine_events(&state),
+            vec![(id, QuarantineReason::Escaped, true)]
+        );
+        assert!(state.grid.get_molecule(id).is_none());
+        assert!(state.is_mass_conserved());
+        assert_grid_consistent(&state);
+    }
+
+    #[test]
+    fn bond_to_quarantined_molecule_is_dropped() {
+        let mut state = dough();
+        let glutenins: Vec<MoleculeId> = state
+            .grid
+            .molecules()
+            .filter(|mol| mol.mol_type.species() == Species::ReactiveGlutenin)
+            .map(|mol| mol.id)
+            .collect();
+        let (a, b) = (glutenins[0], glutenins[1]);
+        // Bridged the way the disulfide rule does it
+        let bound = MoleculeType::Glutenin {
+            has_free_thiol: false,
+        };
+        state.grid.set_mol_type(a, bound.clone());
+        state.grid.set_mol_type(b, bound);
+        let bond = state.solver.new_bond(a, b, 5.0);
+        state.push_bond(bond);
+        let freed = state.reactions.thiols_freed;
+
+        state.grid.get_molecule_mut(a).unwrap().velocity.y = f32::INFINITY;
+        state.quarantine_degenerate_molecules();
+
+        assert_eq!(state.quarantine.non_finite_removed, 1);
+        assert_eq!(state.quarantine.bonds_dropped, 1);
+        assert_eq!(
+            quarantine_events(&state),
+            vec![(a, QuarantineReason::NonFinite, true)]
+        );
+        assert!(state.bonds.is_empty());
+        assert!(!state.bond_pairs.contains(a, b));
+        assert!(state.grid.get_molecule(a).is_none());
+        assert_eq!(
+            state.grid.get_molecule(b).map(|mol| mol.mol_type.clone()),
+            Some(MoleculeType::Glutenin {
+                has_free_thiol: true
+            })
+        );
+        // The partner's thiol, and the one that left with the molecule
+        assert_eq!(state.reactions.thiols_freed, freed + 2);
+        assert_grid_consistent(&state);
+    }
+}
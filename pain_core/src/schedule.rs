@@ -0,0 +1,188 @@
+use crate::actions::SimAction;
+use crate::SimulationState;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleStep {
+    pub label: String,
+    pub at: f32, // Sim time in seconds
+    pub action: Option<SimAction>,
+    // Once `at` is reached the step also waits for this, e.g. "mix until the
+    // gluten is 80% developed"; later steps wait behind it
+    #[serde(default)]
+    pub when: Option<StepCondition>,
+    // Sim seconds after `at` when the step fires even if `when` is not met,
+    // so a dough that never gets there does not hold the whole process
+    #[serde(default)]
+    pub timeout: Option<f32>,
+}
+
+impl ScheduleStep {
+    fn timed_out(&self, time: f32) -> bool {
+        self.timeout
+            .is_some_and(|timeout| time >= self.at + timeout)
+    }
+}
+
+// What the baker waits for rather than a clock time: "bulk until 50% rise"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StepCondition {
+    DevelopmentAtLeast(f32), // Gluten development gauge, 0 to 1
+    RiseAtLeast(f32),        // Rise as a share of the starting dough height, 0.5 for 50%
+    PhAtMost(f32),
+    TemperatureAtLeast(f32), // °C, warming up in the proofer
+    TemperatureAtMost(f32),  // °C, cooling down in the fridge
+}
+
+impl StepCondition {
+    pub fn is_met(&self, state: &SimulationState) -> bool {
+        // Gauges that walk the molecules are read from the metrics, polling runs every tick
+        let latest = state.metrics.latest();
+        match *self {
+            StepCondition::DevelopmentAtLeast(threshold) => {
+                let development = match latest {
+                    Some(sample) => sample.development,
+                    None => state.gluten_development().total,
+                };
+                development >= threshold
+            }
+            StepCondition::RiseAtLeast(threshold) => {
+                let Some(start) = state.collapse.start_height().filter(|start| *start > 0.0) else {
+                    return false;
+                };
+                let rise = latest.map_or_else(|| state.rise(), |sample| sample.rise);
+                rise / start >= threshold
+            }
+            StepCondition::PhAtMost(threshold) => state.dough_ph() <= threshold,
+            StepCondition::TemperatureAtLeast(threshold) => state.temperature >= threshold,
+            StepCondition::TemperatureAtMost(threshold) => state.temperature <= threshold,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match *self {
+            StepCondition::DevelopmentAtLeast(threshold) => {
+                format!("développement ≥ {:.0} %", threshold * 100.0)
+            }
+            StepCondition::RiseAtLeast(threshold) => {
+                format!("levée ≥ {:.0} %", threshold * 100.0)
+            }
+            StepCondition::PhAtMost(threshold) => format!("pH ≤ {:.1}", threshold),
+            StepCondition::TemperatureAtLeast(threshold) => format!("≥ {:.0} °C", threshold),
+            StepCondition::TemperatureAtMost(threshold) => format!("≤ {:.0} °C", threshold),
+        }
+    }
+}
+
+// Compact view of the dough at a given moment, what the simulation "predicts"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DoughSummary {
+    pub time: f32,
+    pub temperature: f32,
+    pub bond_count: usize,
+    pub co2_count: usize,
+    pub sugar_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepNotification {
+    pub label: String,
+    pub scheduled_at: f32,
+    pub predicted: DoughSummary,
+    pub timed_out: bool, // Fired by its timeout, its condition was never met
+}
+
+// The baker's process: an ordered list of steps (fold, shape, bake...) that
+// fire once sim time reaches them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessSchedule {
+    steps: Vec<ScheduleStep>,
+    next_step: usize,
+}
+
+impl ProcessSchedule {
+    pub fn new(mut steps: Vec<ScheduleStep>) -> Self {
+        steps.sort_by(|a, b| a.at.total_cmp(&b.at));
+        ProcessSchedule {
+            steps,
+            next_step: 0,
+        }
+    }
+
+    pub fn steps(&self) -> &[ScheduleStep] {
+        &self.steps
+    }
+
+    pub fn next_step(&self) -> Option<&ScheduleStep> {
+        self.steps.get(self.next_step)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_step >= self.steps.len()
+    }
+
+    // Back to the first step, for a new dough
+    pub fn restart(&mut self) {
+        self.next_step = 0;
+    }
+
+    pub fn skip_before(&mut self, time: f32) {
+        self.next_step = self.steps.iter().take_while(|step| step.at < time).count();
+    }
+
+    // Steps fired so far
+    pub fn completed(&self) -> usize {
+        self.next_step
+    }
+
+    // Fires every step that is due, applying its action to the simulation and
+    // returning a notification with the dough state at that moment.
+    pub fn poll(&mut self, state: &mut SimulationState) -> Vec<StepNotification> {
+        let mut notifications = Vec::new();
+
+        while let Some(step) = self.steps.get(self.next_step) {
+            if step.at > state.time_elapsed {
+                break;
+            }
+            let waiting = step.when.is_some_and(|condition| !condition.is_met(state));
+            let timed_out = waiting && step.timed_out(state.time_elapsed);
+            if waiting && !timed_out {
+                break;
+            }
+            if let Some(action) = &step.action {
+                state.apply_action(action);
+            }
+            notifications.push(StepNotification {
+                label: step.label.clone(),
+                scheduled_at: step.at,
+                predicted: state.summary(),
+                timed_out,
+            });
+            self.next_step += 1;
+        }
+
+        notifications
+    }
+}
+
+impl SimulationState {
+    pub fn summary(&self) -> DoughSummary {
+        let mut co2_count = 0;
+        let mut sugar_count = 0;
+        for mol in self.grid.get_all_molecules() {
+            match mol.mol_type {
+                crate::MoleculeType::CO2 => co2_count += 1,
+                crate::MoleculeType::Sugar => sugar_count += 1,
+                _ => {}
+            }
+        }
+
+        DoughSummary {
+            time: self.time_elapsed,
+            temperature: self.temperature,
+            bond_count: self.bonds.len(),
+            co2_count,
+            sugar_count,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{MoleculeType, SimulationState};
+
+// A slice through the middle of the dough, like the crumb shot of a loaf cut
+// in half: the dough and the gas molecules of a thin slab counted per pixel,
+// top row first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossSection {
+    pub columns: usize,
+    pub rows: usize,
+    pub dough: Vec<u32>, // Molecules other than CO2 per pixel
+    pub gas: Vec<u32>,   // CO2 per pixel, the bubbles of the crumb
+}
+
+impl CrossSection {
+    pub fn index(&self, column: usize, row: usize) -> usize {
+        row * self.columns + column
+    }
+
+    // Densest pixel, to scale a picture of the slice
+    pub fn max_dough(&self) -> u32 {
+        self.dough.iter().copied().max().unwrap_or(0)
+    }
+}
+
+impl SimulationState {
+    // Slab of `thickness` around the middle of the depth, cut in `columns`
+    // pixels across the width and as many rows as the height needs
+    pub fn cross_section(&self, columns: usize, thickness: f32) -> CrossSection {
+        let columns = columns.max(1);
+        let pixel = self.width.max(f32::EPSILON) / columns as f32;
+        let rows = ((self.height / pixel).round() as usize).max(1);
+        let mut section = CrossSection {
+            columns,
+            rows,
+            dough: vec![0; columns * rows],
+            gas: vec![0; columns * rows],
+        };
+        let middle = self.depth / 2.0;
+        for mol in self.grid.molecules() {
+            if (mol.pos.z - middle).abs() > thickness / 2.0 {
+                continue;
+            }
+            let column = ((mol.pos.x / pixel).max(0.0) as usize).min(columns - 1);
+            let row_from_bottom = ((mol.pos.y / pixel).max(0.0) as usize).min(rows - 1);
+            let index = section.index(column, rows - 1 - row_from_bottom);
+            if matches!(mol.mol_type, MoleculeType::CO2) {
+                section.gas[index] += 1;
+            } else {
+                section.dough[index] += 1;
+            }
+        }
+        section
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{Recipe, SimulationConfig, SimulationState};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Which knob of a recipe matters: every parameter is nudged down and up by
+// a few percent, one at a time, and the same seeded headless runs are redone
+// to see how far the loaf moves. The spread of the baseline over the seeds
+// tells a real effect from run-to-run noise.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RecipeParameter {
+    Hydration,
+    Salt,
+    Yeast,
+    ProteinContent,
+    GluteninFraction,
+    AutolyseTime,
+    Temperature,
+    AmylaseActivity,
+}
+
+impl RecipeParameter {
+    pub fn all() -> [RecipeParameter; 8] {
+        [
+            RecipeParameter::Hydration,
+            RecipeParameter::Salt,
+            RecipeParameter::Yeast,
+            RecipeParameter::ProteinContent,
+            RecipeParameter::GluteninFraction,
+            RecipeParameter::AutolyseTime,
+            RecipeParameter::Temperature,
+            RecipeParameter::AmylaseActivity,
+        ]
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecipeParameter::Hydration => "hydratation",
+            RecipeParameter::Salt => "sel",
+            RecipeParameter::Yeast => "levain",
+            RecipeParameter::ProteinContent => "protéines",
+            RecipeParameter::GluteninFraction => "part de gluténines",
+            RecipeParameter::AutolyseTime => "autolyse",
+            RecipeParameter::Temperature => "température",
+            RecipeParameter::AmylaseActivity => "activité amylasique",
+        }
+    }
+
+    pub fn get(&self, recipe: &Recipe) -> f32 {
+        match self {
+            RecipeParameter::Hydration => recipe.hydration,
+            RecipeParameter::Salt => recipe.salt,
+            RecipeParameter::Yeast => recipe.yeast,
+            RecipeParameter::ProteinContent => recipe.protein_content,
+            RecipeParameter::GluteninFraction => recipe.glutenin_fraction,
+            RecipeParameter::AutolyseTime => recipe.autolyse_time,
+            RecipeParameter::Temperature => recipe.temperature,
+            RecipeParameter::AmylaseActivity => recipe.amylase_activity,
+        }
+    }
+
+    pub fn set(&self, recipe: &mut Recipe, value: f32) {
+        let field = match self {
+            RecipeParameter::Hydration => &mut recipe.hydration,
+            RecipeParameter::Salt => &mut recipe.salt,
+            RecipeParameter::Yeast => &mut recipe.yeast,
+            RecipeParameter::ProteinContent => &mut recipe.protein_content,
+            RecipeParameter::GluteninFraction => &mut recipe.glutenin_fraction,
+            RecipeParameter::AutolyseTime => &mut recipe.autolyse_time,
+            RecipeParameter::Temperature => &mut recipe.temperature,
+            RecipeParameter::AmylaseActivity => &mut recipe.amylase_activity,
+        };
+        *field = value;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Outcome {
+    Volume,
+    OpenCrumb,
+    Ph,
+}
+
+impl Outcome {
+    pub fn all() -> [Outcome; 3] {
+        [Outcome::Volume, Outcome::OpenCrumb, Outcome::Ph]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Outcome::Volume => "volume",
+            Outcome::OpenCrumb => "mie ouverte",
+            Outcome::Ph => "pH",
+        }
+    }
+}
+
+// The end of one run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Outcomes {
+    pub volume_ratio: f32,
+    pub open_crumb: f32, // Open-crumb score, 0 to 1
+    pub ph: f32,
+}
+
+impl Outcomes {
+    pub fn get(&self, outcome: Outcome) -> f32 {
+        match outcome {
+            Outcome::Volume => self.volume_ratio,
+            Outcome::OpenCrumb => self.open_crumb,
+            Outcome::Ph => self.ph,
+        }
+    }
+
+    fn mean(runs: &[Outcomes]) -> Outcomes {
+        let count = runs.len().max(1) as f32;
+        Outcomes {
+            volume_ratio: runs.iter().map(|run| run.volume_ratio).sum::<f32>() / count,
+            open_crumb: runs.iter().map(|run| run.open_crumb).sum::<f32>() / count,
+            ph: runs.iter().map(|run| run.ph).sum::<f32>() / count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivitySettings {
+    pub step: f32, // Relative nudge of each parameter, down and up
+    pub parameters: Vec<RecipeParameter>,
+    pub seeds: Vec<u64>, // Every recipe variant is run once per seed
+    pub duration: f32,   // Simulated seconds of each run
+    pub dt: f32,
+    pub size: (f32, f32, f32), // Container of each run, small enough to run many
+    pub config: SimulationConfig,
+}
+
+impl Default for SensitivitySettings {
+    fn default() -> Self {
+        SensitivitySettings {
+            step: 0.05,
+            parameters: RecipeParameter::all().to_vec(),
+            seeds: vec![1, 2, 3],
+            duration: 2.0 * 3600.0,
+            dt: 0.5,
+            size: (200.0, 200.0, 200.0),
+            config: SimulationConfig::default(),
+        }
+    }
+}
+
+impl SensitivitySettings {
+    pub fn run_count(&self) -> usize {
+        (1 + 2 * self.parameters.len()) * self.seeds.len()
+    }
+}
+
+// One parameter nudged down and up, the outcomes averaged over the seeds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterEffect {
+    pub parameter: RecipeParameter,
+    pub base_value: f32,
+    pub low_value: f32,
+    pub high_value: f32,
+    pub low: Outcomes,
+    pub high: Outcomes,
+}
+
+// One bar of a tornado chart: how far an outcome moves from the baseline
+// with the parameter low and high
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TornadoBar {
+    pub parameter: RecipeParameter,
+    pub outcome: Outcome,
+    pub low_change: f32,
+    pub high_change: f32,
+    pub swing: f32, // |high - low|, what the bars are sorted by
+    // Swing over the spread of the baseline across seeds; None with a single
+    // seed or a baseline that never varies
+    pub effect_size: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityReport {
+    pub recipe: Recipe,
+    pub step: f32,
+    pub baseline: Outcomes,
+    pub baseline_runs: Vec<Outcomes>, // One per seed
+    pub effects: Vec<ParameterEffect>,
+}
+
+impl SensitivityReport {
+    // Spread of an outcome over the baseline seeds (sample standard deviation)
+    pub fn noise(&self, outcome: Outcome) -> Option<f32> {
+        let count = self.baseline_runs.len();
+        if count < 2 {
+            return None;
+        }
+        let mean = self.baseline.get(outcome);
+        let variance = self
+            .baseline_runs
+            .iter()
+            .map(|run| (run.get(outcome) - mean).powi(2))
+            .sum::<f32>()
+            / (count - 1) as f32;
+        Some(variance.sqrt()).filter(|noise| *noise > 0.0)
+    }
+
+    // The tornado chart of one outcome, widest swing first
+    pub fn tornado(&self, outcome: Outcome) -> Vec<TornadoBar> {
+        let baseline = self.baseline.get(outcome);
+        let noise = self.noise(outcome);
+        let mut bars: Vec<TornadoBar> = self
+            .effects
+            .iter()
+            .map(|effect| {
+                let (low, high) = (effect.low.get(outcome), effect.high.get(outcome));
+                let swing = (high - low).abs();
+                TornadoBar {
+                    parameter: effect.parameter,
+                    outcome,
+                    low_change: low - baseline,
+                    high_change: high - baseline,
+                    swing,
+                    effect_size: noise.map(|noise| swing / noise),
+                }
+            })
+            .collect();
+        bars.sort_by(|a, b| b.swing.total_cmp(&a.swing));
+        bars
+    }
+}
+
+// Builds, runs and measures one dough. Salt and levain go in when the
+// autolyse ends, the way the headless runs add them.
+pub fn run_recipe(recipe: &Recipe, seed: u64, settings: &SensitivitySettings) -> Outcomes {
+    let (width, height, depth) = settings.size;
+    let mut state = SimulationState::builder()
+        .recipe(recipe.clone())
+        .config(settings.config.clone())
+        .seed(seed)
+        .size(width, height, depth)
+        .build();
+    state.use_fixed_steps(settings.dt);
+    let ticks = (settings.duration / settings.dt).ceil() as u64;
+    for _ in 0..ticks {
+        if state.time_elapsed >= recipe.autolyse_time {
+            state.add_salt();
+            state.add_yeast();
+        }
+        state.tick(settings.dt);
+    }
+    Outcomes {
+        volume_ratio: state.rise_metrics().volume_ratio,
+        open_crumb: state.crumb_analysis().open_crumb_score,
+        ph: state.dough_ph(),
+    }
+}
+
+// Runs the baseline and every nudged recipe over all the seeds, on all cores
+// with the `parallel` feature. `progress` hears the number of runs done.
+pub fn run_sensitivity(
+    recipe: &Recipe,
+    settings: &SensitivitySettings,
+    progress: impl Fn(usize) + Sync,
+) -> SensitivityReport {
+    // Variant 0 is the baseline, then each parameter low and high
+    let mut variants = vec![recipe.clone()];
+    for parameter in &settings.parameters {
+        let base = parameter.get(recipe);
+        for factor in [1.0 - settings.step, 1.0 + settings.step] {
+            let mut variant = recipe.clone();
+            parameter.set(&mut variant, base * factor);
+            variants.push(variant);
+        }
+    }
+    let jobs: Vec<(usize, u64)> = (0..variants.len())
+        .flat_map(|variant| settings.seeds.iter().map(move |&seed| (variant, seed)))
+        .collect();
+    let done = AtomicUsize::new(0);
+    let run = |&(variant, seed): &(usize, u64)| {
+        let outcomes = run_recipe(&variants[variant], seed, settings);
+        progress(done.fetch_add(1, Ordering::Relaxed) + 1);
+        outcomes
+    };
+    #[cfg(feature = "parallel")]
+    let results: Vec<Outcomes> = jobs.par_iter().map(run).collect();
+    #[cfg(not(feature = "parallel"))]
+    let results: Vec<Outcomes> = jobs.iter().map(run).collect();
+
+    let per_variant: Vec<&[Outcomes]> = results.chunks(settings.seeds.len().max(1)).collect();
+    let baseline_runs = per_variant
+        .first()
+        .map(|runs| runs.to_vec())
+        .unwrap_or_default();
+    let effects = settings
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(index, &parameter)| ParameterEffect {
+            parameter,
+            base_value: parameter.get(recipe),
+            low_value: parameter.get(&variants[1 + 2 * index]),
+            high_value: parameter.get(&variants[2 + 2 * index]),
+            low: Outcomes::mean(per_variant.get(1 + 2 * index).copied().unwrap_or_default()),
+            high: Outcomes::mean(per_variant.get(2 + 2 * index).copied().unwrap_or_default()),
+        })
+        .collect();
+    SensitivityReport {
+        recipe: recipe.clone(),
+        step: settings.step,
+        baseline: Outcomes::mean(&baseline_runs),
+        baseline_runs,
+        effects,
+    }
+}
@@ -0,0 +1,657 @@
+use crate::snapshot::{decode_state, encode_state, SAVE_VERSION};
+use crate::{DoughPhase, Perturbation, SimAction, SimulationEvent, SimulationState};
+use nalgebra::Vector3;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::RangeInclusive;
+
+pub const DEFAULT_PORT: u16 = 7878;
+// The host is a participant like the others
+pub const HOST: u32 = 0;
+// State frames are the save format, compressed
+pub const FRAME_VERSION: u32 = SAVE_VERSION;
+// Seconds of sim time during which someone else's gesture of the same kind
+// blocks yours: two bakers folding at once is a conflict, not two folds
+const CONFLICT_WINDOW: f32 = 10.0;
+// Messages are length-prefixed; anything bigger is a broken peer
+const MAX_MESSAGE: usize = 64 << 20;
+const FRAME_ZSTD_LEVEL: i32 = 1;
+// What a guest may ask for: anything beyond is a broken or hostile peer
+// and would crash the host or make it allocate without end
+const MAX_DOSES: f32 = 10.0; // Improvers and dusting, in usual doses
+const MAX_FORCE: f32 = 1000.0;
+const DOUGH_TEMPERATURE: RangeInclusive<f32> = -20.0..=60.0;
+const OVEN_TEMPERATURE: RangeInclusive<f32> = 100.0..=300.0;
+const MAX_DURATION: f32 = 3600.0; // Hand moves and temperature spikes, s
+const MAX_NOISE: f32 = 10.0;
+const MAX_SPIKE: f32 = 30.0; // °C either way
+const MAX_DRAFT: f32 = 10.0; // m/s
+
+// Where a participant's camera is and what it looks at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraCursor {
+    pub participant: u32,
+    pub name: String,
+    pub position: Vector3<f32>,
+    pub target: Vector3<f32>,
+}
+
+// One line of the log every participant sees
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub time: f32, // Sim time
+    pub participant: u32,
+    pub name: String,
+    pub text: String,
+    pub action: Option<SimAction>,
+    pub refused: bool, // Conflicting or pointless, not applied
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Hello {
+        name: String,
+    },
+    Action(SimAction),
+    Cursor {
+        position: Vector3<f32>,
+        target: Vector3<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome {
+        participant: u32,
+        log: Vec<SessionEntry>,
+    },
+    // Compressed state, see decode_frame. A guest built with another
+    // FRAME_VERSION cannot read it and must leave rather than decode garbage.
+    State {
+        version: u32,
+        frame: Vec<u8>,
+    },
+    Cursors(Vec<CameraCursor>),
+    Entry(SessionEntry),
+}
+
+// Gestures of the same kind conflict with each other
+fn action_kind(action: &SimAction) -> &'static str {
+    match action {
+        SimAction::AddSalt => "sel",
+        SimAction::AddYeast => "levure",
+        SimAction::Fold { .. } | SimAction::Knead(_) => "rabat",
+        SimAction::AdvanceTo(_) | SimAction::Bake(_) => "phase",
+        SimAction::SetTemperature(_) => "température",
+        SimAction::DustFlour(_) => "farine",
+        SimAction::AddMalt(_) | SimAction::AddAscorbicAcid(_) => "améliorant",
+        SimAction::MoveHand { .. } | SimAction::LiftHands => "mains",
+        SimAction::SetEnvironment(_) | SimAction::SetDraft { .. } => "environnement",
+        SimAction::Perturb(_) => "perturbation",
+    }
+}
+
+pub fn action_label(action: &SimAction) -> String {
+    match action {
+        SimAction::AddSalt => "ajoute le sel".to_string(),
+        SimAction::AddYeast => "ajoute la levure".to_string(),
+        SimAction::Fold { .. } => "rabat la pâte".to_string(),
+        SimAction::Knead(knead) => knead.label().to_lowercase(),
+        SimAction::AdvanceTo(phase) => format!("passe à : {}", phase.label()),
+        SimAction::Bake(oven) => format!("enfourne à {:.0} °C", oven),
+        SimAction::SetTemperature(temperature) => {
+            format!("met la pâte à {:.1} °C", temperature)
+        }
+        SimAction::DustFlour(_) => "fleure la pâte".to_string(),
+        SimAction::AddMalt(amount) => format!("ajoute {:.1} % de malt", amount * 100.0),
+        SimAction::AddAscorbicAcid(amount) => {
+            format!("ajoute {:.0} ppm d'acide ascorbique", amount * 1e6)
+        }
+        SimAction::MoveHand { hand, .. } => format!("déplace la main {}", hand + 1),
+        SimAction::LiftHands => "lève les mains".to_string(),
+        SimAction::SetEnvironment(preset) => format!("place la pâte : {}", preset.label()),
+        SimAction::Perturb(Perturbation::VelocityNoise { .. }) => "secoue la pâte".to_string(),
+        SimAction::Perturb(Perturbation::RemoveBonds { fraction }) => {
+            format!("coupe {:.0} % des liaisons", fraction * 100.0)
+        }
+        SimAction::Perturb(Perturbation::TemperatureSpike { delta, .. }) => {
+            format!("chauffe la pâte de {:+.1} °C", delta)
+        }
+        SimAction::SetDraft { speed, .. } => format!("règle le courant d'air à {:.1} m/s", speed),
+    }
+}
+
+// Whether a guest's gesture asks for more than the dough can take
+fn out_of_range(state: &SimulationState, action: &SimAction) -> bool {
+    let finite = |v: &Vector3<f32>| v.iter().all(|x| x.is_finite());
+    let dose = |amount: f32, usual: f32| amount > 0.0 && amount <= usual * MAX_DOSES;
+    let duration = |seconds: f32| (0.0..=MAX_DURATION).contains(&seconds);
+    let diagonal = Vector3::new(state.width, state.height, state.depth).magnitude();
+    let fits = match action {
+        SimAction::AddSalt
+        | SimAction::AddYeast
+        | SimAction::AdvanceTo(_)
+        | SimAction::Knead(_)
+        | SimAction::LiftHands
+        | SimAction::SetEnvironment(_) => true,
+        SimAction::AddMalt(amount) => dose(*amount, state.improver_settings.malt_amount),
+        SimAction::AddAscorbicAcid(amount) => {
+            dose(*amount, state.improver_settings.ascorbic_amount)
+        }
+        SimAction::DustFlour(amount) => dose(*amount, state.stickiness_settings.dust_amount),
+        SimAction::Fold {
+            center,
+            radius,
+            force,
+        } => {
+            finite(center)
+                && finite(force)
+                && force.magnitude() <= MAX_FORCE
+                && *radius > 0.0
+                && *radius <= diagonal
+        }
+        SimAction::SetTemperature(temperature) => DOUGH_TEMPERATURE.contains(temperature),
+        SimAction::Bake(oven) => OVEN_TEMPERATURE.contains(oven),
+        SimAction::MoveHand {
+            hand,
+            target,
+            duration: seconds,
+        } => *hand < state.tools.hands.len() && finite(target) && duration(*seconds),
+        SimAction::Perturb(Perturbation::VelocityNoise { magnitude }) => {
+            *magnitude > 0.0 && *magnitude <= MAX_NOISE
+        }
+        SimAction::Perturb(Perturbation::RemoveBonds { fraction }) => {
+            (0.0..=1.0).contains(fraction)
+        }
+        SimAction::Perturb(Perturbation::TemperatureSpike {
+            delta,
+            duration: seconds,
+        }) => (-MAX_SPIKE..=MAX_SPIKE).contains(delta) && duration(*seconds),
+        SimAction::SetDraft { speed, direction } => {
+            (0.0..=MAX_DRAFT).contains(speed) && finite(direction)
+        }
+    };
+    !fits
+}
+
+// What the baker did, read back from the event log of the host's own dough
+fn baker_action(event: &SimulationEvent) -> Option<SimAction> {
+    match event {
+        SimulationEvent::SaltAdded => Some(SimAction::AddSalt),
+        SimulationEvent::YeastAdded => Some(SimAction::AddYeast),
+        SimulationEvent::ForceApplied {
+            center,
+            radius,
+            force,
+        } => Some(SimAction::Fold {
+            center: *center,
+            radius: *radius,
+            force: *force,
+        }),
+        SimulationEvent::Kneaded(knead) => Some(SimAction::Knead(*knead)),
+        SimulationEvent::FlourDusted { amount } => Some(SimAction::DustFlour(*amount)),
+        SimulationEvent::MaltAdded { amount } => Some(SimAction::AddMalt(*amount)),
+        SimulationEvent::AscorbicAcidAdded { amount } => Some(SimAction::AddAscorbicAcid(*amount)),
+        // Entering the bake is logged by BakeStarted
+        SimulationEvent::PhaseChanged { to, .. } if *to != DoughPhase::Bake => {
+            Some(SimAction::AdvanceTo(*to))
+        }
+        SimulationEvent::BakeStarted { oven_temperature } => {
+            Some(SimAction::Bake(*oven_temperature))
+        }
+        _ => None,
+    }
+}
+
+pub fn encode_frame(state: &SimulationState) -> io::Result<Vec<u8>> {
+    zstd::bulk::compress(&encode_state(state)?, FRAME_ZSTD_LEVEL)
+}
+
+pub fn decode_frame(bytes: &[u8]) -> io::Result<SimulationState> {
+    decode_state(&zstd::stream::decode_all(bytes)?)
+}
+
+// Non-blocking stream of length-prefixed bincode messages
+struct Connection {
+    stream: TcpStream,
+    inbox: Vec<u8>,
+    outbox: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            stream,
+            inbox: Vec::new(),
+            outbox: Vec::new(),
+        })
+    }
+
+    // Still writing an earlier message
+    fn is_backed_up(&self) -> bool {
+        !self.outbox.is_empty()
+    }
+
+    fn send<T: Serialize>(&mut self, message: &T) -> io::Result<()> {
+        let bytes = bincode::serialize(message)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.outbox
+            .extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        self.outbox.extend_from_slice(&bytes);
+        self.flush()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outbox.is_empty() {
+            match self.stream.write(&self.outbox) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outbox.drain(..written);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    // Every complete message received so far; an error once the peer is gone
+    fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Vec<T>> {
+        self.flush()?;
+        let mut buffer = [0u8; 16 * 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => self.inbox.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let mut messages = Vec::new();
+        while self.inbox.len() >= 4 {
+            let len =
+                u32::from_le_bytes([self.inbox[0], self.inbox[1], self.inbox[2], self.inbox[3]])
+                    as usize;
+            if len > MAX_MESSAGE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("message of {} bytes", len),
+                ));
+            }
+            if self.inbox.len() < 4 + len {
+                break;
+            }
+            let message = bincode::deserialize(&self.inbox[4..4 + len])
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            self.inbox.drain(..4 + len);
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+}
+
+struct Peer {
+    participant: u32,
+    name: Option<String>, // Known once it said hello
+    connection: Connection,
+}
+
+// Co-baking session hosted next to the simulation: guests connect, send
+// their gestures and camera, and get the dough, the cursors and the log back.
+// The host polls it every frame; nothing here blocks.
+pub struct SessionServer {
+    listener: TcpListener,
+    host_name: String,
+    peers: Vec<Peer>,
+    next_participant: u32,
+    log: Vec<SessionEntry>,
+    cursors: Vec<CameraCursor>,
+    // Last host event already logged: its time and how many events share it
+    seen: Option<(f32, usize)>,
+}
+
+impl SessionServer {
+    pub fn bind(addr: impl ToSocketAddrs, host_name: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(SessionServer {
+            listener,
+            host_name: host_name.to_string(),
+            peers: Vec::new(),
+            next_participant: HOST + 1,
+            log: Vec::new(),
+            cursors: Vec::new(),
+            seen: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Guests that said hello
+    pub fn guests(&self) -> impl Iterator<Item = &str> {
+        self.peers.iter().filter_map(|peer| peer.name.as_deref())
+    }
+
+    pub fn log(&self) -> &[SessionEntry] {
+        &self.log
+    }
+
+    pub fn cursors(&self) -> &[CameraCursor] {
+        &self.cursors
+    }
+
+    pub fn set_host_cursor(&mut self, position: Vector3<f32>, target: Vector3<f32>) {
+        let name = self.host_name.clone();
+        self.update_cursor(HOST, name, position, target);
+    }
+
+    fn update_cursor(
+        &mut self,
+        participant: u32,
+        name: String,
+        position: Vector3<f32>,
+        target: Vector3<f32>,
+    ) {
+        let cursor = CameraCursor {
+            participant,
+            name,
+            position,
+            target,
+        };
+        match self
+            .cursors
+            .iter_mut()
+            .find(|cursor| cursor.participant == participant)
+        {
+            Some(existing) => *existing = cursor,
+            None => self.cursors.push(cursor),
+        }
+    }
+
+    fn name_of(&self, participant: u32) -> String {
+        if participant == HOST {
+            return self.host_name.clone();
+        }
+        self.peers
+            .iter()
+            .find(|peer| peer.participant == participant)
+            .and_then(|peer| peer.name.clone())
+            .unwrap_or_else(|| format!("invité {}", participant))
+    }
+
+    fn record(
+        &mut self,
+        state: &SimulationState,
+        participant: u32,
+        text: String,
+        action: Option<SimAction>,
+        refused: bool,
+    ) {
+        let name = self.name_of(participant);
+        self.record_as(state, participant, name, text, action, refused);
+    }
+
+    fn record_as(
+        &mut self,
+        state: &SimulationState,
+        participant: u32,
+        name: String,
+        text: String,
+        action: Option<SimAction>,
+        refused: bool,
+    ) {
+        let entry = SessionEntry {
+            time: state.time_elapsed,
+            participant,
+            name,
+            text,
+            action,
+            refused,
+        };
+        self.log.push(entry.clone());
+        for peer in self.peers.iter_mut().filter(|peer| peer.name.is_some()) {
+            // A peer that cannot be written to is dropped on the next poll
+            let _ = peer.connection.send(&ServerMessage::Entry(entry.clone()));
+        }
+    }
+
+    // Why `participant` may not do this now, if anything
+    fn conflict(
+        &self,
+        state: &SimulationState,
+        participant: u32,
+        action: &SimAction,
+    ) -> Option<String> {
+        let pointless = match action {
+            SimAction::AddSalt => state.salt_added,
+            SimAction::AddYeast => state.yeast_added,
+            SimAction::AdvanceTo(phase) => state.phase >= *phase,
+            SimAction::Bake(_) => state.phase > DoughPhase::Bake,
+            _ => false,
+        };
+        if pointless {
+            return Some("déjà fait".to_string());
+        }
+        let kind = action_kind(action);
+        self.log
+            .iter()
+            .rev()
+            .take_while(|entry| state.time_elapsed - entry.time < CONFLICT_WINDOW)
+            .find(|entry| {
+                !entry.refused
+                    && entry.time <= state.time_elapsed
+                    && entry.participant != participant
+                    && entry.action.as_ref().map(action_kind) == Some(kind)
+            })
+            .map(|entry| format!("{} vient de le faire", entry.name))
+    }
+
+    // Logs the gestures the host made on its own dough since the last poll
+    fn observe_host(&mut self, state: &SimulationState, log_them: bool) {
+        let events: Vec<_> = state.events.iter().collect();
+        // A fresh dough starts its clock again: only the events since the
+        // last restart belong to the current dough, in time order
+        let restart = events
+            .windows(2)
+            .rposition(|pair| pair[1].time < pair[0].time)
+            .map_or(0, |drop| drop + 1);
+        let current = &events[restart..];
+        let at = |time: f32| current.iter().filter(|logged| logged.time == time).count();
+        let fresh_from = match (self.seen, current.last()) {
+            // Nothing to compare with yet: the dough's past is not news
+            (None, _) | (_, None) => events.len(),
+            (Some((seen_time, _)), Some(last)) if last.time < seen_time => restart,
+            (Some((seen_time, seen_count)), Some(_)) => {
+                let older = current
+                    .iter()
+                    .filter(|logged| logged.time < seen_time)
+                    .count();
+                restart + older + at(seen_time).min(seen_count)
+            }
+        };
+        self.seen = Some(match current.last() {
+            Some(last) => (last.time, at(last.time)),
+            None => (f32::NEG_INFINITY, 0),
+        });
+        if !log_them {
+            return;
+        }
+        let fresh: Vec<SimulationEvent> = events[fresh_from..]
+            .iter()
+            .map(|logged| logged.event.clone())
+            .collect();
+        for event in fresh {
+            if let SimulationEvent::Annotated(note) = &event {
+                self.record(state, HOST, format!("note : {}", note), None, false);
+            } else if let Some(action) = baker_action(&event) {
+                self.record(state, HOST, action_label(&action), Some(action), false);
+            }
+        }
+    }
+
+    // Accepts newcomers, applies the guests' gestures unless they conflict
+    // and collects their cursors. Returns the new log entries.
+    pub fn poll(&mut self, state: &mut SimulationState) -> Vec<SessionEntry> {
+        let first = self.log.len();
+        self.observe_host(state, true);
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Ok(connection) = Connection::new(stream) {
+                        self.peers.push(Peer {
+                            participant: self.next_participant,
+                            name: None,
+                            connection,
+                        });
+                        self.next_participant += 1;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+
+        let mut index = 0;
+        while index < self.peers.len() {
+            let participant = self.peers[index].participant;
+            let messages = match self.peers[index].connection.receive::<ClientMessage>() {
+                Ok(messages) => messages,
+                Err(_) => {
+                    let peer = self.peers.remove(index);
+                    self.cursors
+                        .retain(|cursor| cursor.participant != participant);
+                    if let Some(name) = peer.name {
+                        let text = "est parti".to_string();
+                        self.record_as(state, participant, name, text, None, false);
+                    }
+                    continue;
+                }
+            };
+            for message in messages {
+                match message {
+                    ClientMessage::Hello { name } => {
+                        let welcome = ServerMessage::Welcome {
+                            participant,
+                            log: self.log.clone(),
+                        };
+                        let peer = &mut self.peers[index];
+                        peer.name = Some(name);
+                        let _ = peer.connection.send(&welcome);
+                        self.record(state, participant, "a rejoint".to_string(), None, false);
+                    }
+                    ClientMessage::Action(action) => {
+                        let reason = if out_of_range(state, &action) {
+                            Some("hors limites".to_string())
+                        } else {
+                            self.conflict(state, participant, &action)
+                        };
+                        match reason {
+                            Some(reason) => {
+                                let text =
+                                    format!("{} : refusé, {}", action_label(&action), reason);
+                                self.record(state, participant, text, Some(action), true);
+                            }
+                            None => {
+                                state.apply_action(&action);
+                                // Already attributed, not a host gesture
+                                self.observe_host(state, false);
+                                self.record(
+                                    state,
+                                    participant,
+                                    action_label(&action),
+                                    Some(action),
+                                    false,
+                                );
+                            }
+                        }
+                    }
+                    ClientMessage::Cursor { position, target } => {
+                        let name = self.name_of(participant);
+                        self.update_cursor(participant, name, position, target);
+                    }
+                }
+            }
+            index += 1;
+        }
+        self.log[first..].to_vec()
+    }
+
+    // Sends the dough and the cursors to every guest; a guest still busy
+    // with the previous frame skips this one
+    pub fn broadcast_state(&mut self, state: &SimulationState) -> io::Result<()> {
+        if self.peers.is_empty() {
+            return Ok(());
+        }
+        let frame = ServerMessage::State {
+            version: FRAME_VERSION,
+            frame: encode_frame(state)?,
+        };
+        let cursors = ServerMessage::Cursors(self.cursors.clone());
+        for peer in self.peers.iter_mut().filter(|peer| peer.name.is_some()) {
+            if peer.connection.is_backed_up() {
+                continue;
+            }
+            let _ = peer.connection.send(&frame);
+            let _ = peer.connection.send(&cursors);
+        }
+        Ok(())
+    }
+}
+
+// A guest of a co-baking session: the dough lives on the host
+pub struct SessionClient {
+    connection: Connection,
+    participant: Option<u32>,
+}
+
+impl SessionClient {
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> io::Result<Self> {
+        let mut connection = Connection::new(TcpStream::connect(addr)?)?;
+        connection.send(&ClientMessage::Hello {
+            name: name.to_string(),
+        })?;
+        Ok(SessionClient {
+            connection,
+            participant: None,
+        })
+    }
+
+    // Known once the host has welcomed us
+    pub fn participant(&self) -> Option<u32> {
+        self.participant
+    }
+
+    pub fn send_action(&mut self, action: SimAction) -> io::Result<()> {
+        self.connection.send(&ClientMessage::Action(action))
+    }
+
+    // Skipped while an earlier message is still on its way
+    pub fn send_cursor(&mut self, position: Vector3<f32>, target: Vector3<f32>) -> io::Result<()> {
+        if self.connection.is_backed_up() {
+            return self.connection.flush();
+        }
+        self.connection
+            .send(&ClientMessage::Cursor { position, target })
+    }
+
+    // Everything the host sent since the last call; an error once the host
+    // is gone
+    pub fn poll(&mut self) -> io::Result<Vec<ServerMessage>> {
+        let messages = self.connection.receive::<ServerMessage>()?;
+        for message in &messages {
+            if let ServerMessage::Welcome { participant, .. } = message {
+                self.participant = Some(*participant);
+            }
+        }
+        Ok(messages)
+    }
+}
@@ -0,0 +1,168 @@
+use crate::SimulationState;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const EMERGENCY_STATE_FILE: &str = "emergency.snapshot";
+const EMERGENCY_LOG_FILE: &str = "emergency_events.log";
+
+// Save files start with a magic tag and a format version so that files from an
+// incompatible build are rejected with a clear error instead of garbage. The
+// body is bincode, which has no field names: any change to the state's layout
+// bumps the version, and older saves are not migrated.
+const SAVE_MAGIC: &[u8; 8] = b"HOPSAVE\0";
+// 2: dense spatial grid, 3: starch hydrolysis ledger, 4: zstd-compressed body,
+// 5: dough phase, 6: bake, 7: bond tearing settings and molecule quarantine,
+// 8: environment presets and acidity, 9: dough pH weakening the gluten,
+// 10: collapse monitor, 11: metrics history, 12: gluten development gauge,
+// 13: scheduled actions, 14: kneading techniques, 15: lab notebook,
+// 16: temperature field, 17: dough volume, 18: crumb analysis settings,
+// 19: mystery flour challenge, 20: dissolved salt field,
+// 21: Arrhenius reaction kinetics, 22: integration and grid timed apart,
+// 23: generational molecule ids, 24: reaction rule table, 25: gas burps,
+// 26: dough stickiness and flour dusting, 27: bench and hands for shaping,
+// 28: bond stiffness and damping, 29: integrator choice, 30: fixed timestep,
+// 31: molecule repulsion, 32: gravity and buoyancy, 33: Brownian motion,
+// 34: local viscosity, 35: CO2 escape rate and gas counts, 36: boundary modes,
+// 37: container shapes, 38: fold falloff, 39: recipes weighed in grams,
+// 40: molecule budget, 41: flour absorption, 42: fat, 43: preferments,
+// 44: levain from a starter, 45: malt and ascorbic acid, 46: proteases,
+// 47: poke test, 48: proofing monitor, 49: species counts,
+// 50: checkpoint settings, 51: fast-forward settings
+pub(crate) const SAVE_VERSION: u32 = 51;
+const ZSTD_LEVEL: i32 = 3;
+
+fn invalid_data<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+// Magic tag and format version, ahead of every dough written to a file
+fn write_header(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(SAVE_MAGIC)?;
+    out.write_all(&SAVE_VERSION.to_le_bytes())
+}
+
+// Refuses anything but a dough written by a build with the same format
+fn read_header(input: &mut impl Read) -> io::Result<()> {
+    let mut magic = [0u8; 8];
+    let mut version = [0u8; 4];
+    if input.read_exact(&mut magic).is_err() || &magic != SAVE_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a house_of_pain save file",
+        ));
+    }
+    input.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SAVE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "save format version {} is not supported by this build (version {}), saves do not survive format upgrades",
+                version, SAVE_VERSION
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub fn encode_state(state: &SimulationState) -> io::Result<Vec<u8>> {
+    bincode::serialize(state).map_err(invalid_data)
+}
+
+pub fn decode_state(bytes: &[u8]) -> io::Result<SimulationState> {
+    bincode::deserialize(bytes).map_err(invalid_data)
+}
+
+impl SimulationState {
+    // Saves the whole running dough so a long fermentation can be resumed
+    // later. The state is serialized straight into the compressor, without a
+    // full uncompressed copy in memory.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        write_header(&mut file)?;
+        let mut encoder = zstd::Encoder::new(file, ZSTD_LEVEL)?;
+        bincode::serialize_into(&mut encoder, self).map_err(invalid_data)?;
+        encoder.finish()?.flush()
+    }
+
+    pub fn load_from_file(path: &Path) -> io::Result<Self> {
+        let mut file = io::BufReader::new(fs::File::open(path)?);
+        read_header(&mut file)?;
+        bincode::deserialize_from(zstd::Decoder::with_buffer(file)?).map_err(invalid_data)
+    }
+}
+
+// What gets written to disk when the simulation blows up: the last known-good
+// state (already encoded, so it can be written from a panic hook) plus the
+// recent event log and the reason for the crash.
+#[derive(Debug, Clone)]
+pub struct EmergencySnapshot {
+    pub reason: String,
+    pub state_bytes: Vec<u8>,
+    pub event_log: String,
+}
+
+impl EmergencySnapshot {
+    pub fn capture(state: &SimulationState, reason: &str) -> io::Result<Self> {
+        Ok(EmergencySnapshot {
+            reason: reason.to_string(),
+            state_bytes: encode_state(state)?,
+            event_log: state.events.to_text(),
+        })
+    }
+
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        // Same header as a save, so another build's snapshot is never decoded
+        let mut state_file = Vec::with_capacity(self.state_bytes.len() + 12);
+        write_header(&mut state_file)?;
+        state_file.extend_from_slice(&self.state_bytes);
+        fs::write(dir.join(EMERGENCY_STATE_FILE), state_file)?;
+        let log = format!("reason: {}\n\n{}", self.reason, self.event_log);
+        fs::write(dir.join(EMERGENCY_LOG_FILE), log)
+    }
+
+    // Returns Ok(None) when there is nothing to recover, and an error for a
+    // snapshot left by a build with another save format
+    pub fn read_from(dir: &Path) -> io::Result<Option<Self>> {
+        let state_path = dir.join(EMERGENCY_STATE_FILE);
+        if !state_path.exists() {
+            return Ok(None);
+        }
+        let state_file = fs::read(state_path)?;
+        let mut body = &state_file[..];
+        read_header(&mut body)?;
+        let state_bytes = body.to_vec();
+        let log = fs::read_to_string(dir.join(EMERGENCY_LOG_FILE)).unwrap_or_default();
+        let (reason, event_log) = match log.split_once("\n\n") {
+            Some((header, events)) => (
+                header.trim_start_matches("reason: ").to_string(),
+                events.to_string(),
+            ),
+            None => (String::from("unknown"), log),
+        };
+        Ok(Some(EmergencySnapshot {
+            reason,
+            state_bytes,
+            event_log,
+        }))
+    }
+
+    pub fn restore(&self) -> io::Result<SimulationState> {
+        decode_state(&self.state_bytes)
+    }
+
+    pub fn discard(dir: &Path) -> io::Result<()> {
+        for file in [EMERGENCY_STATE_FILE, EMERGENCY_LOG_FILE] {
+            let path = dir.join(file);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use crate::{container, Bond, MoleculeId, SimulationEvent, SimulationState, SpatialGrid3D};
+use nalgebra::Vector3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Floor on target distances so the relative error never divides by zero
+const MIN_TARGET_DISTANCE: f32 = 1e-3;
+
+// Bonds are XPBD distance constraints: each pass moves both ends towards the
+// rest length by as much as the bond's compliance (1 / stiffness) allows for
+// the tick, and the damping holds back the ends' speed along the bond. The
+// positions are corrected in place and the velocities follow the moves, so a
+// strand settles instead of ringing around its rest length.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolverSettings {
+    pub iterations: u32,        // Passes per tick in calm dough
+    pub fold_iterations: u32,   // Passes per tick while a fold is settling
+    pub fold_settle_time: f32,  // Seconds after a force application counted as folding
+    pub tolerance: f32,         // Mean relative bond length error accepted as converged
+    pub max_velocity: f32,      // Clamp on the velocity after corrections
+    pub bond_stiffness: f32,    // Stiffness of new bonds, 0 leaves them slack
+    pub bond_damping: f32,      // Damping of new bonds, force per unit of stretching speed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SolverReport {
+    pub iterations: u32,
+    pub residual: f32, // Mean relative bond length error after the last pass
+    pub converged: bool,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        SolverSettings {
+            iterations: 2,
+            fold_iterations: 6,
+            fold_settle_time: 5.0,
+            tolerance: 0.2,
+            max_velocity: 3.0,
+            bond_stiffness: 20.0,
+            bond_damping: 0.2,
+        }
+    }
+}
+
+impl SolverSettings {
+    // A bond with the stiffness and damping new bonds get
+    pub fn new_bond(&self, a: MoleculeId, b: MoleculeId, target_distance: f32) -> Bond {
+        Bond {
+            molecule_a_id: a,
+            molecule_b_id: b,
+            target_distance,
+            stiffness: self.bond_stiffness,
+            damping: self.bond_damping,
+        }
+    }
+}
+
+impl Default for SolverReport {
+    fn default() -> Self {
+        SolverReport {
+            iterations: 0,
+            residual: 0.0,
+            converged: true,
+        }
+    }
+}
+
+impl SimulationState {
+    fn solver_iterations(&self) -> u32 {
+        let folding = self
+            .last_force_time
+            .is_some_and(|t| self.time_elapsed - t < self.solver.fold_settle_time);
+        let iterations = if folding {
+            self.solver.fold_iterations
+        } else {
+            self.solver.iterations
+        };
+        iterations.max(1)
+    }
+
+    // Mean relative length error of all bonds, with molecules displaced by
+    // `offsets` (indexed by molecule slot)
+    fn bond_residual(&self, offsets: &[Vector3<f32>]) -> f32 {
+        let mut error_sum = 0.0;
+        let mut count = 0;
+
+        for bond in &self.bonds {
+            if let (Some(mol_a), Some(mol_b)) = (
+                self.grid.get_molecule(bond.molecule_a_id),
+                self.grid.get_molecule(bond.molecule_b_id),
+            ) {
+                let pos_a = mol_a.pos + offsets[mol_a.id.index()];
+                let pos_b = mol_b.pos + offsets[mol_b.id.index()];
+                let current_dist = self.grid.displacement(pos_a, pos_b).magnitude();
+                let target = bond.target_distance.max(MIN_TARGET_DISTANCE);
+                error_sum += ((current_dist - target) / target).abs();
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            0.0
+        } else {
+            error_sum / count as f32
+        }
+    }
+
+    // Strain a bond can still take before tearing, relative to the break
+    // strain of a fresh dough: 1 for a relaxed bond in young dough, 0 when it
+    // is about to tear. Stretching, over-kneading and acid all lower it.
+    pub fn bond_strength(&self, bond: &Bond) -> Option<f32> {
+        self.bond_reserve(bond).map(|reserve| reserve.clamp(0.0, 1.0))
+    }
+
+    // Mean strength of the bonds, None without any
+    pub fn average_bond_strength(&self) -> Option<f32> {
+        let (sum, count) = self
+            .bonds
+            .iter()
+            .filter_map(|bond| self.bond_strength(bond))
+            .fold((0.0, 0), |(sum, count), strength| (sum + strength, count + 1));
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    // Unclamped strength, negative once the bond is stretched past its break strain
+    fn bond_reserve(&self, bond: &Bond) -> Option<f32> {
+        let mol_a = self.grid.get_molecule(bond.molecule_a_id)?;
+        let mol_b = self.grid.get_molecule(bond.molecule_b_id)?;
+        let target = bond.target_distance.max(MIN_TARGET_DISTANCE);
+        let span = self.grid.displacement(mol_a.pos, mol_b.pos);
+        let strain = (span.magnitude() - target) / target;
+        let break_strain = self.bond_break_strain_at(mol_a.pos + span / 2.0);
+        Some((break_strain - strain.max(0.0)) / self.config.bond_break_strain.max(f32::EPSILON))
+    }
+
+    // Drops the bonds stretched past their break strain (folds, shear,
+    // over-kneading, acid) and frees their thiols
+    fn tear_overstretched_bonds(&mut self) {
+        let torn = self.take_bonds_where(|state, bond| {
+            state.bond_reserve(bond).is_some_and(|reserve| reserve < 0.0)
+        });
+        for bond in &torn {
+            self.release_bond(bond);
+        }
+    }
+
+    // Iterative bond solver, one color batch of the bond coloring after the
+    // other: the bonds of a batch share no molecule, so their corrections
+    // are worked out together against the positions nudged by the batches
+    // and passes before, then written back without any two landing on the
+    // same molecule. The accumulated moves go to the positions, and divided
+    // by `dt` to the velocities.
+    pub(crate) fn apply_bond_constraints(&mut self, dt: f32) {
+        self.tear_overstretched_bonds();
+        if dt <= 0.0 {
+            return;
+        }
+        let iterations = self.solver_iterations();
+        let hardening = self.bond_stiffness();
+        self.index_bonds();
+        let batches = self.bond_coloring.batches();
+        // Indexed by molecule slot
+        let mut corrections = vec![Vector3::zeros(); self.grid.id_capacity()];
+        let mut bonded = vec![false; corrections.len()];
+        // Indexed like `bonds`: how hard each bond pulled so far this tick
+        let mut lambdas = vec![0.0; self.bonds.len()];
+
+        for _ in 0..iterations {
+            for batch in &batches {
+                let step = BatchStep {
+                    grid: &self.grid,
+                    bonds: &self.bonds,
+                    corrections: &corrections,
+                    lambdas: &lambdas,
+                    hardening,
+                    dt,
+                };
+                for (index, delta_lambda, moves) in step.corrections(batch) {
+                    lambdas[index] += delta_lambda;
+                    for (id, correction) in moves {
+                        corrections[id.index()] += correction;
+                        bonded[id.index()] = true;
+                    }
+                }
+            }
+        }
+
+        let residual = self.bond_residual(&corrections);
+
+        // Apply accumulated corrections to molecules, held in by the faces
+        // and the wall like the integration
+        let max_vel = self.solver.max_velocity;
+        let restitution = self.config.wall_restitution;
+        let size = self.container_size();
+        let vessel = self.container.vessel(size);
+        let mut moved = Vec::new();
+        let mut left = Vec::new();
+        for mol in self.grid.get_all_molecules_mut() {
+            if bonded[mol.id.index()] {
+                let old_pos = mol.pos;
+                let correction = corrections[mol.id.index()];
+                mol.pos += correction;
+                mol.velocity += correction / dt;
+                if !self.boundaries.confine(mol, size, restitution) {
+                    left.push(mol.id);
+                } else if let Some(vessel) = &vessel {
+                    container::confine_to(vessel, mol, restitution);
+                }
+
+                // Limit max velocity to prevent instability
+                let vel_mag = mol.velocity.magnitude();
+                if vel_mag > max_vel {
+                    mol.velocity = mol.velocity.normalize() * max_vel;
+                }
+                moved.push((mol.id, old_pos));
+            }
+        }
+        for (id, old_pos) in moved {
+            self.grid.relocate(id, old_pos);
+        }
+        self.lose_through_boundaries(left);
+
+        let converged = residual <= self.solver.tolerance;
+        // Only warn when the solver starts failing, not on every tick it keeps failing
+        if !converged && self.last_solver_report.converged {
+            self.events.push(
+                self.time_elapsed,
+                SimulationEvent::SolverNotConverged {
+                    iterations,
+                    residual,
+                },
+            );
+        }
+        self.last_solver_report = SolverReport {
+            iterations,
+            residual,
+            converged,
+        };
+    }
+}
+
+// What one color batch reads: the positions nudged so far and how hard
+// each bond already pulled this tick
+struct BatchStep<'a> {
+    grid: &'a SpatialGrid3D,
+    bonds: &'a [Bond],
+    corrections: &'a [Vector3<f32>],
+    lambdas: &'a [f32],
+    hardening: f32, // Multiplier on every stiffness, see `bond_stiffness()`
+    dt: f32,
+}
+
+type BondMoves = (usize, f32, [(MoleculeId, Vector3<f32>); 2]);
+
+impl BatchStep<'_> {
+    // XPBD step of one bond: the change of its pull and the moves of its two
+    // ends. A stretched bond pulls both ends together, a compressed one
+    // pushes them apart, the lighter end moving more.
+    fn correct(&self, index: usize) -> Option<BondMoves> {
+        let bond = &self.bonds[index];
+        let stiffness = bond.stiffness * self.hardening;
+        if stiffness <= 0.0 {
+            return None;
+        }
+        let mol_a = self.grid.get_molecule(bond.molecule_a_id)?;
+        let mol_b = self.grid.get_molecule(bond.molecule_b_id)?;
+        let offset_a = self.corrections[mol_a.id.index()];
+        let offset_b = self.corrections[mol_b.id.index()];
+        let diff = self
+            .grid
+            .displacement(mol_a.pos + offset_a, mol_b.pos + offset_b);
+        let current_dist = diff.magnitude();
+        if current_dist <= 0.0 {
+            return None;
+        }
+        let normal = diff / current_dist;
+        let (weight_a, weight_b) = (1.0 / mol_a.mass(), 1.0 / mol_b.mass());
+        let compliance = 1.0 / (stiffness * self.dt * self.dt);
+        let gamma = bond.damping.max(0.0) / (stiffness * self.dt);
+        // How fast the ends drift apart this tick, moves so far included
+        let stretch_rate = normal.dot(
+            &((mol_b.velocity - mol_a.velocity) * self.dt + offset_b - offset_a),
+        );
+        let lambda = self.lambdas[index];
+        let constraint = current_dist - bond.target_distance;
+        let delta_lambda = (-constraint - compliance * lambda - gamma * stretch_rate)
+            / ((1.0 + gamma) * (weight_a + weight_b) + compliance);
+        let step = normal * delta_lambda;
+        Some((
+            index,
+            delta_lambda,
+            [(mol_a.id, -step * weight_a), (mol_b.id, step * weight_b)],
+        ))
+    }
+
+    fn corrections(&self, batch: &[usize]) -> Vec<BondMoves> {
+        #[cfg(feature = "parallel")]
+        let moves = batch
+            .par_iter()
+            .filter_map(|&index| self.correct(index))
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let moves = batch
+            .iter()
+            .filter_map(|&index| self.correct(index))
+            .collect();
+        moves
+    }
+}
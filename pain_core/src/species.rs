@@ -0,0 +1,239 @@
+use crate::MoleculeType;
+use serde::{Deserialize, Serialize};
+
+// Visual/physical families of molecules. Glutenin is split by thiol state
+// because the renderers show reactive and bonded glutenin differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Species {
+    Gliadin,
+    ReactiveGlutenin,
+    BondedGlutenin,
+    Water,
+    Yeast,
+    CO2,
+    Ethanol,
+    Sugar,
+    Salt,
+    Ash,
+    Starch,
+    Amylase,
+    Fat,
+}
+
+// Registry entry: the single source for radius, mass, colors and the legend text
+#[derive(Debug, Clone, Copy)]
+pub struct SpeciesInfo {
+    pub species: Species,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub radius: f32,
+    pub mass: f32,
+    pub density: f32, // Relative to water, for the buoyancy (see gravity.rs)
+    pub color: [f32; 3],
+    pub colorblind_color: [f32; 3], // Okabe-Ito palette
+    pub roughness: f32,
+}
+
+pub const SPECIES: [SpeciesInfo; 13] = [
+    SpeciesInfo {
+        species: Species::Gliadin,
+        name: "Gliadine",
+        description: "protéine qui rend la pâte extensible",
+        radius: 3.0,
+        mass: 10.0,
+        density: 1.3,
+        color: [1.0, 0.27, 0.0],
+        colorblind_color: [0.90, 0.62, 0.0],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::ReactiveGlutenin,
+        name: "Gluténine (thiol libre)",
+        description: "peut encore former un pont disulfure",
+        radius: 4.0,
+        mass: 12.0,
+        density: 1.3,
+        color: [1.0, 1.0, 0.0],
+        colorblind_color: [0.94, 0.89, 0.26],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::BondedGlutenin,
+        name: "Gluténine liée",
+        description: "maillon du réseau de gluten, donne l'élasticité",
+        radius: 4.0,
+        mass: 12.0,
+        density: 1.3,
+        color: [0.0, 1.0, 0.0],
+        colorblind_color: [0.0, 0.62, 0.45],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Water,
+        name: "Eau",
+        description: "hydrate les protéines et les fait bouger",
+        radius: 1.5,
+        mass: 1.0,
+        density: 1.0,
+        color: [0.0, 0.4, 1.0],
+        colorblind_color: [0.0, 0.45, 0.70],
+        roughness: 0.2,
+    },
+    SpeciesInfo {
+        species: Species::Yeast,
+        name: "Levure",
+        description: "consomme les sucres, rejette CO₂ et éthanol",
+        radius: 5.0,
+        mass: 15.0,
+        density: 1.1,
+        color: [1.0, 1.0, 1.0],
+        colorblind_color: [1.0, 1.0, 1.0],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::CO2,
+        name: "CO₂",
+        description: "gaz de fermentation qui fait lever la pâte",
+        radius: 8.0,
+        mass: 2.0,
+        density: 0.002,
+        color: [0.0, 1.0, 1.0],
+        colorblind_color: [0.34, 0.71, 0.91],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Ethanol,
+        name: "Éthanol",
+        description: "sous-produit de la fermentation, arômes",
+        radius: 2.0,
+        mass: 3.0,
+        density: 0.79,
+        color: [0.6, 0.0, 0.8],
+        colorblind_color: [0.80, 0.47, 0.65],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Sugar,
+        name: "Sucre",
+        description: "nourriture des levures",
+        radius: 2.5,
+        mass: 5.0, // = CO2 + ethanol, so fermentation conserves mass
+        density: 1.59,
+        color: [1.0, 0.0, 0.6],
+        colorblind_color: [0.84, 0.37, 0.0],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Salt,
+        name: "Sel",
+        description: "resserre le réseau et freine les levures",
+        radius: 1.8,
+        mass: 2.0,
+        density: 2.16,
+        color: [0.5, 0.5, 0.5],
+        colorblind_color: [0.6, 0.6, 0.6],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Ash,
+        name: "Cendres",
+        description: "minéraux de la farine",
+        radius: 2.0,
+        mass: 2.0,
+        density: 2.5,
+        color: [0.0, 0.0, 0.0],
+        colorblind_color: [0.0, 0.0, 0.0],
+        roughness: 0.5,
+    },
+    SpeciesInfo {
+        species: Species::Starch,
+        name: "Amidon",
+        description: "réserve de la farine, découpée en sucres par l'amylase",
+        radius: 3.5,
+        mass: 5.0, // = sugar, hydrolysis conserves mass
+        density: 1.5,
+        color: [0.96, 0.92, 0.78],
+        colorblind_color: [0.85, 0.85, 0.85],
+        roughness: 0.8,
+    },
+    SpeciesInfo {
+        species: Species::Amylase,
+        name: "Amylase",
+        description: "enzyme qui nourrit les levures pendant les longues fermentations",
+        radius: 3.0,
+        mass: 8.0,
+        density: 1.35,
+        color: [0.8, 0.45, 0.15],
+        colorblind_color: [0.35, 0.35, 0.35],
+        roughness: 0.4,
+    },
+    SpeciesInfo {
+        species: Species::Fat,
+        name: "Matière grasse",
+        description: "beurre ou huile, enrobe le gluten et attendrit la mie",
+        radius: 3.0,
+        mass: 6.0,
+        density: 0.92,
+        color: [1.0, 0.86, 0.45],
+        colorblind_color: [0.75, 0.75, 0.45],
+        roughness: 0.2,
+    },
+];
+
+impl Species {
+    pub fn info(&self) -> &'static SpeciesInfo {
+        // The registry is ordered like the enum
+        &SPECIES[*self as usize]
+    }
+
+    pub fn all() -> impl Iterator<Item = &'static SpeciesInfo> {
+        SPECIES.iter()
+    }
+
+    // Molecule created for the species, a single molecule for water
+    pub fn molecule_type(&self) -> MoleculeType {
+        match self {
+            Species::Gliadin => MoleculeType::Gliadin,
+            Species::ReactiveGlutenin => MoleculeType::Glutenin {
+                has_free_thiol: true,
+            },
+            Species::BondedGlutenin => MoleculeType::Glutenin {
+                has_free_thiol: false,
+            },
+            Species::Water => MoleculeType::Water,
+            Species::Yeast => MoleculeType::Yeast,
+            Species::CO2 => MoleculeType::CO2,
+            Species::Ethanol => MoleculeType::Ethanol,
+            Species::Sugar => MoleculeType::Sugar,
+            Species::Salt => MoleculeType::Salt,
+            Species::Ash => MoleculeType::Ash,
+            Species::Starch => MoleculeType::Starch,
+            Species::Amylase => MoleculeType::Amylase,
+            Species::Fat => MoleculeType::Fat,
+        }
+    }
+}
+
+impl MoleculeType {
+    pub fn species(&self) -> Species {
+        match self {
+            MoleculeType::Gliadin => Species::Gliadin,
+            MoleculeType::Glutenin {
+                has_free_thiol: true,
+            } => Species::ReactiveGlutenin,
+            MoleculeType::Glutenin {
+                has_free_thiol: false,
+            } => Species::BondedGlutenin,
+            MoleculeType::Water | MoleculeType::WaterCluster { .. } => Species::Water,
+            MoleculeType::Yeast => Species::Yeast,
+            MoleculeType::CO2 => Species::CO2,
+            MoleculeType::Ethanol => Species::Ethanol,
+            MoleculeType::Sugar => Species::Sugar,
+            MoleculeType::Salt => Species::Salt,
+            MoleculeType::Ash => Species::Ash,
+            MoleculeType::Starch => Species::Starch,
+            MoleculeType::Amylase => Species::Amylase,
+            MoleculeType::Fat => Species::Fat,
+        }
+    }
+}
@@ -0,0 +1,489 @@
+use crate::thermal::ThermalField;
+use crate::{
+    DoughPhase, EnvironmentPreset, GramScale, MoleculeType, Recipe, SimAction, SimulationBuilder,
+    SimulationState,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+const SECONDS_PER_HOUR: f32 = 3600.0;
+// The jar: a small box, about a hundred grams of flour
+const STARTER_SIZE: (f32, f32, f32) = (500.0, 360.0, 500.0);
+const STARTER_FLOUR: f32 = 100.0;
+// Tick of the jar between feedings, and how often the cultures grow
+const STARTER_STEP: f32 = 30.0;
+const GROWTH_INTERVAL: f32 = 1800.0;
+// Levain / flour of a fresh jar, and the most yeast it holds per flour
+// molecule fed
+const LEVAIN_YEAST: f32 = 0.2;
+const YEAST_CAPACITY: f32 = 0.4;
+
+// One refresh of the starter: parts of old starter, flour and water by
+// weight, then how long it was left and at which temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Feeding {
+    pub starter: f32,
+    pub flour: f32,
+    pub water: f32,
+    pub temperature: f32, // °C
+    pub hours: f32,       // Time until the next feeding
+}
+
+impl Feeding {
+    // Parses "1:2:2@26/8": starter:flour:water at 26°C, left 8 hours
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid feeding '{}', expected starter:flour:water@temp/hours",
+                    text
+                ),
+            )
+        };
+        let (ratios, conditions) = text.trim().split_once('@').ok_or_else(invalid)?;
+        let (temperature, hours) = conditions.split_once('/').ok_or_else(invalid)?;
+        let parts: Vec<f32> = ratios
+            .split(':')
+            .map(|part| part.trim().parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        let [starter, flour, water] = parts[..] else {
+            return Err(invalid());
+        };
+        let feeding = Feeding {
+            starter,
+            flour,
+            water,
+            temperature: temperature.trim().parse().map_err(|_| invalid())?,
+            hours: hours.trim().parse().map_err(|_| invalid())?,
+        };
+        if starter <= 0.0 || flour < 0.0 || water < 0.0 || feeding.hours <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(feeding)
+    }
+}
+
+// Comma separated list of feedings, oldest first
+pub fn parse_feeding_history(text: &str) -> io::Result<Vec<Feeding>> {
+    text.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(Feeding::parse)
+        .collect()
+}
+
+// A typical routine: twice a day at room temperature, 1:2:2
+pub fn default_feeding_history() -> Vec<Feeding> {
+    vec![
+        Feeding {
+            starter: 1.0,
+            flour: 2.0,
+            water: 2.0,
+            temperature: 24.0,
+            hours: 12.0,
+        };
+        6
+    ]
+}
+
+fn count(state: &SimulationState, mol_type: MoleculeType) -> usize {
+    state
+        .grid
+        .molecules()
+        .filter(|mol| mol.mol_type == mol_type)
+        .count()
+}
+
+// Lumped starter model, all populations normalized to the carrying capacity
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StarterCulture {
+    yeast: f32,
+    bacteria: f32,
+    food: f32, // Fermentable sugars left in the flour
+    acid: f32, // Lactic/acetic acid, 1.0 = saturated
+}
+
+impl Default for StarterCulture {
+    fn default() -> Self {
+        StarterCulture {
+            yeast: 0.1,
+            bacteria: 0.1,
+            food: 0.5,
+            acid: 0.2,
+        }
+    }
+}
+
+// Yeast prefer mild warmth, lactic bacteria warmer
+fn temperature_factor(temperature: f32, optimum: f32) -> f32 {
+    (-((temperature - optimum) / 9.0).powi(2)).exp()
+}
+
+impl StarterCulture {
+    fn feed(&mut self, feeding: &Feeding) {
+        let total = feeding.starter + feeding.flour + feeding.water;
+        let kept = feeding.starter / total;
+        let fresh_food = feeding.flour / total;
+        self.yeast *= kept;
+        self.bacteria *= kept;
+        self.acid *= kept;
+        self.food = (self.food * kept + fresh_food * 2.0).min(1.0);
+    }
+
+    // Advances one step and returns the CO2 production rate
+    fn step(&mut self, temperature: f32, hours: f32) -> f32 {
+        let hunger = self.food / (self.food + 0.1);
+        let yeast_growth = 0.7
+            * temperature_factor(temperature, 27.0)
+            * self.yeast
+            * hunger
+            * (1.0 - self.yeast)
+            * (1.0 - 0.5 * self.acid);
+        let bacteria_growth = 0.8
+            * temperature_factor(temperature, 32.0)
+            * self.bacteria
+            * hunger
+            * (1.0 - self.bacteria)
+            * (1.0 - self.acid);
+
+        self.yeast = (self.yeast + yeast_growth * hours).clamp(0.0, 1.0);
+        self.bacteria = (self.bacteria + bacteria_growth * hours).clamp(0.0, 1.0);
+        let eaten =
+            (yeast_growth + bacteria_growth + 0.05 * (self.yeast + self.bacteria) * hunger) * hours;
+        self.food = (self.food - 0.5 * eaten).max(0.0);
+        self.acid =
+            (self.acid + (1.2 * bacteria_growth + 0.03 * self.bacteria * hunger) * hours).min(1.0);
+
+        self.yeast * hunger * temperature_factor(temperature, 27.0)
+    }
+
+    fn ph(&self) -> f32 {
+        6.2 - 2.8 * self.acid
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StarterHealth {
+    Healthy,
+    Sluggish,
+    TooAcidic,
+    PeaksTooFast,
+    PeaksTooLate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StarterReport {
+    pub feedings: usize,
+    pub activity: f32, // Yeast population, 0..1
+    pub ph: f32,
+    pub peak_hours: f32, // Hours after the last feeding when CO2 production peaked
+    pub peak_activity: f32, // CO2 production at that peak, 0..1
+    pub health: StarterHealth,
+    pub recommendation: String,
+}
+
+impl StarterReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Activité: {:.0}% | pH: {:.1} | pic {:.1} h après le dernier rafraîchi\n{}",
+            self.activity * 100.0,
+            self.ph,
+            self.peak_hours,
+            self.recommendation
+        )
+    }
+}
+
+// Replays the feeding history and diagnoses the starter as it is after the
+// last feeding
+pub fn diagnose_starter(history: &[Feeding]) -> StarterReport {
+    const STEP_HOURS: f32 = 0.25;
+    let mut culture = StarterCulture::default();
+    let mut peak_hours = 0.0;
+    let mut peak_activity = 0.0;
+
+    for feeding in history {
+        culture.feed(feeding);
+        peak_hours = 0.0;
+        peak_activity = 0.0;
+        let steps = (feeding.hours / STEP_HOURS).ceil() as usize;
+        for step in 1..=steps {
+            let rate = culture.step(feeding.temperature, STEP_HOURS);
+            if rate > peak_activity {
+                peak_activity = rate;
+                peak_hours = step as f32 * STEP_HOURS;
+            }
+        }
+    }
+
+    let ph = culture.ph();
+    // Still rising when the next feeding came: the starter never peaked
+    let never_peaked = history
+        .last()
+        .is_some_and(|last| peak_hours >= last.hours - STEP_HOURS);
+    let (health, recommendation) = if history.is_empty() {
+        (StarterHealth::Sluggish, "Aucun rafraîchi décrit.")
+    } else if ph < 3.8 {
+        (
+            StarterHealth::TooAcidic,
+            "Trop acide : rafraîchir plus souvent ou avec plus de farine (1:5:5).",
+        )
+    } else if culture.yeast < 0.3 {
+        (
+            StarterHealth::Sluggish,
+            "Levain paresseux : rafraîchir deux fois par jour vers 24-27°C.",
+        )
+    } else if peak_hours < 3.0 {
+        (
+            StarterHealth::PeaksTooFast,
+            "Pic très rapide : augmenter la part de farine ou baisser la température.",
+        )
+    } else if peak_hours > 12.0 || never_peaked {
+        (
+            StarterHealth::PeaksTooLate,
+            "Pic tardif ou jamais atteint : garder le levain plus au chaud ou réduire la part de farine.",
+        )
+    } else {
+        (
+            StarterHealth::Healthy,
+            "Levain en forme, prêt à panifier au pic.",
+        )
+    };
+
+    StarterReport {
+        feedings: history.len(),
+        activity: culture.yeast,
+        ph,
+        peak_hours,
+        peak_activity,
+        health,
+        recommendation: recommendation.to_string(),
+    }
+}
+
+// What a recipe's levain brings from the starter it was taken from, against
+// the fresh levain of the recipes: more or fewer yeast, more or fewer lactic
+// bacteria per yeast, and the acid already made
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Levain {
+    pub vigor: f32,   // Active yeast, 1 for a fresh levain
+    pub balance: f32, // Lactic bacteria per yeast, 1 for a fresh levain
+    pub acid: f32,    // Acid per molecule of levain
+    pub acetic_fraction: f32,
+    pub ph: f32,
+}
+
+// A sourdough starter kept between bakes, as a small dough of its own: it is
+// fed flour and water and partly discarded, and between feedings its yeast
+// and lactic bacteria multiply on the flour and sour it. The recipe then
+// takes its levain from it (`Starter::levain`) instead of a fresh one.
+#[derive(Clone)]
+pub struct Starter {
+    pub state: SimulationState, // The jar
+    pub lactobacilli: f32,      // Lactic bacteria, in yeast molecules' worth
+    pub feedings: usize,
+}
+
+impl Starter {
+    // Flour and as much water, with a fresh levain
+    pub fn recipe() -> Recipe {
+        Recipe {
+            name: "levain".to_string(),
+            hydration: 1.0,
+            salt: 0.0,
+            yeast: LEVAIN_YEAST,
+            autolyse_time: 0.0,
+            temperature: 24.0,
+            scale: Some(GramScale::new(STARTER_FLOUR)),
+            ..Recipe::default()
+        }
+    }
+
+    pub fn new(seed: u64) -> Self {
+        let (width, height, depth) = STARTER_SIZE;
+        let mut state = SimulationBuilder::new()
+            .size(width, height, depth)
+            .seed(seed)
+            .recipe(Starter::recipe())
+            .with_yeast()
+            .build();
+        state.apply_action(&SimAction::AdvanceTo(DoughPhase::BulkFerment));
+        // A covered jar: only the gas gets out
+        state.evaporation.water_rate = 0.0;
+        state.evaporation.ethanol_rate = 0.0;
+        let lactobacilli = count(&state, MoleculeType::Yeast) as f32;
+        let mut starter = Starter {
+            state,
+            lactobacilli,
+            feedings: 0,
+        };
+        starter.state.levain = Some(starter.levain());
+        starter
+    }
+
+    // A starter kept by `history`, oldest feeding first
+    pub fn from_history(history: &[Feeding], seed: u64) -> Self {
+        let mut starter = Starter::new(seed);
+        for feeding in history {
+            starter.apply(feeding);
+        }
+        starter
+    }
+
+    // One refresh: keeps the starter's part of the jar, feeds it its parts
+    // of flour and water and leaves it, so the jar stays the same size
+    pub fn apply(&mut self, feeding: &Feeding) {
+        let parts = feeding.starter + feeding.flour + feeding.water;
+        if feeding.starter <= 0.0 || parts <= 0.0 {
+            return;
+        }
+        self.discard(1.0 - feeding.starter / parts);
+        self.feed(
+            feeding.flour / feeding.starter,
+            feeding.water / feeding.starter,
+        );
+        self.rest(feeding.hours, feeding.temperature);
+    }
+
+    // Throws away `share` of the jar, the cultures and the acid with it
+    pub fn discard(&mut self, share: f32) {
+        let share = share.clamp(0.0, 1.0);
+        let state = &mut self.state;
+        let mut ids: Vec<_> = state.grid.molecules().map(|mol| mol.id).collect();
+        ids.shuffle(&mut state.rng);
+        ids.truncate((ids.len() as f32 * share).round() as usize);
+        let mass_before = state.total_mass();
+        for id in ids {
+            state.drop_bonds_of(id);
+            state.grid.remove(id);
+        }
+        state.ledger.added_mass += state.total_mass() - mass_before;
+        let kept = 1.0 - share;
+        state.acidity.lactic *= kept as f64;
+        state.acidity.acetic *= kept as f64;
+        self.lactobacilli *= kept;
+    }
+
+    // Mixes in `flour` and `water` parts per part of what is in the jar,
+    // which dilute its acid
+    pub fn feed(&mut self, flour: f32, water: f32) {
+        let state = &mut self.state;
+        let present = state.grid.len() as f32;
+        let fresh = SimulationBuilder::new()
+            .size(state.width, state.height, state.depth)
+            .seed(state.rng.gen())
+            .recipe(Recipe {
+                hydration: water / flour.max(f32::EPSILON),
+                yeast: 0.0,
+                scale: Some(GramScale::new(present * flour.max(0.0))),
+                ..Starter::recipe()
+            })
+            .config(state.config.clone())
+            .build();
+        let mass_before = state.total_mass();
+        state.ledger.added_mass += state.pour(&fresh);
+        state.acidity.per_levain *= mass_before / state.total_mass().max(f64::EPSILON);
+        state.record_metrics();
+        self.feedings += 1;
+        self.state.levain = Some(self.levain());
+    }
+
+    // Leaves the jar `hours` at `temperature`
+    pub fn rest(&mut self, hours: f32, temperature: f32) {
+        let state = &mut self.state;
+        state.set_environment(EnvironmentPreset::RoomTemp { temp: temperature });
+        state.thermal = ThermalField::uniform(state.thermal_settings.resolution, temperature);
+        state.temperature = temperature;
+        let ticks = (hours.max(0.0) * SECONDS_PER_HOUR / STARTER_STEP).round() as usize;
+        let per_growth = (GROWTH_INTERVAL / STARTER_STEP).round().max(1.0) as usize;
+        for tick in 1..=ticks {
+            self.state.tick(STARTER_STEP);
+            if tick % per_growth == 0 || tick == ticks {
+                let since = match tick % per_growth {
+                    0 => per_growth,
+                    rest => rest,
+                };
+                self.grow(since as f32 * STARTER_STEP / SECONDS_PER_HOUR);
+            }
+        }
+    }
+
+    // Logistic growth of both cultures on what is left of the flour, the
+    // yeast preferring mild warmth, the bacteria warmer and more sour. New
+    // yeast are made of the sugar and starch they eat.
+    fn grow(&mut self, hours: f32) {
+        let state = &self.state;
+        let temperature = state.temperature;
+        let food = count(state, MoleculeType::Sugar) + count(state, MoleculeType::Starch);
+        let capacity = YEAST_CAPACITY * STARTER_FLOUR;
+        let hunger = food as f32 / (food as f32 + 0.1 * state.grid.len().max(1) as f32);
+        let acid = self.sourness();
+        let yeast = count(state, MoleculeType::Yeast) as f32;
+        let yeast_growth = 0.7
+            * temperature_factor(temperature, 27.0)
+            * yeast
+            * hunger
+            * (1.0 - yeast / capacity).max(0.0)
+            * (1.0 - 0.5 * acid);
+        let bacteria_growth = 0.8
+            * temperature_factor(temperature, 32.0)
+            * self.lactobacilli
+            * hunger
+            * (1.0 - self.lactobacilli / capacity).max(0.0)
+            * (1.0 - acid);
+        self.lactobacilli += bacteria_growth * hours;
+
+        let state = &mut self.state;
+        let born = yeast_growth * hours;
+        let born = born.floor() as usize + usize::from(state.rng.gen::<f32>() < born.fract());
+        let mut food: Vec<_> = state
+            .grid
+            .molecules()
+            .filter(|mol| matches!(mol.mol_type, MoleculeType::Sugar | MoleculeType::Starch))
+            .map(|mol| mol.id)
+            .collect();
+        food.shuffle(&mut state.rng);
+        let mass_before = state.total_mass();
+        for id in food.into_iter().take(born) {
+            state.grid.set_mol_type(id, MoleculeType::Yeast);
+        }
+        state.ledger.reaction_delta += state.total_mass() - mass_before;
+        self.state.levain = Some(self.levain());
+    }
+
+    // How far the jar went from fresh to the most sour, 0 to 1
+    fn sourness(&self) -> f32 {
+        let settings = &self.state.acid_settings;
+        let span = (settings.fresh_ph - settings.min_ph).max(f32::EPSILON);
+        ((settings.fresh_ph - self.state.dough_ph()) / span).clamp(0.0, 1.0)
+    }
+
+    // Yeast among the molecules of the jar, the gas left aside
+    fn yeast_share(&self) -> f32 {
+        let jar = self
+            .state
+            .grid
+            .molecules()
+            .filter(|mol| !matches!(mol.mol_type, MoleculeType::CO2 | MoleculeType::Ethanol))
+            .count();
+        count(&self.state, MoleculeType::Yeast) as f32 / jar.max(1) as f32
+    }
+
+    // The levain a recipe takes from the jar now: a sour jar slows its yeast
+    pub fn levain(&self) -> Levain {
+        let yeast = count(&self.state, MoleculeType::Yeast);
+        // Yeast and their sugar, flour and water in a fresh one
+        let fresh = Starter::recipe();
+        let fresh_share = fresh.yeast / (1.0 + fresh.hydration + 2.0 * fresh.yeast);
+        Levain {
+            vigor: self.yeast_share() / fresh_share * (1.0 - 0.5 * self.sourness()),
+            balance: self.lactobacilli / yeast.max(1) as f32,
+            acid: (self.state.acidity.total() / self.state.grid.len().max(1) as f64) as f32,
+            acetic_fraction: self.state.acidity.acetic_fraction() as f32,
+            ph: self.state.dough_ph(),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::species::SPECIES;
+use crate::{Bond, DoughPhase, MoleculeType, SimulationState, Species};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+// Molecules per species, kept by the grid as molecules come, go and change
+// type, so nobody has to walk the dough to count them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeciesCounts {
+    pub counts: [usize; SPECIES.len()], // Indexed like `Species`
+    pub water_units: usize,             // Water molecules, clusters counted by their size
+}
+
+impl SpeciesCounts {
+    pub fn count(&self, species: Species) -> usize {
+        self.counts[species as usize]
+    }
+
+    pub(crate) fn add(&mut self, mol_type: &MoleculeType) {
+        self.counts[mol_type.species() as usize] += 1;
+        self.water_units += mol_type.water_units();
+    }
+
+    pub(crate) fn remove(&mut self, mol_type: &MoleculeType) {
+        let count = &mut self.counts[mol_type.species() as usize];
+        *count = count.saturating_sub(1);
+        self.water_units = self.water_units.saturating_sub(mol_type.water_units());
+    }
+}
+
+// Size and state of the whole dough, read alongside the last `TickSummary`
+// so a bug report carries what it takes to replay the run. Cheap enough for
+// every frame: the fingerprint, which walks the dough, is `state_hash()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimStats {
+    pub time: f32,
+    pub phase: DoughPhase,
+    pub ph: f32,
+    pub molecules: usize,
+    pub molecule_slots: usize, // Arena slots, live or free
+    pub species: SpeciesCounts,
+    pub bonds: usize,
+    pub grid_cells: usize,
+    pub events: usize,
+    pub memory_bytes: usize, // Heap held by the grid, molecules and bonds
+    pub seed: u64,
+}
+
+impl SimStats {
+    pub fn memory_mib(&self) -> f32 {
+        self.memory_bytes as f32 / (1024.0 * 1024.0)
+    }
+
+    pub fn count(&self, species: Species) -> usize {
+        self.species.count(species)
+    }
+
+    // Gliadin and glutenin, bonded or not
+    pub fn proteins(&self) -> usize {
+        self.count(Species::Gliadin)
+            + self.count(Species::ReactiveGlutenin)
+            + self.count(Species::BondedGlutenin)
+    }
+}
+
+fn hash_molecule_type(mol_type: &MoleculeType, hasher: &mut DefaultHasher) {
+    mem::discriminant(mol_type).hash(hasher);
+    match mol_type {
+        MoleculeType::Glutenin { has_free_thiol } => has_free_thiol.hash(hasher),
+        MoleculeType::WaterCluster { size } => size.hash(hasher),
+        _ => {}
+    }
+}
+
+impl SimulationState {
+    pub fn stats(&self) -> SimStats {
+        SimStats {
+            time: self.time_elapsed,
+            phase: self.phase,
+            ph: self.dough_ph(),
+            molecules: self.grid.len(),
+            molecule_slots: self.grid.id_capacity(),
+            species: *self.grid.species_counts(),
+            bonds: self.bonds.len(),
+            grid_cells: self.grid.cell_count(),
+            events: self.events.len(),
+            memory_bytes: self.grid.heap_bytes() + self.bonds.capacity() * mem::size_of::<Bond>(),
+            seed: self.seed,
+        }
+    }
+
+    // Fingerprint of the time, temperature, molecules and bonds, bit for bit:
+    // two runs of the same seed and inputs agree on it tick after tick, and
+    // the first tick they differ on is where a run went its own way
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.time_elapsed.to_bits().hash(&mut hasher);
+        self.temperature.to_bits().hash(&mut hasher);
+        for mol in self.grid.molecules() {
+            mol.id.hash(&mut hasher);
+            hash_molecule_type(&mol.mol_type, &mut hasher);
+            for value in mol.pos.iter().chain(mol.velocity.iter()) {
+                value.to_bits().hash(&mut hasher);
+            }
+        }
+        for bond in &self.bonds {
+            bond.molecule_a_id.hash(&mut hasher);
+            bond.molecule_b_id.hash(&mut hasher);
+            bond.target_distance.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
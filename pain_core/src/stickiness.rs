@@ -0,0 +1,148 @@
+use crate::{
+    DoughPhase, Molecule, MoleculeType, SimulationEvent, SimulationState, Species,
+    REFERENCE_ABSORPTION,
+};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// How sticky the dough feels under the hand: a wet skin sticks, a developed
+// gluten holds its water and sticks less. The hydrations are for a flour
+// taking up REFERENCE_ABSORPTION; a thirstier flour moves both up by the
+// difference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickinessSettings {
+    pub surface_depth: f32,      // Skin the hands touch, under the dough top
+    pub dry_hydration: f32,      // Skin hydration below which nothing sticks
+    pub wet_hydration: f32,      // Skin hydration at which an unworked dough sticks fully
+    pub development_relief: f32, // Share of the stickiness a fully developed gluten takes away
+    pub dust_amount: f32,        // One dusting, as a share of the flour in the dough
+    pub dust_layer: f32,         // Thickness of the dusted flour under the dough top
+}
+
+impl Default for StickinessSettings {
+    fn default() -> Self {
+        StickinessSettings {
+            surface_depth: 100.0,
+            dry_hydration: 0.6,
+            wet_hydration: 0.85,
+            development_relief: 0.6,
+            dust_amount: 0.01,
+            dust_layer: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stickiness {
+    pub surface_hydration: f32, // Water / flour of the skin, in the recipe's terms
+    pub development: f32,       // Gluten development it was read with
+    pub score: f32,             // 0 smooth, 1 sticks to everything
+}
+
+impl Stickiness {
+    pub fn percent(&self) -> f32 {
+        self.score * 100.0
+    }
+}
+
+// Proteins, starch, enzymes and minerals: what the recipe counts as flour
+fn flour_mass(mol: &Molecule) -> f32 {
+    match mol.mol_type {
+        MoleculeType::Gliadin
+        | MoleculeType::Glutenin { .. }
+        | MoleculeType::Starch
+        | MoleculeType::Amylase
+        | MoleculeType::Ash => mol.mass(),
+        _ => 0.0,
+    }
+}
+
+fn water_mass(mol: &Molecule) -> f32 {
+    if mol.mol_type.water_units() > 0 {
+        mol.mass()
+    } else {
+        0.0
+    }
+}
+
+impl SimulationState {
+    pub fn stickiness(&self) -> Stickiness {
+        self.stickiness_of(self.gluten_development().total)
+    }
+
+    // For callers that already measured the gluten development
+    pub fn stickiness_of(&self, development: f32) -> Stickiness {
+        let settings = &self.stickiness_settings;
+        let skin = self.dough_height() - settings.surface_depth;
+        let (mut water, mut flour) = (0.0, 0.0);
+        let (mut skin_water, mut skin_flour) = (0.0, 0.0);
+        for mol in self.grid.molecules() {
+            let (wet, dry) = (water_mass(mol), flour_mass(mol));
+            water += wet;
+            flour += dry;
+            if mol.pos.y >= skin {
+                skin_water += wet;
+                skin_flour += dry;
+            }
+        }
+        // The recipe's hydration, scaled by how much wetter the skin is than
+        // the whole dough
+        let surface_hydration = if water > 0.0 && flour > 0.0 && skin_flour > 0.0 {
+            self.recipe_hydration * (skin_water / skin_flour) / (water / flour)
+        } else {
+            0.0
+        };
+        let thirst = if self.recipe_absorption > 0.0 {
+            self.recipe_absorption - REFERENCE_ABSORPTION
+        } else {
+            0.0
+        };
+        let span = (settings.wet_hydration - settings.dry_hydration).max(f32::EPSILON);
+        let wetness =
+            ((surface_hydration - settings.dry_hydration - thirst) / span).clamp(0.0, 1.0);
+        let relief = settings.development_relief.clamp(0.0, 1.0) * development.clamp(0.0, 1.0);
+        Stickiness {
+            surface_hydration,
+            development,
+            score: wetness * (1.0 - relief),
+        }
+    }
+
+    // Dusts the top of the shaped dough with `amount` of flour, as a share
+    // of the flour already in it: dry starch on the skin, which sticks less,
+    // and the same water over a little more flour. Only while shaping;
+    // returns whether any flour went on.
+    pub fn dust_flour(&mut self, amount: f32) -> bool {
+        if self.phase != DoughPhase::Shape || !amount.is_finite() || amount <= 0.0 {
+            return false;
+        }
+        let flour: f32 = self.grid.molecules().map(flour_mass).sum();
+        let grains = (flour * amount / Species::Starch.info().mass).round() as usize;
+        if grains == 0 {
+            return false;
+        }
+        let top = self.dough_height();
+        let layer = self.stickiness_settings.dust_layer.max(0.0);
+        let mass_before = self.total_mass();
+        for _ in 0..grains {
+            let x = self.rng.gen_range(0.0..self.width);
+            let y = (top - self.rng.gen::<f32>() * layer).clamp(0.0, self.height);
+            let z = self.rng.gen_range(0.0..self.depth);
+            self.grid.insert(Molecule::new(
+                MoleculeType::Starch,
+                Vector3::new(x, y, z),
+                Vector3::zeros(),
+            ));
+        }
+        let dusted = self.total_mass() - mass_before;
+        self.ledger.added_mass += dusted;
+        if flour > 0.0 {
+            self.recipe_hydration *= flour / (flour + dusted as f32);
+        }
+        self.events
+            .push(self.time_elapsed, SimulationEvent::FlourDusted { amount });
+        self.record_metrics();
+        true
+    }
+}
@@ -0,0 +1,194 @@
+use crate::timestep::StepPlan;
+use crate::SimulationState;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Parts of a tick, timed separately to find what makes a tick slow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TickStage {
+    Environment, // Temperature, oven, mixing decay
+    Integration, // Positions, walls, friction
+    Grid,        // Spatial grid and observation volumes
+    Repulsion,   // Overlapping molecules pushed apart
+    Chemistry,
+    SurfaceLosses,
+    WaterClusters,
+    BondSolver,
+}
+
+pub(crate) const TICK_STAGES: usize = 8;
+
+impl TickStage {
+    pub fn all() -> [TickStage; TICK_STAGES] {
+        [
+            TickStage::Environment,
+            TickStage::Integration,
+            TickStage::Grid,
+            TickStage::Repulsion,
+            TickStage::Chemistry,
+            TickStage::SurfaceLosses,
+            TickStage::WaterClusters,
+            TickStage::BondSolver,
+        ]
+    }
+
+    // Label shown to the baker
+    pub fn label(&self) -> &'static str {
+        match self {
+            TickStage::Environment => "environnement",
+            TickStage::Integration => "intégration",
+            TickStage::Grid => "grille spatiale",
+            TickStage::Repulsion => "répulsion",
+            TickStage::Chemistry => "chimie",
+            TickStage::SurfaceLosses => "pertes en surface",
+            TickStage::WaterClusters => "amas d'eau",
+            TickStage::BondSolver => "solveur de liaisons",
+        }
+    }
+}
+
+// What happened during one tick, for overlays, logs and tests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickSummary {
+    pub time: f32, // Sim time at the end of the tick
+    pub sugars_fermented: u64,
+    pub starch_hydrolyzed: u64,
+    pub bonds_formed: u64,
+    pub bonds_broken: u64,
+    pub bond_strength: f32, // Mean remaining strength, see `bond_strength()`
+    pub molecules_added: u64,
+    pub molecules_removed: u64,
+    pub max_velocity: f32,
+    pub solver_residual: f32,
+    pub solver_converged: bool,
+    pub burps: u64, // Gas burps through the surface
+    pub substeps: u32, // Steps the tick ran, 1 without fixed steps
+    pub time_dropped: f32, // Seconds a tick too far behind gave up on
+    pub wall_time: Duration,
+    pub stage_times: [Duration; TICK_STAGES], // Indexed like `TickStage::all()`
+}
+
+impl TickSummary {
+    pub fn reactions_fired(&self) -> u64 {
+        self.sugars_fermented + self.starch_hydrolyzed + self.bonds_formed + self.bonds_broken
+    }
+
+    pub fn stage_time(&self, stage: TickStage) -> Duration {
+        self.stage_times[stage as usize]
+    }
+
+    // Stage that took the longest, None for a summary that was never filled
+    pub fn slowest_stage(&self) -> Option<(TickStage, Duration)> {
+        TickStage::all()
+            .into_iter()
+            .map(|stage| (stage, self.stage_time(stage)))
+            .filter(|(_, time)| !time.is_zero())
+            .max_by_key(|(_, time)| *time)
+    }
+}
+
+// One line per tick, for logs
+impl fmt::Display for TickSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "t={:.2} reactions={} (fermented {}, hydrolyzed {}, bonds +{}/-{}) strength={:.2} molecules +{}/-{} vmax={:.2} residual={:.3}{} in {:?}{}",
+            self.time,
+            self.reactions_fired(),
+            self.sugars_fermented,
+            self.starch_hydrolyzed,
+            self.bonds_formed,
+            self.bonds_broken,
+            self.bond_strength,
+            self.molecules_added,
+            self.molecules_removed,
+            self.max_velocity,
+            self.solver_residual,
+            if self.solver_converged { "" } else { " (not converged)" },
+            self.wall_time,
+            self.slowest_stage()
+                .map(|(stage, time)| format!(", slowest {:?} {:?}", stage, time))
+                .unwrap_or_default()
+        )
+    }
+}
+
+pub(crate) struct StageTimer {
+    last: Instant,
+    times: [Duration; TICK_STAGES],
+}
+
+impl StageTimer {
+    fn new() -> Self {
+        StageTimer {
+            last: Instant::now(),
+            times: [Duration::ZERO; TICK_STAGES],
+        }
+    }
+
+    // Charges the time since the previous lap to `stage`
+    pub(crate) fn lap(&mut self, stage: TickStage) {
+        let now = Instant::now();
+        self.times[stage as usize] += now - self.last;
+        self.last = now;
+    }
+}
+
+// Counters read before the tick, to diff against afterwards
+pub(crate) struct TickStart {
+    pub(crate) stages: StageTimer,
+    started: Instant,
+    sugars_fermented: u64,
+    starch_hydrolyzed: u64,
+    bonds_formed: u64,
+    bonds_broken: u64,
+    inserted: u64,
+    molecule_count: u64,
+    burps: u64,
+}
+
+impl SimulationState {
+    pub(crate) fn tick_start(&self) -> TickStart {
+        TickStart {
+            stages: StageTimer::new(),
+            started: Instant::now(),
+            sugars_fermented: self.reactions.sugars_fermented,
+            starch_hydrolyzed: self.reactions.starch_hydrolyzed,
+            bonds_formed: self.reactions.bonds_formed,
+            bonds_broken: self.reactions.bonds_broken,
+            inserted: self.grid.inserted(),
+            molecule_count: self.grid.len() as u64,
+            burps: self.ledger.burps,
+        }
+    }
+
+    pub(crate) fn tick_summary(&self, start: TickStart, plan: &StepPlan) -> TickSummary {
+        let molecules_added = self.grid.inserted() - start.inserted;
+        let max_velocity = self
+            .grid
+            .get_all_molecules()
+            .iter()
+            .map(|mol| mol.velocity.magnitude())
+            .fold(0.0, f32::max);
+        TickSummary {
+            time: self.time_elapsed,
+            sugars_fermented: self.reactions.sugars_fermented - start.sugars_fermented,
+            starch_hydrolyzed: self.reactions.starch_hydrolyzed - start.starch_hydrolyzed,
+            bonds_formed: self.reactions.bonds_formed - start.bonds_formed,
+            bonds_broken: self.reactions.bonds_broken - start.bonds_broken,
+            bond_strength: self.average_bond_strength().unwrap_or(0.0),
+            molecules_added,
+            molecules_removed: (start.molecule_count + molecules_added)
+                .saturating_sub(self.grid.len() as u64),
+            max_velocity,
+            solver_residual: self.last_solver_report.residual,
+            solver_converged: self.last_solver_report.converged,
+            burps: self.ledger.burps - start.burps,
+            substeps: plan.steps,
+            time_dropped: plan.dropped,
+            wall_time: start.started.elapsed(),
+            stage_times: start.stages.times,
+        }
+    }
+}
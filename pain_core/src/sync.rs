@@ -0,0 +1,280 @@
+use crate::{MoleculeId, MoleculeType, SimulationState, StableHashMap};
+use nalgebra::Vector3;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Steps a renderer may lag behind and still get only what changed
+const JOURNAL_DEPTH: u64 = 256;
+
+// Journals are told apart by an epoch: a new or reloaded dough gets a new
+// one, so a renderer holding a token of another dough resyncs in full
+static NEXT_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+// Where a renderer stands: the journal and the revision it last synced to.
+// The default token matches no journal and asks for everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncToken {
+    epoch: u64,
+    revision: u64,
+}
+
+// What changed since a token. A full resync lists every molecule as added
+// and every bond as added; the renderer drops whatever it had first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimChanges {
+    pub token: SyncToken, // To pass to the next call
+    pub full: bool,
+    pub added: Vec<MoleculeId>,
+    pub removed: Vec<MoleculeId>,
+    pub moved: Vec<MoleculeId>,
+    pub retyped: Vec<MoleculeId>, // Type changed (species, cluster size), moved or not
+    pub bonds_added: Vec<(MoleculeId, MoleculeId)>,
+    pub bonds_removed: Vec<(MoleculeId, MoleculeId)>,
+}
+
+impl SimChanges {
+    pub fn is_empty(&self) -> bool {
+        !self.full
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.retyped.is_empty()
+            && self.bonds_added.is_empty()
+            && self.bonds_removed.is_empty()
+    }
+}
+
+// What the journal last saw in an arena slot
+#[derive(Debug, Clone)]
+struct SlotRecord {
+    id: MoleculeId,
+    pos: Vector3<f32>,
+    mol_type: MoleculeType,
+    added_at: u64,
+    moved_at: u64,
+    retyped_at: u64,
+    seen_at: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BondRecord {
+    added_at: u64,
+    seen_at: u64,
+}
+
+// Molecules and bonds as of the end of each step, compared with the step
+// before, for renderers that update their scene instead of rebuilding it.
+// Off until `track_changes()`; not saved, a loaded dough starts a new one.
+#[derive(Debug, Clone)]
+pub struct ChangeJournal {
+    enabled: bool,
+    epoch: u64,
+    revision: u64,
+    slots: Vec<Option<SlotRecord>>,
+    removed: VecDeque<(u64, MoleculeId)>,
+    bonds: StableHashMap<(MoleculeId, MoleculeId), BondRecord>,
+    bonds_removed: VecDeque<(u64, (MoleculeId, MoleculeId))>,
+}
+
+impl Default for ChangeJournal {
+    fn default() -> Self {
+        ChangeJournal {
+            enabled: false,
+            epoch: NEXT_EPOCH.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
+            slots: Vec::new(),
+            removed: VecDeque::new(),
+            bonds: StableHashMap::default(),
+            bonds_removed: VecDeque::new(),
+        }
+    }
+}
+
+impl ChangeJournal {
+    // Oldest revision a token may hold and still get only the changes
+    fn horizon(&self) -> u64 {
+        self.revision.saturating_sub(JOURNAL_DEPTH)
+    }
+
+    fn token(&self) -> SyncToken {
+        SyncToken {
+            epoch: self.epoch,
+            revision: self.revision,
+        }
+    }
+
+    fn forget_before(&mut self, horizon: u64) {
+        while self.removed.front().is_some_and(|(at, _)| *at <= horizon) {
+            self.removed.pop_front();
+        }
+        while self
+            .bonds_removed
+            .front()
+            .is_some_and(|(at, _)| *at <= horizon)
+        {
+            self.bonds_removed.pop_front();
+        }
+    }
+}
+
+// Same key for a bond whichever way round it was made
+fn bond_key(a: MoleculeId, b: MoleculeId) -> (MoleculeId, MoleculeId) {
+    (a.min(b), a.max(b))
+}
+
+impl SimulationState {
+    // Starts keeping the change journal, for `changes_since()`; the dough
+    // as it is now is the starting point
+    pub fn track_changes(&mut self) {
+        if !self.journal.enabled {
+            self.journal.enabled = true;
+            self.record_changes();
+        }
+    }
+
+    pub fn tracks_changes(&self) -> bool {
+        self.journal.enabled
+    }
+
+    // Compares the dough with the last step and notes what changed
+    pub(crate) fn record_changes(&mut self) {
+        let journal = &mut self.journal;
+        if !journal.enabled {
+            return;
+        }
+        journal.revision += 1;
+        let revision = journal.revision;
+
+        for mol in self.grid.molecules() {
+            let index = mol.id.index();
+            if journal.slots.len() <= index {
+                journal.slots.resize(index + 1, None);
+            }
+            match &mut journal.slots[index] {
+                Some(record) if record.id == mol.id => {
+                    if record.pos != mol.pos {
+                        record.pos = mol.pos;
+                        record.moved_at = revision;
+                    }
+                    if record.mol_type != mol.mol_type {
+                        record.mol_type = mol.mol_type.clone();
+                        record.retyped_at = revision;
+                    }
+                    record.seen_at = revision;
+                }
+                slot => {
+                    // A reused slot: the molecule it held is gone
+                    if let Some(old) = slot {
+                        journal.removed.push_back((revision, old.id));
+                    }
+                    *slot = Some(SlotRecord {
+                        id: mol.id,
+                        pos: mol.pos,
+                        mol_type: mol.mol_type.clone(),
+                        added_at: revision,
+                        moved_at: revision,
+                        retyped_at: revision,
+                        seen_at: revision,
+                    });
+                }
+            }
+        }
+        for slot in &mut journal.slots {
+            if slot
+                .as_ref()
+                .is_some_and(|record| record.seen_at != revision)
+            {
+                if let Some(record) = slot.take() {
+                    journal.removed.push_back((revision, record.id));
+                }
+            }
+        }
+
+        for bond in &self.bonds {
+            journal
+                .bonds
+                .entry(bond_key(bond.molecule_a_id, bond.molecule_b_id))
+                .and_modify(|record| record.seen_at = revision)
+                .or_insert(BondRecord {
+                    added_at: revision,
+                    seen_at: revision,
+                });
+        }
+        let bonds_removed = &mut journal.bonds_removed;
+        journal.bonds.retain(|key, record| {
+            if record.seen_at != revision {
+                bonds_removed.push_back((revision, *key));
+            }
+            record.seen_at == revision
+        });
+        let horizon = journal.horizon();
+        journal.forget_before(horizon);
+    }
+
+    // Molecules and bonds added, removed, moved or changed since `since`, as
+    // of the last step. A token from another dough, or too old, gets a full
+    // resync; so does any token while the journal is off, and the token it
+    // returns then is good for nothing but another full resync.
+    pub fn changes_since(&self, since: SyncToken) -> SimChanges {
+        let journal = &self.journal;
+        let token = journal.token();
+        let partial = journal.enabled
+            && since.epoch == journal.epoch
+            && since.revision >= journal.horizon()
+            && since.revision <= journal.revision;
+        if !partial {
+            return SimChanges {
+                token: if journal.enabled {
+                    token
+                } else {
+                    SyncToken::default()
+                },
+                full: true,
+                added: self.grid.molecules().map(|mol| mol.id).collect(),
+                bonds_added: self
+                    .bonds
+                    .iter()
+                    .map(|bond| bond_key(bond.molecule_a_id, bond.molecule_b_id))
+                    .collect(),
+                ..Default::default()
+            };
+        }
+
+        let after = since.revision;
+        let mut changes = SimChanges {
+            token,
+            ..Default::default()
+        };
+        for record in journal.slots.iter().flatten() {
+            if record.added_at > after {
+                changes.added.push(record.id);
+                continue;
+            }
+            if record.moved_at > after {
+                changes.moved.push(record.id);
+            }
+            if record.retyped_at > after {
+                changes.retyped.push(record.id);
+            }
+        }
+        changes.removed = journal
+            .removed
+            .iter()
+            .filter(|(at, _)| *at > after)
+            .map(|(_, id)| *id)
+            .collect();
+        changes.bonds_added = journal
+            .bonds
+            .iter()
+            .filter(|(_, record)| record.added_at > after)
+            .map(|(key, _)| *key)
+            .collect();
+        changes.bonds_removed = journal
+            .bonds_removed
+            .iter()
+            .filter(|(at, _)| *at > after)
+            .map(|(_, key)| *key)
+            .collect();
+        changes
+    }
+}
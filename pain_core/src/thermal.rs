@@ -0,0 +1,266 @@
+use crate::SimulationState;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalSettings {
+    pub resolution: usize, // Cells along each side of the dough
+    // Seconds for heat to diffuse from the surface to the center: half the
+    // smallest side squared over the diffusivity. A loaf takes about an hour.
+    pub diffusion_time: f32,
+}
+
+impl Default for ThermalSettings {
+    fn default() -> Self {
+        ThermalSettings {
+            resolution: 6,
+            diffusion_time: 3600.0,
+        }
+    }
+}
+
+// Where the surface of the dough exchanges heat: the room or the oven at
+// `temperature`, closing 63% of the gap of a uniform dough in `lag` seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatExchange {
+    pub temperature: f32,
+    pub lag: f32,
+}
+
+// Dough temperature on a coarse grid spanning the container, so the core can
+// lag behind the surface; cells are indexed in fractions of the container and
+// do not depend on its size
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThermalField {
+    resolution: usize,
+    cells: Vec<f32>,
+    mean: f32, // Kept with the cells, read for every molecule
+}
+
+impl ThermalField {
+    pub fn uniform(resolution: usize, temperature: f32) -> Self {
+        let resolution = resolution.max(1);
+        ThermalField {
+            resolution,
+            cells: vec![temperature; resolution.pow(3)],
+            mean: temperature,
+        }
+    }
+
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * self.resolution + y) * self.resolution + z
+    }
+
+    fn is_surface(&self, x: usize, y: usize, z: usize) -> bool {
+        let last = self.resolution - 1;
+        x == 0 || y == 0 || z == 0 || x == last || y == last || z == last
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    fn update_mean(&mut self) {
+        if self.cells.is_empty() {
+            return;
+        }
+        // Summed in f64 so a uniform field averages back to its exact value
+        let sum: f64 = self.cells.iter().map(|&cell| cell as f64).sum();
+        self.mean = (sum / self.cells.len() as f64) as f32;
+    }
+
+    // Coldest and warmest cells
+    pub fn range(&self) -> (f32, f32) {
+        self.cells
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &cell| {
+                (low.min(cell), high.max(cell))
+            })
+    }
+
+    // Moves every cell by `delta`, keeping the gradients
+    pub fn shift(&mut self, delta: f32) {
+        for cell in &mut self.cells {
+            *cell += delta;
+        }
+        self.update_mean();
+    }
+
+    // Interpolated between the cell centers of a container of `size`
+    pub fn temperature_at(&self, pos: Vector3<f32>, size: Vector3<f32>) -> f32 {
+        let n = self.resolution;
+        let axis = |coord: f32, extent: f32| {
+            let u = (coord / extent.max(f32::EPSILON) * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            let low = (u.floor() as usize).min(n - 1);
+            (low, (low + 1).min(n - 1), u - low as f32)
+        };
+        let (x0, x1, tx) = axis(pos.x, size.x);
+        let (y0, y1, ty) = axis(pos.y, size.y);
+        let (z0, z1, tz) = axis(pos.z, size.z);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let at = |x, y, z| self.cells[self.index(x, y, z)];
+        let front = lerp(
+            lerp(at(x0, y0, z0), at(x1, y0, z0), tx),
+            lerp(at(x0, y1, z0), at(x1, y1, z0), tx),
+            ty,
+        );
+        let back = lerp(
+            lerp(at(x0, y0, z1), at(x1, y0, z1), tx),
+            lerp(at(x0, y1, z1), at(x1, y1, z1), tx),
+            ty,
+        );
+        lerp(front, back, tz)
+    }
+
+    // Explicit diffusion between neighboring cells, sub-stepped to stay
+    // stable, with the surface cells exchanging heat with `exchange`. Below
+    // the surface no cell goes past `cap` (water boiling in the crumb).
+    pub fn step(
+        &mut self,
+        dt: f32,
+        size: Vector3<f32>,
+        settings: &ThermalSettings,
+        exchange: Option<HeatExchange>,
+        cap: Option<f32>,
+    ) {
+        let n = self.resolution;
+        if self.cells.is_empty() || dt <= 0.0 {
+            return;
+        }
+        let half_side = size.x.min(size.y).min(size.z).max(f32::EPSILON) / 2.0;
+        let diffusivity = half_side * half_side / settings.diffusion_time.max(1.0);
+        let rates = size.map(|extent| {
+            let cell = extent.max(f32::EPSILON) / n as f32;
+            diffusivity / (cell * cell)
+        });
+        let total_rate = 2.0 * (rates.x + rates.y + rates.z);
+        let substeps = ((dt * total_rate).ceil() as usize).max(1);
+        let sub_dt = dt / substeps as f32;
+
+        // Surface exchange calibrated on the whole dough: a uniform field
+        // moves as fast as the single temperature did before
+        let surface_cells = n.pow(3) - n.saturating_sub(2).pow(3);
+        let surface_fraction = surface_cells as f32 / n.pow(3) as f32;
+
+        let mut next = self.cells.clone();
+        for _ in 0..substeps {
+            for x in 0..n {
+                for y in 0..n {
+                    for z in 0..n {
+                        let here = self.cells[self.index(x, y, z)];
+                        let mut flow = 0.0;
+                        let mut neighbor = |nx: usize, ny: usize, nz: usize, rate: f32| {
+                            flow += rate * (self.cells[self.index(nx, ny, nz)] - here);
+                        };
+                        if x > 0 {
+                            neighbor(x - 1, y, z, rates.x);
+                        }
+                        if x + 1 < n {
+                            neighbor(x + 1, y, z, rates.x);
+                        }
+                        if y > 0 {
+                            neighbor(x, y - 1, z, rates.y);
+                        }
+                        if y + 1 < n {
+                            neighbor(x, y + 1, z, rates.y);
+                        }
+                        if z > 0 {
+                            neighbor(x, y, z - 1, rates.z);
+                        }
+                        if z + 1 < n {
+                            neighbor(x, y, z + 1, rates.z);
+                        }
+                        let mut value = here + flow * sub_dt;
+                        let surface = self.is_surface(x, y, z);
+                        if let (true, Some(exchange)) = (surface, exchange) {
+                            let lag = exchange.lag.max(1.0) * surface_fraction;
+                            let follow = 1.0 - (-sub_dt / lag).exp();
+                            value += (exchange.temperature - value) * follow;
+                        }
+                        if let (false, Some(cap)) = (surface, cap) {
+                            value = value.min(cap);
+                        }
+                        next[self.index(x, y, z)] = value;
+                    }
+                }
+            }
+            std::mem::swap(&mut self.cells, &mut next);
+        }
+        self.update_mean();
+    }
+}
+
+impl SimulationState {
+    pub(crate) fn container_size(&self) -> Vector3<f32> {
+        Vector3::new(self.width, self.height, self.depth)
+    }
+
+    // Set directly since the last tick, `temperature` moves the whole field
+    fn pending_shift(&self) -> f32 {
+        self.temperature - self.thermal.mean()
+    }
+
+    // Local dough temperature, what the reactions around `pos` feel
+    pub fn temperature_at(&self, pos: Vector3<f32>) -> f32 {
+        if self.thermal.is_empty() {
+            return self.temperature;
+        }
+        self.thermal.temperature_at(pos, self.container_size()) + self.pending_shift()
+    }
+
+    // Temperature at the center of the dough, what a probe thermometer reads
+    pub fn core_temperature(&self) -> f32 {
+        self.temperature_at(self.center())
+    }
+
+    // Coldest and warmest spots of the dough
+    pub fn temperature_range(&self) -> (f32, f32) {
+        if self.thermal.is_empty() {
+            return (self.temperature, self.temperature);
+        }
+        let (coldest, warmest) = self.thermal.range();
+        let shift = self.pending_shift();
+        (coldest + shift, warmest + shift)
+    }
+
+    // `temperature` is the mean of the field; when something set it directly
+    // (recipe, action, perturbation, replay) the field follows, keeping its
+    // gradients
+    fn sync_thermal_field(&mut self) {
+        let resolution = self.thermal_settings.resolution.max(1);
+        if self.thermal.resolution() != resolution || self.thermal.is_empty() {
+            self.thermal = ThermalField::uniform(resolution, self.temperature);
+            return;
+        }
+        let delta = self.pending_shift();
+        if delta != 0.0 {
+            self.thermal.shift(delta);
+        }
+    }
+
+    // Heat flows through the dough and across its surface, from the oven
+    // during the bake or from the room. A temperature spike in progress
+    // takes precedence over both.
+    pub(crate) fn update_thermal_field(&mut self, dt: f32) {
+        self.sync_thermal_field();
+        let (exchange, cap) = if self.temperature_spike.is_some() {
+            (None, None)
+        } else if let Some(exchange) = self.oven_exchange() {
+            (Some(exchange), Some(self.bake.crumb_max_temp))
+        } else {
+            (self.ambient_exchange(), None)
+        };
+        let size = self.container_size();
+        self.thermal
+            .step(dt, size, &self.thermal_settings, exchange, cap);
+        self.temperature = self.thermal.mean();
+    }
+}
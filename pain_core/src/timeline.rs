@@ -0,0 +1,449 @@
+use crate::schedule::{ProcessSchedule, ScheduleStep, StepCondition};
+use crate::{DoughPhase, KneadAction, SimAction, SimulationEvent, SimulationState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// A declarative process, read from TOML so a long ferment runs without a
+// keypress for every action:
+//
+//     [[step]]
+//     at = "30min"
+//     action = "salt"
+//
+//     [[step]]
+//     at = ["60min", "90min", "120min"]
+//     action = "fold"
+//
+//     [[step]]
+//     at = "4h"
+//     action = "shape"
+//     when = { RiseAtLeast = 0.5 }
+//     timeout = "1h"
+//
+//     [[step]]
+//     at = "8h"
+//     action = "bake"
+//     temperature = 240
+//
+// Times are sim seconds from the start of the dough, as a number or text
+// like "90s", "30min", "4h" or "1h30".
+
+// What a timeline step does, named like in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineAction {
+    Salt,
+    Yeast,
+    Fold,
+    StretchAndFold,
+    CoilFold,
+    SlapAndFold,
+    Shape,
+    DustFlour,
+    Malt,
+    AscorbicAcid,
+    Proof,
+    Bake,
+    Temperature,
+}
+
+// One step of the timeline as the baker writes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineStep {
+    pub at: f32, // Sim seconds from the start of the dough
+    pub action: Option<TimelineAction>,
+    pub label: Option<String>,
+    pub temperature: Option<f32>, // °C: the target for "temperature", the oven for "bake"
+    pub when: Option<StepCondition>,
+    pub timeout: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    pub steps: Vec<TimelineStep>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimelineFile {
+    #[serde(default, rename = "step")]
+    steps: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimelineEntry {
+    at: Times,
+    action: Option<TimelineAction>,
+    label: Option<String>,
+    temperature: Option<f32>,
+    when: Option<StepCondition>,
+    timeout: Option<Time>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Time {
+    Seconds(f32),
+    Text(String),
+}
+
+// One time or several, "fold at 60, 90 and 120 min" is a single entry
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Times {
+    One(Time),
+    Many(Vec<Time>),
+}
+
+// Written back one step per entry, times as text
+#[derive(Serialize)]
+struct TimelineOutput<'a> {
+    step: Vec<TimelineOutputStep<'a>>,
+}
+
+#[derive(Serialize)]
+struct TimelineOutputStep<'a> {
+    at: String,
+    action: Option<TimelineAction>,
+    label: Option<&'a str>,
+    temperature: Option<f32>,
+    timeout: Option<String>,
+    when: Option<StepCondition>,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// "90", "90s", "30min", "4h", "1h30min", or "1h30" and "2min30" where the
+// trailing number is in the next unit down
+pub fn parse_duration(text: &str) -> Option<f32> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<f32>() {
+        return Some(seconds).filter(|seconds| seconds.is_finite());
+    }
+    let mut total = 0.0;
+    let mut rest = text;
+    let mut last_unit = None;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let value: f32 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = match (&rest[..letters], last_unit) {
+            ("h", _) => 3600.0,
+            ("min" | "m", _) => 60.0,
+            ("s", _) => 1.0,
+            ("", Some(3600.0)) => 60.0,
+            ("", Some(60.0)) => 1.0,
+            _ => return None,
+        };
+        total += value * unit;
+        last_unit = Some(unit);
+        rest = rest[letters..].trim_start();
+    }
+    last_unit.map(|_| total)
+}
+
+// The reverse of parse_duration: 5400 -> "1h30min", 150 -> "2min30s"
+pub fn format_duration(seconds: f32) -> String {
+    let whole = seconds.max(0.0).floor();
+    let hours = (whole / 3600.0).floor();
+    let minutes = ((whole - hours * 3600.0) / 60.0).floor();
+    let rest = seconds.max(0.0) - hours * 3600.0 - minutes * 60.0;
+    let mut text = String::new();
+    if hours > 0.0 {
+        text.push_str(&format!("{}h", hours));
+    }
+    if minutes > 0.0 {
+        text.push_str(&format!("{}min", minutes));
+    }
+    if rest > 0.0 || text.is_empty() {
+        text.push_str(&format!("{}s", rest));
+    }
+    text
+}
+
+impl Time {
+    fn seconds(&self) -> io::Result<f32> {
+        let seconds = match self {
+            Time::Seconds(seconds) => Some(*seconds),
+            Time::Text(text) => parse_duration(text),
+        };
+        seconds
+            .filter(|seconds| *seconds >= 0.0)
+            .ok_or_else(|| match self {
+                Time::Seconds(seconds) => invalid_data(format!("invalid time {}", seconds)),
+                Time::Text(text) => invalid_data(format!("invalid time {:?}", text)),
+            })
+    }
+}
+
+impl TimelineAction {
+    pub fn all() -> [TimelineAction; 13] {
+        [
+            TimelineAction::Salt,
+            TimelineAction::Yeast,
+            TimelineAction::Fold,
+            TimelineAction::StretchAndFold,
+            TimelineAction::CoilFold,
+            TimelineAction::SlapAndFold,
+            TimelineAction::Shape,
+            TimelineAction::DustFlour,
+            TimelineAction::Malt,
+            TimelineAction::AscorbicAcid,
+            TimelineAction::Proof,
+            TimelineAction::Bake,
+            TimelineAction::Temperature,
+        ]
+    }
+
+    // Label shown to the baker when the step has none
+    pub fn label(&self, temperature: Option<f32>) -> String {
+        match self {
+            TimelineAction::Salt => "Sel".to_string(),
+            TimelineAction::Yeast => "Levain".to_string(),
+            TimelineAction::Fold => "Rabat".to_string(),
+            TimelineAction::StretchAndFold => KneadAction::StretchAndFold.label().to_string(),
+            TimelineAction::CoilFold => KneadAction::CoilFold.label().to_string(),
+            TimelineAction::SlapAndFold => KneadAction::SlapAndFold.label().to_string(),
+            TimelineAction::Shape => "Façonnage".to_string(),
+            TimelineAction::DustFlour => "Fleurage".to_string(),
+            TimelineAction::Malt => "Malt diastasique".to_string(),
+            TimelineAction::AscorbicAcid => "Acide ascorbique".to_string(),
+            TimelineAction::Proof => "Apprêt".to_string(),
+            TimelineAction::Bake => match temperature {
+                Some(oven) => format!("Enfournement à {:.0} °C", oven),
+                None => "Enfournement".to_string(),
+            },
+            TimelineAction::Temperature => {
+                format!("Température {:.0} °C", temperature.unwrap_or_default())
+            }
+        }
+    }
+
+    fn sim_action(&self, temperature: Option<f32>, state: &SimulationState) -> SimAction {
+        match self {
+            TimelineAction::Salt => SimAction::AddSalt,
+            TimelineAction::Yeast => SimAction::AddYeast,
+            TimelineAction::Fold => SimAction::centered_fold(state),
+            TimelineAction::StretchAndFold => SimAction::Knead(KneadAction::StretchAndFold),
+            TimelineAction::CoilFold => SimAction::Knead(KneadAction::CoilFold),
+            TimelineAction::SlapAndFold => SimAction::Knead(KneadAction::SlapAndFold),
+            TimelineAction::Shape => SimAction::AdvanceTo(DoughPhase::Shape),
+            TimelineAction::DustFlour => {
+                SimAction::DustFlour(state.stickiness_settings.dust_amount)
+            }
+            TimelineAction::Malt => SimAction::AddMalt(state.improver_settings.malt_amount),
+            TimelineAction::AscorbicAcid => {
+                SimAction::AddAscorbicAcid(state.improver_settings.ascorbic_amount)
+            }
+            TimelineAction::Proof => SimAction::AdvanceTo(DoughPhase::Proof),
+            TimelineAction::Bake => match temperature {
+                Some(oven_temperature) => SimAction::Bake(oven_temperature),
+                None => SimAction::AdvanceTo(DoughPhase::Bake),
+            },
+            TimelineAction::Temperature => {
+                SimAction::SetTemperature(temperature.unwrap_or(state.temperature))
+            }
+        }
+    }
+}
+
+impl TimelineStep {
+    pub fn label(&self) -> String {
+        match (&self.label, self.action) {
+            (Some(label), _) => label.clone(),
+            (None, Some(action)) => action.label(self.temperature),
+            (None, None) => String::new(),
+        }
+    }
+
+    // Latest time the step can fire, None while it may wait forever
+    pub fn deadline(&self) -> Option<f32> {
+        match (self.when, self.timeout) {
+            (None, _) => Some(self.at),
+            (Some(_), Some(timeout)) => Some(self.at + timeout),
+            (Some(_), None) => None,
+        }
+    }
+
+    fn schedule_step(&self, state: &SimulationState) -> ScheduleStep {
+        ScheduleStep {
+            label: self.label(),
+            at: self.at,
+            action: self
+                .action
+                .map(|action| action.sim_action(self.temperature, state)),
+            when: self.when,
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl Timeline {
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let file: TimelineFile =
+            toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut steps = Vec::new();
+        for (index, entry) in file.steps.into_iter().enumerate() {
+            let in_step = |err: io::Error| invalid_data(format!("step {}: {}", index + 1, err));
+            if entry.action.is_none() && entry.label.is_none() {
+                return Err(in_step(invalid_data(
+                    "a step needs an action or a label".to_string(),
+                )));
+            }
+            if entry.action == Some(TimelineAction::Temperature) && entry.temperature.is_none() {
+                return Err(in_step(invalid_data(
+                    "\"temperature\" action without a temperature".to_string(),
+                )));
+            }
+            let timeout = entry
+                .timeout
+                .as_ref()
+                .map(Time::seconds)
+                .transpose()
+                .map_err(in_step)?;
+            let times = match entry.at {
+                Times::One(time) => vec![time],
+                Times::Many(times) => times,
+            };
+            for time in times {
+                steps.push(TimelineStep {
+                    at: time.seconds().map_err(in_step)?,
+                    action: entry.action,
+                    label: entry.label.clone(),
+                    temperature: entry.temperature,
+                    when: entry.when,
+                    timeout,
+                });
+            }
+        }
+        steps.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Ok(Timeline { steps })
+    }
+
+    pub fn to_toml(&self) -> io::Result<String> {
+        let output = TimelineOutput {
+            step: self
+                .steps
+                .iter()
+                .map(|step| TimelineOutputStep {
+                    at: format_duration(step.at),
+                    action: step.action,
+                    label: step.label.as_deref(),
+                    temperature: step.temperature,
+                    timeout: step.timeout.map(format_duration),
+                    when: step.when,
+                })
+                .collect(),
+        };
+        toml::to_string_pretty(&output).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        Timeline::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_toml()?)
+    }
+
+    // Replaces the [[step]] entries of `path` and keeps its other keys, so a
+    // recipe file carries its own timeline. Comments are not kept.
+    pub fn save_into(&self, path: &Path) -> io::Result<()> {
+        let invalid = |e: toml::de::Error| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut file: toml::Table = match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(invalid)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+            Err(err) => return Err(err),
+        };
+        let steps: toml::Table = toml::from_str(&self.to_toml()?).map_err(invalid)?;
+        file.remove("step");
+        file.extend(steps);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    // Folds are centered on the container of `state`
+    pub fn scheduler(&self, state: &SimulationState) -> ActionScheduler {
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| step.schedule_step(state))
+            .collect();
+        ActionScheduler::new(ProcessSchedule::new(steps))
+    }
+}
+
+// The process the simulation runs by itself: every tick fires the steps that
+// are due before moving the molecules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionScheduler {
+    schedule: ProcessSchedule,
+}
+
+impl ActionScheduler {
+    pub fn new(schedule: ProcessSchedule) -> Self {
+        ActionScheduler { schedule }
+    }
+
+    pub fn from_toml(text: &str, state: &SimulationState) -> io::Result<Self> {
+        Ok(Timeline::from_toml(text)?.scheduler(state))
+    }
+
+    pub fn load_from(path: &Path, state: &SimulationState) -> io::Result<Self> {
+        ActionScheduler::from_toml(&fs::read_to_string(path)?, state)
+    }
+
+    pub fn schedule(&self) -> &ProcessSchedule {
+        &self.schedule
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.schedule.is_finished()
+    }
+
+    pub fn restart(&mut self) {
+        self.schedule.restart();
+    }
+
+    // Steps already in the past are taken as done, for a schedule swapped in
+    // while the dough is running
+    pub fn skip_before(&mut self, time: f32) {
+        self.schedule.skip_before(time);
+    }
+}
+
+impl SimulationState {
+    pub(crate) fn run_scheduled_actions(&mut self) {
+        if self.scheduler.is_finished() {
+            return;
+        }
+        // Taken out while it runs, the actions need the whole state
+        let mut scheduler = std::mem::take(&mut self.scheduler);
+        for step in scheduler.schedule.poll(self) {
+            self.events.push(
+                self.time_elapsed,
+                SimulationEvent::ScheduledStep {
+                    label: step.label,
+                    timed_out: step.timed_out,
+                },
+            );
+        }
+        self.scheduler = scheduler;
+    }
+}
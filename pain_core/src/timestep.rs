@@ -0,0 +1,114 @@
+use crate::{Molecule, SimulationState};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Fixed physics steps. Enabled, a tick no longer applies the dt it is given in
+// one go: it adds it to an accumulator and runs as many steps of 1 / `rate`
+// seconds as fit, so the dough behaves the same whatever the frame rate. What
+// is left carries over to the next tick, and renderers draw the molecules
+// `interpolation_alpha()` of the way from the last step to the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FixedTimestep {
+    pub enabled: bool,     // Off, a tick is one step of whatever dt it gets
+    pub rate: f32,         // Steps per simulated second
+    pub max_substeps: u32, // Steps one tick may run; the time beyond is dropped
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        FixedTimestep {
+            enabled: false,
+            rate: 4.0, // The 0.25 s tick the constants were tuned for
+            max_substeps: 64,
+        }
+    }
+}
+
+impl FixedTimestep {
+    // Seconds of one step
+    pub fn step(&self) -> f32 {
+        1.0 / self.rate.max(f32::EPSILON)
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled && self.rate > 0.0
+    }
+}
+
+// The steps a tick runs
+pub(crate) struct StepPlan {
+    pub(crate) steps: u32,
+    pub(crate) dt: f32,
+    pub(crate) dropped: f32, // Seconds left out past `max_substeps`
+}
+
+impl SimulationState {
+    // How far the renderer is between the last step and the next one, 0 to 1;
+    // always 1 without fixed steps
+    pub fn interpolation_alpha(&self) -> f32 {
+        if self.timestep.is_active() {
+            (self.step_accumulator / self.timestep.step()).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    // Where to draw `mol`: between its position before the last step and its
+    // current one, by `interpolation_alpha()`
+    pub fn interpolated_pos(&self, mol: &Molecule) -> Vector3<f32> {
+        let alpha = self.interpolation_alpha();
+        match self.step_origins.get(mol.id) {
+            Some(origin) if alpha < 1.0 => origin.lerp(&mol.pos, alpha),
+            _ => mol.pos,
+        }
+    }
+
+    pub(crate) fn plan_steps(&mut self, dt: f32) -> StepPlan {
+        if !self.timestep.is_active() {
+            return StepPlan {
+                steps: 1,
+                dt,
+                dropped: 0.0,
+            };
+        }
+        let step = self.timestep.step();
+        self.step_accumulator += dt.max(0.0);
+        let due = (self.step_accumulator / step).floor();
+        let steps = (due as u32).min(self.timestep.max_substeps);
+        self.step_accumulator -= steps as f32 * step;
+        // A tick too far behind catches up on at most `max_substeps` steps
+        // and gives up on the rest, keeping only the fraction of a step
+        let dropped = if due as u32 > steps {
+            let kept = self.step_accumulator % step;
+            let dropped = self.step_accumulator - kept;
+            self.step_accumulator = kept;
+            dropped
+        } else {
+            0.0
+        };
+        StepPlan {
+            steps,
+            dt: step,
+            dropped,
+        }
+    }
+
+    // Fixed steps for a headless run ticked by `dt` at a time, so its dough
+    // does not hang on the `dt` it was given; one tick may run all the steps
+    // it is due and none of the time is dropped
+    pub fn use_fixed_steps(&mut self, dt: f32) {
+        self.timestep.enabled = true;
+        let due = (dt / self.timestep.step()).ceil() as u32;
+        self.timestep.max_substeps = self.timestep.max_substeps.max(due);
+    }
+
+    // Positions before a step, for `interpolated_pos()`
+    pub(crate) fn remember_step_origins(&mut self) {
+        if !self.timestep.is_active() {
+            return;
+        }
+        for mol in self.grid.get_all_molecules() {
+            self.step_origins.set(mol.id, mol.pos);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{Molecule, SimulationState, UP};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+// Bodies outside the dough that the baker shapes it with: the bench it lies
+// on and two cupped hands. Molecules cannot enter them and slide along them
+// with some friction, so a hand dragged across the bench pulls the dough
+// skin tight the way a real pre-shape does, without a force field.
+
+// Flat bench under the dough (+y is up)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorkSurface {
+    pub height: f32,
+    pub friction: f32, // Share of the sliding speed lost on contact each tick, 0 to 1
+}
+
+impl Default for WorkSurface {
+    fn default() -> Self {
+        WorkSurface {
+            height: 0.0,
+            friction: 0.3,
+        }
+    }
+}
+
+// A cupped hand: the half of a ball on the `facing` side, the palm being the
+// flat cut through its center
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Hand {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+    pub facing: Vector3<f32>, // Unit direction the curved side presses towards
+    pub velocity: Vector3<f32>,
+    pub friction: f32, // Share of the sliding speed lost on contact each tick, 0 to 1
+    pub target: Option<Vector3<f32>>, // Where a scripted move stops
+    pub active: bool,  // Lifted hands touch nothing
+}
+
+impl Default for Hand {
+    fn default() -> Self {
+        Hand {
+            center: Vector3::zeros(),
+            radius: 60.0,
+            facing: Vector3::new(0.0, -1.0, 0.0),
+            velocity: Vector3::zeros(),
+            friction: 0.6,
+            target: None,
+            active: false,
+        }
+    }
+}
+
+impl Hand {
+    // Scripted moves travel in a straight line and stop on their target; a
+    // dragged hand only moves with the mouse
+    fn advance(&mut self, dt: f32) {
+        let Some(target) = self.target else {
+            return;
+        };
+        let step = self.velocity * dt;
+        if (target - self.center).magnitude() <= step.magnitude() {
+            self.center = target;
+            self.velocity = Vector3::zeros();
+            self.target = None;
+        } else {
+            self.center += step;
+        }
+    }
+
+    // Pushes a molecule inside the curved half back onto its surface
+    fn collide(&self, mol: &mut Molecule) {
+        let offset = mol.pos - self.center;
+        let reach = self.radius + mol.radius();
+        if offset.dot(&self.facing) < 0.0 || offset.magnitude_squared() >= reach * reach {
+            return;
+        }
+        let normal = offset.try_normalize(f32::EPSILON).unwrap_or(self.facing);
+        mol.pos = self.center + normal * reach;
+        mol.velocity = contact_velocity(mol.velocity, self.velocity, normal, self.friction);
+    }
+}
+
+impl WorkSurface {
+    fn collide(&self, mol: &mut Molecule) {
+        let floor = self.height + mol.radius();
+        if mol.pos.y >= floor {
+            return;
+        }
+        mol.pos.y = floor;
+        mol.velocity = contact_velocity(mol.velocity, Vector3::zeros(), UP, self.friction);
+    }
+}
+
+// A molecule touching a body moving at `body_velocity`: it stops going into
+// the body and loses `friction` of its sliding speed relative to it
+fn contact_velocity(
+    velocity: Vector3<f32>,
+    body_velocity: Vector3<f32>,
+    normal: Vector3<f32>,
+    friction: f32,
+) -> Vector3<f32> {
+    let relative = velocity - body_velocity;
+    let into = relative.dot(&normal).min(0.0);
+    let relative = relative - normal * into;
+    let sliding = relative - normal * relative.dot(&normal);
+    body_velocity + relative - sliding * friction.clamp(0.0, 1.0)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShapingTools {
+    pub surface: Option<WorkSurface>,
+    pub hands: [Hand; 2],
+}
+
+impl ShapingTools {
+    pub fn is_idle(&self) -> bool {
+        self.surface.is_none() && self.hands.iter().all(|hand| !hand.active)
+    }
+
+    pub(crate) fn advance(&mut self, dt: f32) {
+        for hand in self.hands.iter_mut().filter(|hand| hand.active) {
+            hand.advance(dt);
+        }
+    }
+
+    pub(crate) fn collide(&self, mol: &mut Molecule) {
+        if let Some(surface) = &self.surface {
+            surface.collide(mol);
+        }
+        for hand in self.hands.iter().filter(|hand| hand.active) {
+            hand.collide(mol);
+        }
+    }
+}
+
+impl SimulationState {
+    // Puts a hand down at `center`, still, its curved side towards `facing`
+    pub fn place_hand(&mut self, hand: usize, center: Vector3<f32>, facing: Vector3<f32>) {
+        let Some(hand) = self.tools.hands.get_mut(hand) else {
+            return;
+        };
+        hand.center = center;
+        hand.facing = facing
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(Vector3::new(0.0, -1.0, 0.0));
+        hand.velocity = Vector3::zeros();
+        hand.target = None;
+        hand.active = true;
+    }
+
+    // Scripted move: the hand travels to `target` in `duration` seconds of
+    // simulation. A lifted hand is put down on the target right away.
+    pub fn move_hand(&mut self, hand: usize, target: Vector3<f32>, duration: f32) {
+        let Some(hand) = self.tools.hands.get_mut(hand) else {
+            return;
+        };
+        if !hand.active || duration <= 0.0 {
+            hand.center = target;
+            hand.velocity = Vector3::zeros();
+            hand.target = None;
+            hand.active = true;
+            return;
+        }
+        hand.velocity = (target - hand.center) / duration;
+        hand.target = Some(target);
+    }
+
+    // Mouse-driven: the hand jumps to `center`, moving at the speed it took
+    // to get there over `dt` so the dough it touches is dragged along
+    pub fn drag_hand(&mut self, hand: usize, center: Vector3<f32>, dt: f32) {
+        let Some(hand) = self.tools.hands.get_mut(hand) else {
+            return;
+        };
+        hand.velocity = if hand.active && dt > 0.0 {
+            (center - hand.center) / dt
+        } else {
+            Vector3::zeros()
+        };
+        hand.center = center;
+        hand.target = None;
+        hand.active = true;
+    }
+
+    pub fn lift_hands(&mut self) {
+        for hand in &mut self.tools.hands {
+            hand.active = false;
+            hand.velocity = Vector3::zeros();
+            hand.target = None;
+        }
+    }
+}
@@ -0,0 +1,672 @@
+use crate::snapshot::{decode_state, encode_state};
+use crate::{
+    Molecule, MoleculeId, MoleculeType, Note, SimulationState, StableHashMap, StableHashSet,
+};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::JoinHandle;
+
+const TRAJECTORY_MAGIC: &[u8; 8] = b"HOPTRAJ\0";
+// 1: plain record stream, 2: zstd-compressed chunks of records, 3: notes,
+// 4: generational molecule ids
+const TRAJECTORY_VERSION: u32 = 4;
+// Older files hold plain integer ids, which would not map onto the arena
+const OLDEST_READABLE_VERSION: u32 = 4;
+
+// Records are grouped into chunks of about this size before compression; a
+// savepoint always starts a new chunk
+const CHUNK_BYTES: usize = 1 << 20;
+const ZSTD_LEVEL: i32 = 3;
+// Chunks waiting for the writer thread; past this the recorder waits, which
+// bounds memory when the disk cannot keep up
+const WRITER_QUEUE: usize = 4;
+
+// Moves smaller than this are not recorded; playback positions stay within it
+const POSITION_EPSILON: f32 = 0.05;
+// Slack on frame times, sim time accumulates float error
+const TIME_EPSILON: f32 = 1e-3;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameMolecule {
+    pub id: MoleculeId,
+    pub mol_type: MoleculeType,
+    pub pos: Vector3<f32>,
+}
+
+// A trajectory is a stream of small delta frames (only what changed since the
+// previous frame) with a full savepoint every few minutes of sim time, so a
+// player can jump anywhere by restoring the closest savepoint and replaying
+// the few deltas after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TrajectoryRecord {
+    Keyframe {
+        time: f32,
+        state: Vec<u8>, // Encoded SimulationState, also usable to resume the run
+    },
+    Delta {
+        time: f32,
+        temperature: f32,
+        changed: Vec<FrameMolecule>, // Moved, changed type or created
+        removed: Vec<MoleculeId>,
+        bonds: Option<Vec<(MoleculeId, MoleculeId, f32)>>, // Whole bond list, only when it changed
+    },
+    // A note added since the previous record, not a frame of its own
+    Note(Note),
+}
+
+impl TrajectoryRecord {
+    pub fn time(&self) -> f32 {
+        match self {
+            TrajectoryRecord::Keyframe { time, .. } | TrajectoryRecord::Delta { time, .. } => *time,
+            TrajectoryRecord::Note(note) => note.time,
+        }
+    }
+
+    pub fn is_keyframe(&self) -> bool {
+        matches!(self, TrajectoryRecord::Keyframe { .. })
+    }
+
+    // Keyframes and deltas, what exporters write out
+    pub fn is_frame(&self) -> bool {
+        !matches!(self, TrajectoryRecord::Note(_))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Trajectory {
+    records: Vec<TrajectoryRecord>,
+}
+
+impl Trajectory {
+    pub fn records(&self) -> &[TrajectoryRecord] {
+        &self.records
+    }
+
+    pub fn duration(&self) -> f32 {
+        let start = self.records.first().map(|record| record.time()).unwrap_or(0.0);
+        let end = self.records.last().map(|record| record.time()).unwrap_or(0.0);
+        end - start
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.records.first().map(|record| record.time()).unwrap_or(0.0)
+    }
+
+    pub fn keyframe_count(&self) -> usize {
+        self.records.iter().filter(|record| record.is_keyframe()).count()
+    }
+
+    // Index of the last keyframe at or before `time`
+    fn keyframe_before(&self, time: f32) -> Option<usize> {
+        self.records
+            .iter()
+            .rposition(|record| record.is_keyframe() && record.time() <= time)
+            .or_else(|| self.records.iter().position(|record| record.is_keyframe()))
+    }
+
+    // Every note of the run: those already in the first savepoint, then those
+    // recorded along the way
+    pub fn notes(&self) -> io::Result<Vec<Note>> {
+        let mut notes = match self.records.iter().find(|record| record.is_keyframe()) {
+            Some(TrajectoryRecord::Keyframe { state, .. }) => {
+                decode_state(state)?.notebook.notes().to_vec()
+            }
+            _ => Vec::new(),
+        };
+        for record in &self.records {
+            if let TrajectoryRecord::Note(note) = record {
+                if !notes.contains(note) {
+                    notes.push(note.clone());
+                }
+            }
+        }
+        Ok(notes)
+    }
+
+    // The state at the end of the recording: the last savepoint and every
+    // record after it
+    pub fn final_state(&self) -> io::Result<Option<SimulationState>> {
+        let Some(keyframe) = self.records.iter().rposition(|record| record.is_keyframe()) else {
+            return Ok(None);
+        };
+        let TrajectoryRecord::Keyframe { state: bytes, .. } = &self.records[keyframe] else {
+            return Ok(None);
+        };
+        let mut state = decode_state(bytes)?;
+        for record in &self.records[keyframe + 1..] {
+            apply_record(&mut state, record)?;
+        }
+        Ok(Some(state))
+    }
+
+    // Full state at the last savepoint before `time`, to resume the simulation
+    pub fn savepoint_before(&self, time: f32) -> io::Result<Option<SimulationState>> {
+        match self.keyframe_before(time).map(|index| &self.records[index]) {
+            Some(TrajectoryRecord::Keyframe { state, .. }) => decode_state(state).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // Header, then chunks as a little-endian u32 length and zstd bytes. Each
+    // chunk holds records as a u32 length and their bincode bytes.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_header(writer)?;
+        let mut chunk = Vec::new();
+        for record in &self.records {
+            if record.is_keyframe() && !chunk.is_empty() {
+                write_chunk(writer, &chunk)?;
+                chunk.clear();
+            }
+            write_record(&mut chunk, record)?;
+            if chunk.len() >= CHUNK_BYTES {
+                write_chunk(writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            write_chunk(writer, &chunk)?;
+        }
+        writer.flush()
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut records = Vec::new();
+        read_header(reader)?;
+        while let Some(chunk) = read_chunk(reader)? {
+            let mut chunk = &chunk[..];
+            while let Some(record) = read_record(&mut chunk)? {
+                records.push(record);
+            }
+        }
+        Ok(Trajectory { records })
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut writer)
+    }
+
+    pub fn load_from_file(path: &Path) -> io::Result<Self> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        Trajectory::read_from(&mut reader)
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(TRAJECTORY_MAGIC)?;
+    writer.write_all(&TRAJECTORY_VERSION.to_le_bytes())
+}
+
+// Returns the format version
+fn read_header<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut magic = [0u8; 8];
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    reader.read_exact(&mut version)?;
+    if &magic != TRAJECTORY_MAGIC {
+        return Err(invalid_data("not a house_of_pain trajectory"));
+    }
+    let version = u32::from_le_bytes(version);
+    if !(OLDEST_READABLE_VERSION..=TRAJECTORY_VERSION).contains(&version) {
+        return Err(invalid_data(format!("unsupported trajectory version {}", version)));
+    }
+    Ok(version)
+}
+
+fn write_record<W: Write>(writer: &mut W, record: &TrajectoryRecord) -> io::Result<()> {
+    let bytes = bincode::serialize(record).map_err(invalid_data)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)
+}
+
+// Ok(None) at a clean end of stream
+fn read_record<R: Read>(reader: &mut R) -> io::Result<Option<TrajectoryRecord>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut bytes)?;
+    bincode::deserialize(&bytes).map(Some).map_err(invalid_data)
+}
+
+fn write_chunk<W: Write>(writer: &mut W, chunk: &[u8]) -> io::Result<()> {
+    let compressed = zstd::bulk::compress(chunk, ZSTD_LEVEL)?;
+    writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+    writer.write_all(&compressed)
+}
+
+// Decompressed chunk, Ok(None) at a clean end of stream
+fn read_chunk<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let mut compressed = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut compressed)?;
+    zstd::stream::decode_all(&compressed[..]).map(Some)
+}
+
+// Streams records to a trajectory file as they are recorded. The sim thread
+// only serializes records into the current chunk; compression and disk
+// writes happen on a writer thread, so memory stays at a few chunks however
+// long the run.
+#[derive(Debug)]
+pub struct TrajectoryWriter {
+    path: PathBuf,
+    chunk: Vec<u8>,
+    sender: Option<SyncSender<Vec<u8>>>,
+    thread: Option<JoinHandle<io::Result<u64>>>,
+}
+
+impl TrajectoryWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        write_header(&mut file)?;
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(WRITER_QUEUE);
+        let thread = std::thread::Builder::new()
+            .name("trajectory-writer".to_string())
+            .spawn(move || {
+                let mut written = (TRAJECTORY_MAGIC.len() + 4) as u64;
+                for chunk in receiver {
+                    let compressed = zstd::bulk::compress(&chunk, ZSTD_LEVEL)?;
+                    file.write_all(&(compressed.len() as u32).to_le_bytes())?;
+                    file.write_all(&compressed)?;
+                    written += 4 + compressed.len() as u64;
+                }
+                file.flush()?;
+                Ok(written)
+            })?;
+        Ok(TrajectoryWriter {
+            path: path.to_path_buf(),
+            chunk: Vec::new(),
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn push(&mut self, record: &TrajectoryRecord) -> io::Result<()> {
+        if record.is_keyframe() && !self.chunk.is_empty() {
+            self.send_chunk()?;
+        }
+        write_record(&mut self.chunk, record)?;
+        if self.chunk.len() >= CHUNK_BYTES {
+            self.send_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn send_chunk(&mut self) -> io::Result<()> {
+        let chunk = std::mem::take(&mut self.chunk);
+        let sent = self
+            .sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(chunk).is_ok());
+        if sent {
+            return Ok(());
+        }
+        // The writer thread stopped, report why
+        self.sender = None;
+        match self.join()? {
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "trajectory writer already closed",
+            )),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn join(&mut self) -> io::Result<io::Result<u64>> {
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .map_err(|_| io::Error::other("trajectory writer thread panicked")),
+            None => Ok(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "trajectory writer already closed",
+            ))),
+        }
+    }
+
+    // Writes the last chunk and waits for the file to be complete; returns
+    // the file size in bytes
+    pub fn finish(mut self) -> io::Result<u64> {
+        if !self.chunk.is_empty() {
+            self.send_chunk()?;
+        }
+        self.sender = None;
+        self.join()?
+    }
+}
+
+impl Drop for TrajectoryWriter {
+    // Dropping without finish still ends the file cleanly
+    fn drop(&mut self) {
+        if self.sender.is_some() && !self.chunk.is_empty() {
+            let _ = self.send_chunk();
+        }
+        self.sender = None;
+        let _ = self.join();
+    }
+}
+
+fn bond_list(state: &SimulationState) -> Vec<(MoleculeId, MoleculeId, f32)> {
+    state
+        .bonds
+        .iter()
+        .map(|bond| (bond.molecule_a_id, bond.molecule_b_id, bond.target_distance))
+        .collect()
+}
+
+// Next time on the grid started at the first recorded time
+fn next_after(scheduled: Option<f32>, time: f32, interval: f32) -> f32 {
+    let interval = interval.max(TIME_EPSILON);
+    let mut next = scheduled.unwrap_or(time);
+    while next <= time + TIME_EPSILON {
+        next += interval;
+    }
+    next
+}
+
+// Samples a running simulation into a trajectory
+#[derive(Debug)]
+pub struct TrajectoryRecorder {
+    pub frame_interval: f32,    // Sim seconds between delta frames
+    pub keyframe_interval: f32, // Sim seconds between savepoints
+    trajectory: Trajectory,
+    writer: Option<TrajectoryWriter>, // Records go to disk instead of `trajectory`
+    frames: usize,
+    keyframes: usize,
+    next_frame: Option<f32>, // Frames stay on a fixed grid of sim times
+    next_keyframe: Option<f32>,
+    recorded: StableHashMap<MoleculeId, (MoleculeType, Vector3<f32>)>, // As the player will see them
+    recorded_bonds: Vec<(MoleculeId, MoleculeId, f32)>,
+    recorded_notes: usize, // Notes of the state already in the records
+}
+
+impl Default for TrajectoryRecorder {
+    fn default() -> Self {
+        TrajectoryRecorder::new(1.0, 300.0)
+    }
+}
+
+impl TrajectoryRecorder {
+    pub fn new(frame_interval: f32, keyframe_interval: f32) -> Self {
+        TrajectoryRecorder {
+            frame_interval,
+            keyframe_interval,
+            trajectory: Trajectory::default(),
+            writer: None,
+            frames: 0,
+            keyframes: 0,
+            next_frame: None,
+            next_keyframe: None,
+            recorded: StableHashMap::default(),
+            recorded_bonds: Vec::new(),
+            recorded_notes: 0,
+        }
+    }
+
+    // Streams the records to a file instead of keeping them in memory
+    pub fn streaming_to(mut self, writer: TrajectoryWriter) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    // Records kept in memory, empty when streaming to a file
+    pub fn trajectory(&self) -> &Trajectory {
+        &self.trajectory
+    }
+
+    pub fn frames_recorded(&self) -> usize {
+        self.frames
+    }
+
+    pub fn keyframes_recorded(&self) -> usize {
+        self.keyframes
+    }
+
+    // Completes the file when streaming; the returned trajectory holds the
+    // in-memory records only
+    pub fn finish(mut self) -> io::Result<Trajectory> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(self.trajectory)
+    }
+
+    fn push(&mut self, record: TrajectoryRecord) -> io::Result<()> {
+        if record.is_frame() {
+            self.frames += 1;
+        }
+        if record.is_keyframe() {
+            self.keyframes += 1;
+        }
+        match self.writer.as_mut() {
+            Some(writer) => writer.push(&record),
+            None => {
+                self.trajectory.records.push(record);
+                Ok(())
+            }
+        }
+    }
+
+    // Called after each tick; records a frame (or a savepoint) when one is due
+    pub fn record(&mut self, state: &SimulationState) -> io::Result<()> {
+        let time = state.time_elapsed;
+        let keyframe_due = self.next_keyframe.is_none_or(|next| time >= next - TIME_EPSILON);
+        let frame_due = self.next_frame.is_none_or(|next| time >= next - TIME_EPSILON);
+
+        if keyframe_due {
+            self.push(TrajectoryRecord::Keyframe {
+                time,
+                state: encode_state(state)?,
+            })?;
+            self.recorded = state
+                .grid
+                .get_all_molecules()
+                .iter()
+                .map(|mol| (mol.id, (mol.mol_type.clone(), mol.pos)))
+                .collect();
+            self.recorded_bonds = bond_list(state);
+            self.recorded_notes = state.notebook.len();
+        } else if frame_due {
+            // Notes first, the player applies records in order
+            let notes = state.notebook.notes();
+            let new_notes = notes.get(self.recorded_notes..).unwrap_or_default().to_vec();
+            self.recorded_notes = notes.len();
+            for note in new_notes {
+                self.push(TrajectoryRecord::Note(note))?;
+            }
+            let record = self.delta(state);
+            self.push(record)?;
+        }
+        if keyframe_due {
+            self.next_keyframe = Some(next_after(self.next_keyframe, time, self.keyframe_interval));
+        }
+        if keyframe_due || frame_due {
+            self.next_frame = Some(next_after(self.next_frame, time, self.frame_interval));
+        }
+        Ok(())
+    }
+
+    fn delta(&mut self, state: &SimulationState) -> TrajectoryRecord {
+        let mut changed = Vec::new();
+        let mut seen = StableHashSet::default();
+        for mol in state.grid.get_all_molecules() {
+            seen.insert(mol.id);
+            let unchanged = self.recorded.get(&mol.id).is_some_and(|(mol_type, pos)| {
+                *mol_type == mol.mol_type && (pos - mol.pos).magnitude() < POSITION_EPSILON
+            });
+            if !unchanged {
+                self.recorded.insert(mol.id, (mol.mol_type.clone(), mol.pos));
+                changed.push(FrameMolecule {
+                    id: mol.id,
+                    mol_type: mol.mol_type.clone(),
+                    pos: mol.pos,
+                });
+            }
+        }
+        let removed: Vec<MoleculeId> = self
+            .recorded
+            .keys()
+            .filter(|id| !seen.contains(id))
+            .copied()
+            .collect();
+        for id in &removed {
+            self.recorded.remove(id);
+        }
+
+        let bonds = bond_list(state);
+        let bonds = if bonds != self.recorded_bonds {
+            self.recorded_bonds = bonds.clone();
+            Some(bonds)
+        } else {
+            None
+        };
+
+        TrajectoryRecord::Delta {
+            time: state.time_elapsed,
+            temperature: state.temperature,
+            changed,
+            removed,
+            bonds,
+        }
+    }
+}
+
+// Plays a trajectory back into a simulation state used for display only
+#[derive(Debug, Clone)]
+pub struct TrajectoryPlayer {
+    trajectory: Trajectory,
+    next_record: usize, // First record not applied to the state yet
+    time: f32,
+    loaded: bool,
+}
+
+impl TrajectoryPlayer {
+    pub fn new(trajectory: Trajectory) -> Self {
+        TrajectoryPlayer {
+            time: trajectory.start_time(),
+            trajectory,
+            next_record: 0,
+            loaded: false,
+        }
+    }
+
+    pub fn trajectory(&self) -> &Trajectory {
+        &self.trajectory
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_record >= self.trajectory.records.len()
+    }
+
+    // Brings `state` to the last frame at or before `time`. Going forward
+    // applies the deltas in between, unless a savepoint gets there faster;
+    // going back restarts from the closest savepoint.
+    pub fn seek(&mut self, state: &mut SimulationState, time: f32) -> io::Result<()> {
+        let records = &self.trajectory.records;
+        let Some(keyframe) = self.trajectory.keyframe_before(time) else {
+            return Ok(());
+        };
+        let tracking = state.tracks_changes();
+        if !self.loaded || time < self.time || keyframe >= self.next_record {
+            if let TrajectoryRecord::Keyframe { state: bytes, .. } = &records[keyframe] {
+                *state = decode_state(bytes)?;
+            }
+            self.next_record = keyframe + 1;
+            self.loaded = true;
+        }
+
+        while let Some(record) = records.get(self.next_record) {
+            if record.time() > time {
+                break;
+            }
+            apply_record(state, record)?;
+            self.next_record += 1;
+        }
+        // A renderer following the change journal sees the jump as changes
+        if tracking {
+            state.track_changes();
+            state.record_changes();
+        }
+        self.time = time;
+        Ok(())
+    }
+}
+
+pub(crate) fn apply_record(state: &mut SimulationState, record: &TrajectoryRecord) -> io::Result<()> {
+    match record {
+        TrajectoryRecord::Keyframe { state: bytes, .. } => *state = decode_state(bytes)?,
+        TrajectoryRecord::Delta {
+            time,
+            temperature,
+            changed,
+            removed,
+            bonds,
+        } => {
+            state.time_elapsed = *time;
+            state.temperature = *temperature;
+            for id in removed {
+                state.grid.remove(*id);
+            }
+            for frame_mol in changed {
+                match state.grid.get_molecule(frame_mol.id) {
+                    Some(_) => {
+                        state.grid.set_mol_type(frame_mol.id, frame_mol.mol_type.clone());
+                        state.grid.update_molecule_pos(frame_mol.id, frame_mol.pos);
+                    }
+                    None => {
+                        let mut mol = Molecule::new(
+                            frame_mol.mol_type.clone(),
+                            frame_mol.pos,
+                            Vector3::zeros(),
+                        );
+                        mol.id = frame_mol.id;
+                        state.grid.insert_with_id(mol);
+                    }
+                }
+            }
+            if let Some(bonds) = bonds {
+                let bonds = bonds
+                    .iter()
+                    .map(|&(molecule_a_id, molecule_b_id, target_distance)| {
+                        state
+                            .solver
+                            .new_bond(molecule_a_id, molecule_b_id, target_distance)
+                    })
+                    .collect();
+                state.set_bonds(bonds);
+            }
+        }
+        TrajectoryRecord::Note(note) => state.notebook.add(note.clone()),
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use crate::snapshot::decode_state;
+use crate::trajectory::{apply_record, Trajectory, TrajectoryRecord};
+use crate::{MoleculeId, SimulationState, Species, StableHashMap, UP_AXIS};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// Compact replay for the browser viewer (web_viewer.html), little endian:
+//
+//   header   "PWEB", u16 version, f32 width/height/depth, u16 + UTF-8 recipe,
+//            u8 species count, then per species u16 + UTF-8 name, f32 radius,
+//            u8 r, g, b
+//   frames   u32 count, then per frame u8 kind (0 keyframe, 1 delta),
+//            f32 time, f32 temperature and
+//     keyframe  u32 n, n molecules, u32 m, m bonds
+//     delta     u32 n changed molecules, u32 r, r removed ids,
+//               u8 bonds changed, if 1: u32 m, m bonds
+//   molecule u32 slot, u8 species, u16 x/y/z quantized over the box
+//   bond     u32 slot, u32 slot
+//
+// Molecules are keyed by their arena slot: a slot reused by a new molecule
+// shows up as a change of that slot.
+//
+// A keyframe every KEYFRAME_EVERY frames lets the viewer seek without
+// replaying everything; deltas only hold what moved by a quantization step.
+pub const WEB_MAGIC: &[u8; 4] = b"PWEB";
+pub const WEB_VERSION: u16 = 1;
+pub const KEYFRAME_EVERY: usize = 50;
+
+const VIEWER_TEMPLATE: &str = include_str!("web_viewer.html");
+// Replaced in the template by the base64 replay
+const REPLAY_PLACEHOLDER: &str = "\"__REPLAY__\"";
+// And the up axis, so the page draws the dough the way up the physics has it
+const UP_AXIS_PLACEHOLDER: &str = "/*UP_AXIS*/1";
+
+type Quantized = (u8, [u16; 3]);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WebExport {
+    pub frames: usize,
+    pub keyframes: usize,
+    pub bytes: usize,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    let bytes = &text.as_bytes()[..text.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn slot(id: MoleculeId) -> u32 {
+    id.index() as u32
+}
+
+fn write_header(out: &mut Vec<u8>, state: &SimulationState) {
+    out.extend_from_slice(WEB_MAGIC);
+    out.extend_from_slice(&WEB_VERSION.to_le_bytes());
+    for extent in [state.width, state.height, state.depth] {
+        out.extend_from_slice(&extent.to_le_bytes());
+    }
+    write_text(out, &state.recipe_name);
+    out.push(Species::all().count() as u8);
+    for info in Species::all() {
+        write_text(out, info.name);
+        out.extend_from_slice(&info.radius.to_le_bytes());
+        out.extend(
+            info.color
+                .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8),
+        );
+    }
+}
+
+// Positions rounded to 1/65535 of the box along each axis
+fn quantize(state: &SimulationState) -> StableHashMap<u32, Quantized> {
+    let size = [state.width, state.height, state.depth];
+    state
+        .grid
+        .molecules()
+        .map(|mol| {
+            let pos = [mol.pos.x, mol.pos.y, mol.pos.z];
+            let mut q = [0u16; 3];
+            for axis in 0..3 {
+                let fraction = pos[axis] / size[axis].max(f32::EPSILON);
+                q[axis] = (fraction.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+            }
+            (slot(mol.id), (mol.mol_type.species() as u8, q))
+        })
+        .collect()
+}
+
+fn bond_pairs(state: &SimulationState) -> Vec<(u32, u32)> {
+    state
+        .bonds
+        .iter()
+        .map(|bond| (slot(bond.molecule_a_id), slot(bond.molecule_b_id)))
+        .collect()
+}
+
+fn write_molecules<'a>(
+    out: &mut Vec<u8>,
+    molecules: impl Iterator<Item = (&'a u32, &'a Quantized)>,
+) {
+    let mut sorted: Vec<_> = molecules.collect();
+    sorted.sort_by_key(|(id, _)| **id);
+    out.extend_from_slice(&(sorted.len() as u32).to_le_bytes());
+    for (id, (species, q)) in sorted {
+        out.extend_from_slice(&id.to_le_bytes());
+        out.push(*species);
+        for coord in q {
+            out.extend_from_slice(&coord.to_le_bytes());
+        }
+    }
+}
+
+fn write_bonds(out: &mut Vec<u8>, bonds: &[(u32, u32)]) {
+    out.extend_from_slice(&(bonds.len() as u32).to_le_bytes());
+    for (a, b) in bonds {
+        out.extend_from_slice(&a.to_le_bytes());
+        out.extend_from_slice(&b.to_le_bytes());
+    }
+}
+
+// Replays the trajectory into the compact binary replay
+pub fn encode_web_replay(trajectory: &Trajectory) -> io::Result<(Vec<u8>, WebExport)> {
+    let records = trajectory.records();
+    let Some(TrajectoryRecord::Keyframe { state: bytes, .. }) = records.first() else {
+        return Err(invalid_data("trajectory does not start with a savepoint"));
+    };
+    let mut state = decode_state(bytes)?;
+
+    let mut out = Vec::new();
+    write_header(&mut out, &state);
+    let frame_count = records.iter().filter(|record| record.is_frame()).count();
+    out.extend_from_slice(&(frame_count as u32).to_le_bytes());
+
+    let mut summary = WebExport::default();
+    let mut previous: StableHashMap<u32, Quantized> = StableHashMap::default();
+    let mut previous_bonds: Vec<(u32, u32)> = Vec::new();
+    for record in records {
+        apply_record(&mut state, record)?;
+        if !record.is_frame() {
+            continue;
+        }
+        let current = quantize(&state);
+        let bonds = bond_pairs(&state);
+        let keyframe = summary.frames % KEYFRAME_EVERY == 0;
+        out.push(if keyframe { 0 } else { 1 });
+        out.extend_from_slice(&state.time_elapsed.to_le_bytes());
+        out.extend_from_slice(&state.temperature.to_le_bytes());
+        if keyframe {
+            write_molecules(&mut out, current.iter());
+            write_bonds(&mut out, &bonds);
+            summary.keyframes += 1;
+        } else {
+            write_molecules(
+                &mut out,
+                current
+                    .iter()
+                    .filter(|(id, molecule)| previous.get(*id) != Some(*molecule)),
+            );
+            let mut removed: Vec<u32> = previous
+                .keys()
+                .filter(|id| !current.contains_key(*id))
+                .copied()
+                .collect();
+            removed.sort_unstable();
+            out.extend_from_slice(&(removed.len() as u32).to_le_bytes());
+            for id in removed {
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            if bonds == previous_bonds {
+                out.push(0);
+            } else {
+                out.push(1);
+                write_bonds(&mut out, &bonds);
+            }
+        }
+        previous = current;
+        previous_bonds = bonds;
+        summary.frames += 1;
+    }
+    summary.bytes = out.len();
+    Ok((out, summary))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, &byte)| {
+            value | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(value >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+// The viewer page with the replay embedded: a single file that opens in any
+// browser, nothing to install
+pub fn web_viewer_page(replay: &[u8]) -> String {
+    VIEWER_TEMPLATE
+        .replacen(UP_AXIS_PLACEHOLDER, &UP_AXIS.to_string(), 1)
+        .replacen(REPLAY_PLACEHOLDER, &format!("\"{}\"", base64(replay)), 1)
+}
+
+// .html / .htm writes the self-contained page, anything else the bare replay
+// that the page can open
+pub fn export_trajectory_web(trajectory: &Trajectory, path: &Path) -> io::Result<WebExport> {
+    let (replay, summary) = encode_web_replay(trajectory)?;
+    let page = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("html") || extension.eq_ignore_ascii_case("htm")
+        });
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    if page {
+        file.write_all(web_viewer_page(&replay).as_bytes())?;
+    } else {
+        file.write_all(&replay)?;
+    }
+    file.flush()?;
+    Ok(summary)
+}
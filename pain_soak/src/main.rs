@@ -0,0 +1,363 @@
+// Soak test: one dough run for millions of ticks, kneaded now and then, with
+// the invariants checked after every tick and the state hash logged at
+// checkpoints. Meant for nightly runs, to catch the slow leaks and drifts
+// short runs never reach:
+//   cargo run --release -p pain_soak -- --ticks 2000000 --hashes soak.hashes
+// A failed invariant saves the dough and exits with 1; a later run given
+// `--expect soak.hashes` must hit the same hashes at the same ticks.
+use pain_core::prelude::*;
+use pain_core::snapshot::{decode_state, encode_state};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const USAGE: &str = "pain_soak [--ticks <n>] [--dt <s>] [--seed <n>] [--size <l,h,p>] \
+[--recipe <fichier.toml|classic|baguette|...>] [--config <fichier.toml>] \
+[--knead-every <s simulées>] [--hash-every <pas>] [--hashes <fichier>] [--expect <fichier>] \
+[--max-entities <n>] [--max-energy <e>] [--max-memory <Mio>] [--failure <fichier.pain>]";
+
+// Kneading cycles through the three techniques, one every `knead_every`
+const KNEADS: [KneadAction; 3] = [
+    KneadAction::StretchAndFold,
+    KneadAction::CoilFold,
+    KneadAction::SlapAndFold,
+];
+
+struct Options {
+    recipe: Recipe,
+    config: SimulationConfig,
+    ticks: u64,
+    dt: f32,
+    seed: u64,
+    size: (f32, f32, f32), // Small by default, the run is long enough as it is
+    knead_every: f32,      // Sim seconds between two kneads, 0 never kneads
+    hash_every: u64,       // Ticks between two checkpoints
+    hashes: Option<PathBuf>,
+    expect: Option<PathBuf>,
+    max_entities: Option<usize>,
+    max_energy: Option<f32>,
+    max_memory: Option<f32>, // MiB
+    failure: PathBuf,        // Where the dough goes when an invariant breaks
+}
+
+// Bounds the dough must stay within, from the options or from its start
+struct Limits {
+    max_entities: usize, // Molecules and bonds
+    max_energy: f32,     // Kinetic energy per unit of mass
+    max_memory: usize,   // Bytes
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.windows(2)
+        .find(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+}
+
+fn number<T: std::str::FromStr>(args: &[String], flag: &str) -> io::Result<Option<T>> {
+    value(args, flag)
+        .map(|text| {
+            text.parse()
+                .map_err(|_| invalid(format!("{} : nombre attendu, pas {:?}", flag, text)))
+        })
+        .transpose()
+}
+
+fn recipe(arg: Option<&str>) -> io::Result<Recipe> {
+    match arg {
+        None => Ok(RecipePreset::Classic.into()),
+        Some(arg) => match RecipePreset::from_id(arg) {
+            Some(preset) => Ok(preset.into()),
+            None => Recipe::load_from(Path::new(arg))
+                .map_err(|err| invalid(format!("recette {} : {}", arg, err))),
+        },
+    }
+}
+
+fn config(arg: Option<&str>) -> io::Result<SimulationConfig> {
+    match arg {
+        Some(path) => SimulationConfig::load_from(Path::new(path))
+            .map_err(|err| invalid(format!("configuration {} : {}", path, err))),
+        None => Ok(SimulationConfig::default()),
+    }
+}
+
+fn size(arg: Option<&str>) -> io::Result<(f32, f32, f32)> {
+    let Some(arg) = arg else {
+        return Ok((200.0, 200.0, 200.0));
+    };
+    let dims: Option<Vec<f32>> = arg
+        .split(',')
+        .map(|dim| dim.trim().parse::<f32>().ok().filter(|dim| *dim > 0.0))
+        .collect();
+    match dims.as_deref() {
+        Some(&[width, height, depth]) => Ok((width, height, depth)),
+        _ => Err(invalid(format!(
+            "--size : trois longueurs positives attendues, pas {:?}",
+            arg
+        ))),
+    }
+}
+
+fn parse_options(args: &[String]) -> io::Result<Options> {
+    let options = Options {
+        recipe: recipe(value(args, "--recipe"))?,
+        config: config(value(args, "--config"))?,
+        ticks: number(args, "--ticks")?.unwrap_or(2_000_000),
+        dt: number(args, "--dt")?.unwrap_or(0.25),
+        seed: number(args, "--seed")?.unwrap_or(1),
+        size: size(value(args, "--size"))?,
+        knead_every: number(args, "--knead-every")?.unwrap_or(1800.0),
+        hash_every: number(args, "--hash-every")?.unwrap_or(100_000),
+        hashes: value(args, "--hashes").map(PathBuf::from),
+        expect: value(args, "--expect").map(PathBuf::from),
+        max_entities: number(args, "--max-entities")?,
+        max_energy: number(args, "--max-energy")?,
+        max_memory: number(args, "--max-memory")?,
+        failure: PathBuf::from(value(args, "--failure").unwrap_or("soak_failure.pain")),
+    };
+    if !(options.dt > 0.0 && options.knead_every >= 0.0 && options.hash_every > 0) {
+        return Err(invalid(
+            "--dt et --hash-every doivent être positifs, --knead-every aussi ou nul".to_string(),
+        ));
+    }
+    Ok(options)
+}
+
+// `<tick> <hash>` lines written by `--hashes`
+fn expected_hashes(path: &Path) -> io::Result<HashMap<u64, u64>> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| invalid(format!("hashs attendus {} : {}", path.display(), err)))?;
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let tick = fields.next().and_then(|tick| tick.parse().ok());
+            let hash = fields
+                .next()
+                .and_then(|hash| u64::from_str_radix(hash, 16).ok());
+            tick.zip(hash)
+                .ok_or_else(|| invalid(format!("{} : ligne illisible {:?}", path.display(), line)))
+        })
+        .collect()
+}
+
+// Kinetic energy per unit of mass, so the bound holds whatever the dough size
+fn specific_energy(state: &SimulationState) -> f32 {
+    let mass: f32 = state
+        .grid
+        .get_all_molecules()
+        .iter()
+        .map(|mol| mol.mass())
+        .sum();
+    state.kinetic_energy() / mass.max(f32::EPSILON)
+}
+
+fn limits(options: &Options, state: &SimulationState) -> Limits {
+    let stats = state.stats();
+    // Kneading caps a kick at `force_max_velocity`: twice that everywhere
+    // means something feeds energy in
+    let max_speed = 2.0 * state.config.force_max_velocity;
+    Limits {
+        max_entities: options
+            .max_entities
+            .unwrap_or(4 * (stats.molecules + stats.bonds).max(1)),
+        max_energy: options.max_energy.unwrap_or(0.5 * max_speed * max_speed),
+        max_memory: options
+            .max_memory
+            .map(|mib| (mib * 1024.0 * 1024.0) as usize)
+            .unwrap_or(8 * stats.memory_bytes.max(1024 * 1024)),
+    }
+}
+
+// The first invariant the dough breaks, if any
+fn violation(state: &SimulationState, limits: &Limits, non_finite_before: u64) -> Option<String> {
+    if let Some(id) = state.first_non_finite() {
+        return Some(format!("molécule {:?} non finie", id));
+    }
+    // The sanity pass removes non-finite molecules before anyone sees them
+    let removed = state.quarantine.non_finite_removed - non_finite_before;
+    if removed > 0 {
+        return Some(format!(
+            "{} molécules non finies mises en quarantaine",
+            removed
+        ));
+    }
+    if !state.temperature.is_finite() || !state.last_tick.solver_residual.is_finite() {
+        return Some(format!(
+            "température {} ou résidu du solveur {} non fini",
+            state.temperature, state.last_tick.solver_residual
+        ));
+    }
+    let energy = specific_energy(state);
+    if energy.is_nan() || energy > limits.max_energy {
+        return Some(format!(
+            "énergie cinétique {:.3} par unité de masse, au-delà de {:.3}",
+            energy, limits.max_energy
+        ));
+    }
+    let entities = state.grid.len() + state.bonds().len();
+    if entities > limits.max_entities {
+        return Some(format!(
+            "{} molécules et liaisons, au-delà de {}",
+            entities, limits.max_entities
+        ));
+    }
+    if !state.is_mass_conserved() {
+        return Some(format!(
+            "masse non conservée (écart relatif {:.2e})",
+            state.conservation_error()
+        ));
+    }
+    if let Some(violation) = state.reaction_violation() {
+        return Some(format!("bilan des réactions faux : {}", violation));
+    }
+    None
+}
+
+// Heavier checks, only at checkpoints: memory and a save round trip that
+// must give back the same dough
+fn checkpoint_violation(state: &SimulationState, limits: &Limits) -> Option<String> {
+    let memory = state.stats().memory_bytes;
+    if memory > limits.max_memory {
+        return Some(format!(
+            "{:.1} Mio de mémoire, au-delà de {:.1}",
+            memory as f32 / (1024.0 * 1024.0),
+            limits.max_memory as f32 / (1024.0 * 1024.0)
+        ));
+    }
+    match encode_state(state).and_then(|bytes| decode_state(&bytes)) {
+        Ok(reloaded) if reloaded.state_hash() == state.state_hash() => None,
+        Ok(reloaded) => Some(format!(
+            "sauvegarde relue différente (hash {:016x} au lieu de {:016x})",
+            reloaded.state_hash(),
+            state.state_hash()
+        )),
+        Err(err) => Some(format!("sauvegarde impossible : {}", err)),
+    }
+}
+
+// Reports the broken invariant and saves the dough that broke it
+fn fail(state: &SimulationState, options: &Options, tick: u64, message: &str) {
+    eprintln!(
+        "!! Invariant violé au pas {} (t={:.0} s, graine {}) : {}",
+        tick, state.time_elapsed, state.seed, message
+    );
+    match state.save_to_file(&options.failure) {
+        Ok(()) => eprintln!("Pâte sauvegardée dans {}", options.failure.display()),
+        Err(err) => eprintln!("Pâte non sauvegardée : {}", err),
+    }
+}
+
+// Whether the dough kept every invariant to the end
+fn run(options: &Options) -> io::Result<bool> {
+    let (width, height, depth) = options.size;
+    let mut state = SimulationState::builder()
+        .recipe(options.recipe.clone())
+        .config(options.config.clone())
+        .seed(options.seed)
+        .size(width, height, depth)
+        .build();
+    state.use_fixed_steps(options.dt);
+    let limits = limits(options, &state);
+    let expected = options.expect.as_deref().map(expected_hashes).transpose()?;
+    let mut hashes = options
+        .hashes
+        .as_ref()
+        .map(|path| File::create(path).map(BufWriter::new))
+        .transpose()?;
+    let knead_ticks = (options.knead_every / options.dt).round() as u64;
+    eprintln!(
+        "Endurance : {} pas de {} s (graine {}), au plus {} entités, énergie {:.3}, {:.0} Mio",
+        options.ticks,
+        options.dt,
+        state.seed,
+        limits.max_entities,
+        limits.max_energy,
+        limits.max_memory as f32 / (1024.0 * 1024.0)
+    );
+
+    let started = Instant::now();
+    for tick in 1..=options.ticks {
+        if state.time_elapsed >= options.recipe.autolyse_time {
+            state.add_salt();
+            state.add_yeast();
+        }
+        if knead_ticks > 0 && tick % knead_ticks == 0 {
+            let action = KNEADS[(tick / knead_ticks) as usize % KNEADS.len()];
+            state.knead(action);
+        }
+        let non_finite_before = state.quarantine.non_finite_removed;
+        state.tick(options.dt);
+        if let Some(message) = violation(&state, &limits, non_finite_before) {
+            fail(&state, options, tick, &message);
+            return Ok(false);
+        }
+
+        if tick % options.hash_every == 0 || tick == options.ticks {
+            if let Some(message) = checkpoint_violation(&state, &limits) {
+                fail(&state, options, tick, &message);
+                return Ok(false);
+            }
+            let hash = state.state_hash();
+            let stats = state.stats();
+            println!(
+                "pas {} t={:.0} s hash={:016x} molécules={} liaisons={} énergie={:.4} mémoire={:.1} Mio ({:.0?})",
+                tick,
+                state.time_elapsed,
+                hash,
+                stats.molecules,
+                stats.bonds,
+                specific_energy(&state),
+                stats.memory_mib(),
+                started.elapsed()
+            );
+            if let Some(hashes) = hashes.as_mut() {
+                writeln!(hashes, "{} {:016x}", tick, hash)?;
+                hashes.flush()?;
+            }
+            if let Some(&wanted) = expected.as_ref().and_then(|expected| expected.get(&tick)) {
+                if wanted != hash {
+                    let message = format!("dérive : hash {:016x}, {:016x} attendu", hash, wanted);
+                    fail(&state, options, tick, &message);
+                    return Ok(false);
+                }
+            }
+        }
+    }
+    eprintln!(
+        "Endurance réussie : {} pas, t={:.0} s en {:.0?}",
+        options.ticks,
+        state.time_elapsed,
+        started.elapsed()
+    );
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    match run(&options) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    }
+}